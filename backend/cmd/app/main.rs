@@ -1,14 +1,24 @@
 use actix_cors::Cors;
 use actix_web::http;
 use actix_web::middleware::Logger;
-use actix_web::{web::Data, App, HttpServer};
-use async_graphql::{EmptySubscription, Schema};
-use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use actix_web::{web::{Data, Payload}, App, HttpRequest, HttpServer};
+use async_graphql::Schema;
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
 use dotenv::dotenv;
 use log::{debug, error, info};
-use sea_orm::Database;
+use sea_orm::{Database, DatabaseConnection};
+use template::internal::api::admin::users::loaders::permissions::PermissionLoader;
+use template::internal::api::admin::users::registry::sync_registry;
+use template::internal::graphql::auth::{authenticate, authenticate_connection, extract_token};
+use template::internal::graphql::client::client_info;
+use template::internal::graphql::events::UserEvents;
 use template::internal::graphql::mutations::MutationRoot;
 use template::internal::graphql::queries::QueryRoot;
+use template::internal::graphql::subscriptions::SubscriptionRoot;
+use template::internal::graphql::site::{request_host, resolve_site};
+use template::internal::jobs::purge::spawn_purge_job;
+use template::internal::mail::mailer_from_env;
+use template::internal::security::jwt::{install_jwt_keys, jwt_keys, JwtKeys};
 use std::env;
 use std::sync::Arc;
 
@@ -17,6 +27,17 @@ async fn main() -> std::io::Result<()> {
     dotenv().ok();
     env_logger::init();
 
+    match JwtKeys::from_env() {
+        Ok(keys) => {
+            install_jwt_keys(keys);
+            info!("JWT keys loaded");
+        }
+        Err(e) => {
+            error!("Failed to load the JWT keys: {}", e);
+            return Err(std::io::Error::other("JWT key material is not configured"));
+        }
+    }
+
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let database_port = env::var("DATABASE_PORT").expect("DATABASE_PORT must be set");
     let database_user = env::var("DATABASE_USER").expect("DATABASE_USER must be set");
@@ -38,18 +59,33 @@ async fn main() -> std::io::Result<()> {
         }
         Err(e) => {
             error!("Failed to connect to the database: {:?}", e);
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, "Database connection failed"));
+            return Err(std::io::Error::other("Database connection failed"));
         }
     };
 
+    if let Err(e) = sync_registry(db.as_ref()).await {
+        error!("Failed to sync the admin actions and entities registry: {:?}", e);
+        return Err(std::io::Error::other("Admin registry sync failed"));
+    }
 
+    spawn_purge_job(db.clone());
+
+    let mailer = match mailer_from_env() {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            error!("Failed to configure the mailer: {}", e);
+            return Err(std::io::Error::other("Mailer is not configured"));
+        }
+    };
 
     let schema = Schema::build(
         QueryRoot,
         MutationRoot,
-        EmptySubscription,
+        SubscriptionRoot::default(),
     )
     .data(db.clone())
+    .data(mailer)
+    .data(UserEvents::default())
     .finish();
 
     let bind_address = env::var("BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
@@ -75,21 +111,55 @@ async fn main() -> std::io::Result<()> {
                     .allowed_methods(vec!["GET", "POST", "OPTIONS"])
                     .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
                     .allowed_header(http::header::CONTENT_TYPE)
+                    .supports_credentials()
                     .max_age(3600),
             )
             .service(actix_web::web::resource("/graphql").guard(actix_web::guard::Post()).to(graphql_handler))
+            .service(actix_web::web::resource("/graphql/ws").guard(actix_web::guard::Get()).to(graphql_ws))
             .service(actix_web::web::resource("/graphql").guard(actix_web::guard::Get()).to(graphql_playground))
+            .service(actix_web::web::resource("/.well-known/jwks.json").guard(actix_web::guard::Get()).to(jwks))
     })
     .bind(&bind_address)?
     .run()
     .await
 }
 
-async fn graphql_handler(schema: Data<Schema<QueryRoot, MutationRoot, EmptySubscription>>, req: GraphQLRequest) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+async fn graphql_handler(schema: Data<AppSchema>, db: Data<Arc<DatabaseConnection>>, http_req: HttpRequest, req: GraphQLRequest) -> GraphQLResponse {
+    let site = resolve_site(db.get_ref().as_ref(), request_host(&http_req)).await;
+    let mut request = authenticate(req.into_inner(), extract_token(&http_req))
+        .await
+        .data(PermissionLoader::data_loader(db.get_ref().clone(), site.as_ref().map(|site| site.id)))
+        .data(client_info(&http_req));
+    if let Some(site) = site {
+        request = request.data(site);
+    }
+    schema.execute(request).await.into()
+}
+
+/// Subscriptions, over the `graphql-transport-ws` and legacy `graphql-ws` protocols. The
+/// connection is authenticated once, from the token of its `connection_init` message.
+async fn graphql_ws(schema: Data<AppSchema>, db: Data<Arc<DatabaseConnection>>, http_req: HttpRequest, payload: Payload) -> actix_web::Result<actix_web::HttpResponse> {
+    let site = resolve_site(db.get_ref().as_ref(), request_host(&http_req)).await;
+    // No permission loader: its cache would outlive the checks of a long-lived connection
+    let mut data = async_graphql::Data::default();
+    data.insert(client_info(&http_req));
+    if let Some(site) = site {
+        data.insert(site);
+    }
+
+    GraphQLSubscription::new(Schema::clone(&*schema))
+        .with_data(data)
+        .on_connection_init(authenticate_connection)
+        .start(&http_req, payload)
 }
 
 async fn graphql_playground() -> actix_web::Result<actix_web::HttpResponse> {
-    let playground = async_graphql::http::GraphiQLSource::build().endpoint("/graphql").finish();
+    let playground = async_graphql::http::GraphiQLSource::build().endpoint("/graphql").subscription_endpoint("/graphql/ws").finish();
     Ok(actix_web::HttpResponse::Ok().content_type("text/html").body(playground))
 }
+
+async fn jwks() -> actix_web::HttpResponse {
+    actix_web::HttpResponse::Ok().json(jwt_keys().jwks())
+}
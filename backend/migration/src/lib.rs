@@ -4,6 +4,7 @@ use std::env;
 mod migrations;
 use crate::migrations::users;
 use crate::migrations::admin;
+use crate::migrations::auth;
 
 pub struct Migrator;
 
@@ -17,6 +18,7 @@ impl MigratorTrait for Migrator {
             Box::new(users::address::Migration),
             Box::new(users::organisation::Migration),
             Box::new(users::roles::Migration),
+            Box::new(users::organisation_members::Migration),
 
             Box::new(admin::site::Migration),
             Box::new(admin::admin_users::Migration),
@@ -27,9 +29,22 @@ impl MigratorTrait for Migrator {
             Box::new(admin::roles_permissions_assignements_entities::Migration),
             Box::new(admin::users_permissions_assignements_entities::Migration),
 
+            Box::new(auth::refresh_tokens::Migration),
+            Box::new(admin::site_scoping::Migration),
+            Box::new(admin::admin_users_search::Migration),
+            Box::new(admin::admin_audit_log::Migration),
+            Box::new(auth::login_attempts::Migration),
+            Box::new(admin::admin_mfa::Migration),
+            Box::new(auth::account_tokens::Migration),
+            Box::new(auth::soft_delete::Migration),
+            Box::new(users::erasure::Migration),
+            Box::new(admin::admin_entities_unique_name::Migration),
+
             Box::new(admin::data_seed::add_roles::Migration),
             Box::new(admin::data_seed::add_permissions::Migration),
             Box::new(admin::data_seed::add_entities::Migration),
+            Box::new(admin::data_seed::add_role_entity::Migration),
+            Box::new(admin::data_seed::add_permission_entity::Migration),
         ];
 
         match environment.as_str() {
@@ -43,6 +58,12 @@ impl MigratorTrait for Migrator {
                 migrations.push(Box::new(admin::data_seed::development::add_roles_permissions_assignements::Migration));
                 migrations.push(Box::new(admin::data_seed::development::add_users_permissions_assignements::Migration));
                 migrations.push(Box::new(admin::data_seed::development::add_sites::Migration));
+                migrations.push(Box::new(admin::data_seed::development::add_roles_user_resource_permissions::Migration));
+                migrations.push(Box::new(admin::data_seed::development::add_roles_role_resource_permissions::Migration));
+                migrations.push(Box::new(admin::data_seed::development::add_roles_permission_resource_permissions::Migration));
+                migrations.push(Box::new(admin::data_seed::development::add_roles_site_resource_permissions::Migration));
+                migrations.push(Box::new(admin::data_seed::development::add_roles_audit_log_resource_permissions::Migration));
+                migrations.push(Box::new(admin::data_seed::development::add_roles_user_mfa_resource_permissions::Migration));
             },
             "production" => {
                 println!("Production environment, using default migrations");
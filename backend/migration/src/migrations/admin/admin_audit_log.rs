@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+pub enum AdminAuditLog {
+    Table,
+    Id,
+    ActorId,
+    Action,
+    Entity,
+    TargetId,
+    Outcome,
+    Changes,
+    IpAddress,
+    UserAgent,
+    CreatedAt,
+}
+
+// The actor is kept without a foreign key: entries must outlive the admin who did it.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdminAuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdminAuditLog::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdminAuditLog::ActorId).uuid())
+                    .col(ColumnDef::new(AdminAuditLog::Action).string().not_null())
+                    .col(ColumnDef::new(AdminAuditLog::Entity).string().not_null())
+                    .col(ColumnDef::new(AdminAuditLog::TargetId).uuid())
+                    .col(ColumnDef::new(AdminAuditLog::Outcome).string().not_null())
+                    .col(ColumnDef::new(AdminAuditLog::Changes).json_binary())
+                    .col(ColumnDef::new(AdminAuditLog::IpAddress).string())
+                    .col(ColumnDef::new(AdminAuditLog::UserAgent).string())
+                    .col(
+                        ColumnDef::new(AdminAuditLog::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admin_audit_log_created_at")
+                    .table(AdminAuditLog::Table)
+                    .col(AdminAuditLog::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admin_audit_log_actor_id")
+                    .table(AdminAuditLog::Table)
+                    .col(AdminAuditLog::ActorId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admin_audit_log_entity_target")
+                    .table(AdminAuditLog::Table)
+                    .col(AdminAuditLog::Entity)
+                    .col(AdminAuditLog::TargetId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(AdminAuditLog::Table).to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+use crate::migrations::admin::admin_entities::AdminEntities;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Entities are looked up by name, by the guards as much as by the startup registry. The
+// index lets concurrent registrations insert with `ON CONFLICT DO NOTHING`.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admin_entities_name")
+                    .table(AdminEntities::Table)
+                    .col(AdminEntities::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_admin_entities_name").table(AdminEntities::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use sea_orm_migration::prelude::*;
+
+use super::{admin_roles::AdminRoles, admin_users::AdminUsers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// TOTP second factor of admin users. An enrolment stays pending (`confirmed_at` NULL)
+// until a first code has been checked. `last_used_step` is the time step of the last
+// accepted code, so that a code can never be replayed.
+#[derive(Iden)]
+pub enum AdminUsersMfa {
+    Table,
+    AdminUserId,
+    Secret,
+    ConfirmedAt,
+    LastUsedStep,
+    CreatedAt,
+}
+
+// Single-use recovery codes, only their SHA-256 is stored.
+#[derive(Iden)]
+pub enum AdminUsersRecoveryCodes {
+    Table,
+    Id,
+    AdminUserId,
+    CodeHash,
+    UsedAt,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum MfaPolicy {
+    RequireMfa,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdminUsersMfa::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdminUsersMfa::AdminUserId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdminUsersMfa::Secret).string().not_null())
+                    .col(ColumnDef::new(AdminUsersMfa::ConfirmedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(AdminUsersMfa::LastUsedStep).big_integer())
+                    .col(
+                        ColumnDef::new(AdminUsersMfa::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admin_users_mfa_admin_user_id")
+                            .from(AdminUsersMfa::Table, AdminUsersMfa::AdminUserId)
+                            .to(AdminUsers::Table, AdminUsers::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdminUsersRecoveryCodes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdminUsersRecoveryCodes::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdminUsersRecoveryCodes::AdminUserId).uuid().not_null())
+                    .col(ColumnDef::new(AdminUsersRecoveryCodes::CodeHash).string().not_null())
+                    .col(ColumnDef::new(AdminUsersRecoveryCodes::UsedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(AdminUsersRecoveryCodes::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admin_users_recovery_codes_admin_user_id")
+                            .from(AdminUsersRecoveryCodes::Table, AdminUsersRecoveryCodes::AdminUserId)
+                            .to(AdminUsers::Table, AdminUsers::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admin_users_recovery_codes_admin_user_id")
+                    .table(AdminUsersRecoveryCodes::Table)
+                    .col(AdminUsersRecoveryCodes::AdminUserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Members of a role with `require_mfa` must complete a second factor to log in
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AdminRoles::Table)
+                    .add_column(ColumnDef::new(MfaPolicy::RequireMfa).boolean().not_null().default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(Table::alter().table(AdminRoles::Table).drop_column(MfaPolicy::RequireMfa).to_owned())
+            .await?;
+        manager.drop_table(Table::drop().table(AdminUsersRecoveryCodes::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(AdminUsersMfa::Table).to_owned()).await?;
+        Ok(())
+    }
+}
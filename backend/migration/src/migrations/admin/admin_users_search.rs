@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Backs the global `search` of the admin users listing: a full-text vector for whole
+// words and a trigram index so that `ILIKE '%…%'` on the same text avoids a full scan.
+// The users listing filter repeats these exact expressions so that the planner uses them.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm").await?;
+
+        db.execute_unprepared(
+            r#"ALTER TABLE "admin_users" ADD COLUMN "search_vector" tsvector
+                GENERATED ALWAYS AS (to_tsvector('simple',
+                    "username" || ' ' || "first_name" || ' ' || "last_name" || ' ' || "email")) STORED"#,
+        )
+        .await?;
+
+        db.execute_unprepared(r#"CREATE INDEX "idx_admin_users_search_vector" ON "admin_users" USING GIN ("search_vector")"#)
+            .await?;
+
+        db.execute_unprepared(
+            r#"CREATE INDEX "idx_admin_users_search_trgm" ON "admin_users" USING GIN (
+                ("username" || ' ' || "first_name" || ' ' || "last_name" || ' ' || "email") gin_trgm_ops)"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(r#"DROP INDEX IF EXISTS "idx_admin_users_search_trgm""#).await?;
+        db.execute_unprepared(r#"DROP INDEX IF EXISTS "idx_admin_users_search_vector""#).await?;
+        db.execute_unprepared(r#"ALTER TABLE "admin_users" DROP COLUMN IF EXISTS "search_vector""#).await?;
+
+        Ok(())
+    }
+}
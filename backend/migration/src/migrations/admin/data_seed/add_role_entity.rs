@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+use uuid::Uuid;
+use sea_orm::sqlx::types::chrono::Utc;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+pub enum AdminEntities {
+    Table,
+    Id,
+    Name,
+    Description,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let insert_stmt = Query::insert()
+            .into_table(AdminEntities::Table)
+            .columns([
+                AdminEntities::Id,
+                AdminEntities::Name,
+                AdminEntities::Description,
+                AdminEntities::CreatedAt,
+                AdminEntities::UpdatedAt,
+            ])
+            .values_panic([
+                Uuid::parse_str("123e4567-e89b-12d3-a456-426614174114").unwrap().into(),
+                "Ressource::Role".into(),
+                "Represents the admin Role resource and its permissions.".into(),
+                Utc::now().into(),
+                Utc::now().into(),
+            ])
+            .to_owned();
+
+        manager.exec_stmt(insert_stmt).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let delete_stmt = Query::delete()
+            .from_table(AdminEntities::Table)
+            .and_where(Expr::col(AdminEntities::Name).eq("Ressource::Role"))
+            .to_owned();
+
+        manager.exec_stmt(delete_stmt).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use sea_orm_migration::prelude::*;
+use uuid::Uuid;
+use sea_orm::sqlx::types::chrono::Utc;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+pub enum AdminRolesPermissionsEntities {
+    Table,
+    RoleId,
+    PermissionId,
+    EntityId,
+}
+
+#[derive(Iden)]
+pub enum AdminEntities {
+    Table,
+    Id,
+    Name,
+    Description,
+    CreatedAt,
+    UpdatedAt,
+}
+
+// Admins role may reset the second factor of an admin who lost it. Enrolment is done by
+// each admin for themselves, so there is nothing else to grant on it.
+const ENTITY: &str = "Ressource::UserMfa";
+
+fn role_id() -> Uuid {
+    Uuid::parse_str("123e4567-e89b-12d3-a456-426614174000").unwrap()
+}
+
+fn permission_ids() -> Vec<Uuid> {
+    vec![
+        Uuid::parse_str("123e4567-e89b-12d3-a456-426614174103").unwrap(), // can_delete
+    ]
+}
+
+fn entity_id() -> SelectStatement {
+    Query::select()
+        .column(AdminEntities::Id)
+        .from(AdminEntities::Table)
+        .and_where(Expr::col(AdminEntities::Name).eq(ENTITY))
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The entity belongs to the startup registry, which may not have run yet
+        let insert_entity = Query::insert()
+            .into_table(AdminEntities::Table)
+            .columns([
+                AdminEntities::Id,
+                AdminEntities::Name,
+                AdminEntities::Description,
+                AdminEntities::CreatedAt,
+                AdminEntities::UpdatedAt,
+            ])
+            .values_panic([
+                Uuid::new_v4().into(),
+                ENTITY.into(),
+                "Represents the second factor of the admin users, reset for those who lost it.".into(),
+                Utc::now().into(),
+                Utc::now().into(),
+            ])
+            .on_conflict(OnConflict::column(AdminEntities::Name).do_nothing().to_owned())
+            .to_owned();
+
+        manager.exec_stmt(insert_entity).await?;
+
+        for permission_id in permission_ids() {
+            let insert_stmt = Query::insert()
+                .into_table(AdminRolesPermissionsEntities::Table)
+                // In the order of the select: the entity id first
+                .columns([
+                    AdminRolesPermissionsEntities::EntityId,
+                    AdminRolesPermissionsEntities::RoleId,
+                    AdminRolesPermissionsEntities::PermissionId,
+                ])
+                .select_from(
+                    entity_id()
+                        .expr_as(Expr::val(role_id()), AdminRolesPermissionsEntities::RoleId)
+                        .expr_as(Expr::val(permission_id), AdminRolesPermissionsEntities::PermissionId)
+                        .to_owned(),
+                )
+                .map_err(|e| DbErr::Migration(e.to_string()))?
+                .to_owned();
+
+            manager.exec_stmt(insert_stmt).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for permission_id in permission_ids() {
+            let delete_stmt = Query::delete()
+                .from_table(AdminRolesPermissionsEntities::Table)
+                .and_where(Expr::col(AdminRolesPermissionsEntities::RoleId).eq(role_id()))
+                .and_where(Expr::col(AdminRolesPermissionsEntities::PermissionId).eq(permission_id))
+                .and_where(Expr::col(AdminRolesPermissionsEntities::EntityId).in_subquery(entity_id()))
+                .to_owned();
+
+            manager.exec_stmt(delete_stmt).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -3,4 +3,10 @@ pub mod add_users_roles_assignements;
 pub mod add_roles_permissions_assignements;
 pub mod add_users_permissions_assignements;
 pub mod add_sites;
+pub mod add_roles_user_resource_permissions;
 
+pub mod add_roles_role_resource_permissions;
+pub mod add_roles_permission_resource_permissions;
+pub mod add_roles_site_resource_permissions;
+pub mod add_roles_audit_log_resource_permissions;
+pub mod add_roles_user_mfa_resource_permissions;
@@ -2,3 +2,5 @@ pub mod development;
 pub mod add_roles;
 pub mod add_permissions;
 pub mod add_entities;
+pub mod add_role_entity;
+pub mod add_permission_entity;
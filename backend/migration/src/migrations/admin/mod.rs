@@ -7,3 +7,8 @@ pub mod roles_permissions_assignements_entities;
 pub mod users_permissions_assignements_entities;
 pub mod admin_entities;
 pub mod site;
+pub mod site_scoping;
+pub mod admin_users_search;
+pub mod admin_audit_log;
+pub mod admin_mfa;
+pub mod admin_entities_unique_name;
@@ -0,0 +1,107 @@
+use sea_orm_migration::prelude::*;
+
+use super::{
+    site::Site, users_permissions_assignements_entities::AdminUsersPermissionsEntities,
+    users_roles_assignements::AdminUsersAdminRoles,
+};
+use crate::migrations::users::users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum SiteScoping {
+    SiteId,
+}
+
+// Scopes end users, admin role assignments and direct admin grants to a site. A NULL
+// `site_id` keeps the row shared by every site.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_site_domain")
+                    .table(Site::Table)
+                    .col(Site::Domain)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(SiteScoping::SiteId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_users_site_id")
+                            .from_tbl(Users::Table)
+                            .from_col(SiteScoping::SiteId)
+                            .to_tbl(Site::Table)
+                            .to_col(Site::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Assignments restricted to a site go with it, they must never become global
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AdminUsersAdminRoles::Table)
+                    .add_column(ColumnDef::new(SiteScoping::SiteId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_admin_users_admin_roles_site_id")
+                            .from_tbl(AdminUsersAdminRoles::Table)
+                            .from_col(SiteScoping::SiteId)
+                            .to_tbl(Site::Table)
+                            .to_col(Site::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AdminUsersPermissionsEntities::Table)
+                    .add_column(ColumnDef::new(SiteScoping::SiteId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_admin_users_permissions_entities_site_id")
+                            .from_tbl(AdminUsersPermissionsEntities::Table)
+                            .from_col(SiteScoping::SiteId)
+                            .to_tbl(Site::Table)
+                            .to_col(Site::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            Users::Table.into_iden(),
+            AdminUsersAdminRoles::Table.into_iden(),
+            AdminUsersPermissionsEntities::Table.into_iden(),
+        ] {
+            manager
+                .alter_table(Table::alter().table(table).drop_column(SiteScoping::SiteId).to_owned())
+                .await?;
+        }
+
+        manager
+            .drop_index(Index::drop().name("idx_site_domain").table(Site::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use sea_orm_migration::prelude::*;
+
+use crate::migrations::{admin::admin_users::AdminUsers, users::users::Users};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Single-use tokens sent by email: password resets and email verifications, of both admin
+// users and end users, told apart by `audience`. Only the SHA-256 of the token is stored.
+#[derive(Iden)]
+pub enum AccountTokens {
+    Table,
+    Id,
+    SubjectId,
+    Audience,
+    Purpose,
+    TokenHash,
+    ExpiresAt,
+    UsedAt,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum EmailVerification {
+    EmailVerifiedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccountTokens::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AccountTokens::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AccountTokens::SubjectId).uuid().not_null())
+                    .col(ColumnDef::new(AccountTokens::Audience).string().not_null())
+                    .col(ColumnDef::new(AccountTokens::Purpose).string().not_null())
+                    .col(ColumnDef::new(AccountTokens::TokenHash).string().not_null().unique_key())
+                    .col(
+                        ColumnDef::new(AccountTokens::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AccountTokens::UsedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(AccountTokens::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_account_tokens_subject_purpose")
+                    .table(AccountTokens::Table)
+                    .col(AccountTokens::SubjectId)
+                    .col(AccountTokens::Audience)
+                    .col(AccountTokens::Purpose)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(EmailVerification::EmailVerifiedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AdminUsers::Table)
+                    .add_column(ColumnDef::new(EmailVerification::EmailVerifiedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(Table::alter().table(AdminUsers::Table).drop_column(EmailVerification::EmailVerifiedAt).to_owned())
+            .await?;
+        manager
+            .alter_table(Table::alter().table(Users::Table).drop_column(EmailVerification::EmailVerifiedAt).to_owned())
+            .await?;
+        manager.drop_table(Table::drop().table(AccountTokens::Table).to_owned()).await?;
+        Ok(())
+    }
+}
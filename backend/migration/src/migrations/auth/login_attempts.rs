@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Failed login counters, one row per throttled key: an account (`admin:account:<email>`)
+// or a client address (`admin:ip:<address>`). Rows for unknown emails are kept too, so
+// that a lockout does not reveal whether an account exists.
+#[derive(Iden)]
+pub enum LoginAttempts {
+    Table,
+    Key,
+    Failures,
+    LastFailureAt,
+    LockedUntil,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginAttempts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginAttempts::Key)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LoginAttempts::Failures).integer().not_null().default(0))
+                    .col(
+                        ColumnDef::new(LoginAttempts::LastFailureAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(LoginAttempts::LockedUntil).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(LoginAttempts::Table).to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod refresh_tokens;
+pub mod login_attempts;
+pub mod account_tokens;
+pub mod soft_delete;
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+use crate::migrations::{admin::admin_users::AdminUsers, users::users::Users};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Deleted accounts keep their row, and everything referencing it, until the purge job
+// removes them once the retention window has passed.
+#[derive(Iden)]
+enum SoftDelete {
+    DeletedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(SoftDelete::DeletedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AdminUsers::Table)
+                    .add_column(ColumnDef::new(SoftDelete::DeletedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Looked up by the purge job only
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_users_deleted_at")
+                    .table(Users::Table)
+                    .col(SoftDelete::DeletedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admin_users_deleted_at")
+                    .table(AdminUsers::Table)
+                    .col(SoftDelete::DeletedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_admin_users_deleted_at").table(AdminUsers::Table).to_owned())
+            .await?;
+        manager
+            .drop_index(Index::drop().name("idx_users_deleted_at").table(Users::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(Table::alter().table(AdminUsers::Table).drop_column(SoftDelete::DeletedAt).to_owned())
+            .await?;
+        manager
+            .alter_table(Table::alter().table(Users::Table).drop_column(SoftDelete::DeletedAt).to_owned())
+            .await?;
+        Ok(())
+    }
+}
@@ -1,2 +1,3 @@
 pub mod users;
 pub mod admin;
+pub mod auth;
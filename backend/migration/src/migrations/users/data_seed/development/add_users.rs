@@ -1,6 +1,7 @@
 use sea_orm_migration::prelude::*;
 use sea_orm::sqlx::types::chrono::Utc;
 use uuid::Uuid;
+use bcrypt::{hash, DEFAULT_COST};
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
@@ -22,18 +23,21 @@ pub enum Users {
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         let users = vec![
-            ("123e4567-e89b-12d3-a456-426614174001", "user1", "First1", "Last1", "user1@example.com", "hashedpassword1"),
-            ("123e4567-e89b-12d3-a456-426614174002", "user2", "First2", "Last2", "user2@example.com", "hashedpassword2"),
-            ("123e4567-e89b-12d3-a456-426614174003", "user3", "First3", "Last3", "user3@example.com", "hashedpassword3"),
-            ("123e4567-e89b-12d3-a456-426614174004", "user4", "First4", "Last4", "user4@example.com", "hashedpassword4"),
-            ("123e4567-e89b-12d3-a456-426614174005", "user5", "First5", "Last5", "user5@example.com", "hashedpassword5"),
-            ("123e4567-e89b-12d3-a456-426614174006", "user6", "First6", "Last6", "user6@example.com", "hashedpassword6"),
-            ("123e4567-e89b-12d3-a456-426614174007", "user7", "First7", "Last7", "user7@example.com", "hashedpassword7"),
-            ("123e4567-e89b-12d3-a456-426614174008", "user8", "First8", "Last8", "user8@example.com", "hashedpassword8"),
-            ("123e4567-e89b-12d3-a456-426614174009", "user9", "First9", "Last9", "user9@example.com", "hashedpassword9"),
+            ("123e4567-e89b-12d3-a456-426614174001", "user1", "First1", "Last1", "user1@example.com", "password1"),
+            ("123e4567-e89b-12d3-a456-426614174002", "user2", "First2", "Last2", "user2@example.com", "password2"),
+            ("123e4567-e89b-12d3-a456-426614174003", "user3", "First3", "Last3", "user3@example.com", "password3"),
+            ("123e4567-e89b-12d3-a456-426614174004", "user4", "First4", "Last4", "user4@example.com", "password4"),
+            ("123e4567-e89b-12d3-a456-426614174005", "user5", "First5", "Last5", "user5@example.com", "password5"),
+            ("123e4567-e89b-12d3-a456-426614174006", "user6", "First6", "Last6", "user6@example.com", "password6"),
+            ("123e4567-e89b-12d3-a456-426614174007", "user7", "First7", "Last7", "user7@example.com", "password7"),
+            ("123e4567-e89b-12d3-a456-426614174008", "user8", "First8", "Last8", "user8@example.com", "password8"),
+            ("123e4567-e89b-12d3-a456-426614174009", "user9", "First9", "Last9", "user9@example.com", "password9"),
         ];
 
         for (uuid, username, first_name, last_name, email, password) in users {
+            let hashed_password = hash(password.as_bytes(), DEFAULT_COST)
+                .map_err(|e| DbErr::Custom(format!("Failed to hash password: {}", e)))?;
+
             let insert_stmt = Query::insert()
                 .into_table(Users::Table)
                 .columns([
@@ -52,7 +56,7 @@ impl MigrationTrait for Migration {
                     first_name.into(),
                     last_name.into(),
                     email.into(),
-                    password.into(),
+                    hashed_password.into(),
                     Utc::now().into(),
                     Utc::now().into(),
                 ])
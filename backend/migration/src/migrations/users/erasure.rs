@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+use crate::migrations::users::users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Erased accounts are anonymised rather than deleted: memberships and audit entries keep
+// pointing at the row, which the purge job and restores must leave alone.
+#[derive(Iden)]
+enum Erasure {
+    ErasedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Erasure::ErasedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(Table::alter().table(Users::Table).drop_column(Erasure::ErasedAt).to_owned())
+            .await
+    }
+}
@@ -2,4 +2,6 @@ pub mod data_seed;
 pub mod address;
 pub mod organisation;
 pub mod roles;
+pub mod organisation_members;
 pub mod users;
+pub mod erasure;
@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+use super::{organisation::Organisation, roles::Roles, users::Users};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Membership of a user in an organisation. An invitation is a membership that was not
+// accepted yet (`accepted_at` is NULL).
+#[derive(Iden)]
+pub enum OrganisationMembers {
+    Table,
+    Id,
+    OrganisationId,
+    UserId,
+    RoleId,
+    InvitedBy,
+    CreatedAt,
+    AcceptedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OrganisationMembers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OrganisationMembers::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OrganisationMembers::OrganisationId).uuid().not_null())
+                    .col(ColumnDef::new(OrganisationMembers::UserId).uuid().not_null())
+                    .col(ColumnDef::new(OrganisationMembers::RoleId).uuid())
+                    .col(ColumnDef::new(OrganisationMembers::InvitedBy).uuid())
+                    .col(
+                        ColumnDef::new(OrganisationMembers::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(OrganisationMembers::AcceptedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(OrganisationMembers::Table, OrganisationMembers::OrganisationId)
+                            .to(Organisation::Table, Organisation::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(OrganisationMembers::Table, OrganisationMembers::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(OrganisationMembers::Table, OrganisationMembers::RoleId)
+                            .to(Roles::Table, Roles::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_organisation_members_organisation_user")
+                    .table(OrganisationMembers::Table)
+                    .col(OrganisationMembers::OrganisationId)
+                    .col(OrganisationMembers::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(OrganisationMembers::Table).to_owned()).await?;
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use super::organisation::Organisation;
 pub struct Migration;
 
 #[derive(Iden)]
-enum Roles {
+pub enum Roles {
     Table,
     Id,
     OrganisationId,
@@ -0,0 +1,87 @@
+use async_graphql::{connection::query, Context, Object, SimpleObject};
+use chrono::{DateTime, Utc};
+use log::trace;
+use serde_json::Value as Json;
+use uuid::Uuid;
+
+use super::database;
+
+use crate::internal::{
+    api::admin::users::{
+        errors::interface::CustomGraphQLError,
+        guards::permission::Permission,
+        models::admin_audit_log,
+        services::audit::{AdminAuditService, AdminAuditServiceImpl, AuditLogFilter, AuditOutcome},
+    },
+    graphql::pagination::{into_connection, KeysetConnection, PageRequest, SortDirection, UserOrder, UserSortField},
+};
+
+#[derive(SimpleObject)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    /// Name of the GraphQL field that was called, e.g. `updateAdminUser`.
+    pub action: String,
+    pub entity: String,
+    pub target_id: Option<Uuid>,
+    pub outcome: AuditOutcome,
+    pub changes: Option<Json>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<admin_audit_log::Model> for AuditLogEntry {
+    fn from(entry: admin_audit_log::Model) -> Self {
+        AuditLogEntry {
+            id: entry.id,
+            actor_id: entry.actor_id,
+            action: entry.action,
+            entity: entry.entity,
+            target_id: entry.target_id,
+            // Only the service writes the column, anything else is a denial
+            outcome: AuditOutcome::parse(&entry.outcome).unwrap_or(AuditOutcome::Denied),
+            changes: entry.changes,
+            ip_address: entry.ip_address,
+            user_agent: entry.user_agent,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AdminAuditQuery;
+
+#[Object]
+impl AdminAuditQuery {
+    /// Most recent entries first.
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::AuditLog\")")]
+    async fn audit_log(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<AuditLogFilter>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<KeysetConnection<AuditLogEntry>> {
+        let db = database(ctx)?;
+        let order = UserOrder { field: UserSortField::CreatedAt, direction: SortDirection::Desc };
+        let with_total_count = ctx.look_ahead().field("totalCount").exists();
+
+        query(after, before, first, last, |after, before, first, last| async move {
+            let page = PageRequest::new(order, after, before, first, last)
+                .map_err(|e| e.new())?
+                .with_total_count(with_total_count);
+
+            match AdminAuditServiceImpl::get_entries(db.as_ref(), filter, &page).await {
+                Ok(entries) => {
+                    trace!("audit_log: {} entries found", entries.items.len());
+                    Ok(into_connection::<admin_audit_log::Entity, _>(entries, order.field, AuditLogEntry::from))
+                },
+                Err(e) => Err(e.new()),
+            }
+        })
+        .await
+    }
+}
@@ -3,7 +3,12 @@ use log::trace;
 use sea_orm::DatabaseConnection;
 use async_graphql::{Context, InputObject, Object};
 
-use crate::internal::api::admin::users::{errors::{db::AdminDbError, interface::CustomGraphQLError}, services::{auth::{JwtTokenService, TokenService}, users::{AdminUserService, AdminUserServiceImpl}}};
+use super::{database, record_audit};
+
+use crate::internal::api::admin::users::{errors::{db::AdminDbError, interface::CustomGraphQLError, permission::AdminPermissionError}, loaders::permissions::has_permission, services::{account::{AdminAccountService, AdminAccountServiceImpl}, audit::AuditOutcome, auth::{AdminLogin, JwtTokenService, TokenService}}};
+use crate::internal::mail::mailer;
+use crate::internal::graphql::{auth::admin_claims, client::ClientInfo};
+use crate::internal::security::refresh::TokenPair;
 
 #[derive(InputObject)]
 pub struct GenerateTokenInput {
@@ -16,61 +21,20 @@ pub struct AuthAdminQuery;
 
 #[Object]
 impl AuthAdminQuery {
-    async fn verify_token(&self, token: String) -> async_graphql::Result<bool> {
-        match JwtTokenService::verify_token(&token).await {
-            Ok(_) => {
-                trace!("Verify token: Token verified successfully {:?}", token);
-                Ok(true)
-            },
-            Err(e) => {
-                return Err(e.new());
-            }
-        }
+    async fn verify_token(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        let claims = admin_claims(ctx)?;
+        trace!("Verify token: Token verified successfully for {:?}", claims.sub);
+        Ok(true)
     }
-    async fn get_access_page(&self,ctx: &Context<'_>, token: String, page: String) -> async_graphql::Result<bool> {
-        let claims = match JwtTokenService::verify_token(&token).await {
-            Ok(claims) => {
-                trace!("users: Token verified successfully, claims: {:?}", claims);
-                claims
-            },
-            Err(e) => {
-                return Err(e.new());
-            }
-        };
-
-        let db = match ctx.data::<Arc<DatabaseConnection>>() {
-            Ok(db) => {
-                trace!("users: Database connection found for user {:?}", claims.sub);
-                db
-            }
-            Err(e) => {
-                return Err(
-                    (Box::new(AdminDbError::DatabaseError(format!("{:?}", e))) as Box<dyn CustomGraphQLError>).new()
-                );
-            }
-        };
-
-        let has_permission = match AdminUserServiceImpl::get_user_permissions_from_role(db.as_ref(), claims.sub, "can_read", &page).await {
-            Ok(_) => {
-                trace!("users: User {:?} has permission to read {:?}", claims.sub, page);
-                Ok(true)
-            },
-            Err(_) => {
-                trace!("users: User {:?} doesn't have permission from role, checking user permissions", claims.sub);
-                match AdminUserServiceImpl::get_user_permissions_from_user(db.as_ref(), claims.sub, "can_read", &page).await {
-                    Ok(_) => {
-                        trace!("users: User {:?} has permission to read {:?}", claims.sub, page);
-                        Ok(true)
-                    },
-                    Err(e) => {
-                        trace!("users: User {:?} doesn't have permission to read {:?}", claims.sub, page);
-                        return Err(e.new());
-                    }
-                }
-            }
-        };
+    async fn get_access_page(&self,ctx: &Context<'_>, page: String) -> async_graphql::Result<bool> {
+        let claims = admin_claims(ctx)?;
 
-        has_permission
+        if has_permission(ctx, "can_read", &page).await? {
+            trace!("users: User {:?} has permission to read {:?}", claims.sub, page);
+            Ok(true)
+        } else {
+            Err(AdminPermissionError::PermissionDenied(format!("can_read on {}", page)).new())
+        }
     }
 }
 
@@ -79,7 +43,9 @@ pub struct AuthAdminMutation;
 
 #[Object]
 impl AuthAdminMutation {
-    async fn generate_token(&self, ctx: &Context<'_>, input: GenerateTokenInput) -> async_graphql::Result<String> {
+    /// First login step. Returns tokens, or an MFA challenge to complete with
+    /// `verifyMfaChallenge` (or with the TOTP enrolment mutations when one is required).
+    async fn generate_token(&self, ctx: &Context<'_>, input: GenerateTokenInput) -> async_graphql::Result<AdminLogin> {
         let db = match ctx.data::<Arc<DatabaseConnection>>() {
             Ok(db) => {
                 trace!("Generate token: Database connection found");
@@ -92,14 +58,67 @@ impl AuthAdminMutation {
             }
         };
 
-        match JwtTokenService::generate_token(db.as_ref(), input.email, input.password).await {
-            Ok(token) => {
-                trace!("Generate token: Token generated successfully {:?}", token);
-                Ok(token)
-            },
-            Err(e) => { 
-                return Err(e.new()); 
+        let address = ctx.data_opt::<ClientInfo>().and_then(|client| client.ip_address.clone());
+
+        match JwtTokenService::generate_token(db.as_ref(), input.email, input.password, address).await {
+            Ok(login) => {
+                trace!("Generate token: Password accepted, MFA challenge: {}", login.mfa_challenge.is_some());
+                Ok(login)
             },
+            Err(e) => Err(e.new()),
         }
     }
+
+    async fn verify_mfa_challenge(&self, ctx: &Context<'_>, challenge_token: String, code: String) -> async_graphql::Result<TokenPair> {
+        let db = database(ctx)?;
+        let address = ctx.data_opt::<ClientInfo>().and_then(|client| client.ip_address.clone());
+
+        JwtTokenService::verify_mfa_challenge(db.as_ref(), &challenge_token, &code, address).await.map_err(|e| e.new())
+    }
+
+    async fn refresh_token(&self, ctx: &Context<'_>, refresh_token: String) -> async_graphql::Result<TokenPair> {
+        let db = database(ctx)?;
+        JwtTokenService::refresh_token(db.as_ref(), &refresh_token).await.map_err(|e| e.new())
+    }
+
+    async fn logout(&self, ctx: &Context<'_>, refresh_token: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        JwtTokenService::logout(db.as_ref(), &refresh_token).await.map_err(|e| e.new())
+    }
+
+    /// Always succeeds, whether the email belongs to an admin user or not.
+    async fn request_password_reset(&self, ctx: &Context<'_>, email: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let address = ctx.data_opt::<ClientInfo>().and_then(|client| client.ip_address.clone());
+
+        AdminAccountServiceImpl::request_password_reset(db.as_ref(), mailer(ctx)?.as_ref(), &email, address.as_deref())
+            .await
+            .map_err(|e| e.new())?;
+        Ok(true)
+    }
+
+    async fn reset_password(&self, ctx: &Context<'_>, token: String, password: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+
+        let user_id = AdminAccountServiceImpl::reset_password(db.as_ref(), &token, &password).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::User", Some(user_id), AuditOutcome::Success, None).await;
+        Ok(true)
+    }
+
+    async fn request_email_verification(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let claims = admin_claims(ctx)?;
+
+        AdminAccountServiceImpl::request_email_verification(db.as_ref(), mailer(ctx)?.as_ref(), claims.sub)
+            .await
+            .map_err(|e| e.new())?;
+        Ok(true)
+    }
+
+    async fn verify_email(&self, ctx: &Context<'_>, token: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+
+        AdminAccountServiceImpl::verify_email(db.as_ref(), &token).await.map_err(|e| e.new())?;
+        Ok(true)
+    }
 }
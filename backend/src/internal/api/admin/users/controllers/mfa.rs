@@ -0,0 +1,150 @@
+use log::trace;
+use async_graphql::{Context, Object, SimpleObject};
+use uuid::Uuid;
+
+use super::{database, record_audit};
+
+use crate::internal::api::admin::users::{
+    errors::{interface::CustomGraphQLError, mfa::AdminMfaError},
+    guards::permission::Permission,
+    services::{
+        audit::AuditOutcome,
+        auth::{JwtTokenService, MfaChallengeClaims, TokenService},
+        mfa::{AdminMfaService, AdminMfaServiceImpl},
+        users::{AdminUserService, AdminUserServiceImpl},
+    },
+};
+use crate::internal::graphql::{auth::admin_claims, client::ClientInfo};
+use crate::internal::security::refresh::TokenPair;
+
+#[derive(SimpleObject)]
+pub struct MfaStatus {
+    pub enabled: bool,
+    /// A role of the user requires a second factor, it cannot be disabled.
+    pub required: bool,
+    pub recovery_codes_left: u64,
+}
+
+/// Secret to add to an authenticator app, by hand or by rendering the URI as a QR code.
+#[derive(SimpleObject)]
+pub struct TotpEnrolmentPayload {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(SimpleObject)]
+pub struct TotpConfirmation {
+    /// Shown once, only their hash is kept.
+    pub recovery_codes: Vec<String>,
+    /// Set when the enrolment completed an MFA challenge.
+    pub tokens: Option<TokenPair>,
+}
+
+/// User the enrolment mutations act on: the holder of the challenge when one is given
+/// (enrolment forced at login), the authenticated admin otherwise.
+async fn enrolling_user(ctx: &Context<'_>, challenge_token: Option<&str>) -> async_graphql::Result<(Uuid, Option<MfaChallengeClaims>)> {
+    match challenge_token {
+        Some(challenge_token) => {
+            let challenge = JwtTokenService::decode_mfa_challenge(challenge_token).await.map_err(|e| e.new())?;
+            if !challenge.enrolment_required {
+                return Err(AdminMfaError::AlreadyEnabled(challenge.sub.to_string()).new());
+            }
+            Ok((challenge.sub, Some(challenge)))
+        },
+        None => Ok((admin_claims(ctx)?.sub, None)),
+    }
+}
+
+fn client_address(ctx: &Context<'_>) -> Option<String> {
+    ctx.data_opt::<ClientInfo>().and_then(|client| client.ip_address.clone())
+}
+
+#[derive(Default)]
+pub struct AdminMfaQuery;
+
+#[Object]
+impl AdminMfaQuery {
+    async fn mfa_status(&self, ctx: &Context<'_>) -> async_graphql::Result<MfaStatus> {
+        let db = database(ctx)?;
+        let user_id = admin_claims(ctx)?.sub;
+
+        let enabled = AdminMfaServiceImpl::get_mfa(db.as_ref(), user_id)
+            .await
+            .map_err(|e| e.new())?
+            .is_some_and(|mfa| mfa.confirmed_at.is_some());
+        let required = AdminMfaServiceImpl::is_mfa_required(db.as_ref(), user_id).await.map_err(|e| e.new())?;
+        let recovery_codes_left = AdminMfaServiceImpl::count_recovery_codes_left(db.as_ref(), user_id).await.map_err(|e| e.new())?;
+
+        Ok(MfaStatus { enabled, required, recovery_codes_left })
+    }
+}
+
+#[derive(Default)]
+pub struct AdminMfaMutation;
+
+#[Object]
+impl AdminMfaMutation {
+    async fn start_totp_enrolment(&self, ctx: &Context<'_>, challenge_token: Option<String>) -> async_graphql::Result<TotpEnrolmentPayload> {
+        let db = database(ctx)?;
+        let (user_id, _) = enrolling_user(ctx, challenge_token.as_deref()).await?;
+        let user = AdminUserServiceImpl::get_user_by_id(db.as_ref(), user_id).await.map_err(|e| e.new())?;
+
+        let enrolment = AdminMfaServiceImpl::start_enrolment(db.as_ref(), user_id, &user.email).await.map_err(|e| e.new())?;
+        trace!("start_totp_enrolment: Enrolment started for {:?}", user_id);
+        Ok(TotpEnrolmentPayload { secret: enrolment.secret, provisioning_uri: enrolment.provisioning_uri })
+    }
+
+    async fn confirm_totp_enrolment(&self, ctx: &Context<'_>, code: String, challenge_token: Option<String>) -> async_graphql::Result<TotpConfirmation> {
+        let db = database(ctx)?;
+        let (user_id, challenge) = enrolling_user(ctx, challenge_token.as_deref()).await?;
+
+        let recovery_codes = AdminMfaServiceImpl::confirm_enrolment(db.as_ref(), user_id, &code, client_address(ctx).as_deref())
+            .await
+            .map_err(|e| e.new())?;
+        trace!("confirm_totp_enrolment: Second factor enabled for {:?}", user_id);
+        record_audit(ctx, "Ressource::User", Some(user_id), AuditOutcome::Success, None).await;
+
+        // The code just checked is the second factor of a login forced to enrol
+        let tokens = match challenge {
+            Some(challenge) => {
+                JwtTokenService::consume_mfa_challenge(db.as_ref(), &challenge).await.map_err(|e| e.new())?;
+                Some(JwtTokenService::issue_tokens(db.as_ref(), user_id).await.map_err(|e| e.new())?)
+            },
+            None => None,
+        };
+        Ok(TotpConfirmation { recovery_codes, tokens })
+    }
+
+    async fn disable_totp(&self, ctx: &Context<'_>, code: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let user_id = admin_claims(ctx)?.sub;
+
+        let disabled = AdminMfaServiceImpl::disable(db.as_ref(), user_id, &code, client_address(ctx).as_deref())
+            .await
+            .map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::User", Some(user_id), AuditOutcome::Success, None).await;
+        Ok(disabled)
+    }
+
+    async fn regenerate_recovery_codes(&self, ctx: &Context<'_>, code: String) -> async_graphql::Result<Vec<String>> {
+        let db = database(ctx)?;
+        let user_id = admin_claims(ctx)?.sub;
+
+        let recovery_codes = AdminMfaServiceImpl::regenerate_recovery_codes(db.as_ref(), user_id, &code, client_address(ctx).as_deref())
+            .await
+            .map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::User", Some(user_id), AuditOutcome::Success, None).await;
+        Ok(recovery_codes)
+    }
+
+    /// Removes the second factor of an admin who lost access to it.
+    #[graphql(guard = "Permission::new(\"can_delete\", \"Ressource::UserMfa\")")]
+    async fn reset_admin_mfa(&self, ctx: &Context<'_>, user_id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        AdminUserServiceImpl::get_user_by_id(db.as_ref(), user_id).await.map_err(|e| e.new())?;
+
+        let reset = AdminMfaServiceImpl::reset(db.as_ref(), user_id).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::UserMfa", Some(user_id), AuditOutcome::Success, None).await;
+        Ok(reset)
+    }
+}
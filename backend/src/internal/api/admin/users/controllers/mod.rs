@@ -1,2 +1,66 @@
+use std::sync::Arc;
+use async_graphql::Context;
+use log::error;
+use sea_orm::DatabaseConnection;
+use serde_json::Value as Json;
+use uuid::Uuid;
+
+use crate::internal::{
+    api::admin::users::{
+        errors::{db::AdminDbError, interface::CustomGraphQLError},
+        services::{
+            audit::{AdminAuditService, AdminAuditServiceImpl, AuditEntry, AuditOutcome},
+            auth::Claims,
+        },
+    },
+    graphql::client::ClientInfo,
+};
+
 pub mod auth;
 pub mod users;
+pub mod roles;
+pub mod permissions;
+pub mod sites;
+pub mod audit;
+pub mod mfa;
+#[cfg(test)]
+mod test_mfa;
+#[cfg(test)]
+mod test_roles;
+#[cfg(test)]
+mod test_users;
+
+fn database<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Arc<DatabaseConnection>> {
+    ctx.data::<Arc<DatabaseConnection>>().map_err(|e| {
+        (Box::new(AdminDbError::DatabaseError(format!("{:?}", e))) as Box<dyn CustomGraphQLError>).new()
+    })
+}
+
+/// Writes an audit log entry for the field being resolved, on behalf of the admin of the
+/// request. A failure to write is logged but never fails the operation, which has
+/// already been carried out by then.
+pub(crate) async fn record_audit(ctx: &Context<'_>, entity: &str, target_id: Option<Uuid>, outcome: AuditOutcome, changes: Option<Json>) {
+    let db = match ctx.data_opt::<Arc<DatabaseConnection>>() {
+        Some(db) => db,
+        None => {
+            error!("audit: no database connection in context, entry for {} on {} lost", ctx.field().name(), entity);
+            return;
+        }
+    };
+    let client = ctx.data_opt::<ClientInfo>().cloned().unwrap_or_default();
+
+    let entry = AuditEntry {
+        actor_id: ctx.data_opt::<Claims>().map(|claims| claims.sub),
+        action: ctx.field().name().to_string(),
+        entity: entity.to_string(),
+        target_id,
+        outcome,
+        changes,
+        ip_address: client.ip_address,
+        user_agent: client.user_agent,
+    };
+
+    if let Err(e) = AdminAuditServiceImpl::record(db.as_ref(), entry).await {
+        error!("audit: failed to record {} on {}: {}", ctx.field().name(), entity, e);
+    }
+}
@@ -0,0 +1,154 @@
+use log::trace;
+use async_graphql::{Context, InputObject, Object, SimpleObject};
+use uuid::Uuid;
+
+use super::{database, record_audit};
+
+use crate::internal::api::admin::users::{
+    guards::permission::Permission,
+    models::{admin_actions, admin_entities},
+    services::{actions::{AdminActionService, AdminActionServiceImpl}, audit::{diff, AuditOutcome}, entities::{AdminEntitiesService, AdminEntitiesServiceImpl}},
+};
+
+#[derive(SimpleObject)]
+pub struct AdminAction {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl From<admin_actions::Model> for AdminAction {
+    fn from(action: admin_actions::Model) -> Self {
+        AdminAction {
+            id: action.id,
+            name: action.name,
+            description: action.description,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct AdminEntity {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl From<admin_entities::Model> for AdminEntity {
+    fn from(entity: admin_entities::Model) -> Self {
+        AdminEntity {
+            id: entity.id,
+            name: entity.name,
+            description: entity.description,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreatePermissionItemInput {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(InputObject)]
+pub struct UpdatePermissionItemInput {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Default)]
+pub struct AdminPermissionQuery;
+
+#[Object]
+impl AdminPermissionQuery {
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::Permission\")")]
+    async fn actions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<AdminAction>> {
+        let db = database(ctx)?;
+
+        match AdminActionServiceImpl::get_all_actions(db.as_ref()).await {
+            Ok(actions) => {
+                trace!("actions: {} actions found", actions.len());
+                Ok(actions.into_iter().map(AdminAction::from).collect())
+            },
+            Err(e) => Err(e.new()),
+        }
+    }
+
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::Permission\")")]
+    async fn entities(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<AdminEntity>> {
+        let db = database(ctx)?;
+
+        match AdminEntitiesServiceImpl::get_all_entities(db.as_ref()).await {
+            Ok(entities) => {
+                trace!("entities: {} entities found", entities.len());
+                Ok(entities.into_iter().map(AdminEntity::from).collect())
+            },
+            Err(e) => Err(e.new()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AdminPermissionMutation;
+
+#[Object]
+impl AdminPermissionMutation {
+    #[graphql(guard = "Permission::new(\"can_create\", \"Ressource::Permission\")")]
+    async fn create_action(&self, ctx: &Context<'_>, input: CreatePermissionItemInput) -> async_graphql::Result<AdminAction> {
+        let db = database(ctx)?;
+
+        let created = AdminActionServiceImpl::create_action(db.as_ref(), input.name, input.description).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Permission", Some(created.id), AuditOutcome::Success, diff::<admin_actions::Model, _>(None, Some(&created))).await;
+        Ok(created.into())
+    }
+
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::Permission\")")]
+    async fn update_action(&self, ctx: &Context<'_>, input: UpdatePermissionItemInput) -> async_graphql::Result<AdminAction> {
+        let db = database(ctx)?;
+        let before = AdminActionServiceImpl::get_action_by_id(db.as_ref(), input.id).await.map_err(|e| e.new())?;
+
+        let updated = AdminActionServiceImpl::update_action(db.as_ref(), input.id, input.name, input.description).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Permission", Some(updated.id), AuditOutcome::Success, diff(Some(&before), Some(&updated))).await;
+        Ok(updated.into())
+    }
+
+    #[graphql(guard = "Permission::new(\"can_delete\", \"Ressource::Permission\")")]
+    async fn delete_action(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let before = AdminActionServiceImpl::get_action_by_id(db.as_ref(), id).await.map_err(|e| e.new())?;
+
+        let deleted = AdminActionServiceImpl::delete_action(db.as_ref(), id).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Permission", Some(id), AuditOutcome::Success, diff::<_, admin_actions::Model>(Some(&before), None)).await;
+        Ok(deleted)
+    }
+
+    #[graphql(guard = "Permission::new(\"can_create\", \"Ressource::Permission\")")]
+    async fn create_entity(&self, ctx: &Context<'_>, input: CreatePermissionItemInput) -> async_graphql::Result<AdminEntity> {
+        let db = database(ctx)?;
+
+        let created = AdminEntitiesServiceImpl::create_entity(db.as_ref(), input.name, input.description).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Permission", Some(created.id), AuditOutcome::Success, diff::<admin_entities::Model, _>(None, Some(&created))).await;
+        Ok(created.into())
+    }
+
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::Permission\")")]
+    async fn update_entity(&self, ctx: &Context<'_>, input: UpdatePermissionItemInput) -> async_graphql::Result<AdminEntity> {
+        let db = database(ctx)?;
+        let before = AdminEntitiesServiceImpl::get_entity_by_id(db.as_ref(), input.id).await.map_err(|e| e.new())?;
+
+        let updated = AdminEntitiesServiceImpl::update_entity(db.as_ref(), input.id, input.name, input.description).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Permission", Some(updated.id), AuditOutcome::Success, diff(Some(&before), Some(&updated))).await;
+        Ok(updated.into())
+    }
+
+    #[graphql(guard = "Permission::new(\"can_delete\", \"Ressource::Permission\")")]
+    async fn delete_entity(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let before = AdminEntitiesServiceImpl::get_entity_by_id(db.as_ref(), id).await.map_err(|e| e.new())?;
+
+        let deleted = AdminEntitiesServiceImpl::delete_entity(db.as_ref(), id).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Permission", Some(id), AuditOutcome::Success, diff::<_, admin_entities::Model>(Some(&before), None)).await;
+        Ok(deleted)
+    }
+}
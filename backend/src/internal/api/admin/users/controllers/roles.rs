@@ -0,0 +1,201 @@
+use log::trace;
+use async_graphql::{Context, InputObject, Object, SimpleObject};
+use uuid::Uuid;
+
+use serde_json::json;
+
+use super::{database, record_audit};
+
+use crate::internal::api::admin::users::{
+    guards::{
+        permission::Permission,
+        privileges::{ensure_holds_everywhere, ensure_role_not_held},
+    },
+    models::admin_roles,
+    services::{
+        audit::{diff, AuditOutcome},
+        roles::{AdminRoleService, AdminRoleServiceImpl, RolePermissionMatrix},
+    },
+};
+
+#[derive(SimpleObject)]
+pub struct AdminRole {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// Members must log in with a second factor.
+    pub require_mfa: bool,
+}
+
+impl From<admin_roles::Model> for AdminRole {
+    fn from(role: admin_roles::Model) -> Self {
+        AdminRole {
+            id: role.id,
+            name: role.name,
+            description: role.description,
+            require_mfa: role.require_mfa,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct PermissionCell {
+    pub action: String,
+    pub granted: bool,
+}
+
+#[derive(SimpleObject)]
+pub struct PermissionMatrixRow {
+    pub entity: String,
+    pub description: Option<String>,
+    pub permissions: Vec<PermissionCell>,
+}
+
+/// One row per entity, one cell per action, in the same action order on every row.
+#[derive(SimpleObject)]
+pub struct RolePermissions {
+    pub role: AdminRole,
+    pub actions: Vec<String>,
+    pub rows: Vec<PermissionMatrixRow>,
+}
+
+impl From<RolePermissionMatrix> for RolePermissions {
+    fn from(matrix: RolePermissionMatrix) -> Self {
+        let rows = matrix.entities.iter().map(|entity| PermissionMatrixRow {
+            entity: entity.name.clone(),
+            description: entity.description.clone(),
+            permissions: matrix.actions.iter().map(|action| PermissionCell {
+                action: action.name.clone(),
+                granted: matrix.grants.contains(&(action.id, entity.id)),
+            }).collect(),
+        }).collect();
+
+        RolePermissions {
+            role: matrix.role.into(),
+            actions: matrix.actions.into_iter().map(|action| action.name).collect(),
+            rows,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreateRoleInput {
+    pub name: String,
+    pub description: Option<String>,
+    #[graphql(default)]
+    pub require_mfa: bool,
+}
+
+#[derive(InputObject)]
+pub struct UpdateRoleInput {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub require_mfa: Option<bool>,
+}
+
+#[derive(InputObject)]
+pub struct RolePermissionInput {
+    pub role_id: Uuid,
+    pub action: String,
+    pub entity: String,
+}
+
+#[derive(Default)]
+pub struct AdminRoleQuery;
+
+#[Object]
+impl AdminRoleQuery {
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::Role\")")]
+    async fn roles(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<AdminRole>> {
+        let db = database(ctx)?;
+
+        match AdminRoleServiceImpl::get_all_roles(db.as_ref()).await {
+            Ok(roles) => {
+                trace!("roles: {} roles found", roles.len());
+                Ok(roles.into_iter().map(AdminRole::from).collect())
+            },
+            Err(e) => Err(e.new()),
+        }
+    }
+
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::Role\")")]
+    async fn role_permissions(&self, ctx: &Context<'_>, role_id: Uuid) -> async_graphql::Result<RolePermissions> {
+        let db = database(ctx)?;
+
+        AdminRoleServiceImpl::get_permission_matrix(db.as_ref(), role_id)
+            .await
+            .map(RolePermissions::from)
+            .map_err(|e| e.new())
+    }
+}
+
+#[derive(Default)]
+pub struct AdminRoleMutation;
+
+#[Object]
+impl AdminRoleMutation {
+    #[graphql(guard = "Permission::new(\"can_create\", \"Ressource::Role\")")]
+    async fn create_role(&self, ctx: &Context<'_>, input: CreateRoleInput) -> async_graphql::Result<AdminRole> {
+        let db = database(ctx)?;
+
+        match AdminRoleServiceImpl::create_role(db.as_ref(), input.name, input.description, input.require_mfa).await {
+            Ok(role) => {
+                trace!("create_role: Role created: {:?}", role.id);
+                record_audit(ctx, "Ressource::Role", Some(role.id), AuditOutcome::Success, diff::<admin_roles::Model, _>(None, Some(&role))).await;
+                Ok(role.into())
+            },
+            Err(e) => Err(e.new()),
+        }
+    }
+
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::Role\")")]
+    async fn update_role(&self, ctx: &Context<'_>, input: UpdateRoleInput) -> async_graphql::Result<AdminRole> {
+        let db = database(ctx)?;
+        let before = AdminRoleServiceImpl::get_role_by_id(db.as_ref(), input.id).await.map_err(|e| e.new())?;
+
+        let updated = AdminRoleServiceImpl::update_role(db.as_ref(), input.id, input.name, input.description, input.require_mfa)
+            .await
+            .map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Role", Some(updated.id), AuditOutcome::Success, diff(Some(&before), Some(&updated))).await;
+        Ok(updated.into())
+    }
+
+    #[graphql(guard = "Permission::new(\"can_delete\", \"Ressource::Role\")")]
+    async fn delete_role(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let before = AdminRoleServiceImpl::get_role_by_id(db.as_ref(), id).await.map_err(|e| e.new())?;
+
+        let deleted = AdminRoleServiceImpl::delete_role(db.as_ref(), id).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Role", Some(id), AuditOutcome::Success, diff::<_, admin_roles::Model>(Some(&before), None)).await;
+        Ok(deleted)
+    }
+
+    /// Only grants what the caller holds on every site, and never on a role they hold.
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::Role\")")]
+    async fn grant_role_permission(&self, ctx: &Context<'_>, input: RolePermissionInput) -> async_graphql::Result<RolePermissions> {
+        let db = database(ctx)?;
+        ensure_role_not_held(ctx, db.as_ref(), input.role_id).await?;
+        ensure_holds_everywhere(ctx, db.as_ref(), input.role_id, &input.action, &input.entity).await?;
+
+        let matrix = AdminRoleServiceImpl::grant_permission(db.as_ref(), input.role_id, &input.action, &input.entity)
+            .await
+            .map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Role", Some(input.role_id), AuditOutcome::Success, Some(json!({ "granted": { "action": input.action, "entity": input.entity } }))).await;
+        Ok(matrix.into())
+    }
+
+    /// Same bounds as `grant_role_permission`.
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::Role\")")]
+    async fn revoke_role_permission(&self, ctx: &Context<'_>, input: RolePermissionInput) -> async_graphql::Result<RolePermissions> {
+        let db = database(ctx)?;
+        ensure_role_not_held(ctx, db.as_ref(), input.role_id).await?;
+        ensure_holds_everywhere(ctx, db.as_ref(), input.role_id, &input.action, &input.entity).await?;
+
+        let matrix = AdminRoleServiceImpl::revoke_permission(db.as_ref(), input.role_id, &input.action, &input.entity)
+            .await
+            .map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Role", Some(input.role_id), AuditOutcome::Success, Some(json!({ "revoked": { "action": input.action, "entity": input.entity } }))).await;
+        Ok(matrix.into())
+    }
+}
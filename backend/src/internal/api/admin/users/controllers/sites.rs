@@ -0,0 +1,137 @@
+use log::trace;
+use async_graphql::{Context, InputObject, Object, SimpleObject};
+use uuid::Uuid;
+
+use super::{database, record_audit};
+
+use crate::internal::{
+    api::admin::users::{
+        guards::permission::Permission,
+        models::site,
+        services::{
+            audit::{diff, AuditOutcome},
+            sites::{AdminSiteService, AdminSiteServiceImpl},
+        },
+    },
+    graphql::site::CurrentSite,
+};
+
+#[derive(SimpleObject)]
+pub struct Site {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub domain: String,
+}
+
+impl From<site::Model> for Site {
+    fn from(site: site::Model) -> Self {
+        Site {
+            id: site.id,
+            name: site.name,
+            description: site.description,
+            domain: site.domain,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreateSiteInput {
+    pub name: String,
+    pub description: Option<String>,
+    /// Host name the site is served on, e.g. `shop.example.com`.
+    pub domain: String,
+}
+
+#[derive(InputObject)]
+pub struct UpdateSiteInput {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub domain: Option<String>,
+}
+
+#[derive(Default)]
+pub struct AdminSiteQuery;
+
+#[Object]
+impl AdminSiteQuery {
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::Site\")")]
+    async fn sites(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Site>> {
+        let db = database(ctx)?;
+
+        match AdminSiteServiceImpl::get_all_sites(db.as_ref()).await {
+            Ok(sites) => {
+                trace!("sites: {} sites found", sites.len());
+                Ok(sites.into_iter().map(Site::from).collect())
+            },
+            Err(e) => Err(e.new()),
+        }
+    }
+
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::Site\")")]
+    async fn site(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Site> {
+        let db = database(ctx)?;
+
+        AdminSiteServiceImpl::get_site_by_id(db.as_ref(), id)
+            .await
+            .map(Site::from)
+            .map_err(|e| e.new())
+    }
+
+    /// Site resolved from the request's `Host` header, if any.
+    async fn current_site(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Site>> {
+        let current = match ctx.data_opt::<CurrentSite>() {
+            Some(current) => current,
+            None => return Ok(None),
+        };
+        let db = database(ctx)?;
+
+        AdminSiteServiceImpl::get_site_by_id(db.as_ref(), current.id)
+            .await
+            .map(|site| Some(Site::from(site)))
+            .map_err(|e| e.new())
+    }
+}
+
+#[derive(Default)]
+pub struct AdminSiteMutation;
+
+#[Object]
+impl AdminSiteMutation {
+    #[graphql(guard = "Permission::new(\"can_create\", \"Ressource::Site\")")]
+    async fn create_site(&self, ctx: &Context<'_>, input: CreateSiteInput) -> async_graphql::Result<Site> {
+        let db = database(ctx)?;
+
+        match AdminSiteServiceImpl::create_site(db.as_ref(), input.name, input.description, input.domain).await {
+            Ok(site) => {
+                trace!("create_site: Site created: {:?}", site.id);
+                record_audit(ctx, "Ressource::Site", Some(site.id), AuditOutcome::Success, diff::<site::Model, _>(None, Some(&site))).await;
+                Ok(site.into())
+            },
+            Err(e) => Err(e.new()),
+        }
+    }
+
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::Site\")")]
+    async fn update_site(&self, ctx: &Context<'_>, input: UpdateSiteInput) -> async_graphql::Result<Site> {
+        let db = database(ctx)?;
+        let before = AdminSiteServiceImpl::get_site_by_id(db.as_ref(), input.id).await.map_err(|e| e.new())?;
+
+        let updated = AdminSiteServiceImpl::update_site(db.as_ref(), input.id, input.name, input.description, input.domain)
+            .await
+            .map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Site", Some(updated.id), AuditOutcome::Success, diff(Some(&before), Some(&updated))).await;
+        Ok(updated.into())
+    }
+
+    #[graphql(guard = "Permission::new(\"can_delete\", \"Ressource::Site\")")]
+    async fn delete_site(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let before = AdminSiteServiceImpl::get_site_by_id(db.as_ref(), id).await.map_err(|e| e.new())?;
+
+        let deleted = AdminSiteServiceImpl::delete_site(db.as_ref(), id).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::Site", Some(id), AuditOutcome::Success, diff::<_, site::Model>(Some(&before), None)).await;
+        Ok(deleted)
+    }
+}
@@ -0,0 +1,87 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_graphql::{EmptySubscription, Request, Response, Schema};
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, MockDatabase, Value};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    controllers::mfa::{AdminMfaMutation, AdminMfaQuery},
+    models::{admin_audit_log, admin_users},
+    services::auth::Claims,
+};
+
+fn claims(admin_id: Uuid) -> Claims {
+    Claims {
+        sub: admin_id,
+        iss: "template".to_owned(),
+        aud: "admin".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+        roles: None,
+        permissions: None,
+    }
+}
+
+fn grant(admin_id: Uuid, action: &str, entity: &str) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("user_id".to_owned(), Value::from(admin_id)),
+        ("action".to_owned(), Value::from(action)),
+        ("entity".to_owned(), Value::from(entity)),
+    ])
+}
+
+fn denial(admin_id: Uuid) -> admin_audit_log::Model {
+    admin_audit_log::Model {
+        id: Uuid::new_v4(),
+        actor_id: Some(admin_id),
+        action: "resetAdminMfa".to_owned(),
+        entity: "Ressource::UserMfa".to_owned(),
+        target_id: None,
+        outcome: "denied".to_owned(),
+        changes: None,
+        ip_address: None,
+        user_agent: None,
+        created_at: Utc::now(),
+    }
+}
+
+async fn reset_admin_mfa(db: MockDatabase, admin_id: Uuid, user_id: Uuid) -> Response {
+    let schema = Schema::build(AdminMfaQuery, AdminMfaMutation, EmptySubscription)
+        .data(Arc::new(db.into_connection()))
+        .finish();
+
+    let mutation = format!(r#"mutation {{ resetAdminMfa(userId: "{}") }}"#, user_id);
+    schema.execute(Request::new(mutation).data(claims(admin_id))).await
+}
+
+fn error_message(response: &Response) -> Option<String> {
+    let extensions = response.errors.first()?.extensions.as_ref()?;
+    extensions.get("message").map(|message| message.to_string())
+}
+
+#[tokio::test]
+async fn test_reset_admin_mfa_needs_its_own_permission() {
+    let admin_id = Uuid::new_v4();
+    // Updating users is not enough to remove someone's second factor
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User")]])
+        .append_query_results([vec![denial(admin_id)]]);
+
+    let response = reset_admin_mfa(db, admin_id, Uuid::new_v4()).await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+}
+
+#[tokio::test]
+async fn test_reset_admin_mfa_granted() {
+    let admin_id = Uuid::new_v4();
+    // Past the guard, the unknown user is reported
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_delete", "Ressource::UserMfa")]])
+        .append_query_results([Vec::<admin_users::Model>::new()]);
+
+    let response = reset_admin_mfa(db, admin_id, Uuid::new_v4()).await;
+    assert_eq!(error_message(&response), Some("\"USER_NOT_FOUND\"".to_owned()));
+}
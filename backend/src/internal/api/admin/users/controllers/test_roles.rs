@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_graphql::{EmptySubscription, Request, Response, Schema};
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, Value};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    controllers::roles::{AdminRoleMutation, AdminRoleQuery},
+    models::{admin_audit_log, admin_roles, admin_users_roles},
+    services::auth::Claims,
+};
+
+fn claims(admin_id: Uuid) -> Claims {
+    Claims {
+        sub: admin_id,
+        iss: "template".to_owned(),
+        aud: "admin".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+        roles: None,
+        permissions: None,
+    }
+}
+
+fn grant(admin_id: Uuid, action: &str, entity: &str) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("user_id".to_owned(), Value::from(admin_id)),
+        ("action".to_owned(), Value::from(action)),
+        ("entity".to_owned(), Value::from(entity)),
+    ])
+}
+
+fn assignment(user_id: Uuid, role_id: Uuid) -> admin_users_roles::Model {
+    admin_users_roles::Model {
+        admin_user_id: user_id,
+        role_admin_id: role_id,
+        site_id: None,
+    }
+}
+
+fn denial(admin_id: Uuid) -> admin_audit_log::Model {
+    admin_audit_log::Model {
+        id: Uuid::new_v4(),
+        actor_id: Some(admin_id),
+        action: "grantRolePermission".to_owned(),
+        entity: "Ressource::Role".to_owned(),
+        target_id: None,
+        outcome: "denied".to_owned(),
+        changes: None,
+        ip_address: None,
+        user_agent: None,
+        created_at: Utc::now(),
+    }
+}
+
+async fn change_role_permission(db: MockDatabase, admin_id: Uuid, field: &str, role_id: Uuid, action: &str, entity: &str) -> (Response, Arc<DatabaseConnection>) {
+    let db = Arc::new(db.into_connection());
+    let schema = Schema::build(AdminRoleQuery, AdminRoleMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let mutation = format!(
+        r#"mutation {{ {}(input: {{ roleId: "{}", action: "{}", entity: "{}" }}) {{ role {{ id }} }} }}"#,
+        field, role_id, action, entity
+    );
+    let response = schema.execute(Request::new(mutation).data(claims(admin_id))).await;
+    (response, db)
+}
+
+fn transaction_log(db: Arc<DatabaseConnection>) -> String {
+    let db = Arc::try_unwrap(db).unwrap_or_else(|_| panic!("Connection still shared"));
+    format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"")
+}
+
+fn error_message(response: &Response) -> Option<String> {
+    let extensions = response.errors.first()?.extensions.as_ref()?;
+    extensions.get("message").map(|message| message.to_string())
+}
+
+#[tokio::test]
+async fn test_grant_permission_not_held_refused() {
+    let (admin_id, role_id) = (Uuid::new_v4(), Uuid::new_v4());
+    // A role editor cannot hand out the MFA reset they do not hold
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::Role")]])
+        .append_query_results([Vec::<admin_users_roles::Model>::new()])
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::Role")]])
+        .append_query_results([vec![denial(admin_id)]]);
+
+    let (response, db) = change_role_permission(db, admin_id, "grantRolePermission", role_id, "can_delete", "Ressource::UserMfa").await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(log.contains("can_delete on Ressource::UserMfa"), "Refusal not audited: {}", log);
+    assert!(!log.contains(r#"INSERT INTO "admin_roles_permissions_entities""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_grant_permission_on_own_role_refused() {
+    let (admin_id, role_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::Role")]])
+        .append_query_results([vec![assignment(admin_id, role_id)]])
+        .append_query_results([vec![denial(admin_id)]]);
+
+    let (response, db) = change_role_permission(db, admin_id, "grantRolePermission", role_id, "can_update", "Ressource::Role").await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(log.contains("own role"), "Refusal not audited: {}", log);
+    assert!(!log.contains(r#"INSERT INTO "admin_roles_permissions_entities""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_revoke_permission_not_held_refused() {
+    let (admin_id, role_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::Role")]])
+        .append_query_results([Vec::<admin_users_roles::Model>::new()])
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::Role")]])
+        .append_query_results([vec![denial(admin_id)]]);
+
+    let (response, db) = change_role_permission(db, admin_id, "revokeRolePermission", role_id, "can_read", "Ressource::AuditLog").await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(!log.contains(r#"DELETE FROM "admin_roles_permissions_entities""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_revoke_permission_on_own_role_refused() {
+    let (admin_id, role_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::Role")]])
+        .append_query_results([vec![assignment(admin_id, role_id)]])
+        .append_query_results([vec![denial(admin_id)]]);
+
+    let (response, db) = change_role_permission(db, admin_id, "revokeRolePermission", role_id, "can_update", "Ressource::Role").await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(!log.contains(r#"DELETE FROM "admin_roles_permissions_entities""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_grant_permission_held_passes_the_bounds() {
+    let (admin_id, role_id) = (Uuid::new_v4(), Uuid::new_v4());
+    // Past the bounds, the unknown role is reported by the service
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::Role")]])
+        .append_query_results([Vec::<admin_users_roles::Model>::new()])
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::Role"), grant(admin_id, "can_read", "Ressource::Site")]])
+        .append_query_results([Vec::<admin_roles::Model>::new()]);
+
+    let (response, _) = change_role_permission(db, admin_id, "grantRolePermission", role_id, "can_read", "Ressource::Site").await;
+    assert_eq!(error_message(&response), Some("\"RESOURCE_NOT_FOUND\"".to_owned()));
+}
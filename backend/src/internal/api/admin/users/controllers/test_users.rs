@@ -0,0 +1,284 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use async_graphql::{futures_util::{FutureExt, StreamExt}, EmptySubscription, Request, Response, Schema};
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, MockExecResult, Value};
+use uuid::Uuid;
+
+use crate::internal::{
+    api::admin::users::{
+        controllers::users::{AdminUserMutation, AdminUserQuery, AdminUserSubscription},
+        models::{admin_audit_log, admin_roles, admin_users, admin_users_roles},
+        services::auth::Claims,
+    },
+    graphql::events::UserEvents,
+};
+
+fn claims(admin_id: Uuid) -> Claims {
+    Claims {
+        sub: admin_id,
+        iss: "template".to_owned(),
+        aud: "admin".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+        roles: None,
+        permissions: None,
+    }
+}
+
+fn grant(admin_id: Uuid, action: &str, entity: &str) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("user_id".to_owned(), Value::from(admin_id)),
+        ("action".to_owned(), Value::from(action)),
+        ("entity".to_owned(), Value::from(entity)),
+    ])
+}
+
+fn role_grant(action: &str, entity: &str) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("action".to_owned(), Value::from(action)),
+        ("entity".to_owned(), Value::from(entity)),
+    ])
+}
+
+fn admin(id: Uuid) -> admin_users::Model {
+    admin_users::Model {
+        id,
+        username: "test_admin".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "admin".to_owned(),
+        email: "admin@example.com".to_owned(),
+        password: "hash".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        email_verified_at: None,
+        deleted_at: None,
+    }
+}
+
+fn role(id: Uuid) -> admin_roles::Model {
+    admin_roles::Model {
+        id,
+        name: "Editors".to_owned(),
+        description: None,
+        require_mfa: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn audit_entry(admin_id: Uuid, outcome: &str) -> admin_audit_log::Model {
+    admin_audit_log::Model {
+        id: Uuid::new_v4(),
+        actor_id: Some(admin_id),
+        action: "assignRole".to_owned(),
+        entity: "Ressource::User".to_owned(),
+        target_id: None,
+        outcome: outcome.to_owned(),
+        changes: None,
+        ip_address: None,
+        user_agent: None,
+        created_at: Utc::now(),
+    }
+}
+
+async fn execute(db: MockDatabase, admin_id: Uuid, mutation: String) -> (Response, Arc<DatabaseConnection>) {
+    let db = Arc::new(db.into_connection());
+    let schema = Schema::build(AdminUserQuery, AdminUserMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let response = schema.execute(Request::new(mutation).data(claims(admin_id))).await;
+    (response, db)
+}
+
+async fn assign_role(db: MockDatabase, admin_id: Uuid, user_id: Uuid, role_id: Uuid) -> (Response, Arc<DatabaseConnection>) {
+    execute(db, admin_id, format!(r#"mutation {{ assignRole(userId: "{}", roleId: "{}") }}"#, user_id, role_id)).await
+}
+
+/// The target holds the sites, which the caller cannot manage.
+fn outranked_db(admin_id: Uuid, user_id: Uuid, required: &str) -> MockDatabase {
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, required, "Ressource::User")]])
+        .append_query_results([vec![grant(admin_id, required, "Ressource::User"), grant(user_id, "can_delete", "Ressource::Site")]])
+        .append_query_results([vec![audit_entry(admin_id, "denied")]])
+}
+
+fn transaction_log(db: Arc<DatabaseConnection>) -> String {
+    let db = Arc::try_unwrap(db).unwrap_or_else(|_| panic!("Connection still shared"));
+    format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"")
+}
+
+fn error_message(response: &Response) -> Option<String> {
+    let extensions = response.errors.first()?.extensions.as_ref()?;
+    extensions.get("message").map(|message| message.to_string())
+}
+
+#[tokio::test]
+async fn test_assign_role_to_oneself_refused() {
+    let admin_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User")]]);
+
+    let (response, db) = assign_role(db, admin_id, admin_id, Uuid::new_v4()).await;
+    assert_eq!(error_message(&response), Some("\"INVALID_INPUT\"".to_owned()));
+
+    // Only the guard ran
+    let log = transaction_log(db);
+    assert!(!log.contains(r#"INSERT INTO "admin_users_admin_roles""#), "Unexpected queries: {}", log);
+    assert!(!log.contains(r#""admin_roles_permissions_entities"."role_id" = "#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_assign_role_beyond_own_permissions_refused() {
+    let (admin_id, user_id, role_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    // The role grants the sites, which the caller cannot manage
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User")]])
+        .append_query_results([vec![role_grant("can_update", "Ressource::User"), role_grant("can_delete", "Ressource::Site")]])
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User")]])
+        .append_query_results([vec![audit_entry(admin_id, "denied")]]);
+
+    let (response, db) = assign_role(db, admin_id, user_id, role_id).await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(log.contains(r#"INSERT INTO "admin_audit_log""#), "Refusal not audited: {}", log);
+    assert!(log.contains("can_delete on Ressource::Site"), "Missing permission not recorded: {}", log);
+    assert!(!log.contains(r#"INSERT INTO "admin_users_admin_roles""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_assign_role_within_own_permissions() {
+    let (admin_id, user_id, role_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User")]])
+        .append_query_results([vec![role_grant("can_update", "Ressource::User")]])
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User"), grant(admin_id, "can_read", "Ressource::Site")]])
+        .append_query_results([vec![admin(user_id)]])
+        .append_query_results([vec![role(role_id)]])
+        .append_query_results([Vec::<admin_users_roles::Model>::new()])
+        .append_query_results([vec![admin_users_roles::Model { admin_user_id: user_id, role_admin_id: role_id, site_id: None }]])
+        .append_query_results([vec![audit_entry(admin_id, "success")]]);
+
+    let (response, db) = assign_role(db, admin_id, user_id, role_id).await;
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    assert_eq!(response.data.to_string(), "{assignRole: true}");
+
+    let log = transaction_log(db);
+    assert!(log.contains(r#"INSERT INTO "admin_users_admin_roles""#), "Role not assigned: {}", log);
+}
+
+#[tokio::test]
+async fn test_assign_role_to_higher_admin_refused() {
+    let (admin_id, user_id, role_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User")]])
+        .append_query_results([vec![role_grant("can_update", "Ressource::User")]])
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User"), grant(user_id, "can_delete", "Ressource::Site")]])
+        .append_query_results([vec![audit_entry(admin_id, "denied")]]);
+
+    let (response, db) = assign_role(db, admin_id, user_id, role_id).await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(log.contains("can_delete on Ressource::Site"), "Missing permission not recorded: {}", log);
+    assert!(!log.contains(r#"INSERT INTO "admin_users_admin_roles""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_update_higher_admin_refused() {
+    let (admin_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let mutation = format!(r#"mutation {{ updateAdminUser(input: {{ id: "{}", email: "taken@example.com" }}) {{ id }} }}"#, user_id);
+
+    let (response, db) = execute(outranked_db(admin_id, user_id, "can_update"), admin_id, mutation).await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(log.contains("can_delete on Ressource::Site"), "Missing permission not recorded: {}", log);
+    assert!(!log.contains(r#"UPDATE "admin_users""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_delete_higher_admin_refused() {
+    let (admin_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let mutation = format!(r#"mutation {{ deleteAdminUser(id: "{}") }}"#, user_id);
+
+    let (response, db) = execute(outranked_db(admin_id, user_id, "can_delete"), admin_id, mutation).await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(log.contains("can_delete on Ressource::Site"), "Missing permission not recorded: {}", log);
+    assert!(!log.contains(r#"UPDATE "admin_users""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_revoke_role_from_higher_admin_refused() {
+    let (admin_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let mutation = format!(r#"mutation {{ revokeRole(userId: "{}", roleId: "{}") }}"#, user_id, Uuid::new_v4());
+
+    let (response, db) = execute(outranked_db(admin_id, user_id, "can_update"), admin_id, mutation).await;
+    assert_eq!(error_message(&response), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(log.contains("can_delete on Ressource::Site"), "Missing permission not recorded: {}", log);
+    assert!(!log.contains(r#"DELETE FROM "admin_users_admin_roles""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_revoke_role_from_lower_admin() {
+    let (admin_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User")]])
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User"), grant(user_id, "can_update", "Ressource::User")]])
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .append_query_results([vec![audit_entry(admin_id, "success")]]);
+    let mutation = format!(r#"mutation {{ revokeRole(userId: "{}", roleId: "{}") }}"#, user_id, Uuid::new_v4());
+
+    let (response, db) = execute(db, admin_id, mutation).await;
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    assert_eq!(response.data.to_string(), "{revokeRole: true}");
+
+    let log = transaction_log(db);
+    assert!(log.contains(r#"DELETE FROM "admin_users_admin_roles""#), "Role not revoked: {}", log);
+}
+
+#[tokio::test]
+async fn test_restore_user_is_pushed_to_subscribers() {
+    let (admin_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let events = UserEvents::default();
+
+    let subscriptions = Schema::build(AdminUserQuery, AdminUserMutation, AdminUserSubscription)
+        .data(Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results([vec![grant(admin_id, "can_read", "Ressource::User")]])
+                .into_connection(),
+        ))
+        .data(events.clone())
+        .finish();
+    let live_claims = Claims { exp: Utc::now().timestamp() as usize + 60, ..claims(admin_id) };
+    let mut stream = subscriptions.execute_stream(Request::new("subscription { userRestored }").data(live_claims));
+    // Runs the guard and subscribes, nothing has happened yet
+    assert!(stream.next().now_or_never().is_none());
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_update", "Ressource::User")]])
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .append_query_results([vec![audit_entry(admin_id, "success")]]);
+    let mutations = Schema::build(AdminUserQuery, AdminUserMutation, EmptySubscription)
+        .data(Arc::new(db.into_connection()))
+        .data(events)
+        .finish();
+    let mutation = format!(r#"mutation {{ restoreUser(id: "{}") }}"#, user_id);
+    let response = mutations.execute(Request::new(mutation).data(claims(admin_id))).await;
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+
+    let pushed = tokio::time::timeout(Duration::from_secs(1), stream.next())
+        .await
+        .expect("The restore was not pushed")
+        .expect("The subscription ended");
+    assert!(pushed.errors.is_empty(), "Unexpected errors: {:?}", pushed.errors);
+    assert_eq!(pushed.data.into_json().unwrap()["userRestored"], user_id.to_string());
+}
@@ -1,10 +1,35 @@
-use std::sync::Arc;
+use std::time::Duration;
 use log::trace;
-use sea_orm::DatabaseConnection;
-use async_graphql::{Context, Object, SimpleObject};
+use async_graphql::{connection::query, futures_util::{Stream, StreamExt}, Context, Object, SimpleObject, Subscription};
 use uuid::Uuid;
 
-use crate::internal::api::admin::users::{errors::{db::AdminDbError, interface::CustomGraphQLError}, services::{auth::{ JwtTokenService, TokenService}, users::{AdminUserService, AdminUserServiceImpl, UserFilter}}};
+use serde_json::json;
+
+use super::{database, record_audit};
+
+use crate::internal::{
+    api::admin::users::{
+        errors::{db::AdminDbError, interface::CustomGraphQLError, validation::AdminValidationError},
+        guards::{permission::Permission, privileges::ensure_outranks},
+        models::admin_users,
+        services::{
+            audit::{diff, AuditOutcome},
+            permissions::{AdminPermissionService, AdminPermissionServiceImpl, PermissionSet},
+            users::{AdminUserService, AdminUserServiceImpl, CreateAdminUserInput, UpdateAdminUserInput, UserFilter},
+        },
+    },
+    api::users::services::{
+        gdpr::{GdprService, GdprServiceImpl},
+        users::{UserService, UserServiceImpl},
+    },
+    graphql::{
+        auth::admin_claims,
+        events::{publish_user_event, UserEvent, UserEvents},
+        site::current_site_id,
+        pagination::{into_connection, KeysetConnection, PageRequest, UserOrder},
+    },
+    security::throttle::{LoginThrottleService, LoginThrottleServiceImpl, ThrottleKey},
+};
 
 #[derive(SimpleObject)]
 pub struct UserAdmin {
@@ -15,66 +40,288 @@ pub struct UserAdmin {
     pub last_name: String,
 }
 
+impl From<admin_users::Model> for UserAdmin {
+    fn from(u: admin_users::Model) -> Self {
+        UserAdmin {
+            id: u.id,
+            username: u.username,
+            first_name: u.first_name,
+            last_name: u.last_name,
+            email: u.email,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct AdminUserQuery;
 
 #[Object]
 impl AdminUserQuery {
-    async fn users(&self, ctx: &Context<'_>, token: String, filter: Option<UserFilter>) -> async_graphql::Result<Vec<UserAdmin>> {
-        let claims = match JwtTokenService::verify_token(&token).await {
-            Ok(claims) => {
-                trace!("users: Token verified successfully, claims: {:?}", claims);
-                claims
+    #[graphql(guard = "Permission::new(\"can_read\", \"/admin/dashboard/users\")")]
+    #[allow(clippy::too_many_arguments)]
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<UserFilter>,
+        order_by: Option<UserOrder>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<KeysetConnection<UserAdmin>> {
+        let db = database(ctx)?;
+        let order = order_by.unwrap_or_default();
+        let with_total_count = ctx.look_ahead().field("totalCount").exists();
+
+        query(after, before, first, last, |after, before, first, last| async move {
+            let page = PageRequest::new(order, after, before, first, last)
+                .map_err(|e| e.new())?
+                .with_total_count(with_total_count);
+
+            match AdminUserServiceImpl::get_all_users(db, filter, &page).await {
+                Ok(users) => {
+                    trace!("users: Users found: {:?}", users.items);
+                    Ok(into_connection::<admin_users::Entity, _>(users, order.field, UserAdmin::from))
+                },
+                Err(e) => {
+                    Err(e.new())
+                }
+            }
+        })
+        .await
+    }
+}
+
+#[derive(Default)]
+pub struct AdminUserMutation;
+
+#[Object]
+impl AdminUserMutation {
+    #[graphql(guard = "Permission::new(\"can_create\", \"Ressource::User\")")]
+    async fn create_admin_user(&self, ctx: &Context<'_>, input: CreateAdminUserInput) -> async_graphql::Result<UserAdmin> {
+        let db = database(ctx)?;
+
+        match AdminUserServiceImpl::create_user(db.as_ref(), input).await {
+            Ok(user) => {
+                trace!("create_admin_user: Admin user created: {:?}", user.id);
+                record_audit(ctx, "Ressource::User", Some(user.id), AuditOutcome::Success, diff::<admin_users::Model, _>(None, Some(&user))).await;
+                publish_user_event(ctx, UserEvent::Created(user.clone()));
+                Ok(user.into())
             },
-            Err(e) => {
-                return Err(e.new());
+            Err(e) => Err(e.new()),
+        }
+    }
+
+    /// Only for an admin the caller outranks: every permission they hold on the site must be
+    /// among the caller's.
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::User\")")]
+    async fn update_admin_user(&self, ctx: &Context<'_>, input: UpdateAdminUserInput) -> async_graphql::Result<UserAdmin> {
+        let db = database(ctx)?;
+        ensure_outranks(ctx, db.as_ref(), input.id, current_site_id(ctx), &PermissionSet::default()).await?;
+        let before = AdminUserServiceImpl::get_user_by_id(db.as_ref(), input.id).await.map_err(|e| e.new())?;
+
+        match AdminUserServiceImpl::update_user(db.as_ref(), input).await {
+            Ok(user) => {
+                trace!("update_admin_user: Admin user updated: {:?}", user.id);
+                record_audit(ctx, "Ressource::User", Some(user.id), AuditOutcome::Success, diff(Some(&before), Some(&user))).await;
+                publish_user_event(ctx, UserEvent::Updated(user.clone()));
+                Ok(user.into())
+            },
+            Err(e) => Err(e.new()),
+        }
+    }
+
+    /// Only for an admin the caller outranks: every permission they hold on the site must be
+    /// among the caller's.
+    #[graphql(guard = "Permission::new(\"can_delete\", \"Ressource::User\")")]
+    async fn delete_admin_user(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let claims = admin_claims(ctx)?;
+        if claims.sub == id {
+            return Err(AdminValidationError::invalid_field("id", "An admin user cannot delete their own account.").new());
+        }
+
+        let db = database(ctx)?;
+        ensure_outranks(ctx, db.as_ref(), id, current_site_id(ctx), &PermissionSet::default()).await?;
+        let before = AdminUserServiceImpl::get_user_by_id(db.as_ref(), id).await.map_err(|e| e.new())?;
+
+        let deleted = AdminUserServiceImpl::delete_user(db.as_ref(), id).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::User", Some(id), AuditOutcome::Success, diff::<_, admin_users::Model>(Some(&before), None)).await;
+        if deleted {
+            publish_user_event(ctx, UserEvent::Deleted(id));
+        }
+        Ok(deleted)
+    }
+
+    /// Brings back a deleted admin user, roles and grants included, until it is purged.
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::User\")")]
+    async fn restore_admin_user(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+
+        let restored = AdminUserServiceImpl::restore_user(db.as_ref(), id).await.map_err(|e| e.new())?;
+        if restored {
+            record_audit(ctx, "Ressource::User", Some(id), AuditOutcome::Success, None).await;
+            // Back in the listings, as if it had just been created
+            if let Ok(user) = AdminUserServiceImpl::get_user_by_id(db.as_ref(), id).await {
+                publish_user_event(ctx, UserEvent::Created(user));
             }
-        };
+        }
+        Ok(restored)
+    }
+
+    /// Brings back a deleted end user until it is purged.
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::User\")")]
+    async fn restore_user(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
 
-        let db = match ctx.data::<Arc<DatabaseConnection>>() {
-            Ok(db) => {
-                trace!("users: Database connection found for user {:?}", claims.sub);
-                db
+        let restored = UserServiceImpl::restore_user(db.as_ref(), id)
+            .await
+            .map_err(|e| (Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>).new())?;
+        if restored {
+            record_audit(ctx, "Ressource::User", Some(id), AuditOutcome::Success, None).await;
+            publish_user_event(ctx, UserEvent::EndUserRestored(id));
+        }
+        Ok(restored)
+    }
+
+    /// Anonymises an end user and their addresses for an erasure request, and deletes the
+    /// account. Memberships and audit entries are kept and keep pointing at the row.
+    #[graphql(guard = "Permission::new(\"can_delete\", \"Ressource::User\")")]
+    async fn erase_user(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+
+        let addresses = GdprServiceImpl::erase_user(db.as_ref(), id).await.map_err(|e| e.new())?;
+        // Which data went, never the data itself
+        let changes = json!({
+            "erased": ["username", "first_name", "last_name", "email", "password"],
+            "addresses": addresses,
+        });
+        record_audit(ctx, "Ressource::User", Some(id), AuditOutcome::Success, Some(changes)).await;
+        publish_user_event(ctx, UserEvent::EndUserErased(id));
+        Ok(true)
+    }
+
+    /// Only grants what the caller already holds on the site, to an admin they outrank there:
+    /// every permission of the role must be among theirs, and nobody assigns roles to themselves.
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::User\")")]
+    async fn assign_role(&self, ctx: &Context<'_>, user_id: Uuid, role_id: Uuid, site_id: Option<Uuid>) -> async_graphql::Result<bool> {
+        let claims = admin_claims(ctx)?;
+        if claims.sub == user_id {
+            return Err(AdminValidationError::invalid_field("user_id", "An admin user cannot assign a role to themselves.").new());
+        }
+
+        let db = database(ctx)?;
+        let granted = AdminPermissionServiceImpl::get_role_permissions(db.as_ref(), role_id).await.map_err(|e| e.new())?;
+        ensure_outranks(ctx, db.as_ref(), user_id, site_id, &granted).await?;
+
+        let assigned = AdminUserServiceImpl::assign_role(db.as_ref(), user_id, role_id, site_id).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::User", Some(user_id), AuditOutcome::Success, Some(json!({ "role_id": role_id, "site_id": site_id }))).await;
+        Ok(assigned)
+    }
+
+    /// Lifts the lockout of an admin account after too many failed logins. Lockouts of
+    /// client addresses are left to expire.
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::User\")")]
+    async fn unlock_admin_user(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let user = AdminUserServiceImpl::get_user_by_id(db.as_ref(), id).await.map_err(|e| e.new())?;
+
+        let unlocked = LoginThrottleServiceImpl::reset(db.as_ref(), &ThrottleKey::account("admin", &user.email))
+            .await
+            .map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::User", Some(id), AuditOutcome::Success, None).await;
+        Ok(unlocked)
+    }
+
+    /// Only for an admin the caller outranks: every permission they hold on the site must be
+    /// among the caller's.
+    #[graphql(guard = "Permission::new(\"can_update\", \"Ressource::User\")")]
+    async fn revoke_role(&self, ctx: &Context<'_>, user_id: Uuid, role_id: Uuid) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        ensure_outranks(ctx, db.as_ref(), user_id, current_site_id(ctx), &PermissionSet::default()).await?;
+
+        let revoked = AdminUserServiceImpl::revoke_role(db.as_ref(), user_id, role_id).await.map_err(|e| e.new())?;
+        record_audit(ctx, "Ressource::User", Some(user_id), AuditOutcome::Success, Some(json!({ "role_id": role_id }))).await;
+        Ok(revoked)
+    }
+}
+
+/// Live changes to the admin users, for the dashboard table to follow without polling.
+/// The permission is checked when a subscription starts, and the subscription ends when
+/// the token of the connection expires.
+#[derive(Default)]
+pub struct AdminUserSubscription;
+
+impl AdminUserSubscription {
+    fn events(ctx: &Context<'_>) -> async_graphql::Result<impl Stream<Item = UserEvent>> {
+        let expires_in = Duration::from_secs(admin_claims(ctx)?.expires_in());
+        let events = ctx.data::<UserEvents>()?.subscribe();
+        Ok(events.take_until(tokio::time::sleep(expires_in)))
+    }
+}
+
+#[Subscription]
+impl AdminUserSubscription {
+    #[graphql(guard = "Permission::new(\"can_read\", \"/admin/dashboard/users\")")]
+    async fn admin_user_created(&self, ctx: &Context<'_>) -> async_graphql::Result<impl Stream<Item = UserAdmin>> {
+        Ok(Self::events(ctx)?.filter_map(|event| async move {
+            match event {
+                UserEvent::Created(user) => Some(user.into()),
+                _ => None,
             }
-            Err(e) => {
-                return Err(
-                    (Box::new(AdminDbError::DatabaseError(format!("{:?}", e))) as Box<dyn CustomGraphQLError>).new()
-                );
+        }))
+    }
+
+    #[graphql(guard = "Permission::new(\"can_read\", \"/admin/dashboard/users\")")]
+    async fn admin_user_updated(&self, ctx: &Context<'_>) -> async_graphql::Result<impl Stream<Item = UserAdmin>> {
+        Ok(Self::events(ctx)?.filter_map(|event| async move {
+            match event {
+                UserEvent::Updated(user) => Some(user.into()),
+                _ => None,
             }
-        };
+        }))
+    }
 
-        let _ = match AdminUserServiceImpl::get_user_permissions_from_role(db.as_ref(), claims.sub, "can_read", "/admin/dashboard/users").await {
-            Ok(_) => {
-                trace!("users: User {:?} has permission to read admin home", claims.sub);
-            },
-            Err(_) => {
-                trace!("users: User {:?} doesn't have permission from role, checking user permissions", claims.sub);
-                match AdminUserServiceImpl::get_user_permissions_from_user(db.as_ref(), claims.sub, "can_read", "/admin/dashboard/users").await {
-                    Ok(_) => {
-                        trace!("users: User {:?} has permission to read admin home", claims.sub);
-                    },
-                    Err(e) => {
-                        trace!("users: User {:?} doesn't have permission to read admin home", claims.sub);
-                        return Err(e.new());
-                    }
-                }
+    /// Streams the ids of the deleted admin users.
+    #[graphql(guard = "Permission::new(\"can_read\", \"/admin/dashboard/users\")")]
+    async fn admin_user_deleted(&self, ctx: &Context<'_>) -> async_graphql::Result<impl Stream<Item = Uuid>> {
+        Ok(Self::events(ctx)?.filter_map(|event| async move {
+            match event {
+                UserEvent::Deleted(id) => Some(id),
+                _ => None,
             }
-        };
-
-        match AdminUserServiceImpl::get_all_users(db.as_ref(), filter).await {
-            Ok(users) => {
-                trace!("users: Users found: {:?}", users);
-                Ok(users.into_iter().map(|u| UserAdmin {
-                    id: u.id,
-                    username: u.username,
-                    first_name: u.first_name,
-                    last_name: u.last_name,
-                    email: u.email,
-                }).collect())
-            },
-            Err(e) => {
-                Err(e.new())
+        }))
+    }
+
+    /// Streams the ids of the deleted end users.
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::User\")")]
+    async fn user_deleted(&self, ctx: &Context<'_>) -> async_graphql::Result<impl Stream<Item = Uuid>> {
+        Ok(Self::events(ctx)?.filter_map(|event| async move {
+            match event {
+                UserEvent::EndUserDeleted(id) => Some(id),
+                _ => None,
             }
-        }
+        }))
+    }
+
+    /// Streams the ids of the restored end users.
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::User\")")]
+    async fn user_restored(&self, ctx: &Context<'_>) -> async_graphql::Result<impl Stream<Item = Uuid>> {
+        Ok(Self::events(ctx)?.filter_map(|event| async move {
+            match event {
+                UserEvent::EndUserRestored(id) => Some(id),
+                _ => None,
+            }
+        }))
+    }
+
+    /// Streams the ids of the erased end users.
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::User\")")]
+    async fn user_erased(&self, ctx: &Context<'_>) -> async_graphql::Result<impl Stream<Item = Uuid>> {
+        Ok(Self::events(ctx)?.filter_map(|event| async move {
+            match event {
+                UserEvent::EndUserErased(id) => Some(id),
+                _ => None,
+            }
+        }))
     }
 }
@@ -1,7 +1,7 @@
 use actix_web::http::StatusCode;
 use async_graphql::{Error, ErrorExtensions};
 use jsonwebtoken::errors::ErrorKind;
-use log::{error, info};
+use log::{error, info, warn};
 use thiserror::Error;
 
 use super::interface::CustomGraphQLError;
@@ -16,6 +16,18 @@ pub enum AuthTokenError {
     
     #[error("Invalid token")]
     InvalidToken,
+
+    #[error("Missing token")]
+    MissingToken,
+
+    #[error("Invalid refresh token")]
+    InvalidRefreshToken,
+
+    #[error("Refresh token reused")]
+    RefreshTokenReused,
+
+    #[error("Invalid account token")]
+    InvalidAccountToken,
 }
 
 impl CustomGraphQLError for AuthTokenError {
@@ -39,12 +51,28 @@ impl CustomGraphQLError for AuthTokenError {
             AuthTokenError::InvalidToken => {
                 info!("Invalid token");
             }
+            AuthTokenError::MissingToken => {
+                info!("Missing token");
+            }
+            AuthTokenError::InvalidRefreshToken => {
+                info!("Invalid refresh token");
+            }
+            AuthTokenError::RefreshTokenReused => {
+                warn!("Refresh token reused, token family revoked");
+            }
+            AuthTokenError::InvalidAccountToken => {
+                info!("Invalid, used or expired account token");
+            }
         }
 
         Error::new(match self {
             AuthTokenError::JwtError(_) => "An internal error occurred during token validation.",
             AuthTokenError::TokenExpired => "The authentication token has expired.",
             AuthTokenError::InvalidToken => "The token provided is invalid.",
+            AuthTokenError::MissingToken => "An authentication token is required.",
+            AuthTokenError::InvalidRefreshToken => "The refresh token provided is invalid or expired.",
+            AuthTokenError::RefreshTokenReused => "The refresh token has already been used, the session has been revoked.",
+            AuthTokenError::InvalidAccountToken => "The link is invalid, has already been used or has expired.",
         })
         .extend_with(|_err, extensions| {
             match self {
@@ -60,6 +88,22 @@ impl CustomGraphQLError for AuthTokenError {
                     extensions.set("code", StatusCode::UNAUTHORIZED.as_u16()); // HTTP 401
                     extensions.set("message", "INVALID_TOKEN");
                 }
+                AuthTokenError::MissingToken => {
+                    extensions.set("code", StatusCode::UNAUTHORIZED.as_u16()); // HTTP 401
+                    extensions.set("message", "MISSING_TOKEN");
+                }
+                AuthTokenError::InvalidRefreshToken => {
+                    extensions.set("code", StatusCode::UNAUTHORIZED.as_u16()); // HTTP 401
+                    extensions.set("message", "INVALID_REFRESH_TOKEN");
+                }
+                AuthTokenError::RefreshTokenReused => {
+                    extensions.set("code", StatusCode::UNAUTHORIZED.as_u16()); // HTTP 401
+                    extensions.set("message", "REFRESH_TOKEN_REUSED");
+                }
+                AuthTokenError::InvalidAccountToken => {
+                    extensions.set("code", StatusCode::BAD_REQUEST.as_u16()); // HTTP 400
+                    extensions.set("message", "INVALID_ACCOUNT_TOKEN");
+                }
             }
         })
     }
@@ -1,6 +1,6 @@
 use actix_web::http::StatusCode;
 use async_graphql::{Error, ErrorExtensions};
-use log::{error, info};
+use log::info;
 use thiserror::Error;
 
 use super::interface::CustomGraphQLError;
@@ -9,6 +9,9 @@ use super::interface::CustomGraphQLError;
 pub enum AdminEntityError {
     #[error("Resource not found: {0}")]
     NotFound(String),
+
+    #[error("Entity declared by the application: {0}")]
+    Declared(String),
 }
 
 impl CustomGraphQLError for AdminEntityError {
@@ -17,10 +20,14 @@ impl CustomGraphQLError for AdminEntityError {
             AdminEntityError::NotFound(resource) => {
                 info!("Entity not found: {}", resource);
             }
+            AdminEntityError::Declared(name) => {
+                info!("Entity '{}' is declared by the application", name);
+            }
         }
 
         Error::new(match self {
             AdminEntityError::NotFound(_) => "The requested resource does not exist.",
+            AdminEntityError::Declared(_) => "The entity is declared by the application and cannot be renamed or deleted.",
         })
         .extend_with(|_err, extensions| {
             match self {
@@ -28,6 +35,10 @@ impl CustomGraphQLError for AdminEntityError {
                     extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
                     extensions.set("message", "RESOURCE_NOT_FOUND");
                 }
+                AdminEntityError::Declared(_) => {
+                    extensions.set("code", StatusCode::CONFLICT.as_u16()); // HTTP 409
+                    extensions.set("message", "ENTITY_DECLARED");
+                }
             }
         })
     }
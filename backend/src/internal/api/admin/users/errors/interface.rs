@@ -1,5 +1,6 @@
 use std::error::Error as Error;
 
 pub trait CustomGraphQLError: Error + Send + Sync {
+    #[allow(clippy::new_ret_no_self, clippy::wrong_self_convention)]
     fn new(&self) -> async_graphql::Error;
 }
@@ -0,0 +1,78 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::info;
+use thiserror::Error;
+
+use super::interface::CustomGraphQLError;
+
+#[derive(Error, Debug)]
+pub enum AdminMfaError {
+    #[error("Invalid MFA code for admin user {0}")]
+    InvalidCode(String),
+
+    #[error("Admin user {0} has no confirmed second factor")]
+    NotEnrolled(String),
+
+    #[error("Admin user {0} already has a second factor")]
+    AlreadyEnabled(String),
+
+    #[error("A role of admin user {0} requires a second factor")]
+    Required(String),
+
+    #[error("Invalid MFA challenge")]
+    InvalidChallenge,
+}
+
+impl CustomGraphQLError for AdminMfaError {
+    fn new(&self) -> Error {
+        match &self {
+            AdminMfaError::InvalidCode(user) => {
+                info!("Invalid MFA code for admin user {}", user);
+            }
+            AdminMfaError::NotEnrolled(user) => {
+                info!("Admin user {} has no confirmed second factor", user);
+            }
+            AdminMfaError::AlreadyEnabled(user) => {
+                info!("Admin user {} already has a second factor", user);
+            }
+            AdminMfaError::Required(user) => {
+                info!("A role of admin user {} requires a second factor", user);
+            }
+            AdminMfaError::InvalidChallenge => {
+                info!("Invalid MFA challenge");
+            }
+        }
+
+        Error::new(match self {
+            AdminMfaError::InvalidCode(_) => "The authentication code is invalid.",
+            AdminMfaError::NotEnrolled(_) => "Two-factor authentication is not enabled.",
+            AdminMfaError::AlreadyEnabled(_) => "Two-factor authentication is already enabled.",
+            AdminMfaError::Required(_) => "Two-factor authentication is required by one of your roles.",
+            AdminMfaError::InvalidChallenge => "The login challenge is invalid or expired, please log in again.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                AdminMfaError::InvalidCode(_) => {
+                    extensions.set("code", StatusCode::UNAUTHORIZED.as_u16()); // HTTP 401
+                    extensions.set("message", "INVALID_MFA_CODE");
+                }
+                AdminMfaError::NotEnrolled(_) => {
+                    extensions.set("code", StatusCode::BAD_REQUEST.as_u16()); // HTTP 400
+                    extensions.set("message", "MFA_NOT_ENROLLED");
+                }
+                AdminMfaError::AlreadyEnabled(_) => {
+                    extensions.set("code", StatusCode::CONFLICT.as_u16()); // HTTP 409
+                    extensions.set("message", "MFA_ALREADY_ENABLED");
+                }
+                AdminMfaError::Required(_) => {
+                    extensions.set("code", StatusCode::FORBIDDEN.as_u16()); // HTTP 403
+                    extensions.set("message", "MFA_REQUIRED");
+                }
+                AdminMfaError::InvalidChallenge => {
+                    extensions.set("code", StatusCode::UNAUTHORIZED.as_u16()); // HTTP 401
+                    extensions.set("message", "INVALID_MFA_CHALLENGE");
+                }
+            }
+        })
+    }
+}
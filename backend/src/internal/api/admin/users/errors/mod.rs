@@ -4,4 +4,8 @@ pub mod permission;
 pub mod user;
 pub mod action;
 pub mod entity;
+pub mod role;
+pub mod site;
+pub mod mfa;
+pub mod validation;
 pub mod interface;
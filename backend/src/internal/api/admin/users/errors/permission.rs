@@ -1,6 +1,6 @@
 use actix_web::http::StatusCode;
 use async_graphql::{Error, ErrorExtensions};
-use log::{error, info};
+use log::info;
 use thiserror::Error;
 
 use super::interface::CustomGraphQLError;
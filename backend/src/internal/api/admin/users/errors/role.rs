@@ -0,0 +1,34 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::info;
+use thiserror::Error;
+
+use super::interface::CustomGraphQLError;
+
+#[derive(Error, Debug)]
+pub enum AdminRoleError {
+    #[error("Resource not found: {0}")]
+    NotFound(String),
+}
+
+impl CustomGraphQLError for AdminRoleError {
+    fn new(&self) -> Error {
+        match &self {
+            AdminRoleError::NotFound(resource) => {
+                info!("Role not found: {}", resource);
+            }
+        }
+
+        Error::new(match self {
+            AdminRoleError::NotFound(_) => "The requested resource does not exist.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                AdminRoleError::NotFound(_) => {
+                    extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
+                    extensions.set("message", "RESOURCE_NOT_FOUND");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::info;
+use thiserror::Error;
+
+use super::interface::CustomGraphQLError;
+
+#[derive(Error, Debug)]
+pub enum AdminSiteError {
+    #[error("Site not found: {0}")]
+    NotFound(String),
+
+    #[error("Site {0} still has users")]
+    InUse(String),
+}
+
+impl CustomGraphQLError for AdminSiteError {
+    fn new(&self) -> Error {
+        match &self {
+            AdminSiteError::NotFound(site) => {
+                info!("Site not found: {}", site);
+            }
+            AdminSiteError::InUse(site) => {
+                info!("Site {} still has users", site);
+            }
+        }
+
+        Error::new(match self {
+            AdminSiteError::NotFound(_) => "The requested site does not exist.",
+            AdminSiteError::InUse(_) => "The site still has users and cannot be deleted.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                AdminSiteError::NotFound(_) => {
+                    extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
+                    extensions.set("message", "SITE_NOT_FOUND");
+                }
+                AdminSiteError::InUse(_) => {
+                    extensions.set("code", StatusCode::CONFLICT.as_u16()); // HTTP 409
+                    extensions.set("message", "SITE_IN_USE");
+                }
+            }
+        })
+    }
+}
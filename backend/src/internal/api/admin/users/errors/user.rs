@@ -10,8 +10,12 @@ pub enum AdminUserAuthError {
     #[error("User not found: {0}")]
     UserNotFound(String),
 
-    #[error("Invalid password")]
-    InvalidPassword,
+    /// Wrong password or unknown email, told apart in the logs only.
+    #[error("Invalid credentials")]
+    InvalidCredentials,
+
+    #[error("Too many failed logins, retry in {0}s")]
+    TooManyAttempts(i64),
 
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
@@ -23,8 +27,11 @@ impl CustomGraphQLError for AdminUserAuthError {
             AdminUserAuthError::UserNotFound(user) => {
                 info!("User not found: {}", user);
             }
-            AdminUserAuthError::InvalidPassword => {
-                info!("Password mismatch");
+            AdminUserAuthError::InvalidCredentials => {
+                info!("Invalid credentials");
+            }
+            AdminUserAuthError::TooManyAttempts(retry_after) => {
+                info!("Login locked, retry in {}s", retry_after);
             }
             AdminUserAuthError::UnexpectedError(msg) => {
                 error!("Unexpected error: {}", msg);
@@ -33,7 +40,8 @@ impl CustomGraphQLError for AdminUserAuthError {
 
         Error::new(match self {
             AdminUserAuthError::UserNotFound(_) => "The requested user does not exist.",
-            AdminUserAuthError::InvalidPassword => "Invalid credentials.",
+            AdminUserAuthError::InvalidCredentials => "Invalid credentials.",
+            AdminUserAuthError::TooManyAttempts(_) => "Too many failed login attempts, please retry later.",
             AdminUserAuthError::UnexpectedError(_) => "An unexpected internal error occurred.",
         })
         .extend_with(|_err, extensions| {
@@ -42,9 +50,14 @@ impl CustomGraphQLError for AdminUserAuthError {
                     extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
                     extensions.set("message", "USER_NOT_FOUND");
                 }
-                AdminUserAuthError::InvalidPassword => {
+                AdminUserAuthError::InvalidCredentials => {
                     extensions.set("code", StatusCode::UNAUTHORIZED.as_u16()); // HTTP 401
-                    extensions.set("message", "INVALID_PASSWORD");
+                    extensions.set("message", "INVALID_CREDENTIALS");
+                }
+                AdminUserAuthError::TooManyAttempts(retry_after) => {
+                    extensions.set("code", StatusCode::TOO_MANY_REQUESTS.as_u16()); // HTTP 429
+                    extensions.set("message", "TOO_MANY_ATTEMPTS");
+                    extensions.set("retry_after", *retry_after);
                 }
                 AdminUserAuthError::UnexpectedError(_) => {
                     extensions.set("code", StatusCode::INTERNAL_SERVER_ERROR.as_u16()); // HTTP 500
@@ -0,0 +1,55 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::info;
+use thiserror::Error;
+
+use super::interface::CustomGraphQLError;
+
+#[derive(Error, Debug)]
+pub enum AdminValidationError {
+    #[error("Invalid field '{field}': {reason}")]
+    InvalidField { field: String, reason: String },
+
+    #[error("Already exists: {0}")]
+    AlreadyExists(String),
+}
+
+impl AdminValidationError {
+    pub fn invalid_field(field: &str, reason: &str) -> Self {
+        AdminValidationError::InvalidField {
+            field: field.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl CustomGraphQLError for AdminValidationError {
+    fn new(&self) -> Error {
+        match &self {
+            AdminValidationError::InvalidField { field, reason } => {
+                info!("Invalid field '{}': {}", field, reason);
+            }
+            AdminValidationError::AlreadyExists(resource) => {
+                info!("Already exists: {}", resource);
+            }
+        }
+
+        Error::new(match self {
+            AdminValidationError::InvalidField { reason, .. } => reason.clone(),
+            AdminValidationError::AlreadyExists(_) => "The resource already exists.".to_string(),
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                AdminValidationError::InvalidField { field, .. } => {
+                    extensions.set("code", StatusCode::BAD_REQUEST.as_u16()); // HTTP 400
+                    extensions.set("message", "INVALID_INPUT");
+                    extensions.set("field", field.as_str());
+                }
+                AdminValidationError::AlreadyExists(_) => {
+                    extensions.set("code", StatusCode::CONFLICT.as_u16()); // HTTP 409
+                    extensions.set("message", "ALREADY_EXISTS");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,4 @@
+pub mod permission;
+pub mod privileges;
+#[cfg(test)]
+mod test_permission;
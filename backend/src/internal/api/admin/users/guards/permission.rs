@@ -0,0 +1,42 @@
+use async_graphql::{Context, Guard};
+use serde_json::json;
+
+use crate::internal::api::admin::users::{
+    controllers::record_audit,
+    errors::{interface::CustomGraphQLError, permission::AdminPermissionError},
+    loaders::permissions::has_permission,
+    services::audit::AuditOutcome,
+};
+
+/// Field guard granting access when the authenticated admin holds `action` on `entity`,
+/// either through one of their roles or through a direct user grant. Refusals are
+/// written to the audit log.
+///
+/// ```ignore
+/// #[graphql(guard = "Permission::new(\"can_read\", \"/admin/dashboard/users\")")]
+/// async fn users(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<UserAdmin>> { ... }
+/// ```
+pub struct Permission {
+    action: String,
+    entity: String,
+}
+
+impl Permission {
+    pub fn new(action: impl Into<String>, entity: impl Into<String>) -> Self {
+        Permission {
+            action: action.into(),
+            entity: entity.into(),
+        }
+    }
+}
+
+impl Guard for Permission {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        if has_permission(ctx, &self.action, &self.entity).await? {
+            Ok(())
+        } else {
+            record_audit(ctx, &self.entity, None, AuditOutcome::Denied, Some(json!({ "required": self.action }))).await;
+            Err(AdminPermissionError::PermissionDenied(format!("{} on {}", self.action, self.entity)).new())
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use async_graphql::Context;
+use sea_orm::DatabaseConnection;
+use serde_json::{json, Value as Json};
+use uuid::Uuid;
+
+use crate::internal::{
+    api::admin::users::{
+        controllers::record_audit,
+        errors::{interface::CustomGraphQLError, permission::AdminPermissionError},
+        services::{
+            audit::AuditOutcome,
+            permissions::{AdminPermissionService, AdminPermissionServiceImpl, PermissionSet},
+            users::{AdminUserService, AdminUserServiceImpl},
+        },
+    },
+    graphql::auth::admin_claims,
+};
+
+// Bounds on top of the field guards of the mutations that hand out permissions: an admin
+// only ever grants what they hold themselves. Refusals are audited like the guard's.
+
+async fn refuse(ctx: &Context<'_>, entity: &str, target_id: Option<Uuid>, changes: Json, reason: String) -> async_graphql::Error {
+    record_audit(ctx, entity, target_id, AuditOutcome::Denied, Some(changes)).await;
+    AdminPermissionError::PermissionDenied(reason).new()
+}
+
+/// Fails unless the caller holds `action` on `entity` on every site, since a role grant
+/// applies wherever the role is assigned.
+pub async fn ensure_holds_everywhere(ctx: &Context<'_>, db: &DatabaseConnection, role_id: Uuid, action: &str, entity: &str) -> async_graphql::Result<()> {
+    let caller = admin_claims(ctx)?.sub;
+    let held = AdminPermissionServiceImpl::get_effective_permissions(db, &[caller], None)
+        .await
+        .map_err(|e| e.new())?
+        .remove(&caller)
+        .unwrap_or_default();
+
+    if held.contains(action, entity) {
+        return Ok(());
+    }
+    let missing = format!("{} on {}", action, entity);
+    Err(refuse(ctx, "Ressource::Role", Some(role_id), json!({ "missing": [missing] }), format!("changing role {} requires {}", role_id, missing)).await)
+}
+
+/// Fails when the caller holds the role, whatever the site: nobody edits the
+/// permissions of their own role.
+pub async fn ensure_role_not_held(ctx: &Context<'_>, db: &DatabaseConnection, role_id: Uuid) -> async_graphql::Result<()> {
+    let caller = admin_claims(ctx)?.sub;
+    let held = AdminUserServiceImpl::get_user_roles(db, caller)
+        .await
+        .map_err(|e| e.new())?
+        .iter()
+        .any(|assignment| assignment.role_admin_id == role_id);
+
+    if !held {
+        return Ok(());
+    }
+    Err(refuse(ctx, "Ressource::Role", Some(role_id), json!({ "reason": "own role" }), format!("role {} is held by the caller", role_id)).await)
+}
+
+/// Fails unless the caller holds on the site every permission `target_id` holds there, plus
+/// the ones being `granted` to them: nobody manages an admin who outranks them, nor raises
+/// one above themselves.
+pub async fn ensure_outranks(ctx: &Context<'_>, db: &DatabaseConnection, target_id: Uuid, site_id: Option<Uuid>, granted: &PermissionSet) -> async_graphql::Result<()> {
+    let caller = admin_claims(ctx)?.sub;
+    let mut effective = AdminPermissionServiceImpl::get_effective_permissions(db, &[caller, target_id], site_id)
+        .await
+        .map_err(|e| e.new())?;
+    let held = effective.remove(&caller).unwrap_or_default();
+    let target = effective.remove(&target_id).unwrap_or_default();
+
+    let mut missing: Vec<String> = target
+        .iter()
+        .chain(granted.iter())
+        .filter(|(action, entity)| !held.contains(action, entity))
+        .map(|(action, entity)| format!("{} on {}", action, entity))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    missing.sort();
+    missing.dedup();
+    let reason = format!("managing admin user {} requires {}", target_id, missing.join(", "));
+    Err(refuse(ctx, "Ressource::User", Some(target_id), json!({ "missing": missing }), reason).await)
+}
@@ -0,0 +1,125 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, Value};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{guards::permission::Permission, models::admin_audit_log, services::auth::Claims};
+
+struct GuardedQuery;
+
+#[Object]
+impl GuardedQuery {
+    #[graphql(guard = "Permission::new(\"can_read\", \"Ressource::User\")")]
+    async fn secret(&self) -> i32 {
+        42
+    }
+}
+
+fn claims(admin_id: Uuid) -> Claims {
+    Claims {
+        sub: admin_id,
+        iss: "template".to_owned(),
+        aud: "admin".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+        roles: None,
+        permissions: None,
+    }
+}
+
+fn grant(admin_id: Uuid, action: &str, entity: &str) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("user_id".to_owned(), Value::from(admin_id)),
+        ("action".to_owned(), Value::from(action)),
+        ("entity".to_owned(), Value::from(entity)),
+    ])
+}
+
+fn denial(admin_id: Uuid) -> admin_audit_log::Model {
+    admin_audit_log::Model {
+        id: Uuid::new_v4(),
+        actor_id: Some(admin_id),
+        action: "secret".to_owned(),
+        entity: "Ressource::User".to_owned(),
+        target_id: None,
+        outcome: "denied".to_owned(),
+        changes: None,
+        ip_address: None,
+        user_agent: None,
+        created_at: Utc::now(),
+    }
+}
+
+async fn query_secret(db: MockDatabase, admin_id: Uuid) -> (async_graphql::Response, Arc<DatabaseConnection>) {
+    let db = Arc::new(db.into_connection());
+    let schema = Schema::build(GuardedQuery, EmptyMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let response = schema.execute(Request::new("{ secret }").data(claims(admin_id))).await;
+    (response, db)
+}
+
+fn transaction_log(db: Arc<DatabaseConnection>) -> String {
+    let db = Arc::try_unwrap(db).unwrap_or_else(|_| panic!("Connection still shared"));
+    format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"")
+}
+
+#[tokio::test]
+async fn test_permission_granted() {
+    let admin_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_read", "Ressource::User")]]);
+
+    let (response, db) = query_secret(db, admin_id).await;
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    assert_eq!(response.data.to_string(), "{secret: 42}");
+
+    let log = transaction_log(db);
+    assert!(!log.contains("admin_audit_log"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_permission_denied_is_audited() {
+    let admin_id = Uuid::new_v4();
+    // A grant on another entity does not count
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![grant(admin_id, "can_read", "Ressource::Site")]])
+        .append_query_results([vec![denial(admin_id)]]);
+
+    let (response, db) = query_secret(db, admin_id).await;
+    assert_eq!(response.errors.len(), 1);
+    let extensions = response.errors[0].extensions.as_ref().expect("No error extensions");
+    assert_eq!(extensions.get("message").map(|message| message.to_string()), Some("\"PERMISSION_DENIED\"".to_owned()));
+
+    let log = transaction_log(db);
+    assert!(log.contains(r#"INSERT INTO "admin_audit_log""#), "Denial not audited: {}", log);
+    assert!(log.contains("String(Some(\"denied\"))"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_permission_denied_without_grants() {
+    let admin_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+        .append_query_results([vec![denial(admin_id)]]);
+
+    let (response, _) = query_secret(db, admin_id).await;
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(response.data.to_string(), "null");
+}
+
+#[tokio::test]
+async fn test_permission_requires_admin_token() {
+    let db = Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+    let schema = Schema::build(GuardedQuery, EmptyMutation, EmptySubscription)
+        .data(db)
+        .finish();
+
+    let response = schema.execute(Request::new("{ secret }")).await;
+    assert_eq!(response.errors.len(), 1);
+}
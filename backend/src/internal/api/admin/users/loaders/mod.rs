@@ -0,0 +1,3 @@
+pub mod permissions;
+#[cfg(test)]
+mod test_permissions;
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_graphql::{dataloader::{DataLoader, HashMapCache, Loader}, Context};
+use log::trace;
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::internal::{
+    api::admin::users::{
+        errors::{db::AdminDbError, interface::CustomGraphQLError},
+        services::permissions::{AdminPermissionService, AdminPermissionServiceImpl, PermissionSet},
+    },
+    graphql::{auth::admin_claims, site::current_site_id},
+};
+
+pub type PermissionDataLoader = DataLoader<PermissionLoader, HashMapCache>;
+
+/// Loads the effective permission set of admin users on the request's site, batching
+/// every lookup issued while a request resolves into a single query.
+pub struct PermissionLoader {
+    db: Arc<DatabaseConnection>,
+    site_id: Option<Uuid>,
+}
+
+impl PermissionLoader {
+    pub fn new(db: Arc<DatabaseConnection>, site_id: Option<Uuid>) -> Self {
+        PermissionLoader { db, site_id }
+    }
+
+    /// Builds a caching loader meant to be attached to a single request's data, so the
+    /// permissions are fetched at most once per request and never outlive it.
+    pub fn data_loader(db: Arc<DatabaseConnection>, site_id: Option<Uuid>) -> PermissionDataLoader {
+        DataLoader::with_cache(PermissionLoader::new(db, site_id), tokio::spawn, HashMapCache::default())
+    }
+}
+
+impl Loader<Uuid> for PermissionLoader {
+    type Value = PermissionSet;
+    type Error = Arc<Box<dyn CustomGraphQLError>>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        AdminPermissionServiceImpl::get_effective_permissions(self.db.as_ref(), keys, self.site_id)
+            .await
+            .map_err(Arc::new)
+    }
+}
+
+/// Whether the authenticated admin holds `action` on `entity`. Uses the request's
+/// [`PermissionDataLoader`] when present and queries the database directly otherwise.
+pub async fn has_permission(ctx: &Context<'_>, action: &str, entity: &str) -> async_graphql::Result<bool> {
+    let claims = admin_claims(ctx)?;
+
+    let permissions = match ctx.data_opt::<PermissionDataLoader>() {
+        Some(loader) => loader
+            .load_one(claims.sub)
+            .await
+            .map_err(|e| e.new())?
+            .unwrap_or_default(),
+        None => {
+            let db = match ctx.data::<Arc<DatabaseConnection>>() {
+                Ok(db) => db,
+                Err(e) => {
+                    return Err(
+                        (Box::new(AdminDbError::DatabaseError(format!("{:?}", e))) as Box<dyn CustomGraphQLError>).new()
+                    );
+                }
+            };
+
+            AdminPermissionServiceImpl::get_effective_permissions(db.as_ref(), &[claims.sub], current_site_id(ctx))
+                .await
+                .map_err(|e| e.new())?
+                .remove(&claims.sub)
+                .unwrap_or_default()
+        }
+    };
+
+    let granted = permissions.contains(action, entity);
+    trace!("has_permission: {:?} {} {} -> {}", claims.sub, action, entity, granted);
+    Ok(granted)
+}
@@ -0,0 +1,77 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use sea_orm::{DatabaseBackend, MockDatabase, Value};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::loaders::permissions::*;
+
+fn row(user_id: Uuid, action: &str, entity: &str) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("user_id".to_owned(), Value::from(user_id)),
+        ("action".to_owned(), Value::from(action)),
+        ("entity".to_owned(), Value::from(entity)),
+    ])
+}
+
+#[tokio::test]
+async fn test_loader_batches_users_into_one_query() {
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![
+                row(alice, "can_read", "Ressource::User"),
+                row(bob, "can_delete", "Ressource::Role"),
+            ]])
+            .into_connection(),
+    );
+    let loader = PermissionLoader::data_loader(db.clone(), None);
+
+    let (first, second) = tokio::join!(loader.load_one(alice), loader.load_one(bob));
+    let first = first.unwrap_or_else(|_| panic!("Failed to load the permissions")).expect("No permission set");
+    let second = second.unwrap_or_else(|_| panic!("Failed to load the permissions")).expect("No permission set");
+    assert!(first.contains("can_read", "Ressource::User"));
+    assert!(!first.contains("can_delete", "Ressource::Role"));
+    assert!(second.contains("can_delete", "Ressource::Role"));
+
+    // Cached for the rest of the request
+    let again = loader.load_one(alice).await.unwrap_or_else(|_| panic!("Failed to load the permissions"));
+    assert!(again.is_some());
+
+    drop(loader);
+    let db = Arc::try_unwrap(db).unwrap_or_else(|_| panic!("Connection still shared"));
+    assert_eq!(db.into_transaction_log().len(), 1);
+}
+
+#[tokio::test]
+async fn test_loader_scopes_to_its_site() {
+    let site_id = Uuid::new_v4();
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+            .into_connection(),
+    );
+    let loader = PermissionLoader::data_loader(db.clone(), Some(site_id));
+
+    let permissions = loader.load_one(Uuid::new_v4()).await.unwrap_or_else(|_| panic!("Failed to load the permissions"));
+    assert_eq!(permissions.map(|set| set.iter().count()), Some(0));
+
+    drop(loader);
+    let db = Arc::try_unwrap(db).unwrap_or_else(|_| panic!("Connection still shared"));
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(&site_id.to_string()), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_loader_reports_database_errors() {
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_errors([sea_orm::DbErr::Custom("connection reset".to_owned())])
+            .into_connection(),
+    );
+    let loader = PermissionLoader::data_loader(db, None);
+
+    let error = loader.load_one(Uuid::new_v4()).await.expect_err("Database error swallowed");
+    let extensions = error.new().extensions.expect("No error extensions");
+    assert_eq!(extensions.get("message").map(|message| message.to_string()), Some("\"DATABASE_ACCESS_ERROR\"".to_owned()));
+}
@@ -2,3 +2,8 @@ pub mod models;
 pub mod controllers;
 pub mod services;
 pub mod errors;
+pub mod guards;
+pub mod loaders;
+pub mod registry;
+#[cfg(test)]
+mod test_registry;
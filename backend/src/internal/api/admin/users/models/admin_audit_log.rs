@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use uuid::Uuid;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "admin_audit_log")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// `sub` of the admin token, absent when the request carried none.
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub entity: String,
+    pub target_id: Option<Uuid>,
+    /// `success` or `denied`.
+    pub outcome: String,
+    /// Changed fields as `{ "field": { "before": …, "after": … } }`.
+    pub changes: Option<Json>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {}
@@ -9,6 +9,10 @@ pub struct Model {
     pub id: Uuid,
     pub name: String,
     pub description: Option<String>, // Champ optionnel
+    /// Members must log in with a second factor.
+    pub require_mfa: bool,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
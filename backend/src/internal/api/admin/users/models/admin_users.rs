@@ -14,6 +14,17 @@ pub struct Model {
     pub password: String,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    /// Set once the user followed a verification link, or reset their password by email.
+    pub email_verified_at: Option<DateTimeUtc>,
+    /// Soft deletion time, the row is purged once the retention window has passed.
+    pub deleted_at: Option<DateTimeUtc>,
+}
+
+impl Entity {
+    /// Accounts that are not soft-deleted, where every lookup should start.
+    pub fn find_active() -> Select<Entity> {
+        Self::find().filter(Column::DeletedAt.is_null())
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -11,6 +11,8 @@ pub struct Model {
     pub permission_id: Uuid,
     #[sea_orm(primary_key, auto_increment = false)]
     pub entity_id: Uuid,
+    /// Site the assignment applies to, every site when `None`.
+    pub site_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
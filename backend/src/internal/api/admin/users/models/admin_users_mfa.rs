@@ -0,0 +1,27 @@
+use sea_orm::entity::prelude::*;
+use uuid::Uuid;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "admin_users_mfa")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub admin_user_id: Uuid,
+    /// Base32 TOTP shared secret.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Unset while the enrolment waits for its first code.
+    pub confirmed_at: Option<DateTimeUtc>,
+    /// Time step of the last accepted code, older or equal steps are refused.
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(belongs_to = "super::admin_users::Entity", from = "Column::AdminUserId", to = "super::admin_users::Column::Id")]
+    AdminUser,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {}
@@ -9,6 +9,8 @@ pub struct Model {
     pub admin_user_id: Uuid,
     #[sea_orm(primary_key, auto_increment = false)]
     pub role_admin_id: Uuid,
+    /// Site the assignment applies to, every site when `None`.
+    pub site_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -5,3 +5,7 @@ pub mod admin_actions;
 pub mod admin_entities;
 pub mod admin_roles_actions_entities_assignements;
 pub mod admin_users_actions_entities_assignements;
+pub mod site;
+pub mod admin_audit_log;
+pub mod admin_users_mfa;
+pub mod admin_users_recovery_codes;
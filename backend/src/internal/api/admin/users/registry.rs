@@ -0,0 +1,63 @@
+use log::info;
+use sea_orm::DatabaseConnection;
+
+use crate::internal::api::admin::users::{
+    errors::interface::CustomGraphQLError,
+    services::{actions::{AdminActionService, AdminActionServiceImpl}, entities::{AdminEntitiesService, AdminEntitiesServiceImpl}},
+};
+
+/// An action or entity the code relies on, registered at startup when missing.
+pub struct Declaration {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Every action used by a `Permission` guard or `has_permission` check.
+pub const DECLARED_ACTIONS: &[Declaration] = &[
+    Declaration { name: "can_create", description: "Permission to create resources." },
+    Declaration { name: "can_read", description: "Permission to read resources." },
+    Declaration { name: "can_update", description: "Permission to update resources." },
+    Declaration { name: "can_delete", description: "Permission to delete resources." },
+    Declaration { name: "can_list", description: "Permission to list resources." },
+    Declaration { name: "can_upload", description: "Permission to upload files." },
+    Declaration { name: "can_download", description: "Permission to download files." },
+];
+
+/// Every page or resource guarded in the admin API. Add new pages here instead of
+/// writing a seed migration; grants are then managed through the role mutations.
+pub const DECLARED_ENTITIES: &[Declaration] = &[
+    Declaration { name: "/admin/dashboard", description: "Represents the Admin space." },
+    Declaration { name: "/admin/dashboard/users", description: "Represents the Users page." },
+    Declaration { name: "Ressource::User", description: "Represents the User resource." },
+    Declaration { name: "Ressource::UserMfa", description: "Represents the second factor of the admin users, reset for those who lost it." },
+    Declaration { name: "Ressource::Role", description: "Represents the admin Role resource and its permissions." },
+    Declaration { name: "Ressource::Permission", description: "Represents the admin actions and entities catalogue." },
+    Declaration { name: "Ressource::Site", description: "Represents the Site resource, the domains served by the deployment." },
+    Declaration { name: "Ressource::AuditLog", description: "Represents the admin audit log." },
+];
+
+/// Whether the code relies on `name`, in which case it must not be renamed or deleted.
+pub fn is_declared(declarations: &[Declaration], name: &str) -> bool {
+    declarations.iter().any(|declaration| declaration.name == name)
+}
+
+/// Inserts the declared actions and entities that are not in the database yet. Existing
+/// rows are left untouched, and nothing is ever removed.
+pub async fn sync_registry(db: &DatabaseConnection) -> Result<(), Box<dyn CustomGraphQLError>> {
+    let mut registered = 0;
+
+    for action in DECLARED_ACTIONS {
+        if AdminActionServiceImpl::register_action(db, action.name, action.description).await? {
+            registered += 1;
+        }
+    }
+
+    for entity in DECLARED_ENTITIES {
+        if AdminEntitiesServiceImpl::register_entity(db, entity.name, entity.description).await? {
+            registered += 1;
+        }
+    }
+
+    info!("Admin registry synced, {} new action(s)/entity(ies) registered", registered);
+    Ok(())
+}
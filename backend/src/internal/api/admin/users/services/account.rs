@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use log::{error, trace};
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    errors::{db::AdminDbError, interface::CustomGraphQLError, user::AdminUserAuthError},
+    models::admin_users,
+    services::{
+        auth::admin_token_audience,
+        users::{AdminUserService, AdminUserServiceImpl},
+        validation::validate_password,
+    },
+};
+use crate::internal::mail::{templates, Mailer};
+use crate::internal::security::{
+    account_tokens::{AccountTokenPurpose, AccountTokenService, AccountTokenServiceImpl},
+    password::PasswordService,
+    refresh::{RefreshTokenService, RefreshTokenServiceImpl},
+    throttle::{login_keys, LoginThrottleService, LoginThrottleServiceImpl, ThrottleKey},
+};
+
+#[async_trait]
+pub trait AdminAccountService {
+    /// Emails a reset link when the address belongs to an admin user. Unknown addresses
+    /// succeed alike, and every request counts against the address like a failed login,
+    /// so the mutation can be used neither to probe accounts nor to flood a mailbox.
+    async fn request_password_reset(db: &DatabaseConnection, mailer: &dyn Mailer, email: &str, address: Option<&str>) -> Result<(), Box<dyn CustomGraphQLError>>;
+    /// Sets the new password, spending the reset token, and ends every session of the user.
+    async fn reset_password(db: &DatabaseConnection, token: &str, password: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>>;
+    async fn request_email_verification(db: &DatabaseConnection, mailer: &dyn Mailer, user_id: Uuid) -> Result<(), Box<dyn CustomGraphQLError>>;
+    async fn verify_email(db: &DatabaseConnection, token: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct AdminAccountServiceImpl;
+
+async fn mark_email_verified(db: &DatabaseConnection, user_id: Uuid) -> Result<(), Box<dyn CustomGraphQLError>> {
+    admin_users::Entity::update_many()
+        .col_expr(admin_users::Column::EmailVerifiedAt, Expr::value(Utc::now()))
+        .filter(admin_users::Column::Id.eq(user_id))
+        .filter(admin_users::Column::EmailVerifiedAt.is_null())
+        .exec(db)
+        .await
+        .map(|_| ())
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+}
+
+#[async_trait]
+impl AdminAccountService for AdminAccountServiceImpl {
+    async fn request_password_reset(db: &DatabaseConnection, mailer: &dyn Mailer, email: &str, address: Option<&str>) -> Result<(), Box<dyn CustomGraphQLError>> {
+        trace!("Password reset requested for admin email '{}'", email);
+
+        let keys = login_keys("admin-reset", email, address);
+        LoginThrottleServiceImpl::check(db, &keys).await?;
+        LoginThrottleServiceImpl::record_failure(db, &keys).await?;
+
+        let user = admin_users::Entity::find_active()
+            .filter(admin_users::Column::Email.eq(email.trim()))
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        let user = match user {
+            Some(user) => user,
+            None => {
+                trace!("Unknown admin email '{}', no reset link sent", email);
+                return Ok(());
+            },
+        };
+
+        let purpose = AccountTokenPurpose::PasswordReset;
+        let token = AccountTokenServiceImpl::issue(db, user.id, &admin_token_audience(), purpose).await?;
+        let link = templates::token_link("/admin/reset-password", &token);
+
+        // Only logged: failing here would tell that the account exists
+        if let Err(e) = mailer.send(&templates::password_reset(&user.email, &link, purpose.ttl().num_minutes())).await {
+            error!("Failed to send the password reset email of admin user {}: {}", user.id, e);
+        }
+        Ok(())
+    }
+
+    async fn reset_password(db: &DatabaseConnection, token: &str, password: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>> {
+        // Checked first, so that a rejected password does not spend the token
+        validate_password(password)?;
+
+        let audience = admin_token_audience();
+        let user_id = AccountTokenServiceImpl::consume(db, token, &audience, AccountTokenPurpose::PasswordReset).await?;
+        trace!("Resetting password of admin user {}", user_id);
+
+        let hash = PasswordService::from_env()
+            .hash(password)
+            .map_err(|e| Box::new(AdminUserAuthError::UnexpectedError(format!("Failed to hash password: {}", e))) as Box<dyn CustomGraphQLError>)?;
+        let user = AdminUserServiceImpl::update_user_password(db, user_id, hash).await?;
+
+        // Following the link proved the address
+        mark_email_verified(db, user_id).await?;
+        RefreshTokenServiceImpl::revoke_all(db, user_id, &audience).await?;
+        LoginThrottleServiceImpl::reset(db, &ThrottleKey::account("admin", &user.email)).await?;
+
+        Ok(user_id)
+    }
+
+    async fn request_email_verification(db: &DatabaseConnection, mailer: &dyn Mailer, user_id: Uuid) -> Result<(), Box<dyn CustomGraphQLError>> {
+        let user = AdminUserServiceImpl::get_user_by_id(db, user_id).await?;
+        if user.email_verified_at.is_some() {
+            trace!("Email of admin user {} already verified", user_id);
+            return Ok(());
+        }
+
+        let purpose = AccountTokenPurpose::EmailVerification;
+        let token = AccountTokenServiceImpl::issue(db, user.id, &admin_token_audience(), purpose).await?;
+        let link = templates::token_link("/admin/verify-email", &token);
+
+        mailer
+            .send(&templates::email_verification(&user.email, &link, purpose.ttl().num_minutes()))
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn verify_email(db: &DatabaseConnection, token: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>> {
+        let user_id = AccountTokenServiceImpl::consume(db, token, &admin_token_audience(), AccountTokenPurpose::EmailVerification).await?;
+        trace!("Email of admin user {} verified", user_id);
+
+        mark_email_verified(db, user_id).await?;
+        Ok(user_id)
+    }
+}
@@ -1,12 +1,20 @@
 use async_trait::async_trait;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use chrono::Utc;
+use log::{info, trace};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
 use uuid::Uuid;
 
-use crate::internal::api::admin::users::{errors::{action::AdminActionError, db::AdminDbError, interface::CustomGraphQLError}, models::admin_actions};
+use crate::internal::api::admin::users::{errors::{action::AdminActionError, db::AdminDbError, interface::CustomGraphQLError, validation::AdminValidationError}, models::{admin_actions, admin_roles_actions_entities_assignements, admin_users_actions_entities_assignements}, registry::{is_declared, DECLARED_ACTIONS}, services::validation::validate_required};
 
 #[async_trait]
 pub trait AdminActionService {
     async fn get_action_id_by_name(db: &DatabaseConnection, action: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>>;
+    async fn get_action_by_id(db: &DatabaseConnection, id: Uuid) -> Result<admin_actions::Model, Box<dyn CustomGraphQLError>>;
+    async fn get_all_actions(db: &DatabaseConnection) -> Result<Vec<admin_actions::Model>, Box<dyn CustomGraphQLError>>;
+    async fn create_action(db: &DatabaseConnection, name: String, description: Option<String>) -> Result<admin_actions::Model, Box<dyn CustomGraphQLError>>;
+    async fn update_action(db: &DatabaseConnection, id: Uuid, name: Option<String>, description: Option<String>) -> Result<admin_actions::Model, Box<dyn CustomGraphQLError>>;
+    async fn delete_action(db: &DatabaseConnection, id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    async fn register_action(db: &DatabaseConnection, name: &str, description: &str) -> Result<bool, Box<dyn CustomGraphQLError>>;
 }
 
 pub struct AdminActionServiceImpl;
@@ -22,4 +30,145 @@ impl AdminActionService for AdminActionServiceImpl {
             .ok_or_else(|| Box::new(AdminActionError::NotFound("Action not found".to_string())) as Box<dyn CustomGraphQLError>)
             .map(|action| action.id)
     }
+
+    async fn get_action_by_id(db: &DatabaseConnection, id: Uuid) -> Result<admin_actions::Model, Box<dyn CustomGraphQLError>> {
+        admin_actions::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(AdminActionError::NotFound(id.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn get_all_actions(db: &DatabaseConnection) -> Result<Vec<admin_actions::Model>, Box<dyn CustomGraphQLError>> {
+        trace!("Fetching all admin actions");
+
+        admin_actions::Entity::find()
+            .order_by_asc(admin_actions::Column::Name)
+            .all(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn create_action(db: &DatabaseConnection, name: String, description: Option<String>) -> Result<admin_actions::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Creating admin action '{}'", name);
+
+        let name = validate_required("name", &name)?;
+        ensure_name_available(db, &name, None).await?;
+
+        admin_actions::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name),
+            description: Set(description),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn update_action(db: &DatabaseConnection, id: Uuid, name: Option<String>, description: Option<String>) -> Result<admin_actions::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Updating admin action {}", id);
+
+        let existing = admin_actions::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(AdminActionError::NotFound(id.to_string())) as Box<dyn CustomGraphQLError>)?;
+        let current_name = existing.name.clone();
+        let mut model: admin_actions::ActiveModel = existing.into();
+
+        if let Some(name) = name {
+            let name = validate_required("name", &name)?;
+            // The guards look the action up by the declared name
+            if name != current_name && is_declared(DECLARED_ACTIONS, &current_name) {
+                return Err(Box::new(AdminActionError::Declared(current_name)));
+            }
+            ensure_name_available(db, &name, Some(id)).await?;
+            model.name = Set(name);
+        }
+        if let Some(description) = description {
+            model.description = Set(Some(description));
+        }
+        model.updated_at = Set(Utc::now());
+
+        model.update(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn delete_action(db: &DatabaseConnection, id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Deleting admin action {}", id);
+
+        let existing = admin_actions::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        match existing {
+            None => return Ok(false),
+            Some(existing) if is_declared(DECLARED_ACTIONS, &existing.name) => {
+                return Err(Box::new(AdminActionError::Declared(existing.name)));
+            }
+            Some(_) => {}
+        }
+
+        let txn = db.begin()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        // Role and user grants reference the action, they go first
+        admin_roles_actions_entities_assignements::Entity::delete_many()
+            .filter(admin_roles_actions_entities_assignements::Column::PermissionId.eq(id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        admin_users_actions_entities_assignements::Entity::delete_many()
+            .filter(admin_users_actions_entities_assignements::Column::PermissionId.eq(id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let result = admin_actions::Entity::delete_by_id(id)
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        txn.commit()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn register_action(db: &DatabaseConnection, name: &str, description: &str) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        let existing = admin_actions::Entity::find()
+            .filter(admin_actions::Column::Name.eq(name))
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        if existing.is_some() {
+            return Ok(false);
+        }
+
+        AdminActionServiceImpl::create_action(db, name.to_string(), Some(description.to_string())).await?;
+        info!("Registered admin action '{}'", name);
+        Ok(true)
+    }
+}
+
+async fn ensure_name_available(db: &DatabaseConnection, name: &str, current: Option<Uuid>) -> Result<(), Box<dyn CustomGraphQLError>> {
+    let existing = admin_actions::Entity::find()
+        .filter(admin_actions::Column::Name.eq(name))
+        .one(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    match existing {
+        Some(model) if Some(model.id) != current => {
+            Err(Box::new(AdminValidationError::AlreadyExists(format!("admin action named '{}'", name))))
+        }
+        _ => Ok(()),
+    }
 }
@@ -0,0 +1,176 @@
+use async_graphql::{Enum, InputObject};
+use async_trait::async_trait;
+use chrono::Utc;
+use log::trace;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, Set,
+};
+use serde::Serialize;
+use serde_json::{json, Map, Value as Json};
+use uuid::Uuid;
+
+use crate::internal::{
+    api::admin::users::{
+        errors::{db::AdminDbError, interface::CustomGraphQLError},
+        models::admin_audit_log,
+    },
+    graphql::{
+        filters::DateRangeFilter,
+        pagination::{paginate, Cursor, Keyset, Page, PageRequest, UserSortField},
+    },
+};
+
+/// Fields left out of diffs: secrets, and bookkeeping that changes on every write.
+const UNTRACKED_FIELDS: &[&str] = &["password", "updated_at"];
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AuditOutcome {
+    Success,
+    Denied,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Denied => "denied",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "success" => Some(AuditOutcome::Success),
+            "denied" => Some(AuditOutcome::Denied),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub entity: String,
+    pub target_id: Option<Uuid>,
+    pub outcome: AuditOutcome,
+    pub changes: Option<Json>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(InputObject, Default)]
+pub struct AuditLogFilter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub entity: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub outcome: Option<AuditOutcome>,
+    pub created_at: Option<DateRangeFilter>,
+}
+
+/// The log is only ever listed by time, whatever field the page asks for.
+impl Keyset for admin_audit_log::Entity {
+    fn id_column() -> admin_audit_log::Column {
+        admin_audit_log::Column::Id
+    }
+
+    fn sort_column(_field: UserSortField) -> admin_audit_log::Column {
+        admin_audit_log::Column::CreatedAt
+    }
+
+    fn cursor(entry: &admin_audit_log::Model, _field: UserSortField) -> Cursor {
+        Cursor::timestamp(UserSortField::CreatedAt, entry.id, entry.created_at)
+    }
+}
+
+#[async_trait]
+pub trait AdminAuditService {
+    async fn record(db: &DatabaseConnection, entry: AuditEntry) -> Result<admin_audit_log::Model, Box<dyn CustomGraphQLError>>;
+    /// One keyset page of the entries, in the order of `page`.
+    async fn get_entries(db: &DatabaseConnection, filter: Option<AuditLogFilter>, page: &PageRequest) -> Result<Page<admin_audit_log::Model>, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct AdminAuditServiceImpl;
+
+#[async_trait]
+impl AdminAuditService for AdminAuditServiceImpl {
+    async fn record(db: &DatabaseConnection, entry: AuditEntry) -> Result<admin_audit_log::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Recording audit entry: {:?} {} on {} ({:?})", entry.actor_id, entry.action, entry.entity, entry.outcome);
+
+        admin_audit_log::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            actor_id: Set(entry.actor_id),
+            action: Set(entry.action),
+            entity: Set(entry.entity),
+            target_id: Set(entry.target_id),
+            outcome: Set(entry.outcome.as_str().to_string()),
+            changes: Set(entry.changes),
+            ip_address: Set(entry.ip_address),
+            user_agent: Set(entry.user_agent),
+            created_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn get_entries(db: &DatabaseConnection, filter: Option<AuditLogFilter>, page: &PageRequest) -> Result<Page<admin_audit_log::Model>, Box<dyn CustomGraphQLError>> {
+        trace!("Fetching audit log entries");
+
+        let mut condition = Condition::all();
+        if let Some(filter) = filter {
+            if let Some(actor_id) = filter.actor_id {
+                condition = condition.add(admin_audit_log::Column::ActorId.eq(actor_id));
+            }
+            if let Some(action) = filter.action {
+                condition = condition.add(admin_audit_log::Column::Action.eq(action));
+            }
+            if let Some(entity) = filter.entity {
+                condition = condition.add(admin_audit_log::Column::Entity.eq(entity));
+            }
+            if let Some(target_id) = filter.target_id {
+                condition = condition.add(admin_audit_log::Column::TargetId.eq(target_id));
+            }
+            if let Some(outcome) = filter.outcome {
+                condition = condition.add(admin_audit_log::Column::Outcome.eq(outcome.as_str()));
+            }
+            if let Some(created_at) = filter.created_at {
+                condition = condition.add(created_at.condition(admin_audit_log::Column::CreatedAt));
+            }
+        }
+
+        paginate(db, admin_audit_log::Entity::find().filter(condition), page)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+}
+
+/// Fields that differ between the serialized `before` and `after` states, as
+/// `{ "field": { "before": …, "after": … } }`. A missing state reads as every field being
+/// null, so creations and deletions list the whole record. `None` when nothing changed.
+pub fn diff<B: Serialize, A: Serialize>(before: Option<&B>, after: Option<&A>) -> Option<Json> {
+    let before = as_object(before);
+    let after = as_object(after);
+
+    let mut changes = Map::new();
+    for field in before.keys().chain(after.keys()) {
+        if UNTRACKED_FIELDS.contains(&field.as_str()) || changes.contains_key(field) {
+            continue;
+        }
+
+        let old = before.get(field).unwrap_or(&Json::Null);
+        let new = after.get(field).unwrap_or(&Json::Null);
+        if old != new {
+            changes.insert(field.clone(), json!({ "before": old, "after": new }));
+        }
+    }
+
+    (!changes.is_empty()).then_some(Json::Object(changes))
+}
+
+fn as_object<T: Serialize>(state: Option<&T>) -> Map<String, Json> {
+    match state.map(serde_json::to_value) {
+        Some(Ok(Json::Object(fields))) => fields,
+        _ => Map::new(),
+    }
+}
@@ -1,35 +1,126 @@
-use std::time::{SystemTime, UNIX_EPOCH};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, DecodingKey, Validation, encode, EncodingKey, Header};
-use sea_orm::DatabaseConnection;
+use std::{env, sync::OnceLock, time::{SystemTime, UNIX_EPOCH}};
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use async_trait::async_trait;
-use log::trace;
+use log::{trace, warn};
 use uuid::Uuid;
-use std::env;
 use crate::internal::api::admin::users::{
     errors::{
-        auth::AuthTokenError, interface::CustomGraphQLError, user::AdminUserAuthError
-    }, 
-    services::users::{
-        AdminUserService,
-        AdminUserServiceImpl
+        auth::AuthTokenError, db::AdminDbError, interface::CustomGraphQLError, mfa::AdminMfaError, user::AdminUserAuthError
     },
+    models::{admin_roles, admin_users},
+    services::{
+        mfa::{AdminMfaService, AdminMfaServiceImpl},
+        permissions::{AdminPermissionService, AdminPermissionServiceImpl},
+        users::{
+            AdminUserService,
+            AdminUserServiceImpl
+        },
+    },
+};
+use crate::internal::security::{
+    account_tokens::{AccountTokenPurpose, AccountTokenService, AccountTokenServiceImpl},
+    jwt::{jwt_issuer, jwt_keys, validation_for},
+    password::PasswordService,
+    refresh::{RefreshTokenService, RefreshTokenServiceImpl, TokenPair},
+    throttle::{login_keys, LoginThrottleService, LoginThrottleServiceImpl},
 };
-use bcrypt::verify;
+
+/// Audience of admin access and refresh tokens, so they are never accepted by the end-user API.
+pub fn admin_token_audience() -> String {
+    env::var("JWT_ADMIN_AUDIENCE").unwrap_or_else(|_| "admin".to_string())
+}
+
+// Embedding the roles/permissions snapshot is opt-in, it makes tokens noticeably larger.
+fn embed_permissions() -> bool {
+    env::var("JWT_EMBED_PERMISSIONS").map(|value| value == "true").unwrap_or(false)
+}
+
+/// Audience of MFA challenge tokens. Distinct from the access token audience, so that a
+/// challenge is never accepted in place of an access token.
+pub fn mfa_challenge_audience() -> String {
+    format!("{}-mfa", admin_token_audience())
+}
+
+/// Lifetime of admin access tokens, in seconds.
+const ACCESS_TOKEN_TTL: i64 = 3600;
+/// Time left to enter the second factor after the password, in seconds.
+pub const MFA_CHALLENGE_TTL: i64 = 300;
+
+/// Issued instead of tokens when the password checked out but a second factor is needed.
+#[derive(SimpleObject, Debug)]
+pub struct MfaChallenge {
+    pub challenge_token: String,
+    /// Lifetime of the challenge token, in seconds.
+    pub expires_in: i64,
+    /// A role of the user requires a second factor they have not set up yet: the
+    /// challenge must be completed through `startTotpEnrolment`/`confirmTotpEnrolment`.
+    pub enrolment_required: bool,
+}
+
+/// Outcome of the password step, exactly one of the fields is set.
+#[derive(SimpleObject, Debug)]
+pub struct AdminLogin {
+    pub tokens: Option<TokenPair>,
+    pub mfa_challenge: Option<MfaChallenge>,
+}
 
 #[async_trait]
 pub trait TokenService {
-    async fn generate_token(db: &DatabaseConnection, email: String, password: String) -> Result<String, Box<dyn CustomGraphQLError>>;
+    /// Failed attempts count against the account and against `address`, both get locked
+    /// for a while past their limit. Wrong passwords and unknown emails fail alike. Users
+    /// with a second factor, or whose role requires one, get an MFA challenge instead of tokens.
+    async fn generate_token(db: &DatabaseConnection, email: String, password: String, address: Option<String>) -> Result<AdminLogin, Box<dyn CustomGraphQLError>>;
+    /// Second step of the login: exchanges a challenge and a TOTP or recovery code for tokens.
+    async fn verify_mfa_challenge(db: &DatabaseConnection, challenge_token: &str, code: &str, address: Option<String>) -> Result<TokenPair, Box<dyn CustomGraphQLError>>;
+    async fn decode_mfa_challenge(challenge_token: &str) -> Result<MfaChallengeClaims, Box<dyn CustomGraphQLError>>;
+    /// Marks the challenge as completed, it cannot be replayed afterwards.
+    async fn consume_mfa_challenge(db: &DatabaseConnection, challenge: &MfaChallengeClaims) -> Result<(), Box<dyn CustomGraphQLError>>;
+    /// Tokens of a new session, once every required factor has been checked.
+    async fn issue_tokens(db: &DatabaseConnection, user_id: Uuid) -> Result<TokenPair, Box<dyn CustomGraphQLError>>;
+    async fn refresh_token(db: &DatabaseConnection, refresh_token: &str) -> Result<TokenPair, Box<dyn CustomGraphQLError>>;
+    async fn logout(db: &DatabaseConnection, refresh_token: &str) -> Result<bool, Box<dyn CustomGraphQLError>>;
     async fn verify_token(token: &str) -> Result<Claims, Box<dyn CustomGraphQLError>>;
 }
 
 pub struct JwtTokenService;
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PermissionClaim {
+    pub action: String,
+    pub entity: String,
+}
+
 // Model for JWT claims
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct Claims {
     pub sub: Uuid,
+    pub iss: String,
+    pub aud: String,
+    pub iat: usize,
+    pub nbf: usize,
+    pub exp: usize,
+    pub jti: Uuid,
+    /// Snapshot taken when the token was issued, for clients only: guards always
+    /// check the permissions against the database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<PermissionClaim>>,
+}
+
+/// Claims of an MFA challenge token: who passed the password step, and when.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct MfaChallengeClaims {
+    pub sub: Uuid,
+    pub iss: String,
+    pub aud: String,
+    pub iat: usize,
+    pub nbf: usize,
     pub exp: usize,
+    pub jti: Uuid,
+    pub enrolment_required: bool,
 }
 
 impl Claims {
@@ -37,6 +128,11 @@ impl Claims {
         let now = current_time_as_secs();
         self.exp < now
     }
+
+    /// Seconds left before the token expires, zero once it has.
+    pub fn expires_in(&self) -> u64 {
+        self.exp.saturating_sub(current_time_as_secs()) as u64
+    }
 }
 
 fn current_time_as_secs() -> usize {
@@ -46,21 +142,14 @@ fn current_time_as_secs() -> usize {
         .as_secs() as usize
 }
 
-fn get_jwt_secret() -> String {
-    env::var("JWT_SECRET").unwrap_or_else(|_| "votre_secret".to_string())
-}
-
 #[async_trait]
 impl TokenService for JwtTokenService {
     async fn verify_token(token: &str) -> Result<Claims, Box<dyn CustomGraphQLError>> {
         trace!("Verifying token: {}", token);
 
-        let secret = get_jwt_secret();
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(secret.as_ref()), 
-            &Validation::default(),
-        ).map_err(|e| Box::new(AuthTokenError::JwtError(e)) as Box<dyn CustomGraphQLError>)?;
+        let token_data = jwt_keys()
+            .decode::<Claims>(token, validation_for(&admin_token_audience()))
+            .map_err(|e| Box::new(AuthTokenError::JwtError(e)) as Box<dyn CustomGraphQLError>)?;
 
         if token_data.claims.is_expired() {
             return Err(Box::new(AuthTokenError::TokenExpired) as Box<dyn CustomGraphQLError>);
@@ -69,37 +158,209 @@ impl TokenService for JwtTokenService {
         Ok(token_data.claims)
     }
 
-    async fn generate_token(db: &DatabaseConnection, email: String, password: String) -> Result<String, Box<dyn CustomGraphQLError>> {
+    async fn generate_token(db: &DatabaseConnection, email: String, password: String, address: Option<String>) -> Result<AdminLogin, Box<dyn CustomGraphQLError>> {
         trace!("Generating token for user with email: '{}'", email);
 
-        let user = AdminUserServiceImpl::get_user_by_email(db, &email).await?;
+        let keys = login_keys("admin", &email, address.as_deref());
+        LoginThrottleServiceImpl::check(db, &keys).await?;
 
-        match verify(&password, &user.password) {
-            Ok(is_valid) => {
-                if !is_valid {
-                    return Err(Box::new(AdminUserAuthError::InvalidPassword));
-                }
+        let user = admin_users::Entity::find_active()
+            .filter(admin_users::Column::Email.eq(email.as_str()))
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
 
-                let expiration = Utc::now()
-                    .checked_add_signed(Duration::seconds(3600))
-                    .ok_or_else(|| Box::new(AdminUserAuthError::UnexpectedError("Failed to create expiration timestamp".to_string())) as Box<dyn CustomGraphQLError>)?
-                    .timestamp();
+        let passwords = PasswordService::from_env();
+        let is_valid = match &user {
+            Some(user) => passwords.verify(&password, &user.password)
+                .map_err(|_| Box::new(AdminUserAuthError::UnexpectedError("Erreur lors de la vérification du mot de passe".to_string())) as Box<dyn CustomGraphQLError>)?,
+            None => {
+                // Spend the same time as for a wrong password, the timing must not tell
+                // which emails exist
+                let _ = passwords.verify(&password, dummy_hash(&passwords));
+                trace!("Unknown admin email '{}'", email);
+                false
+            },
+        };
 
-                let claims = Claims { 
-                    sub: user.id.clone(), 
-                    exp: expiration as usize 
-                };
+        let user = match user {
+            Some(user) if is_valid => user,
+            _ => {
+                LoginThrottleServiceImpl::record_failure(db, &keys).await?;
+                return Err(Box::new(AdminUserAuthError::InvalidCredentials));
+            },
+        };
 
-                let secret = get_jwt_secret();
-                let token = encode(
-                    &Header::default(), 
-                    &claims, 
-                    &EncodingKey::from_secret(secret.as_ref())
-                ).map_err(|e| Box::new(AuthTokenError::JwtError(e)) as Box<dyn CustomGraphQLError>)?;
+        LoginThrottleServiceImpl::reset(db, &keys[0]).await?;
 
-                Ok(token)
-            },
-            Err(_) => Err(Box::new(AdminUserAuthError::UnexpectedError("Erreur lors de la vérification du mot de passe".to_string())))
+        if passwords.needs_rehash(&user.password) {
+            rehash_password(db, &passwords, user.id, &password).await;
+        }
+
+        let enrolled = AdminMfaServiceImpl::get_mfa(db, user.id)
+            .await?
+            .is_some_and(|mfa| mfa.confirmed_at.is_some());
+        if !enrolled && !AdminMfaServiceImpl::is_mfa_required(db, user.id).await? {
+            return Ok(AdminLogin { tokens: Some(JwtTokenService::issue_tokens(db, user.id).await?), mfa_challenge: None });
         }
+
+        trace!("Admin user {} must complete a second factor", user.id);
+        Ok(AdminLogin { tokens: None, mfa_challenge: Some(mfa_challenge(user.id, !enrolled)?) })
+    }
+
+    async fn verify_mfa_challenge(db: &DatabaseConnection, challenge_token: &str, code: &str, address: Option<String>) -> Result<TokenPair, Box<dyn CustomGraphQLError>> {
+        let challenge = JwtTokenService::decode_mfa_challenge(challenge_token).await?;
+        trace!("Verifying MFA challenge of admin user {}", challenge.sub);
+
+        AdminMfaServiceImpl::verify_code(db, challenge.sub, code, address.as_deref()).await?;
+        JwtTokenService::consume_mfa_challenge(db, &challenge).await?;
+        JwtTokenService::issue_tokens(db, challenge.sub).await
+    }
+
+    async fn decode_mfa_challenge(challenge_token: &str) -> Result<MfaChallengeClaims, Box<dyn CustomGraphQLError>> {
+        jwt_keys()
+            .decode::<MfaChallengeClaims>(challenge_token, validation_for(&mfa_challenge_audience()))
+            .map(|token_data| token_data.claims)
+            .map_err(|_| Box::new(AdminMfaError::InvalidChallenge) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn consume_mfa_challenge(db: &DatabaseConnection, challenge: &MfaChallengeClaims) -> Result<(), Box<dyn CustomGraphQLError>> {
+        let expires_at = DateTime::from_timestamp(challenge.exp as i64, 0).unwrap_or_else(Utc::now);
+        let spent = AccountTokenServiceImpl::spend_signed(db, challenge.jti, challenge.sub, &mfa_challenge_audience(), AccountTokenPurpose::MfaChallenge, expires_at).await?;
+        match spent {
+            true => Ok(()),
+            false => Err(Box::new(AdminMfaError::InvalidChallenge)),
+        }
+    }
+
+    async fn issue_tokens(db: &DatabaseConnection, user_id: Uuid) -> Result<TokenPair, Box<dyn CustomGraphQLError>> {
+        Ok(TokenPair {
+            access_token: access_token(db, user_id).await?,
+            refresh_token: RefreshTokenServiceImpl::issue(db, user_id, &admin_token_audience()).await?,
+            expires_in: ACCESS_TOKEN_TTL,
+        })
+    }
+
+    async fn refresh_token(db: &DatabaseConnection, refresh_token: &str) -> Result<TokenPair, Box<dyn CustomGraphQLError>> {
+        let rotated = RefreshTokenServiceImpl::rotate(db, refresh_token, &admin_token_audience()).await?;
+        trace!("Refreshing token for admin user {}", rotated.subject_id);
+
+        // The account may have been deleted since the session started
+        let user = AdminUserServiceImpl::get_user_by_id(db, rotated.subject_id).await?;
+
+        Ok(TokenPair {
+            access_token: access_token(db, user.id).await?,
+            refresh_token: rotated.refresh_token,
+            expires_in: ACCESS_TOKEN_TTL,
+        })
+    }
+
+    async fn logout(db: &DatabaseConnection, refresh_token: &str) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        RefreshTokenServiceImpl::revoke(db, refresh_token, &admin_token_audience()).await
+    }
+}
+
+async fn access_token(db: &DatabaseConnection, user_id: Uuid) -> Result<String, Box<dyn CustomGraphQLError>> {
+    let now = Utc::now();
+    let expiration = now
+        .checked_add_signed(Duration::seconds(ACCESS_TOKEN_TTL))
+        .ok_or_else(|| Box::new(AdminUserAuthError::UnexpectedError("Failed to create expiration timestamp".to_string())) as Box<dyn CustomGraphQLError>)?
+        .timestamp();
+
+    let (roles, permissions) = if embed_permissions() {
+        let (roles, permissions) = permissions_snapshot(db, user_id).await?;
+        (Some(roles), Some(permissions))
+    } else {
+        (None, None)
+    };
+
+    let claims = Claims {
+        sub: user_id,
+        iss: jwt_issuer(),
+        aud: admin_token_audience(),
+        iat: now.timestamp() as usize,
+        nbf: now.timestamp() as usize,
+        exp: expiration as usize,
+        jti: Uuid::new_v4(),
+        roles,
+        permissions,
+    };
+
+    jwt_keys()
+        .encode(&claims)
+        .map_err(|e| Box::new(AuthTokenError::JwtError(e)) as Box<dyn CustomGraphQLError>)
+}
+
+fn mfa_challenge(user_id: Uuid, enrolment_required: bool) -> Result<MfaChallenge, Box<dyn CustomGraphQLError>> {
+    let now = Utc::now();
+    let claims = MfaChallengeClaims {
+        sub: user_id,
+        iss: jwt_issuer(),
+        aud: mfa_challenge_audience(),
+        iat: now.timestamp() as usize,
+        nbf: now.timestamp() as usize,
+        exp: (now + Duration::seconds(MFA_CHALLENGE_TTL)).timestamp() as usize,
+        jti: Uuid::new_v4(),
+        enrolment_required,
+    };
+
+    let challenge_token = jwt_keys()
+        .encode(&claims)
+        .map_err(|e| Box::new(AuthTokenError::JwtError(e)) as Box<dyn CustomGraphQLError>)?;
+
+    Ok(MfaChallenge { challenge_token, expires_in: MFA_CHALLENGE_TTL, enrolment_required })
+}
+
+async fn permissions_snapshot(db: &DatabaseConnection, user_id: Uuid) -> Result<(Vec<String>, Vec<PermissionClaim>), Box<dyn CustomGraphQLError>> {
+    let role_ids: Vec<Uuid> = AdminUserServiceImpl::get_user_roles(db, user_id)
+        .await?
+        .into_iter()
+        .map(|assignment| assignment.role_admin_id)
+        .collect();
+
+    let roles = admin_roles::Entity::find()
+        .filter(admin_roles::Column::Id.is_in(role_ids))
+        .all(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+        .into_iter()
+        .map(|role| role.name)
+        .collect();
+
+    // Grants restricted to a site are left out of the snapshot, guards check them live
+    let mut permissions: Vec<PermissionClaim> = AdminPermissionServiceImpl::get_effective_permissions(db, &[user_id], None)
+        .await?
+        .remove(&user_id)
+        .unwrap_or_default()
+        .iter()
+        .map(|(action, entity)| PermissionClaim { action: action.clone(), entity: entity.clone() })
+        .collect();
+    permissions.sort_by(|a, b| (&a.entity, &a.action).cmp(&(&b.entity, &b.action)));
+
+    Ok((roles, permissions))
+}
+
+// Hash checked when the email is unknown. Computed once, with the configured algorithm
+// so that it costs the same as a real one.
+fn dummy_hash(passwords: &PasswordService) -> &'static str {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    DUMMY_HASH.get_or_init(|| passwords.hash("not a real password").unwrap_or_default())
+}
+
+// Upgrades a stored hash to the configured algorithm/cost after a successful login.
+// Failures are only logged: the user already proved their password, the upgrade will
+// simply be retried on the next login.
+async fn rehash_password(db: &DatabaseConnection, passwords: &PasswordService, user_id: Uuid, password: &str) {
+    let new_hash = match passwords.hash(password) {
+        Ok(hash) => hash,
+        Err(e) => {
+            warn!("Failed to rehash password for admin user {}: {}", user_id, e);
+            return;
+        }
+    };
+
+    match AdminUserServiceImpl::update_user_password(db, user_id, new_hash).await {
+        Ok(_) => trace!("Password hash upgraded for admin user {}", user_id),
+        Err(e) => warn!("Failed to store rehashed password for admin user {}: {}", user_id, e),
     }
 }
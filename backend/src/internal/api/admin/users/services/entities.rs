@@ -1,13 +1,20 @@
 use async_trait::async_trait;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use chrono::Utc;
+use log::{info, trace};
+use sea_orm::{sea_query::OnConflict, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
 use uuid::Uuid;
 
-use crate::internal::api::admin::users::{errors::{db::AdminDbError, entity::AdminEntityError, interface::CustomGraphQLError}, models::admin_entities};
-
+use crate::internal::api::admin::users::{errors::{entity::AdminEntityError, db::AdminDbError, interface::CustomGraphQLError, validation::AdminValidationError}, models::{admin_entities, admin_roles_actions_entities_assignements, admin_users_actions_entities_assignements}, registry::{is_declared, DECLARED_ENTITIES}, services::validation::validate_required};
 
 #[async_trait]
 pub trait AdminEntitiesService {
     async fn get_entity_id_by_name(db: &DatabaseConnection, entity: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>>;
+    async fn get_entity_by_id(db: &DatabaseConnection, id: Uuid) -> Result<admin_entities::Model, Box<dyn CustomGraphQLError>>;
+    async fn get_all_entities(db: &DatabaseConnection) -> Result<Vec<admin_entities::Model>, Box<dyn CustomGraphQLError>>;
+    async fn create_entity(db: &DatabaseConnection, name: String, description: Option<String>) -> Result<admin_entities::Model, Box<dyn CustomGraphQLError>>;
+    async fn update_entity(db: &DatabaseConnection, id: Uuid, name: Option<String>, description: Option<String>) -> Result<admin_entities::Model, Box<dyn CustomGraphQLError>>;
+    async fn delete_entity(db: &DatabaseConnection, id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    async fn register_entity(db: &DatabaseConnection, name: &str, description: &str) -> Result<bool, Box<dyn CustomGraphQLError>>;
 }
 
 pub struct AdminEntitiesServiceImpl;
@@ -23,4 +30,151 @@ impl AdminEntitiesService for AdminEntitiesServiceImpl {
             .ok_or_else(|| Box::new(AdminEntityError::NotFound("Entity not found".to_string())) as Box<dyn CustomGraphQLError>)
             .map(|entity| entity.id)
     }
+
+    async fn get_entity_by_id(db: &DatabaseConnection, id: Uuid) -> Result<admin_entities::Model, Box<dyn CustomGraphQLError>> {
+        admin_entities::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(AdminEntityError::NotFound(id.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn get_all_entities(db: &DatabaseConnection) -> Result<Vec<admin_entities::Model>, Box<dyn CustomGraphQLError>> {
+        trace!("Fetching all admin entities");
+
+        admin_entities::Entity::find()
+            .order_by_asc(admin_entities::Column::Name)
+            .all(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn create_entity(db: &DatabaseConnection, name: String, description: Option<String>) -> Result<admin_entities::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Creating admin entity '{}'", name);
+
+        let name = validate_required("name", &name)?;
+        ensure_name_available(db, &name, None).await?;
+
+        admin_entities::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name),
+            description: Set(description),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn update_entity(db: &DatabaseConnection, id: Uuid, name: Option<String>, description: Option<String>) -> Result<admin_entities::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Updating admin entity {}", id);
+
+        let existing = admin_entities::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(AdminEntityError::NotFound(id.to_string())) as Box<dyn CustomGraphQLError>)?;
+        let current_name = existing.name.clone();
+        let mut model: admin_entities::ActiveModel = existing.into();
+
+        if let Some(name) = name {
+            let name = validate_required("name", &name)?;
+            // The guards look the entity up by the declared name
+            if name != current_name && is_declared(DECLARED_ENTITIES, &current_name) {
+                return Err(Box::new(AdminEntityError::Declared(current_name)));
+            }
+            ensure_name_available(db, &name, Some(id)).await?;
+            model.name = Set(name);
+        }
+        if let Some(description) = description {
+            model.description = Set(Some(description));
+        }
+        model.updated_at = Set(Utc::now());
+
+        model.update(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn delete_entity(db: &DatabaseConnection, id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Deleting admin entity {}", id);
+
+        let existing = admin_entities::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        match existing {
+            None => return Ok(false),
+            Some(existing) if is_declared(DECLARED_ENTITIES, &existing.name) => {
+                return Err(Box::new(AdminEntityError::Declared(existing.name)));
+            }
+            Some(_) => {}
+        }
+
+        let txn = db.begin()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        // Role and user grants reference the entity, they go first
+        admin_roles_actions_entities_assignements::Entity::delete_many()
+            .filter(admin_roles_actions_entities_assignements::Column::EntityId.eq(id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        admin_users_actions_entities_assignements::Entity::delete_many()
+            .filter(admin_users_actions_entities_assignements::Column::EntityId.eq(id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let result = admin_entities::Entity::delete_by_id(id)
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        txn.commit()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn register_entity(db: &DatabaseConnection, name: &str, description: &str) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        // Another instance may be registering the same entity, the unique name decides
+        let inserted = admin_entities::Entity::insert(admin_entities::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name.to_string()),
+            description: Set(Some(description.to_string())),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+        })
+        .on_conflict(OnConflict::column(admin_entities::Column::Name).do_nothing().to_owned())
+        .exec_without_returning(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        info!("Registered admin entity '{}'", name);
+        Ok(true)
+    }
+}
+
+async fn ensure_name_available(db: &DatabaseConnection, name: &str, current: Option<Uuid>) -> Result<(), Box<dyn CustomGraphQLError>> {
+    let existing = admin_entities::Entity::find()
+        .filter(admin_entities::Column::Name.eq(name))
+        .one(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    match existing {
+        Some(model) if Some(model.id) != current => {
+            Err(Box::new(AdminValidationError::AlreadyExists(format!("admin entity named '{}'", name))))
+        }
+        _ => Ok(()),
+    }
 }
@@ -0,0 +1,292 @@
+use std::env;
+use async_trait::async_trait;
+use chrono::Utc;
+use log::{trace, warn};
+use rand::RngCore;
+use sea_orm::{
+    sea_query::{Expr, Query},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    errors::{db::AdminDbError, interface::CustomGraphQLError, mfa::AdminMfaError},
+    models::{admin_roles, admin_users_mfa, admin_users_recovery_codes, admin_users_roles},
+};
+use crate::internal::security::{
+    refresh::hash_token,
+    throttle::{login_keys, LoginThrottleService, LoginThrottleServiceImpl},
+    totp,
+};
+
+/// Recovery codes handed out on enrolment and on each regeneration.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Issuer shown by authenticator apps, read from `TOTP_ISSUER` and defaulting to the JWT issuer.
+fn totp_issuer() -> String {
+    env::var("TOTP_ISSUER").unwrap_or_else(|_| crate::internal::security::jwt::jwt_issuer())
+}
+
+/// Secret of a pending enrolment, to be added to an authenticator app.
+pub struct TotpEnrolment {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[async_trait]
+pub trait AdminMfaService {
+    async fn get_mfa(db: &DatabaseConnection, user_id: Uuid) -> Result<Option<admin_users_mfa::Model>, Box<dyn CustomGraphQLError>>;
+    /// Whether any role of the user, on any site, requires a second factor.
+    async fn is_mfa_required(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    async fn count_recovery_codes_left(db: &DatabaseConnection, user_id: Uuid) -> Result<u64, Box<dyn CustomGraphQLError>>;
+    /// Starts over any pending enrolment with a new secret.
+    async fn start_enrolment(db: &DatabaseConnection, user_id: Uuid, account: &str) -> Result<TotpEnrolment, Box<dyn CustomGraphQLError>>;
+    /// Enables the second factor once a first code checks out, and returns the recovery codes.
+    async fn confirm_enrolment(db: &DatabaseConnection, user_id: Uuid, code: &str, address: Option<&str>) -> Result<Vec<String>, Box<dyn CustomGraphQLError>>;
+    /// Accepts a TOTP code or an unused recovery code, which is then spent. Failures are
+    /// throttled like passwords.
+    async fn verify_code(db: &DatabaseConnection, user_id: Uuid, code: &str, address: Option<&str>) -> Result<(), Box<dyn CustomGraphQLError>>;
+    /// Refused while a role of the user requires a second factor.
+    async fn disable(db: &DatabaseConnection, user_id: Uuid, code: &str, address: Option<&str>) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    /// Replaces every recovery code, used or not.
+    async fn regenerate_recovery_codes(db: &DatabaseConnection, user_id: Uuid, code: &str, address: Option<&str>) -> Result<Vec<String>, Box<dyn CustomGraphQLError>>;
+    /// Drops the second factor of a user who lost it, without a code. The user enrols
+    /// again on the next login if a role requires it.
+    async fn reset(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct AdminMfaServiceImpl;
+
+fn unix_time() -> u64 {
+    Utc::now().timestamp().max(0) as u64
+}
+
+/// `XXXX-XXXX` from 40 random bits, in the base32 alphabet so that it reads unambiguously.
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code = totp::base32_encode(&bytes);
+    format!("{}-{}", &code[..4], &code[4..])
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_uppercase()
+}
+
+async fn replace_recovery_codes<C: ConnectionTrait>(db: &C, user_id: Uuid) -> Result<Vec<String>, Box<dyn CustomGraphQLError>> {
+    admin_users_recovery_codes::Entity::delete_many()
+        .filter(admin_users_recovery_codes::Column::AdminUserId.eq(user_id))
+        .exec(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+    admin_users_recovery_codes::Entity::insert_many(codes.iter().map(|code| admin_users_recovery_codes::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        admin_user_id: Set(user_id),
+        code_hash: Set(hash_token(&normalize_recovery_code(code))),
+        used_at: Set(None),
+        created_at: Set(Utc::now()),
+    }))
+    .exec(db)
+    .await
+    .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    Ok(codes)
+}
+
+/// Consumes the TOTP code if it is valid and newer than the last one used. The update is
+/// conditional so that two concurrent requests cannot both spend the same step.
+async fn consume_totp(db: &DatabaseConnection, mfa: &admin_users_mfa::Model, code: &str) -> Result<bool, Box<dyn CustomGraphQLError>> {
+    let last_step = mfa.last_used_step.map(|step| step as u64);
+    let step = match totp::verify(&mfa.secret, code, unix_time(), last_step) {
+        Some(step) => step as i64,
+        None => return Ok(false),
+    };
+
+    let result = admin_users_mfa::Entity::update_many()
+        .col_expr(admin_users_mfa::Column::LastUsedStep, Expr::value(step))
+        .filter(admin_users_mfa::Column::AdminUserId.eq(mfa.admin_user_id))
+        .filter(
+            Condition::any()
+                .add(admin_users_mfa::Column::LastUsedStep.is_null())
+                .add(admin_users_mfa::Column::LastUsedStep.lt(step)),
+        )
+        .exec(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    Ok(result.rows_affected == 1)
+}
+
+async fn consume_recovery_code(db: &DatabaseConnection, user_id: Uuid, code: &str) -> Result<bool, Box<dyn CustomGraphQLError>> {
+    let result = admin_users_recovery_codes::Entity::update_many()
+        .col_expr(admin_users_recovery_codes::Column::UsedAt, Expr::value(Utc::now()))
+        .filter(admin_users_recovery_codes::Column::AdminUserId.eq(user_id))
+        .filter(admin_users_recovery_codes::Column::CodeHash.eq(hash_token(&normalize_recovery_code(code))))
+        .filter(admin_users_recovery_codes::Column::UsedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    if result.rows_affected > 0 {
+        warn!("Recovery code used by admin user {}", user_id);
+    }
+    Ok(result.rows_affected > 0)
+}
+
+/// Runs `check` under the `admin-mfa` throttle of the user: refused while locked, a
+/// failure counts against the user and the address, a success clears the user's counter.
+async fn throttled(db: &DatabaseConnection, user_id: Uuid, address: Option<&str>, accepted: impl std::future::Future<Output = Result<bool, Box<dyn CustomGraphQLError>>>) -> Result<(), Box<dyn CustomGraphQLError>> {
+    let keys = login_keys("admin-mfa", &user_id.to_string(), address);
+    LoginThrottleServiceImpl::check(db, &keys).await?;
+
+    if accepted.await? {
+        LoginThrottleServiceImpl::reset(db, &keys[0]).await?;
+        Ok(())
+    } else {
+        LoginThrottleServiceImpl::record_failure(db, &keys).await?;
+        Err(Box::new(AdminMfaError::InvalidCode(user_id.to_string())))
+    }
+}
+
+async fn confirmed_mfa(db: &DatabaseConnection, user_id: Uuid) -> Result<admin_users_mfa::Model, Box<dyn CustomGraphQLError>> {
+    AdminMfaServiceImpl::get_mfa(db, user_id)
+        .await?
+        .filter(|mfa| mfa.confirmed_at.is_some())
+        .ok_or_else(|| Box::new(AdminMfaError::NotEnrolled(user_id.to_string())) as Box<dyn CustomGraphQLError>)
+}
+
+#[async_trait]
+impl AdminMfaService for AdminMfaServiceImpl {
+    async fn get_mfa(db: &DatabaseConnection, user_id: Uuid) -> Result<Option<admin_users_mfa::Model>, Box<dyn CustomGraphQLError>> {
+        admin_users_mfa::Entity::find_by_id(user_id).one(db).await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn is_mfa_required(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        let count = admin_roles::Entity::find()
+            .filter(admin_roles::Column::RequireMfa.eq(true))
+            .filter(
+                admin_roles::Column::Id.in_subquery(
+                    Query::select()
+                        .column(admin_users_roles::Column::RoleAdminId)
+                        .from(admin_users_roles::Entity)
+                        .and_where(admin_users_roles::Column::AdminUserId.eq(user_id))
+                        .to_owned(),
+                ),
+            )
+            .count(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(count > 0)
+    }
+
+    async fn count_recovery_codes_left(db: &DatabaseConnection, user_id: Uuid) -> Result<u64, Box<dyn CustomGraphQLError>> {
+        admin_users_recovery_codes::Entity::find()
+            .filter(admin_users_recovery_codes::Column::AdminUserId.eq(user_id))
+            .filter(admin_users_recovery_codes::Column::UsedAt.is_null())
+            .count(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn start_enrolment(db: &DatabaseConnection, user_id: Uuid, account: &str) -> Result<TotpEnrolment, Box<dyn CustomGraphQLError>> {
+        trace!("Starting TOTP enrolment for admin user {}", user_id);
+
+        let existing = AdminMfaServiceImpl::get_mfa(db, user_id).await?;
+        if existing.as_ref().is_some_and(|mfa| mfa.confirmed_at.is_some()) {
+            return Err(Box::new(AdminMfaError::AlreadyEnabled(user_id.to_string())));
+        }
+
+        let secret = totp::generate_secret();
+        let pending = admin_users_mfa::ActiveModel {
+            admin_user_id: Set(user_id),
+            secret: Set(secret.clone()),
+            confirmed_at: Set(None),
+            last_used_step: Set(None),
+            created_at: Set(Utc::now()),
+        };
+        match existing {
+            Some(_) => pending.update(db).await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?,
+            None => pending.insert(db).await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?,
+        };
+
+        Ok(TotpEnrolment {
+            provisioning_uri: totp::provisioning_uri(&totp_issuer(), account, &secret),
+            secret,
+        })
+    }
+
+    async fn confirm_enrolment(db: &DatabaseConnection, user_id: Uuid, code: &str, address: Option<&str>) -> Result<Vec<String>, Box<dyn CustomGraphQLError>> {
+        trace!("Confirming TOTP enrolment for admin user {}", user_id);
+
+        let mfa = match AdminMfaServiceImpl::get_mfa(db, user_id).await? {
+            Some(mfa) if mfa.confirmed_at.is_some() => return Err(Box::new(AdminMfaError::AlreadyEnabled(user_id.to_string()))),
+            Some(mfa) => mfa,
+            None => return Err(Box::new(AdminMfaError::NotEnrolled(user_id.to_string()))),
+        };
+
+        throttled(db, user_id, address, consume_totp(db, &mfa, code)).await?;
+
+        let txn = db.begin().await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        admin_users_mfa::Entity::update_many()
+            .col_expr(admin_users_mfa::Column::ConfirmedAt, Expr::value(Utc::now()))
+            .filter(admin_users_mfa::Column::AdminUserId.eq(user_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        let codes = replace_recovery_codes(&txn, user_id).await?;
+        txn.commit().await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(codes)
+    }
+
+    async fn verify_code(db: &DatabaseConnection, user_id: Uuid, code: &str, address: Option<&str>) -> Result<(), Box<dyn CustomGraphQLError>> {
+        trace!("Verifying second factor of admin user {}", user_id);
+
+        let mfa = confirmed_mfa(db, user_id).await?;
+        throttled(db, user_id, address, async {
+            Ok(consume_totp(db, &mfa, code).await? || consume_recovery_code(db, user_id, code).await?)
+        })
+        .await
+    }
+
+    async fn disable(db: &DatabaseConnection, user_id: Uuid, code: &str, address: Option<&str>) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        if AdminMfaServiceImpl::is_mfa_required(db, user_id).await? {
+            return Err(Box::new(AdminMfaError::Required(user_id.to_string())));
+        }
+        AdminMfaServiceImpl::verify_code(db, user_id, code, address).await?;
+
+        AdminMfaServiceImpl::reset(db, user_id).await
+    }
+
+    async fn regenerate_recovery_codes(db: &DatabaseConnection, user_id: Uuid, code: &str, address: Option<&str>) -> Result<Vec<String>, Box<dyn CustomGraphQLError>> {
+        AdminMfaServiceImpl::verify_code(db, user_id, code, address).await?;
+
+        let txn = db.begin().await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        let codes = replace_recovery_codes(&txn, user_id).await?;
+        txn.commit().await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(codes)
+    }
+
+    async fn reset(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Removing second factor of admin user {}", user_id);
+
+        let txn = db.begin().await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        admin_users_recovery_codes::Entity::delete_many()
+            .filter(admin_users_recovery_codes::Column::AdminUserId.eq(user_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        let deleted = admin_users_mfa::Entity::delete_by_id(user_id)
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        txn.commit().await.map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(deleted.rows_affected > 0)
+    }
+}
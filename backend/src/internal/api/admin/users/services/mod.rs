@@ -3,3 +3,23 @@ pub mod users;
 pub mod actions;
 pub mod entities;
 pub mod permissions;
+pub mod roles;
+pub mod validation;
+pub mod sites;
+pub mod audit;
+pub mod mfa;
+pub mod account;
+#[cfg(test)]
+mod test_actions;
+#[cfg(test)]
+mod test_audit;
+#[cfg(test)]
+mod test_auth;
+#[cfg(test)]
+mod test_entities;
+#[cfg(test)]
+mod test_permissions;
+#[cfg(test)]
+mod test_roles;
+#[cfg(test)]
+mod test_users;
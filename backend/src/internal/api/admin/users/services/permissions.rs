@@ -1,60 +1,164 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use async_trait::async_trait;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use log::trace;
+use sea_orm::{
+    sea_query::{Alias, Expr, Query, SelectStatement, UnionType},
+    ConnectionTrait, DatabaseConnection, FromQueryResult,
+};
 use uuid::Uuid;
 
-use crate::internal::api::admin::users::{errors::{db::AdminDbError, interface::CustomGraphQLError}, models::{admin_roles_actions_entities_assignements, admin_users_actions_entities_assignements, admin_users_roles}};
+use crate::internal::api::admin::users::{errors::{db::AdminDbError, interface::CustomGraphQLError}, models::{admin_actions, admin_entities, admin_roles_actions_entities_assignements, admin_users, admin_users_actions_entities_assignements, admin_users_roles}, services::sites::site_scope};
+
+/// Effective `(action, entity)` grants of an admin user on a site, merged from their roles
+/// and their direct user grants.
+#[derive(Clone, Debug, Default)]
+pub struct PermissionSet(Arc<HashSet<(String, String)>>);
+
+impl PermissionSet {
+    pub fn contains(&self, action: &str, entity: &str) -> bool {
+        self.0.contains(&(action.to_string(), entity.to_string()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(String, String)> {
+        self.0.iter()
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct PermissionRow {
+    user_id: Uuid,
+    action: String,
+    entity: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RoleGrantRow {
+    action: String,
+    entity: String,
+}
 
 #[async_trait]
 pub trait AdminPermissionService {
-    async fn get_permissions_for_roles(
+    /// Only assignments shared by every site count when `site_id` is `None`.
+    async fn get_effective_permissions(
         db: &DatabaseConnection,
-        user_roles: &[admin_users_roles::Model],
-        action_id: Uuid,
-        entity_id: Uuid,
-    ) -> Result<Vec<admin_roles_actions_entities_assignements::Model>, Box<dyn CustomGraphQLError>>;
-
-    async fn get_permissions_for_user(
-        db: &DatabaseConnection,
-        user_id: Uuid,
-        action_id: Uuid,
-        entity_id: Uuid,
-    ) -> Result<Vec<admin_users_actions_entities_assignements::Model>, Box<dyn CustomGraphQLError>>;
+        user_ids: &[Uuid],
+        site_id: Option<Uuid>,
+    ) -> Result<HashMap<Uuid, PermissionSet>, Box<dyn CustomGraphQLError>>;
+    /// Grants of a role, whichever site it is assigned on.
+    async fn get_role_permissions(db: &DatabaseConnection, role_id: Uuid) -> Result<PermissionSet, Box<dyn CustomGraphQLError>>;
 }
 
 pub struct AdminPermissionServiceImpl;
 
+// SELECT user_id, action.name, entity.name FROM role grants JOIN the user's roles on the site
+// UNION
+// SELECT user_id, action.name, entity.name FROM direct user grants on the site
+// Both halves join admin_users: deleted admins keep their grants for a restore, but hold
+// no permission until then, whatever their access token says.
+fn effective_permissions_query(user_ids: &[Uuid], site_id: Option<Uuid>) -> SelectStatement {
+    use admin_roles_actions_entities_assignements as role_grants;
+    use admin_users_actions_entities_assignements as user_grants;
+
+    let mut from_roles = Query::select()
+        .expr_as(Expr::col((admin_users_roles::Entity, admin_users_roles::Column::AdminUserId)), Alias::new("user_id"))
+        .expr_as(Expr::col((admin_actions::Entity, admin_actions::Column::Name)), Alias::new("action"))
+        .expr_as(Expr::col((admin_entities::Entity, admin_entities::Column::Name)), Alias::new("entity"))
+        .from(role_grants::Entity)
+        .inner_join(
+            admin_users_roles::Entity,
+            Expr::col((admin_users_roles::Entity, admin_users_roles::Column::RoleAdminId)).equals((role_grants::Entity, role_grants::Column::RoleId)),
+        )
+        .inner_join(
+            admin_actions::Entity,
+            Expr::col((admin_actions::Entity, admin_actions::Column::Id)).equals((role_grants::Entity, role_grants::Column::PermissionId)),
+        )
+        .inner_join(
+            admin_entities::Entity,
+            Expr::col((admin_entities::Entity, admin_entities::Column::Id)).equals((role_grants::Entity, role_grants::Column::EntityId)),
+        )
+        .inner_join(
+            admin_users::Entity,
+            Expr::col((admin_users::Entity, admin_users::Column::Id)).equals((admin_users_roles::Entity, admin_users_roles::Column::AdminUserId)),
+        )
+        .and_where(Expr::col((admin_users_roles::Entity, admin_users_roles::Column::AdminUserId)).is_in(user_ids.iter().copied()))
+        .and_where(Expr::col((admin_users::Entity, admin_users::Column::DeletedAt)).is_null())
+        .cond_where(site_scope((admin_users_roles::Entity, admin_users_roles::Column::SiteId), site_id))
+        .to_owned();
+
+    let from_users = Query::select()
+        .expr_as(Expr::col((user_grants::Entity, user_grants::Column::UserId)), Alias::new("user_id"))
+        .expr_as(Expr::col((admin_actions::Entity, admin_actions::Column::Name)), Alias::new("action"))
+        .expr_as(Expr::col((admin_entities::Entity, admin_entities::Column::Name)), Alias::new("entity"))
+        .from(user_grants::Entity)
+        .inner_join(
+            admin_actions::Entity,
+            Expr::col((admin_actions::Entity, admin_actions::Column::Id)).equals((user_grants::Entity, user_grants::Column::PermissionId)),
+        )
+        .inner_join(
+            admin_entities::Entity,
+            Expr::col((admin_entities::Entity, admin_entities::Column::Id)).equals((user_grants::Entity, user_grants::Column::EntityId)),
+        )
+        .inner_join(
+            admin_users::Entity,
+            Expr::col((admin_users::Entity, admin_users::Column::Id)).equals((user_grants::Entity, user_grants::Column::UserId)),
+        )
+        .and_where(Expr::col((user_grants::Entity, user_grants::Column::UserId)).is_in(user_ids.iter().copied()))
+        .and_where(Expr::col((admin_users::Entity, admin_users::Column::DeletedAt)).is_null())
+        .cond_where(site_scope((user_grants::Entity, user_grants::Column::SiteId), site_id))
+        .to_owned();
+
+    from_roles.union(UnionType::Distinct, from_users);
+    from_roles
+}
+
 #[async_trait]
 impl AdminPermissionService for AdminPermissionServiceImpl {
-    async fn get_permissions_for_roles(
+    async fn get_effective_permissions(
         db: &DatabaseConnection,
-        user_roles: &[admin_users_roles::Model],
-        action_id: Uuid,
-        entity_id: Uuid,
-    ) -> Result<Vec<admin_roles_actions_entities_assignements::Model>, Box<dyn CustomGraphQLError>> {
-
-        let role_ids: Vec<_> = user_roles.iter().map(|role| role.role_admin_id).collect();
+        user_ids: &[Uuid],
+        site_id: Option<Uuid>,
+    ) -> Result<HashMap<Uuid, PermissionSet>, Box<dyn CustomGraphQLError>> {
+        trace!("Resolving effective permissions for users {:?} on site {:?}", user_ids, site_id);
 
-        admin_roles_actions_entities_assignements::Entity::find()
-            .filter(admin_roles_actions_entities_assignements::Column::RoleId.is_in(role_ids))
-            .filter(admin_roles_actions_entities_assignements::Column::PermissionId.eq(action_id))
-            .filter(admin_roles_actions_entities_assignements::Column::EntityId.eq(entity_id))
+        let statement = db.get_database_backend().build(&effective_permissions_query(user_ids, site_id));
+        let rows = PermissionRow::find_by_statement(statement)
             .all(db)
             .await
-            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let mut grants: HashMap<Uuid, HashSet<(String, String)>> = user_ids.iter().map(|id| (*id, HashSet::new())).collect();
+        for row in rows {
+            grants.entry(row.user_id).or_default().insert((row.action, row.entity));
+        }
+
+        Ok(grants.into_iter().map(|(user_id, set)| (user_id, PermissionSet(Arc::new(set)))).collect())
     }
-    async fn get_permissions_for_user(
-        db: &DatabaseConnection,
-        user_id: Uuid,
-        action_id: Uuid,
-        entity_id: Uuid,
-    ) -> Result<Vec<admin_users_actions_entities_assignements::Model>, Box<dyn CustomGraphQLError>> {
-
-        admin_users_actions_entities_assignements::Entity::find()
-            .filter(admin_users_actions_entities_assignements::Column::UserId.eq(user_id))
-            .filter(admin_users_actions_entities_assignements::Column::PermissionId.eq(action_id))
-            .filter(admin_users_actions_entities_assignements::Column::EntityId.eq(entity_id))
+
+    async fn get_role_permissions(db: &DatabaseConnection, role_id: Uuid) -> Result<PermissionSet, Box<dyn CustomGraphQLError>> {
+        use admin_roles_actions_entities_assignements as role_grants;
+
+        let query = Query::select()
+            .expr_as(Expr::col((admin_actions::Entity, admin_actions::Column::Name)), Alias::new("action"))
+            .expr_as(Expr::col((admin_entities::Entity, admin_entities::Column::Name)), Alias::new("entity"))
+            .from(role_grants::Entity)
+            .inner_join(
+                admin_actions::Entity,
+                Expr::col((admin_actions::Entity, admin_actions::Column::Id)).equals((role_grants::Entity, role_grants::Column::PermissionId)),
+            )
+            .inner_join(
+                admin_entities::Entity,
+                Expr::col((admin_entities::Entity, admin_entities::Column::Id)).equals((role_grants::Entity, role_grants::Column::EntityId)),
+            )
+            .and_where(Expr::col((role_grants::Entity, role_grants::Column::RoleId)).eq(role_id))
+            .to_owned();
+
+        let rows = RoleGrantRow::find_by_statement(db.get_database_backend().build(&query))
             .all(db)
             .await
-            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(PermissionSet(Arc::new(rows.into_iter().map(|row| (row.action, row.entity)).collect())))
     }
 }
@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use async_trait::async_trait;
+use chrono::Utc;
+use log::trace;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    errors::{db::AdminDbError, interface::CustomGraphQLError, role::AdminRoleError, validation::AdminValidationError},
+    models::{admin_actions, admin_entities, admin_roles, admin_roles_actions_entities_assignements, admin_users_roles},
+    services::{actions::{AdminActionService, AdminActionServiceImpl}, entities::{AdminEntitiesService, AdminEntitiesServiceImpl}, validation::validate_required},
+};
+
+/// Every known action and entity, plus the `(action_id, entity_id)` pairs granted to a role.
+/// Lets the dashboard render the full grid, not just the granted cells.
+pub struct RolePermissionMatrix {
+    pub role: admin_roles::Model,
+    pub actions: Vec<admin_actions::Model>,
+    pub entities: Vec<admin_entities::Model>,
+    pub grants: HashSet<(Uuid, Uuid)>,
+}
+
+#[async_trait]
+pub trait AdminRoleService {
+    async fn get_all_roles(db: &DatabaseConnection) -> Result<Vec<admin_roles::Model>, Box<dyn CustomGraphQLError>>;
+    async fn get_role_by_id(db: &DatabaseConnection, role_id: Uuid) -> Result<admin_roles::Model, Box<dyn CustomGraphQLError>>;
+    async fn create_role(db: &DatabaseConnection, name: String, description: Option<String>, require_mfa: bool) -> Result<admin_roles::Model, Box<dyn CustomGraphQLError>>;
+    async fn update_role(db: &DatabaseConnection, role_id: Uuid, name: Option<String>, description: Option<String>, require_mfa: Option<bool>) -> Result<admin_roles::Model, Box<dyn CustomGraphQLError>>;
+    async fn delete_role(db: &DatabaseConnection, role_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    async fn get_permission_matrix(db: &DatabaseConnection, role_id: Uuid) -> Result<RolePermissionMatrix, Box<dyn CustomGraphQLError>>;
+    async fn grant_permission(db: &DatabaseConnection, role_id: Uuid, action: &str, entity: &str) -> Result<RolePermissionMatrix, Box<dyn CustomGraphQLError>>;
+    async fn revoke_permission(db: &DatabaseConnection, role_id: Uuid, action: &str, entity: &str) -> Result<RolePermissionMatrix, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct AdminRoleServiceImpl;
+
+#[async_trait]
+impl AdminRoleService for AdminRoleServiceImpl {
+    async fn get_all_roles(db: &DatabaseConnection) -> Result<Vec<admin_roles::Model>, Box<dyn CustomGraphQLError>> {
+        trace!("Fetching all admin roles");
+
+        admin_roles::Entity::find()
+            .order_by_asc(admin_roles::Column::Name)
+            .all(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn get_role_by_id(db: &DatabaseConnection, role_id: Uuid) -> Result<admin_roles::Model, Box<dyn CustomGraphQLError>> {
+        admin_roles::Entity::find_by_id(role_id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(AdminRoleError::NotFound(role_id.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn create_role(db: &DatabaseConnection, name: String, description: Option<String>, require_mfa: bool) -> Result<admin_roles::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Creating admin role '{}'", name);
+
+        let name = validate_required("name", &name)?;
+        ensure_name_available(db, &name, None).await?;
+
+        admin_roles::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name),
+            description: Set(description),
+            require_mfa: Set(require_mfa),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn update_role(db: &DatabaseConnection, role_id: Uuid, name: Option<String>, description: Option<String>, require_mfa: Option<bool>) -> Result<admin_roles::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Updating admin role {}", role_id);
+
+        let mut role: admin_roles::ActiveModel = AdminRoleServiceImpl::get_role_by_id(db, role_id).await?.into();
+
+        if let Some(name) = name {
+            let name = validate_required("name", &name)?;
+            ensure_name_available(db, &name, Some(role_id)).await?;
+            role.name = Set(name);
+        }
+        if let Some(description) = description {
+            role.description = Set(Some(description));
+        }
+        if let Some(require_mfa) = require_mfa {
+            role.require_mfa = Set(require_mfa);
+        }
+        role.updated_at = Set(Utc::now());
+
+        role.update(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn delete_role(db: &DatabaseConnection, role_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Deleting admin role {}", role_id);
+
+        let txn = db.begin()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        // Grants and user assignments reference the role, they go first
+        admin_roles_actions_entities_assignements::Entity::delete_many()
+            .filter(admin_roles_actions_entities_assignements::Column::RoleId.eq(role_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        admin_users_roles::Entity::delete_many()
+            .filter(admin_users_roles::Column::RoleAdminId.eq(role_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let result = admin_roles::Entity::delete_by_id(role_id)
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        txn.commit()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn get_permission_matrix(db: &DatabaseConnection, role_id: Uuid) -> Result<RolePermissionMatrix, Box<dyn CustomGraphQLError>> {
+        trace!("Building permission matrix for admin role {}", role_id);
+
+        let role = AdminRoleServiceImpl::get_role_by_id(db, role_id).await?;
+
+        let actions = admin_actions::Entity::find()
+            .order_by_asc(admin_actions::Column::Name)
+            .all(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let entities = admin_entities::Entity::find()
+            .order_by_asc(admin_entities::Column::Name)
+            .all(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let grants = admin_roles_actions_entities_assignements::Entity::find()
+            .filter(admin_roles_actions_entities_assignements::Column::RoleId.eq(role_id))
+            .all(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .into_iter()
+            .map(|grant| (grant.permission_id, grant.entity_id))
+            .collect();
+
+        Ok(RolePermissionMatrix { role, actions, entities, grants })
+    }
+
+    async fn grant_permission(db: &DatabaseConnection, role_id: Uuid, action: &str, entity: &str) -> Result<RolePermissionMatrix, Box<dyn CustomGraphQLError>> {
+        trace!("Granting {} on {} to admin role {}", action, entity, role_id);
+
+        let mut matrix = AdminRoleServiceImpl::get_permission_matrix(db, role_id).await?;
+        let action_id = AdminActionServiceImpl::get_action_id_by_name(db, action).await?;
+        let entity_id = AdminEntitiesServiceImpl::get_entity_id_by_name(db, entity).await?;
+
+        if matrix.grants.contains(&(action_id, entity_id)) {
+            return Ok(matrix);
+        }
+
+        admin_roles_actions_entities_assignements::ActiveModel {
+            role_id: Set(role_id),
+            permission_id: Set(action_id),
+            entity_id: Set(entity_id),
+        }
+        .insert(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        matrix.grants.insert((action_id, entity_id));
+        Ok(matrix)
+    }
+
+    async fn revoke_permission(db: &DatabaseConnection, role_id: Uuid, action: &str, entity: &str) -> Result<RolePermissionMatrix, Box<dyn CustomGraphQLError>> {
+        trace!("Revoking {} on {} from admin role {}", action, entity, role_id);
+
+        let mut matrix = AdminRoleServiceImpl::get_permission_matrix(db, role_id).await?;
+        let action_id = AdminActionServiceImpl::get_action_id_by_name(db, action).await?;
+        let entity_id = AdminEntitiesServiceImpl::get_entity_id_by_name(db, entity).await?;
+
+        admin_roles_actions_entities_assignements::Entity::delete_many()
+            .filter(admin_roles_actions_entities_assignements::Column::RoleId.eq(role_id))
+            .filter(admin_roles_actions_entities_assignements::Column::PermissionId.eq(action_id))
+            .filter(admin_roles_actions_entities_assignements::Column::EntityId.eq(entity_id))
+            .exec(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        matrix.grants.remove(&(action_id, entity_id));
+        Ok(matrix)
+    }
+}
+
+async fn ensure_name_available(db: &DatabaseConnection, name: &str, current_role: Option<Uuid>) -> Result<(), Box<dyn CustomGraphQLError>> {
+    let existing = admin_roles::Entity::find()
+        .filter(admin_roles::Column::Name.eq(name))
+        .one(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    match existing {
+        Some(role) if Some(role.id) != current_role => {
+            Err(Box::new(AdminValidationError::AlreadyExists(format!("admin role named '{}'", name))))
+        }
+        _ => Ok(()),
+    }
+}
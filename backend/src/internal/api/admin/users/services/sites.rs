@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use log::trace;
+use sea_orm::{
+    sea_query::{Expr, IntoColumnRef},
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use uuid::Uuid;
+
+use crate::internal::api::{
+    admin::users::{
+        errors::{db::AdminDbError, interface::CustomGraphQLError, site::AdminSiteError, validation::AdminValidationError},
+        models::site,
+        services::validation::validate_required,
+    },
+    users::models::users,
+};
+
+/// Lowercases a host and strips its port and trailing dot, so `WWW.Example.com:443`
+/// matches the `www.example.com` site.
+pub fn normalize_host(host: &str) -> String {
+    let host = host.trim().trim_end_matches('.').to_lowercase();
+    match host.rsplit_once(':') {
+        // Bracketed IPv6 literals keep their inner colons
+        Some((name, port)) if !name.is_empty() && !port.contains(']') && port.chars().all(|c| c.is_ascii_digit()) => {
+            name.to_string()
+        }
+        _ => host,
+    }
+}
+
+/// Restricts a query to the rows visible on `site_id`: rows shared by every site
+/// (`site_id IS NULL`) plus, when a site is known, the ones belonging to it.
+pub fn site_scope<C: IntoColumnRef + Clone>(column: C, site_id: Option<Uuid>) -> Condition {
+    let shared = Condition::any().add(Expr::col(column.clone()).is_null());
+    match site_id {
+        Some(site_id) => shared.add(Expr::col(column).eq(site_id)),
+        None => shared,
+    }
+}
+
+/// In-memory counterpart of [`site_scope`], for a row already loaded.
+pub fn is_visible_on(row_site_id: Option<Uuid>, site_id: Option<Uuid>) -> bool {
+    row_site_id.is_none() || row_site_id == site_id
+}
+
+fn validate_domain(domain: &str) -> Result<String, Box<dyn CustomGraphQLError>> {
+    let domain = validate_required("domain", domain)?.trim_end_matches('.').to_lowercase();
+    let valid = domain
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+
+    if valid {
+        Ok(domain)
+    } else {
+        Err(Box::new(AdminValidationError::invalid_field(
+            "domain",
+            "must be a bare host name, without scheme, port or path",
+        )))
+    }
+}
+
+#[async_trait]
+pub trait AdminSiteService {
+    async fn get_all_sites(db: &DatabaseConnection) -> Result<Vec<site::Model>, Box<dyn CustomGraphQLError>>;
+    async fn get_site_by_id(db: &DatabaseConnection, site_id: Uuid) -> Result<site::Model, Box<dyn CustomGraphQLError>>;
+    /// Site served on `host`, as sent in the `Host` header.
+    async fn find_site_by_host(db: &DatabaseConnection, host: &str) -> Result<Option<site::Model>, Box<dyn CustomGraphQLError>>;
+    async fn create_site(db: &DatabaseConnection, name: String, description: Option<String>, domain: String) -> Result<site::Model, Box<dyn CustomGraphQLError>>;
+    async fn update_site(db: &DatabaseConnection, site_id: Uuid, name: Option<String>, description: Option<String>, domain: Option<String>) -> Result<site::Model, Box<dyn CustomGraphQLError>>;
+    /// Refuses to delete a site that still has users. Admin assignments restricted to the
+    /// site are removed with it.
+    async fn delete_site(db: &DatabaseConnection, site_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct AdminSiteServiceImpl;
+
+#[async_trait]
+impl AdminSiteService for AdminSiteServiceImpl {
+    async fn get_all_sites(db: &DatabaseConnection) -> Result<Vec<site::Model>, Box<dyn CustomGraphQLError>> {
+        trace!("Fetching all sites");
+
+        site::Entity::find()
+            .order_by_asc(site::Column::Name)
+            .all(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn get_site_by_id(db: &DatabaseConnection, site_id: Uuid) -> Result<site::Model, Box<dyn CustomGraphQLError>> {
+        site::Entity::find_by_id(site_id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(AdminSiteError::NotFound(site_id.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn find_site_by_host(db: &DatabaseConnection, host: &str) -> Result<Option<site::Model>, Box<dyn CustomGraphQLError>> {
+        let domain = normalize_host(host);
+        trace!("Resolving site for host '{}'", domain);
+
+        site::Entity::find()
+            .filter(site::Column::Domain.eq(domain))
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn create_site(db: &DatabaseConnection, name: String, description: Option<String>, domain: String) -> Result<site::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Creating site '{}' on '{}'", name, domain);
+
+        let name = validate_required("name", &name)?;
+        let domain = validate_domain(&domain)?;
+        ensure_domain_available(db, &domain, None).await?;
+
+        site::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name),
+            description: Set(description),
+            domain: Set(domain),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn update_site(db: &DatabaseConnection, site_id: Uuid, name: Option<String>, description: Option<String>, domain: Option<String>) -> Result<site::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Updating site {}", site_id);
+
+        let mut site: site::ActiveModel = AdminSiteServiceImpl::get_site_by_id(db, site_id).await?.into();
+
+        if let Some(name) = name {
+            site.name = Set(validate_required("name", &name)?);
+        }
+        if let Some(description) = description {
+            site.description = Set(Some(description));
+        }
+        if let Some(domain) = domain {
+            let domain = validate_domain(&domain)?;
+            ensure_domain_available(db, &domain, Some(site_id)).await?;
+            site.domain = Set(domain);
+        }
+        site.updated_at = Set(Utc::now());
+
+        site.update(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn delete_site(db: &DatabaseConnection, site_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Deleting site {}", site_id);
+
+        let users = users::Entity::find()
+            .filter(users::Column::SiteId.eq(site_id))
+            .count(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        if users > 0 {
+            return Err(Box::new(AdminSiteError::InUse(site_id.to_string())));
+        }
+
+        let result = site::Entity::delete_by_id(site_id)
+            .exec(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(result.rows_affected > 0)
+    }
+}
+
+async fn ensure_domain_available(db: &DatabaseConnection, domain: &str, current_site: Option<Uuid>) -> Result<(), Box<dyn CustomGraphQLError>> {
+    let existing = site::Entity::find()
+        .filter(site::Column::Domain.eq(domain))
+        .one(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    match existing {
+        Some(site) if Some(site.id) != current_site => {
+            Err(Box::new(AdminValidationError::AlreadyExists(format!("site with domain '{}'", domain))))
+        }
+        _ => Ok(()),
+    }
+}
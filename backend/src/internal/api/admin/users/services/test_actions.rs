@@ -0,0 +1,57 @@
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, MockDatabase};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{errors::interface::CustomGraphQLError, models::admin_actions, services::actions::*};
+
+fn action(id: Uuid, name: &str) -> admin_actions::Model {
+    admin_actions::Model {
+        id,
+        name: name.to_string(),
+        description: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn error_message(error: Box<dyn CustomGraphQLError>) -> String {
+    error.new().extensions.and_then(|extensions| extensions.get("message").map(|message| message.to_string())).unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_update_action_refuses_to_rename_declared_action() {
+    let id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![action(id, "can_read")]])
+        .into_connection();
+
+    let error = AdminActionServiceImpl::update_action(&db, id, Some("can_view".to_string()), None).await.expect_err("Declared action renamed");
+    assert_eq!(error_message(error), "\"ACTION_DECLARED\"");
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("UPDATE"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_delete_action_refuses_declared_action() {
+    let id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![action(id, "can_delete")]])
+        .into_connection();
+
+    let error = AdminActionServiceImpl::delete_action(&db, id).await.expect_err("Declared action deleted");
+    assert_eq!(error_message(error), "\"ACTION_DECLARED\"");
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("DELETE"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_delete_unknown_action() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<admin_actions::Model>::new()])
+        .into_connection();
+
+    let deleted = AdminActionServiceImpl::delete_action(&db, Uuid::new_v4()).await.unwrap_or_else(|_| panic!("Failed to delete the action"));
+    assert!(!deleted);
+}
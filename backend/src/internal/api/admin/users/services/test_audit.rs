@@ -0,0 +1,200 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_graphql::{EmptyMutation, EmptySubscription, Request, Schema};
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, MockDatabase, Value};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::internal::{
+    api::admin::users::{
+        controllers::audit::AdminAuditQuery,
+        models::{admin_audit_log, admin_users},
+        services::{audit::*, auth::Claims},
+    },
+    graphql::{
+        client::ClientInfo,
+        pagination::{Cursor, PageRequest, SortDirection, UserOrder, UserSortField, MAX_PAGE_SIZE},
+    },
+};
+
+fn entry(outcome: &str) -> admin_audit_log::Model {
+    admin_audit_log::Model {
+        id: Uuid::new_v4(),
+        actor_id: Some(Uuid::new_v4()),
+        action: "updateAdminUser".to_owned(),
+        entity: "Ressource::User".to_owned(),
+        target_id: Some(Uuid::new_v4()),
+        outcome: outcome.to_owned(),
+        changes: None,
+        ip_address: Some("10.0.0.1".to_owned()),
+        user_agent: Some("curl".to_owned()),
+        created_at: Utc::now(),
+    }
+}
+
+fn admin(email: &str, password: &str) -> admin_users::Model {
+    admin_users::Model {
+        id: Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap(),
+        username: "test_admin".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "admin".to_owned(),
+        email: email.to_owned(),
+        password: password.to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        email_verified_at: None,
+        deleted_at: None,
+    }
+}
+
+fn claims(admin_id: Uuid) -> Claims {
+    Claims {
+        sub: admin_id,
+        iss: "template".to_owned(),
+        aud: "admin".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+        roles: None,
+        permissions: None,
+    }
+}
+
+fn grant(admin_id: Uuid, action: &str, entity: &str) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("user_id".to_owned(), Value::from(admin_id)),
+        ("action".to_owned(), Value::from(action)),
+        ("entity".to_owned(), Value::from(entity)),
+    ])
+}
+
+#[tokio::test]
+async fn test_record_writes_the_entry() {
+    let actor_id = Uuid::new_v4();
+    let target_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![entry("success")]])
+        .into_connection();
+
+    let recorded = AdminAuditServiceImpl::record(&db, AuditEntry {
+        actor_id: Some(actor_id),
+        action: "deleteAdminUser".to_owned(),
+        entity: "Ressource::User".to_owned(),
+        target_id: Some(target_id),
+        outcome: AuditOutcome::Success,
+        changes: Some(json!({ "deleted_at": { "before": null, "after": "2024-01-01T00:00:00Z" } })),
+        ip_address: Some("203.0.113.7".to_owned()),
+        user_agent: Some("Mozilla/5.0".to_owned()),
+    })
+    .await;
+    assert!(recorded.is_ok());
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"INSERT INTO "admin_audit_log""#), "Unexpected queries: {}", log);
+    for value in [actor_id.to_string(), target_id.to_string(), "deleteAdminUser".to_owned(), "\"success\"".to_owned(), "203.0.113.7".to_owned(), "Mozilla/5.0".to_owned()] {
+        assert!(log.contains(&value), "{} not recorded: {}", value, log);
+    }
+}
+
+#[test]
+fn test_diff_lists_changed_fields_only() {
+    let before = admin("old@example.com", "old-hash");
+    let after = admin_users::Model { created_at: before.created_at, updated_at: before.updated_at + chrono::Duration::seconds(5), ..admin("new@example.com", "new-hash") };
+
+    let changes = diff(Some(&before), Some(&after)).expect("No change found");
+    // Passwords never reach the log, nor does the bookkeeping
+    assert_eq!(changes, json!({ "email": { "before": "old@example.com", "after": "new@example.com" } }));
+}
+
+#[test]
+fn test_diff_of_creation_and_no_op() {
+    let created = admin("admin@example.com", "hash");
+
+    let changes = diff(None::<&admin_users::Model>, Some(&created)).expect("No change found");
+    assert_eq!(changes["email"], json!({ "before": null, "after": "admin@example.com" }));
+    assert!(changes.get("password").is_none());
+
+    assert_eq!(diff(Some(&created), Some(&created)), None);
+}
+
+#[tokio::test]
+async fn test_get_entries_filters_and_caps_the_page() {
+    let actor_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![entry("denied")]])
+        .into_connection();
+    let filter = AuditLogFilter { actor_id: Some(actor_id), outcome: Some(AuditOutcome::Denied), ..Default::default() };
+    let order = UserOrder { field: UserSortField::CreatedAt, direction: SortDirection::Desc };
+    let page = PageRequest::new(order, None, None, Some(10_000), None).unwrap();
+
+    let entries = AdminAuditServiceImpl::get_entries(&db, Some(filter), &page).await;
+    assert_eq!(entries.map(|entries| entries.items.len()).ok(), Some(1));
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"WHERE "admin_audit_log"."actor_id" = $1 AND "admin_audit_log"."outcome" = $2 ORDER BY "admin_audit_log"."created_at" DESC, "admin_audit_log"."id" DESC LIMIT $3"#), "Unexpected query: {}", log);
+    assert!(log.contains(&format!("BigUnsigned(Some({}))", MAX_PAGE_SIZE + 1)), "Page not capped: {}", log);
+}
+
+#[tokio::test]
+async fn test_get_entries_after_cursor() {
+    let last_seen = entry("success");
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![entry("denied"), entry("success")]])
+        .into_connection();
+    let order = UserOrder { field: UserSortField::CreatedAt, direction: SortDirection::Desc };
+    let after = Cursor::timestamp(UserSortField::CreatedAt, last_seen.id, last_seen.created_at);
+    let page = PageRequest::new(order, Some(after), None, Some(1), None).unwrap();
+
+    let entries = AdminAuditServiceImpl::get_entries(&db, None, &page).await.unwrap();
+    assert_eq!(entries.items.len(), 1);
+    assert!(entries.has_previous_page && entries.has_next_page);
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"WHERE "admin_audit_log"."created_at" < $1 OR ("admin_audit_log"."created_at" = $2 AND "admin_audit_log"."id" < $3) ORDER BY "admin_audit_log"."created_at" DESC"#), "Unexpected query: {}", log);
+}
+
+#[tokio::test]
+async fn test_audit_log_query_requires_permission() {
+    let admin_id = Uuid::new_v4();
+    // Allowed on users only, the denial is recorded with where the request came from
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![grant(admin_id, "can_read", "Ressource::User")]])
+            .append_query_results([vec![entry("denied")]])
+            .into_connection(),
+    );
+    let schema = Schema::build(AdminAuditQuery, EmptyMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+    let client = ClientInfo { ip_address: Some("198.51.100.4".to_owned()), user_agent: Some("curl/8".to_owned()) };
+
+    let response = schema.execute(Request::new("{ auditLog { edges { node { id } } } }").data(claims(admin_id)).data(client)).await;
+    assert_eq!(response.errors.len(), 1);
+
+    drop(schema);
+    let db = Arc::try_unwrap(db).unwrap_or_else(|_| panic!("Connection still shared"));
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(!log.contains(r#"FROM "admin_audit_log""#), "Audit log read: {}", log);
+    assert!(log.contains(r#"INSERT INTO "admin_audit_log""#), "Denial not recorded: {}", log);
+    assert!(log.contains("198.51.100.4") && log.contains("curl/8") && log.contains("Ressource::AuditLog"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_audit_log_query_lists_entries() {
+    let admin_id = Uuid::new_v4();
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![grant(admin_id, "can_read", "Ressource::AuditLog")]])
+            .append_query_results([vec![entry("success"), entry("denied")]])
+            .into_connection(),
+    );
+    let schema = Schema::build(AdminAuditQuery, EmptyMutation, EmptySubscription)
+        .data(db)
+        .finish();
+
+    let response = schema.execute(Request::new("{ auditLog { nodes { outcome } pageInfo { hasNextPage } } }").data(claims(admin_id))).await;
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    assert_eq!(response.data.to_string(), "{auditLog: {nodes: [{outcome: SUCCESS}, {outcome: DENIED}], pageInfo: {hasNextPage: false}}}");
+}
@@ -0,0 +1,173 @@
+use chrono::{Duration, Utc};
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    errors::interface::CustomGraphQLError,
+    models::{admin_users, admin_users_mfa},
+    services::auth::*,
+};
+use crate::internal::security::{
+    jwt::{install_test_jwt_keys, jwt_issuer, jwt_keys},
+    models::login_attempts,
+    password::PasswordService,
+    totp::{base32_decode, code_at, generate_secret, TOTP_DIGITS, TOTP_PERIOD},
+};
+
+fn admin(id: Uuid) -> admin_users::Model {
+    admin_users::Model {
+        id,
+        username: "test_admin".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "admin".to_owned(),
+        email: "admin@example.com".to_owned(),
+        password: PasswordService::from_env().hash("password123").unwrap(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        email_verified_at: None,
+        deleted_at: None,
+    }
+}
+
+fn confirmed_mfa(user_id: Uuid, secret: &str) -> admin_users_mfa::Model {
+    admin_users_mfa::Model {
+        admin_user_id: user_id,
+        secret: secret.to_owned(),
+        confirmed_at: Some(Utc::now()),
+        last_used_step: None,
+        created_at: Utc::now(),
+    }
+}
+
+fn current_code(secret: &str) -> String {
+    let step = Utc::now().timestamp() as u64 / TOTP_PERIOD;
+    format!("{:06}", code_at(&base32_decode(secret).unwrap(), step, TOTP_DIGITS))
+}
+
+fn challenge_token(user_id: Uuid, jti: Uuid) -> String {
+    let now = Utc::now();
+    jwt_keys()
+        .encode(&MfaChallengeClaims {
+            sub: user_id,
+            iss: jwt_issuer(),
+            aud: mfa_challenge_audience(),
+            iat: now.timestamp() as usize,
+            nbf: now.timestamp() as usize,
+            exp: (now + Duration::minutes(5)).timestamp() as usize,
+            jti,
+            enrolment_required: false,
+        })
+        .unwrap()
+}
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult { last_insert_id: 0, rows_affected }
+}
+
+fn error_message(error: Box<dyn CustomGraphQLError>) -> String {
+    error.new().extensions.and_then(|extensions| extensions.get("message").map(|message| message.to_string())).unwrap_or_default()
+}
+
+/// Second factor check: the TOTP code is spent, the throttle cleared, then the challenge.
+fn verifying_db(user_id: Uuid, secret: &str, challenge_spent: bool) -> MockDatabase {
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![confirmed_mfa(user_id, secret)]])
+        .append_query_results([Vec::<login_attempts::Model>::new()])
+        .append_exec_results([exec(1), exec(1), exec(if challenge_spent { 0 } else { 1 }), exec(1)])
+}
+
+#[tokio::test]
+async fn test_verify_mfa_challenge_issues_tokens() {
+    install_test_jwt_keys();
+    let user_id = Uuid::new_v4();
+    let jti = Uuid::new_v4();
+    let secret = generate_secret();
+    let db = verifying_db(user_id, &secret, false).into_connection();
+
+    let tokens = JwtTokenService::verify_mfa_challenge(&db, &challenge_token(user_id, jti), &current_code(&secret), None).await.unwrap();
+
+    assert_eq!(JwtTokenService::verify_token(&tokens.access_token).await.unwrap().sub, user_id);
+    assert_eq!(tokens.refresh_token.len(), 64);
+    let log = db.into_transaction_log();
+    let spent = format!("{:?}", log[4]).replace("\\\"", "\"");
+    assert!(spent.contains(r#"INSERT INTO "account_tokens""#), "Unexpected query: {}", spent);
+    assert!(spent.contains(&jti.to_string()), "The challenge jti should be recorded: {}", spent);
+}
+
+#[tokio::test]
+async fn test_verify_mfa_challenge_refuses_replay() {
+    install_test_jwt_keys();
+    let user_id = Uuid::new_v4();
+    let secret = generate_secret();
+    let db = verifying_db(user_id, &secret, true).into_connection();
+
+    let error = JwtTokenService::verify_mfa_challenge(&db, &challenge_token(user_id, Uuid::new_v4()), &current_code(&secret), None)
+        .await
+        .expect_err("A spent challenge should be refused");
+
+    assert_eq!(error_message(error), "\"INVALID_MFA_CHALLENGE\"");
+}
+
+#[tokio::test]
+async fn test_verify_mfa_challenge_wrong_code() {
+    install_test_jwt_keys();
+    let user_id = Uuid::new_v4();
+    let secret = generate_secret();
+    // The recovery code lookup finds nothing, the failure is counted, the challenge is kept
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![confirmed_mfa(user_id, &secret)]])
+        .append_query_results([Vec::<login_attempts::Model>::new()])
+        .append_exec_results([exec(0)])
+        .append_query_results([vec![login_attempts::Model { key: "admin-mfa:account".to_owned(), failures: 1, last_failure_at: Utc::now(), locked_until: None }]])
+        .into_connection();
+
+    let error = JwtTokenService::verify_mfa_challenge(&db, &challenge_token(user_id, Uuid::new_v4()), "not-a-code", None)
+        .await
+        .expect_err("A wrong code should be refused");
+
+    assert_eq!(error_message(error), "\"INVALID_MFA_CODE\"");
+    let log = db.into_transaction_log();
+    assert!(
+        log.iter().all(|t| !format!("{:?}", t).contains("account_tokens")),
+        "The challenge should not be spent by a wrong code: {:?}",
+        log
+    );
+}
+
+#[tokio::test]
+async fn test_verify_mfa_challenge_invalid_token() {
+    install_test_jwt_keys();
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let error = JwtTokenService::verify_mfa_challenge(&db, "not-a-token", "123456", None)
+        .await
+        .expect_err("A malformed challenge should be refused");
+
+    assert_eq!(error_message(error), "\"INVALID_MFA_CHALLENGE\"");
+}
+
+#[tokio::test]
+async fn test_login_with_second_factor() {
+    install_test_jwt_keys();
+    let user_id = Uuid::new_v4();
+    let secret = generate_secret();
+    // Password step: throttle check, user lookup, throttle reset, second factor lookup
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<login_attempts::Model>::new()])
+        .append_query_results([vec![admin(user_id)]])
+        .append_exec_results([exec(1)])
+        .append_query_results([vec![confirmed_mfa(user_id, &secret)]])
+        .into_connection();
+
+    let login = JwtTokenService::generate_token(&db, "admin@example.com".to_owned(), "password123".to_owned(), None).await.unwrap();
+
+    assert!(login.tokens.is_none(), "No token should be issued before the second factor");
+    let challenge = login.mfa_challenge.expect("An MFA challenge should be issued");
+    assert!(!challenge.enrolment_required);
+    assert!(JwtTokenService::verify_token(&challenge.challenge_token).await.is_err(), "A challenge is not an access token");
+
+    let db = verifying_db(user_id, &secret, false).into_connection();
+    let tokens = JwtTokenService::verify_mfa_challenge(&db, &challenge.challenge_token, &current_code(&secret), None).await.unwrap();
+
+    assert_eq!(JwtTokenService::verify_token(&tokens.access_token).await.unwrap().sub, user_id);
+}
@@ -0,0 +1,116 @@
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{errors::interface::CustomGraphQLError, models::admin_entities, services::entities::*};
+
+fn entity(id: Uuid, name: &str) -> admin_entities::Model {
+    admin_entities::Model {
+        id,
+        name: name.to_string(),
+        description: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn error_message(error: Box<dyn CustomGraphQLError>) -> String {
+    error.new().extensions.and_then(|extensions| extensions.get("message").map(|message| message.to_string())).unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_register_entity_inserts_missing_entity() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .into_connection();
+
+    let registered = AdminEntitiesServiceImpl::register_entity(&db, "Ressource::Site", "Sites").await.unwrap_or_else(|_| panic!("Failed to register the entity"));
+    assert!(registered);
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"INSERT INTO "admin_entities""#), "Unexpected queries: {}", log);
+    assert!(log.contains(r#"ON CONFLICT ("name") DO NOTHING"#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_register_entity_keeps_existing_entity() {
+    // The name is taken, by a previous sync or by another instance
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 0 }])
+        .into_connection();
+
+    let registered = AdminEntitiesServiceImpl::register_entity(&db, "Ressource::Site", "Sites").await.unwrap_or_else(|_| panic!("Failed to register the entity"));
+    assert!(!registered);
+}
+
+#[tokio::test]
+async fn test_update_entity_refuses_to_rename_declared_entity() {
+    let id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![entity(id, "Ressource::User")]])
+        .into_connection();
+
+    let error = AdminEntitiesServiceImpl::update_entity(&db, id, Some("Ressource::Customer".to_string()), None).await.expect_err("Declared entity renamed");
+    assert_eq!(error_message(error), "\"ENTITY_DECLARED\"");
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("UPDATE"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_update_entity_describes_declared_entity() {
+    // Only the name is relied upon
+    let id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![entity(id, "Ressource::User")], vec![entity(id, "Ressource::User")], vec![entity(id, "Ressource::User")]])
+        .into_connection();
+
+    let updated = AdminEntitiesServiceImpl::update_entity(&db, id, Some("Ressource::User".to_string()), Some("Users".to_string())).await;
+    assert!(updated.is_ok());
+}
+
+#[tokio::test]
+async fn test_update_entity_renames_custom_entity() {
+    let id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![entity(id, "/admin/dashboard/invoices")], vec![], vec![entity(id, "/admin/dashboard/bills")]])
+        .into_connection();
+
+    let updated = AdminEntitiesServiceImpl::update_entity(&db, id, Some("/admin/dashboard/bills".to_string()), None).await;
+    assert_eq!(updated.map(|model| model.name).ok(), Some("/admin/dashboard/bills".to_string()));
+}
+
+#[tokio::test]
+async fn test_delete_entity_refuses_declared_entity() {
+    let id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![entity(id, "Ressource::Site")]])
+        .into_connection();
+
+    let error = AdminEntitiesServiceImpl::delete_entity(&db, id).await.expect_err("Declared entity deleted");
+    assert_eq!(error_message(error), "\"ENTITY_DECLARED\"");
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("DELETE"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_delete_entity_removes_grants_first() {
+    let id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![entity(id, "/admin/dashboard/invoices")]])
+        .append_exec_results([
+            MockExecResult { last_insert_id: 0, rows_affected: 2 },
+            MockExecResult { last_insert_id: 0, rows_affected: 0 },
+            MockExecResult { last_insert_id: 0, rows_affected: 1 },
+        ])
+        .into_connection();
+
+    let deleted = AdminEntitiesServiceImpl::delete_entity(&db, id).await.unwrap_or_else(|_| panic!("Failed to delete the entity"));
+    assert!(deleted);
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    let roles = log.find(r#"DELETE FROM "admin_roles_permissions_entities""#).expect("Role grants kept");
+    let entity = log.find(r#"DELETE FROM "admin_entities""#).expect("Entity kept");
+    assert!(roles < entity, "Unexpected queries: {}", log);
+}
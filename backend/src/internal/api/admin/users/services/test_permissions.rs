@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use sea_orm::{DatabaseBackend, MockDatabase, Value};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::services::permissions::*;
+
+fn row(user_id: Uuid, action: &str, entity: &str) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("user_id".to_owned(), Value::from(user_id)),
+        ("action".to_owned(), Value::from(action)),
+        ("entity".to_owned(), Value::from(entity)),
+    ])
+}
+
+#[tokio::test]
+async fn test_effective_permissions_by_user() {
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+    let carol = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![
+            row(alice, "can_read", "Ressource::User"),
+            row(alice, "can_update", "Ressource::User"),
+            row(bob, "can_read", "Ressource::Site"),
+        ]])
+        .into_connection();
+
+    let permissions = AdminPermissionServiceImpl::get_effective_permissions(&db, &[alice, bob, carol], None)
+        .await
+        .unwrap_or_else(|_| panic!("Failed to resolve the permissions"));
+
+    assert!(permissions[&alice].contains("can_read", "Ressource::User"));
+    assert!(permissions[&alice].contains("can_update", "Ressource::User"));
+    assert!(!permissions[&alice].contains("can_read", "Ressource::Site"));
+    assert!(permissions[&bob].contains("can_read", "Ressource::Site"));
+    // Users without any grant still get an (empty) set
+    assert_eq!(permissions[&carol].iter().count(), 0);
+}
+
+#[tokio::test]
+async fn test_effective_permissions_ignore_deleted_admins() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+        .into_connection();
+
+    let _ = AdminPermissionServiceImpl::get_effective_permissions(&db, &[Uuid::new_v4()], None).await;
+
+    // Role grants and direct grants both require a live account
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert_eq!(log.matches(r#"INNER JOIN "admin_users""#).count(), 2, "Unexpected query: {}", log);
+    assert_eq!(log.matches(r#""admin_users"."deleted_at" IS NULL"#).count(), 2, "Unexpected query: {}", log);
+}
+
+#[tokio::test]
+async fn test_effective_permissions_merge_roles_and_direct_grants() {
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+        .into_connection();
+
+    let _ = AdminPermissionServiceImpl::get_effective_permissions(&db, &[alice, bob], None).await;
+
+    // One statement for every user, role grants and direct grants deduplicated together
+    let log = db.into_transaction_log();
+    assert_eq!(log.len(), 1);
+    let sql = format!("{:?}", log[0]).replace("\\\"", "\"");
+    let roles = sql.find(r#"FROM "admin_roles_permissions_entities""#).expect("Role grants not queried");
+    let union = sql.find(" UNION (").expect("Grants not merged");
+    let users = sql.find(r#"FROM "admin_users_permissions_entities""#).expect("Direct grants not queried");
+    assert!(roles < union && union < users, "Unexpected query: {}", sql);
+    assert!(!sql.contains("UNION ALL"), "Unexpected query: {}", sql);
+    assert!(sql.contains(r#""admin_users_admin_roles"."admin_user_id" IN ($1, $2)"#), "Unexpected query: {}", sql);
+    assert!(sql.contains(r#""admin_users_permissions_entities"."user_id" IN ($"#), "Unexpected query: {}", sql);
+}
+
+#[tokio::test]
+async fn test_effective_permissions_on_a_site() {
+    let site_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+        .into_connection();
+
+    let _ = AdminPermissionServiceImpl::get_effective_permissions(&db, &[Uuid::new_v4()], Some(site_id)).await;
+
+    // Shared assignments plus the ones of the site, for roles and direct grants alike
+    let sql = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(sql.contains(r#"("admin_users_admin_roles"."site_id" IS NULL OR "admin_users_admin_roles"."site_id" = $"#), "Unexpected query: {}", sql);
+    assert!(sql.contains(r#"("admin_users_permissions_entities"."site_id" IS NULL OR "admin_users_permissions_entities"."site_id" = $"#), "Unexpected query: {}", sql);
+    assert_eq!(sql.matches(&site_id.to_string()).count(), 2, "Unexpected query: {}", sql);
+}
+
+#[tokio::test]
+async fn test_effective_permissions_without_site() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+        .into_connection();
+
+    let _ = AdminPermissionServiceImpl::get_effective_permissions(&db, &[Uuid::new_v4()], None).await;
+
+    // Only the assignments shared by every site count
+    let sql = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(sql.contains(r#""admin_users_admin_roles"."site_id" IS NULL"#), "Unexpected query: {}", sql);
+    assert!(!sql.contains(r#""admin_users_admin_roles"."site_id" ="#), "Unexpected query: {}", sql);
+    assert!(!sql.contains(r#""admin_users_permissions_entities"."site_id" ="#), "Unexpected query: {}", sql);
+}
@@ -0,0 +1,185 @@
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    errors::interface::CustomGraphQLError,
+    models::{admin_actions, admin_entities, admin_roles, admin_roles_actions_entities_assignements},
+    services::roles::*,
+};
+
+fn role(id: Uuid, name: &str) -> admin_roles::Model {
+    admin_roles::Model {
+        id,
+        name: name.to_owned(),
+        description: None,
+        require_mfa: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn action(id: Uuid, name: &str) -> admin_actions::Model {
+    admin_actions::Model { id, name: name.to_owned(), description: None, created_at: Utc::now(), updated_at: Utc::now() }
+}
+
+fn entity(id: Uuid, name: &str) -> admin_entities::Model {
+    admin_entities::Model { id, name: name.to_owned(), description: None, created_at: Utc::now(), updated_at: Utc::now() }
+}
+
+fn grant(role_id: Uuid, action_id: Uuid, entity_id: Uuid) -> admin_roles_actions_entities_assignements::Model {
+    admin_roles_actions_entities_assignements::Model { role_id, permission_id: action_id, entity_id }
+}
+
+fn error_message(error: Box<dyn CustomGraphQLError>) -> String {
+    error.new().extensions.and_then(|extensions| extensions.get("message").map(|message| message.to_string())).unwrap_or_default()
+}
+
+/// Mock answering the queries of `get_permission_matrix` for a role granted `grants`.
+fn matrix_queries(db: MockDatabase, role_id: Uuid, action: &admin_actions::Model, entity: &admin_entities::Model, grants: Vec<admin_roles_actions_entities_assignements::Model>) -> MockDatabase {
+    db.append_query_results([vec![role(role_id, "Editors")]])
+        .append_query_results([vec![action.clone()]])
+        .append_query_results([vec![entity.clone()]])
+        .append_query_results([grants])
+}
+
+#[tokio::test]
+async fn test_create_role_rejects_taken_name() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![role(Uuid::new_v4(), "Editors")]])
+        .into_connection();
+
+    let error = AdminRoleServiceImpl::create_role(&db, "Editors".to_owned(), None, false).await.expect_err("Role name taken twice");
+    assert_eq!(error_message(error), "\"ALREADY_EXISTS\"");
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("INSERT"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_create_role_requires_a_name() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let error = AdminRoleServiceImpl::create_role(&db, "  ".to_owned(), None, false).await.expect_err("Blank role name accepted");
+    assert_eq!(error_message(error), "\"INVALID_INPUT\"");
+}
+
+#[tokio::test]
+async fn test_delete_role_removes_grants_and_assignments_first() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([
+            MockExecResult { last_insert_id: 0, rows_affected: 3 },
+            MockExecResult { last_insert_id: 0, rows_affected: 2 },
+            MockExecResult { last_insert_id: 0, rows_affected: 1 },
+        ])
+        .into_connection();
+
+    let deleted = AdminRoleServiceImpl::delete_role(&db, Uuid::new_v4()).await.unwrap_or_else(|_| panic!("Failed to delete the role"));
+    assert!(deleted);
+
+    let log = db.into_transaction_log();
+    assert_eq!(log.len(), 1);
+    let transaction = format!("{:?}", log[0]).replace("\\\"", "\"");
+    let grants = transaction.find(r#"DELETE FROM "admin_roles_permissions_entities""#).expect("Grants kept");
+    let assignments = transaction.find(r#"DELETE FROM "admin_users_admin_roles""#).expect("Assignments kept");
+    let role = transaction.find(r#"DELETE FROM "admin_roles""#).expect("Role kept");
+    assert!(grants < role && assignments < role, "Unexpected queries: {}", transaction);
+}
+
+#[tokio::test]
+async fn test_permission_matrix_lists_every_cell() {
+    let (role_id, read, update, users) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![role(role_id, "Editors")]])
+        .append_query_results([vec![action(read, "can_read"), action(update, "can_update")]])
+        .append_query_results([vec![entity(users, "Ressource::User")]])
+        .append_query_results([vec![grant(role_id, read, users)]])
+        .into_connection();
+
+    let matrix = AdminRoleServiceImpl::get_permission_matrix(&db, role_id).await.unwrap_or_else(|_| panic!("Failed to build the matrix"));
+    assert_eq!(matrix.actions.len(), 2);
+    assert_eq!(matrix.entities.len(), 1);
+    assert!(matrix.grants.contains(&(read, users)));
+    assert!(!matrix.grants.contains(&(update, users)));
+}
+
+#[tokio::test]
+async fn test_permission_matrix_of_unknown_role() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<admin_roles::Model>::new()])
+        .into_connection();
+
+    let error = AdminRoleServiceImpl::get_permission_matrix(&db, Uuid::new_v4()).await.err().map(error_message);
+    assert_eq!(error.as_deref(), Some("\"RESOURCE_NOT_FOUND\""));
+}
+
+#[tokio::test]
+async fn test_grant_permission() {
+    let role_id = Uuid::new_v4();
+    let read = action(Uuid::new_v4(), "can_read");
+    let users = entity(Uuid::new_v4(), "Ressource::User");
+    let db = matrix_queries(MockDatabase::new(DatabaseBackend::Postgres), role_id, &read, &users, vec![])
+        .append_query_results([vec![read.clone()]])
+        .append_query_results([vec![users.clone()]])
+        .append_query_results([vec![grant(role_id, read.id, users.id)]])
+        .into_connection();
+
+    let matrix = AdminRoleServiceImpl::grant_permission(&db, role_id, "can_read", "Ressource::User").await.unwrap_or_else(|_| panic!("Failed to grant"));
+    assert!(matrix.grants.contains(&(read.id, users.id)));
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"INSERT INTO "admin_roles_permissions_entities""#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_grant_permission_already_granted() {
+    let role_id = Uuid::new_v4();
+    let read = action(Uuid::new_v4(), "can_read");
+    let users = entity(Uuid::new_v4(), "Ressource::User");
+    let db = matrix_queries(MockDatabase::new(DatabaseBackend::Postgres), role_id, &read, &users, vec![grant(role_id, read.id, users.id)])
+        .append_query_results([vec![read.clone()]])
+        .append_query_results([vec![users.clone()]])
+        .into_connection();
+
+    let matrix = AdminRoleServiceImpl::grant_permission(&db, role_id, "can_read", "Ressource::User").await.unwrap_or_else(|_| panic!("Failed to grant"));
+    assert!(matrix.grants.contains(&(read.id, users.id)));
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("INSERT"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_grant_permission_on_unknown_entity() {
+    let role_id = Uuid::new_v4();
+    let read = action(Uuid::new_v4(), "can_read");
+    let users = entity(Uuid::new_v4(), "Ressource::User");
+    let db = matrix_queries(MockDatabase::new(DatabaseBackend::Postgres), role_id, &read, &users, vec![])
+        .append_query_results([vec![read.clone()]])
+        .append_query_results([Vec::<admin_entities::Model>::new()])
+        .into_connection();
+
+    let error = AdminRoleServiceImpl::grant_permission(&db, role_id, "can_read", "Ressource::Invoice").await.err().map(error_message);
+    assert_eq!(error.as_deref(), Some("\"RESOURCE_NOT_FOUND\""));
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("INSERT"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_revoke_permission() {
+    let role_id = Uuid::new_v4();
+    let read = action(Uuid::new_v4(), "can_read");
+    let users = entity(Uuid::new_v4(), "Ressource::User");
+    let db = matrix_queries(MockDatabase::new(DatabaseBackend::Postgres), role_id, &read, &users, vec![grant(role_id, read.id, users.id)])
+        .append_query_results([vec![read.clone()]])
+        .append_query_results([vec![users.clone()]])
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .into_connection();
+
+    let matrix = AdminRoleServiceImpl::revoke_permission(&db, role_id, "can_read", "Ressource::User").await.unwrap_or_else(|_| panic!("Failed to revoke"));
+    assert!(matrix.grants.is_empty());
+
+    // Only this role's cell goes
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"DELETE FROM "admin_roles_permissions_entities" WHERE "admin_roles_permissions_entities"."role_id" = $1 AND "admin_roles_permissions_entities"."permission_id" = $2 AND "admin_roles_permissions_entities"."entity_id" = $3"#), "Unexpected queries: {}", log);
+}
@@ -0,0 +1,258 @@
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    errors::interface::CustomGraphQLError,
+    models::{admin_roles, admin_users, admin_users_roles, site},
+    services::users::*,
+};
+
+fn admin(id: Uuid) -> admin_users::Model {
+    admin_users::Model {
+        id,
+        username: "test_admin".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "admin".to_owned(),
+        email: "admin@example.com".to_owned(),
+        password: "hash".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        email_verified_at: None,
+        deleted_at: None,
+    }
+}
+
+fn role(id: Uuid) -> admin_roles::Model {
+    admin_roles::Model {
+        id,
+        name: "Editors".to_owned(),
+        description: None,
+        require_mfa: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn site(id: Uuid) -> site::Model {
+    site::Model {
+        id,
+        name: "Shop".to_owned(),
+        description: None,
+        domain: "shop.example.com".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn assignment(user_id: Uuid, role_id: Uuid, site_id: Option<Uuid>) -> admin_users_roles::Model {
+    admin_users_roles::Model {
+        admin_user_id: user_id,
+        role_admin_id: role_id,
+        site_id,
+    }
+}
+
+fn error_message(error: Box<dyn CustomGraphQLError>) -> String {
+    error.new().extensions.and_then(|extensions| extensions.get("message").map(|message| message.to_string())).unwrap_or_default()
+}
+
+fn create_input(email: &str, password: &str) -> CreateAdminUserInput {
+    CreateAdminUserInput {
+        username: " new_admin ".to_owned(),
+        first_name: "new".to_owned(),
+        last_name: "admin".to_owned(),
+        email: email.to_owned(),
+        password: password.to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn test_create_user_hashes_the_password() {
+    let id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<admin_users::Model>::new()]) // email available
+        .append_query_results([vec![admin(id)]])
+        .into_connection();
+
+    let created = AdminUserServiceImpl::create_user(&db, create_input("new@example.com", "correct horse")).await;
+    assert!(created.is_ok());
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"INSERT INTO "admin_users""#), "Unexpected queries: {}", log);
+    assert!(log.contains("String(Some(\"new_admin\"))"), "Username not trimmed: {}", log);
+    assert!(!log.contains("correct horse"), "Password stored in clear: {}", log);
+}
+
+#[tokio::test]
+async fn test_create_user_rejects_taken_email() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![admin(Uuid::new_v4())]])
+        .into_connection();
+
+    let error = AdminUserServiceImpl::create_user(&db, create_input("admin@example.com", "correct horse")).await.expect_err("Email taken twice");
+    assert_eq!(error_message(error), "\"ALREADY_EXISTS\"");
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("INSERT"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_create_user_validates_input() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let error = AdminUserServiceImpl::create_user(&db, create_input("not an email", "correct horse")).await.expect_err("Invalid email accepted");
+    assert_eq!(error_message(error), "\"INVALID_INPUT\"");
+    let error = AdminUserServiceImpl::create_user(&db, create_input("new@example.com", "short")).await.expect_err("Short password accepted");
+    assert_eq!(error_message(error), "\"INVALID_INPUT\"");
+
+    assert!(db.into_transaction_log().is_empty());
+}
+
+#[tokio::test]
+async fn test_update_unknown_user() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<admin_users::Model>::new()])
+        .into_connection();
+
+    let input = UpdateAdminUserInput { id: Uuid::new_v4(), username: Some("renamed".to_owned()), first_name: None, last_name: None, email: None, password: None };
+    let error = AdminUserServiceImpl::update_user(&db, input).await.expect_err("Unknown user updated");
+    assert_eq!(error_message(error), "\"USER_NOT_FOUND\"");
+
+    // Deleted admins are not found either
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#""admin_users"."deleted_at" IS NULL"#), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_assign_role_on_a_site() {
+    let (user_id, role_id, site_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![admin(user_id)]])
+        .append_query_results([vec![role(role_id)]])
+        .append_query_results([vec![site(site_id)]])
+        .append_query_results([Vec::<admin_users_roles::Model>::new()])
+        .append_query_results([vec![assignment(user_id, role_id, Some(site_id))]])
+        .into_connection();
+
+    let assigned = AdminUserServiceImpl::assign_role(&db, user_id, role_id, Some(site_id)).await.unwrap_or_else(|_| panic!("Failed to assign the role"));
+    assert!(assigned);
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"INSERT INTO "admin_users_admin_roles" ("admin_user_id", "role_admin_id", "site_id")"#), "Unexpected queries: {}", log);
+    assert_eq!(log.matches(&site_id.to_string()).count(), 2, "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_assign_role_moves_existing_assignment_to_site() {
+    let (user_id, role_id, site_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![admin(user_id)]])
+        .append_query_results([vec![role(role_id)]])
+        .append_query_results([vec![site(site_id)]])
+        .append_query_results([vec![assignment(user_id, role_id, None)]])
+        .append_query_results([vec![assignment(user_id, role_id, Some(site_id))]])
+        .into_connection();
+
+    let assigned = AdminUserServiceImpl::assign_role(&db, user_id, role_id, Some(site_id)).await.unwrap_or_else(|_| panic!("Failed to assign the role"));
+    assert!(assigned);
+
+    // One assignment per role: the shared one becomes site specific
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#"UPDATE "admin_users_admin_roles" SET "site_id" = $1"#), "Unexpected queries: {}", log);
+    assert!(!log.contains("INSERT"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_assign_role_already_assigned() {
+    let (user_id, role_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![admin(user_id)]])
+        .append_query_results([vec![role(role_id)]])
+        .append_query_results([vec![assignment(user_id, role_id, None)]])
+        .into_connection();
+
+    let assigned = AdminUserServiceImpl::assign_role(&db, user_id, role_id, None).await.unwrap_or_else(|_| panic!("Failed to assign the role"));
+    assert!(assigned);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("INSERT") && !log.contains("UPDATE"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_assign_role_on_unknown_site() {
+    let (user_id, role_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![admin(user_id)]])
+        .append_query_results([vec![role(role_id)]])
+        .append_query_results([Vec::<site::Model>::new()])
+        .into_connection();
+
+    let error = AdminUserServiceImpl::assign_role(&db, user_id, role_id, Some(Uuid::new_v4())).await.expect_err("Role assigned on an unknown site");
+    assert_eq!(error_message(error), "\"SITE_NOT_FOUND\"");
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("INSERT"), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_assign_unknown_role() {
+    let user_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![admin(user_id)]])
+        .append_query_results([Vec::<admin_roles::Model>::new()])
+        .into_connection();
+
+    let error = AdminUserServiceImpl::assign_role(&db, user_id, Uuid::new_v4(), None).await.expect_err("Unknown role assigned");
+    assert_eq!(error_message(error), "\"RESOURCE_NOT_FOUND\"");
+}
+
+#[tokio::test]
+async fn test_revoke_role() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([
+            MockExecResult { last_insert_id: 0, rows_affected: 1 },
+            MockExecResult { last_insert_id: 0, rows_affected: 0 },
+        ])
+        .into_connection();
+    let (user_id, role_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+    assert!(AdminUserServiceImpl::revoke_role(&db, user_id, role_id).await.unwrap_or_else(|_| panic!("Failed to revoke the role")));
+    // Not assigned anymore
+    assert!(!AdminUserServiceImpl::revoke_role(&db, user_id, role_id).await.unwrap_or_else(|_| panic!("Failed to revoke the role")));
+}
+
+#[tokio::test]
+async fn test_delete_user_revokes_sessions() {
+    let user_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([
+            MockExecResult { last_insert_id: 0, rows_affected: 1 },
+            MockExecResult { last_insert_id: 0, rows_affected: 2 },
+        ])
+        .into_connection();
+
+    let deleted = AdminUserServiceImpl::delete_user(&db, user_id).await.unwrap_or_else(|_| panic!("Failed to delete the user"));
+    assert!(deleted);
+
+    // The soft delete and the revocation commit together
+    let log = db.into_transaction_log();
+    assert_eq!(log.len(), 1);
+    let transaction = format!("{:?}", log[0]).replace("\\\"", "\"");
+    let deleted_at = transaction.find(r#"UPDATE "admin_users" SET "deleted_at""#).expect("No soft delete");
+    let revoked_at = transaction.find(r#"UPDATE "refresh_tokens" SET "revoked_at""#).expect("No revocation");
+    assert!(deleted_at < revoked_at);
+}
+
+#[tokio::test]
+async fn test_delete_unknown_user_revokes_nothing() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 0 }])
+        .into_connection();
+
+    let deleted = AdminUserServiceImpl::delete_user(&db, Uuid::new_v4()).await.unwrap_or_else(|_| panic!("Failed to delete the user"));
+    assert!(!deleted);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("refresh_tokens"), "Unexpected revocation: {}", log);
+}
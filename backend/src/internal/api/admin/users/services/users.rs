@@ -1,71 +1,169 @@
 use async_graphql::InputObject;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use chrono::Utc;
+use sea_orm::{prelude::DateTimeUtc, sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, Set, TransactionTrait};
 use async_trait::async_trait;
 use log::trace;
 use uuid::Uuid;
-use crate::internal::api::admin::users::{errors::{db::AdminDbError, interface::CustomGraphQLError, permission::AdminPermissionError, user::AdminUserAuthError}, models::{admin_users, admin_users_roles}, services::{actions::{AdminActionService, AdminActionServiceImpl}, entities::{AdminEntitiesService, AdminEntitiesServiceImpl}, permissions::{AdminPermissionService, AdminPermissionServiceImpl}}};
+use crate::internal::api::admin::users::{errors::{db::AdminDbError, interface::CustomGraphQLError, permission::AdminPermissionError, role::AdminRoleError, user::AdminUserAuthError, validation::AdminValidationError}, models::{admin_roles, admin_users, admin_users_actions_entities_assignements, admin_users_roles}, services::{permissions::{AdminPermissionService, AdminPermissionServiceImpl}, sites::{AdminSiteService, AdminSiteServiceImpl}, validation::{validate_email, validate_password, validate_required}}};
+use crate::internal::graphql::{
+    filters::{search_condition, DateRangeFilter, StringFilter, UuidFilter},
+    pagination::{paginate, Cursor, Keyset, Page, PageRequest, UserSortField},
+};
+use crate::internal::api::admin::users::services::auth::admin_token_audience;
+use crate::internal::security::{
+    account_tokens::{AccountTokenPurpose, AccountTokenService, AccountTokenServiceImpl},
+    models::{account_tokens, refresh_tokens},
+    password::PasswordService,
+    refresh::revoke_subject,
+};
 
-#[derive(InputObject)]
+/// Every field given must match. `and` and `or` nest further filters, `search` looks for
+/// words or substrings across the username, names and email.
+#[derive(InputObject, Default)]
 pub struct UserFilter {
-    pub id : Option<Uuid>,
-    pub email: Option<String>,
+    pub id: Option<UuidFilter>,
+    pub email: Option<StringFilter>,
+    pub username: Option<StringFilter>,
+    pub first_name: Option<StringFilter>,
+    pub last_name: Option<StringFilter>,
+    pub created_at: Option<DateRangeFilter>,
+    pub updated_at: Option<DateRangeFilter>,
+    pub search: Option<String>,
+    pub and: Option<Vec<UserFilter>>,
+    pub or: Option<Vec<UserFilter>>,
+}
+
+impl UserFilter {
+    pub fn condition(self) -> Condition {
+        let mut condition = Condition::all();
+
+        if let Some(id) = self.id {
+            condition = condition.add(id.condition(admin_users::Column::Id));
+        }
+        if let Some(email) = self.email {
+            condition = condition.add(email.condition(admin_users::Column::Email));
+        }
+        if let Some(username) = self.username {
+            condition = condition.add(username.condition(admin_users::Column::Username));
+        }
+        if let Some(first_name) = self.first_name {
+            condition = condition.add(first_name.condition(admin_users::Column::FirstName));
+        }
+        if let Some(last_name) = self.last_name {
+            condition = condition.add(last_name.condition(admin_users::Column::LastName));
+        }
+        if let Some(created_at) = self.created_at {
+            condition = condition.add(created_at.condition(admin_users::Column::CreatedAt));
+        }
+        if let Some(updated_at) = self.updated_at {
+            condition = condition.add(updated_at.condition(admin_users::Column::UpdatedAt));
+        }
+        if let Some(search) = self.search.filter(|search| !search.trim().is_empty()) {
+            // Same expressions as the indexes of the admin_users_search migration
+            condition = condition.add(search_condition(
+                r#""admin_users"."search_vector""#,
+                r#""admin_users"."username" || ' ' || "admin_users"."first_name" || ' ' || "admin_users"."last_name" || ' ' || "admin_users"."email""#,
+                search.trim(),
+            ));
+        }
+        if let Some(filters) = self.and {
+            condition = filters.into_iter().fold(condition, |condition, filter| condition.add(filter.condition()));
+        }
+        if let Some(filters) = self.or {
+            let any = filters.into_iter().fold(Condition::any(), |any, filter| any.add(filter.condition()));
+            condition = condition.add(any);
+        }
+
+        condition
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreateAdminUserInput {
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(InputObject)]
+pub struct UpdateAdminUserInput {
+    pub id: Uuid,
     pub username: Option<String>,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub password: Option<String>,
 }
 
 #[async_trait]
 pub trait AdminUserService {
-    async fn get_all_users(db: &DatabaseConnection, filter: Option<UserFilter>) -> Result<Vec<admin_users::Model>, Box<dyn CustomGraphQLError>>;
+    async fn get_all_users(db: &DatabaseConnection, filter: Option<UserFilter>, page: &PageRequest) -> Result<Page<admin_users::Model>, Box<dyn CustomGraphQLError>>;
     async fn get_user_by_id(db: &DatabaseConnection, user_id: Uuid) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>>;
     async fn get_user_by_email(db: &DatabaseConnection, email: &str) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>>;
     async fn get_user_roles(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<admin_users_roles::Model>, Box<dyn CustomGraphQLError>>;
-    async fn get_user_permissions_from_role<'a>(
-        db: &'a DatabaseConnection,
-        user_id: Uuid,
-        action: &'a str,
-        entities: &'a str,
-    ) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>>;
-
-    async fn get_user_permissions_from_user<'a>(
+    async fn update_user_password(db: &DatabaseConnection, user_id: Uuid, password_hash: String) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>>;
+    async fn create_user(db: &DatabaseConnection, input: CreateAdminUserInput) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>>;
+    async fn update_user(db: &DatabaseConnection, input: UpdateAdminUserInput) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>>;
+    /// Soft deletes the user, keeping their roles and grants until the purge so that
+    /// `restore_user` brings the account back as it was.
+    async fn delete_user(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    async fn restore_user(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    /// Hard deletes the users soft-deleted before `deleted_before`, with their roles, grants,
+    /// MFA enrolment and tokens. Returns how many users were purged.
+    async fn purge_deleted_users(db: &DatabaseConnection, deleted_before: DateTimeUtc) -> Result<u64, Box<dyn CustomGraphQLError>>;
+    /// Assigns the role on `site_id` only, or on every site when `None`. Assigning a role
+    /// the user already holds moves it to the given scope.
+    async fn assign_role(db: &DatabaseConnection, user_id: Uuid, role_id: Uuid, site_id: Option<Uuid>) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    async fn revoke_role(db: &DatabaseConnection, user_id: Uuid, role_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    async fn check_user_permission<'a>(
         db: &'a DatabaseConnection,
         user_id: Uuid,
         action: &'a str,
         entities: &'a str,
-    ) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>>;
+    ) -> Result<(), Box<dyn CustomGraphQLError>>;
 }
 
 pub struct AdminUserServiceImpl;
 
+impl Keyset for admin_users::Entity {
+    fn id_column() -> admin_users::Column {
+        admin_users::Column::Id
+    }
+
+    fn sort_column(field: UserSortField) -> admin_users::Column {
+        match field {
+            UserSortField::CreatedAt => admin_users::Column::CreatedAt,
+            UserSortField::Email => admin_users::Column::Email,
+            UserSortField::Username => admin_users::Column::Username,
+        }
+    }
+
+    fn cursor(user: &admin_users::Model, field: UserSortField) -> Cursor {
+        match field {
+            UserSortField::CreatedAt => Cursor::timestamp(field, user.id, user.created_at),
+            UserSortField::Email => Cursor::text(field, user.id, &user.email),
+            UserSortField::Username => Cursor::text(field, user.id, &user.username),
+        }
+    }
+}
+
 
 #[async_trait]
 impl AdminUserService for AdminUserServiceImpl {
-    async fn get_all_users(db: &DatabaseConnection, filter: Option<UserFilter>) -> Result<Vec<admin_users::Model>, Box<dyn CustomGraphQLError>> {
-        trace!("Fetching all users");
+    async fn get_all_users(db: &DatabaseConnection, filter: Option<UserFilter>, page: &PageRequest) -> Result<Page<admin_users::Model>, Box<dyn CustomGraphQLError>> {
+        trace!("Fetching users, page: {:?}", page);
 
-        let mut query = admin_users::Entity::find();
+        let mut query = admin_users::Entity::find_active();
 
         if let Some(filter) = filter {
-            if let Some(id) = filter.id {
-                query = query.filter(admin_users::Column::Id.eq(id));
-            }
-            if let Some(email) = filter.email {
-                query = query.filter(admin_users::Column::Email.eq(email));
-            }
-            if let Some(username) = filter.username {
-                query = query.filter(admin_users::Column::Username.eq(username));
-            }
-            if let Some(first_name) = filter.first_name {
-                query = query.filter(admin_users::Column::FirstName.eq(first_name));
-            }
-            if let Some(last_name) = filter.last_name {
-                query = query.filter(admin_users::Column::LastName.eq(last_name));
-            }
+            query = query.filter(filter.condition());
         }
 
-         match query.all(db).await {
+        match paginate(db, query, page).await {
             Ok(users) => {
-                trace!("Users found: {:?}", users);
+                trace!("Users found: {:?}", users.items);
                 Ok(users)
             },
             Err(e) => Err(Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>),
@@ -73,7 +171,8 @@ impl AdminUserService for AdminUserServiceImpl {
     }
 
     async fn get_user_by_id(db: &DatabaseConnection, user_id: Uuid) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>> {
-        admin_users::Entity::find_by_id(user_id)
+        admin_users::Entity::find_active()
+            .filter(admin_users::Column::Id.eq(user_id))
             .one(db)
             .await
             .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
@@ -81,7 +180,7 @@ impl AdminUserService for AdminUserServiceImpl {
     }
 
     async fn get_user_by_email(db: &DatabaseConnection, email: &str) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>> {
-        admin_users::Entity::find()
+        admin_users::Entity::find_active()
             .filter(admin_users::Column::Email.eq(email))
             .one(db)
             .await
@@ -97,122 +196,284 @@ impl AdminUserService for AdminUserServiceImpl {
             .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
     }
 
-    async fn get_user_permissions_from_role<'a>(
-        db: &'a DatabaseConnection,
-        user_id: Uuid,
-        action: &'a str,
-        entities: &'a str,
-    ) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>> {
-        trace!("Fetching roles for user_id: {}", user_id);
-
-        let user_roles: Result<Vec<admin_users_roles::Model>, Box<dyn CustomGraphQLError>> = match AdminUserServiceImpl::get_user_roles(db, user_id).await {
-            Ok(user_roles) => {
-                if user_roles.is_empty() {
-                    Err(Box::new(AdminPermissionError::PermissionDenied("User has no roles assigned".to_string())) as Box<dyn CustomGraphQLError>)
-                } else {
-                trace!("get_user_permissions: user_roles: {:?}", user_roles);
-                    Ok(user_roles)
-                }
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        };
+    async fn update_user_password(db: &DatabaseConnection, user_id: Uuid, password_hash: String) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Updating password hash for admin user {}", user_id);
 
-        let action_id: Result<Uuid, Box<dyn CustomGraphQLError>> = match AdminActionServiceImpl::get_action_id_by_name(db, action).await {
-            Ok(action_id) => {
-                trace!("get_user_permissions: action_id: {}", action_id);
-                Ok(action_id)
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        };
+        let mut user: admin_users::ActiveModel = AdminUserServiceImpl::get_user_by_id(db, user_id).await?.into();
+        user.password = Set(password_hash);
+        user.updated_at = Set(Utc::now());
 
-        let entity_id: Result<Uuid, Box<dyn CustomGraphQLError>> = match AdminEntitiesServiceImpl::get_entity_id_by_name(db, entities).await {
-            Ok(entity_id) => {
-                trace!("get_user_permissions: entity_id: {}", entity_id);
-                Ok(entity_id)
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        };
+        user.update(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
 
-        match AdminPermissionServiceImpl::get_permissions_for_roles(db, &user_roles?, action_id?, entity_id?).await {
-            Ok(permissions) => {
-                if permissions.is_empty() {
-                    return Err(Box::new(AdminPermissionError::PermissionDenied("No permissions found for the user".to_string())));
-                } else {
-                    trace!("get_user_permissions: permissions: {:?}", permissions);
-                }
-            }
-            Err(e) => {
-                return Err(e);
-            }
+    async fn create_user(db: &DatabaseConnection, input: CreateAdminUserInput) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Creating admin user with username: '{}', email: '{}'", input.username, input.email);
+
+        validate_required("username", &input.username)?;
+        validate_required("first_name", &input.first_name)?;
+        validate_required("last_name", &input.last_name)?;
+        validate_email(&input.email)?;
+        validate_password(&input.password)?;
+        ensure_email_available(db, &input.email, None).await?;
+
+        let new_user = admin_users::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            username: Set(input.username.trim().to_string()),
+            first_name: Set(input.first_name.trim().to_string()),
+            last_name: Set(input.last_name.trim().to_string()),
+            email: Set(input.email.trim().to_string()),
+            password: Set(hash_password(&input.password)?),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+            email_verified_at: Set(None),
+            deleted_at: Set(None),
         };
 
-        match AdminUserServiceImpl::get_user_by_id(db, user_id).await {
-            Ok(user) => {
-                trace!("get_user_permissions: user: {:?}", user);
-                Ok(user)
+        new_user.insert(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn update_user(db: &DatabaseConnection, input: UpdateAdminUserInput) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>> {
+        trace!("Updating admin user with id: '{}'", input.id);
+
+        let mut user: admin_users::ActiveModel = AdminUserServiceImpl::get_user_by_id(db, input.id).await?.into();
+
+        if let Some(username) = input.username {
+            validate_required("username", &username)?;
+            user.username = Set(username.trim().to_string());
+        }
+        if let Some(first_name) = input.first_name {
+            validate_required("first_name", &first_name)?;
+            user.first_name = Set(first_name.trim().to_string());
+        }
+        if let Some(last_name) = input.last_name {
+            validate_required("last_name", &last_name)?;
+            user.last_name = Set(last_name.trim().to_string());
+        }
+        if let Some(email) = input.email {
+            validate_email(&email)?;
+            ensure_email_available(db, &email, Some(input.id)).await?;
+            // A new address has to be verified again, links sent to the old one are void
+            if user.email.as_ref() != email.trim() {
+                user.email_verified_at = Set(None);
+                AccountTokenServiceImpl::revoke(db, input.id, &admin_token_audience(), AccountTokenPurpose::EmailVerification).await?;
             }
-            Err(e) => {
-                return Err(e);
+            user.email = Set(email.trim().to_string());
+        }
+        if let Some(password) = input.password {
+            validate_password(&password)?;
+            user.password = Set(hash_password(&password)?);
+        }
+        user.updated_at = Set(Utc::now());
+
+        user.update(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn delete_user(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Deleting admin user with id: {}", user_id);
+
+        let txn = db.begin()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let result = admin_users::Entity::update_many()
+            .col_expr(admin_users::Column::DeletedAt, Expr::value(Utc::now()))
+            .filter(admin_users::Column::Id.eq(user_id))
+            .filter(admin_users::Column::DeletedAt.is_null())
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        // Roles are kept for a restore, the sessions are not: a restored admin logs in again
+        if result.rows_affected > 0 {
+            revoke_subject(&txn, user_id, &admin_token_audience()).await?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn restore_user(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Restoring admin user with id: {}", user_id);
+
+        let result = admin_users::Entity::update_many()
+            .col_expr(admin_users::Column::DeletedAt, Expr::value(Option::<DateTimeUtc>::None))
+            .col_expr(admin_users::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(admin_users::Column::Id.eq(user_id))
+            .filter(admin_users::Column::DeletedAt.is_not_null())
+            .exec(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn purge_deleted_users(db: &DatabaseConnection, deleted_before: DateTimeUtc) -> Result<u64, Box<dyn CustomGraphQLError>> {
+        let txn = db.begin()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        // Locked, so that a concurrent restore waits for the purge
+        let ids: Vec<Uuid> = admin_users::Entity::find()
+            .select_only()
+            .column(admin_users::Column::Id)
+            .filter(admin_users::Column::DeletedAt.lt(deleted_before))
+            .lock_exclusive()
+            .into_tuple()
+            .all(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        trace!("Purging {} deleted admin user(s)", ids.len());
+
+        // Role and direct grants reference the user, they go first. MFA rows cascade.
+        admin_users_roles::Entity::delete_many()
+            .filter(admin_users_roles::Column::AdminUserId.is_in(ids.clone()))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        admin_users_actions_entities_assignements::Entity::delete_many()
+            .filter(admin_users_actions_entities_assignements::Column::UserId.is_in(ids.clone()))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        refresh_tokens::Entity::delete_many()
+            .filter(refresh_tokens::Column::SubjectId.is_in(ids.clone()))
+            .filter(refresh_tokens::Column::Audience.eq(admin_token_audience()))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        account_tokens::Entity::delete_many()
+            .filter(account_tokens::Column::SubjectId.is_in(ids.clone()))
+            .filter(account_tokens::Column::Audience.eq(admin_token_audience()))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let result = admin_users::Entity::delete_many()
+            .filter(admin_users::Column::Id.is_in(ids))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        txn.commit()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(result.rows_affected)
+    }
+
+    async fn assign_role(db: &DatabaseConnection, user_id: Uuid, role_id: Uuid, site_id: Option<Uuid>) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Assigning role {} to admin user {}", role_id, user_id);
+
+        AdminUserServiceImpl::get_user_by_id(db, user_id).await?;
+
+        admin_roles::Entity::find_by_id(role_id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(AdminRoleError::NotFound(role_id.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        if let Some(site_id) = site_id {
+            AdminSiteServiceImpl::get_site_by_id(db, site_id).await?;
+        }
+
+        let existing = AdminUserServiceImpl::get_user_roles(db, user_id)
+            .await?
+            .into_iter()
+            .find(|role| role.role_admin_id == role_id);
+        if let Some(existing) = existing {
+            if existing.site_id == site_id {
+                trace!("Role {} already assigned to admin user {}", role_id, user_id);
+                return Ok(true);
             }
+
+            trace!("Moving role {} of admin user {} to site {:?}", role_id, user_id, site_id);
+            let mut assignment: admin_users_roles::ActiveModel = existing.into();
+            assignment.site_id = Set(site_id);
+            assignment.update(db)
+                .await
+                .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+            return Ok(true);
         }
+
+        admin_users_roles::ActiveModel {
+            admin_user_id: Set(user_id),
+            role_admin_id: Set(role_id),
+            site_id: Set(site_id),
+        }
+        .insert(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(true)
     }
 
-    async fn get_user_permissions_from_user<'a>(
+    async fn revoke_role(db: &DatabaseConnection, user_id: Uuid, role_id: Uuid) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Revoking role {} from admin user {}", role_id, user_id);
+
+        let result = admin_users_roles::Entity::delete_many()
+            .filter(admin_users_roles::Column::AdminUserId.eq(user_id))
+            .filter(admin_users_roles::Column::RoleAdminId.eq(role_id))
+            .exec(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn check_user_permission<'a>(
         db: &'a DatabaseConnection,
         user_id: Uuid,
         action: &'a str,
         entities: &'a str,
-    ) -> Result<admin_users::Model, Box<dyn CustomGraphQLError>> {
-        trace!("Fetching roles for user_id: {}", user_id);
-
-
-        let action_id: Result<Uuid, Box<dyn CustomGraphQLError>> = match AdminActionServiceImpl::get_action_id_by_name(db, action).await {
-            Ok(action_id) => {
-                trace!("get_user_permissions: action_id: {}", action_id);
-                Ok(action_id)
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        };
-
-        let entity_id: Result<Uuid, Box<dyn CustomGraphQLError>> = match AdminEntitiesServiceImpl::get_entity_id_by_name(db, entities).await {
-            Ok(entity_id) => {
-                trace!("get_user_permissions: entity_id: {}", entity_id);
-                Ok(entity_id)
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        };
+    ) -> Result<(), Box<dyn CustomGraphQLError>> {
+        let permissions = AdminPermissionServiceImpl::get_effective_permissions(db, &[user_id], None).await?;
 
-        match AdminPermissionServiceImpl::get_permissions_for_user(db, user_id, action_id?, entity_id?).await {
-            Ok(permissions) => {
-                if permissions.is_empty() {
-                    return Err(Box::new(AdminPermissionError::PermissionDenied("No permissions found for the user".to_string())));
-                } else {
-                    trace!("get_user_permissions: permissions: {:?}", permissions);
-                }
+        match permissions.get(&user_id) {
+            Some(permissions) if permissions.contains(action, entities) => {
+                trace!("check_user_permission: User {:?} can {} {}", user_id, action, entities);
+                Ok(())
             }
-            Err(e) => {
-                return Err(e);
+            _ => {
+                trace!("check_user_permission: User {:?} cannot {} {}", user_id, action, entities);
+                Err(Box::new(AdminPermissionError::PermissionDenied(format!("{} on {}", action, entities))))
             }
         }
+    }
+}
 
-        match AdminUserServiceImpl::get_user_by_id(db, user_id).await {
-            Ok(user) => {
-                trace!("get_user_permissions: user: {:?}", user);
-                Ok(user)
-            }
-            Err(e) => {
-                return Err(e);
-            }
+async fn ensure_email_available(db: &DatabaseConnection, email: &str, current_user: Option<Uuid>) -> Result<(), Box<dyn CustomGraphQLError>> {
+    // Deleted accounts keep their email until purged, so that they can be restored
+    let existing = admin_users::Entity::find()
+        .filter(admin_users::Column::Email.eq(email.trim()))
+        .one(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    match existing {
+        Some(user) if Some(user.id) != current_user => {
+            Err(Box::new(AdminValidationError::AlreadyExists(format!("admin user with email '{}'", email))))
         }
+        _ => Ok(()),
     }
 }
+
+fn hash_password(password: &str) -> Result<String, Box<dyn CustomGraphQLError>> {
+    PasswordService::from_env()
+        .hash(password)
+        .map_err(|e| Box::new(AdminUserAuthError::UnexpectedError(format!("Failed to hash password: {}", e))) as Box<dyn CustomGraphQLError>)
+}
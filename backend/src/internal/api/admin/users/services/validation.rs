@@ -0,0 +1,32 @@
+use crate::internal::api::admin::users::errors::{interface::CustomGraphQLError, validation::AdminValidationError};
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Returns the trimmed value, rejecting blank ones.
+pub fn validate_required(field: &str, value: &str) -> Result<String, Box<dyn CustomGraphQLError>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(Box::new(AdminValidationError::invalid_field(field, "This field cannot be empty.")));
+    }
+    Ok(value.to_string())
+}
+
+pub fn validate_email(email: &str) -> Result<(), Box<dyn CustomGraphQLError>> {
+    let email = email.trim();
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.'),
+        None => false,
+    };
+
+    if !valid || email.contains(char::is_whitespace) {
+        return Err(Box::new(AdminValidationError::invalid_field("email", "The email address is not valid.")));
+    }
+    Ok(())
+}
+
+pub fn validate_password(password: &str) -> Result<(), Box<dyn CustomGraphQLError>> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(Box::new(AdminValidationError::invalid_field("password", "The password must be at least 8 characters long.")));
+    }
+    Ok(())
+}
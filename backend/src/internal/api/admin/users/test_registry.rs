@@ -0,0 +1,64 @@
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{models::admin_actions, registry::*};
+
+fn action(name: &str) -> admin_actions::Model {
+    admin_actions::Model { id: Uuid::new_v4(), name: name.to_owned(), description: None, created_at: Utc::now(), updated_at: Utc::now() }
+}
+
+fn inserted(rows_affected: u64) -> MockExecResult {
+    MockExecResult { last_insert_id: 0, rows_affected }
+}
+
+#[tokio::test]
+async fn test_sync_registry_leaves_existing_rows() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(DECLARED_ACTIONS.iter().map(|declared| vec![action(declared.name)]))
+        .append_exec_results(DECLARED_ENTITIES.iter().map(|_| inserted(0)))
+        .into_connection();
+
+    assert!(sync_registry(&db).await.is_ok());
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(!log.contains(r#"INSERT INTO "admin_actions""#), "Unexpected queries: {}", log);
+    assert!(!log.contains("UPDATE") && !log.contains("DELETE"), "Unexpected queries: {}", log);
+    assert_eq!(log.matches(r#"ON CONFLICT ("name") DO NOTHING"#).count(), DECLARED_ENTITIES.len(), "Unexpected queries: {}", log);
+}
+
+#[tokio::test]
+async fn test_sync_registry_registers_missing_rows() {
+    // The last declared action is missing: looked up, its name checked, then inserted
+    let missing = DECLARED_ACTIONS.len() - 1;
+    let mut lookups: Vec<Vec<admin_actions::Model>> = DECLARED_ACTIONS.iter().map(|declared| vec![action(declared.name)]).collect();
+    lookups[missing] = vec![];
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(lookups)
+        .append_query_results([vec![], vec![action(DECLARED_ACTIONS[missing].name)]])
+        .append_exec_results(DECLARED_ENTITIES.iter().map(|_| inserted(1)))
+        .into_connection();
+
+    assert!(sync_registry(&db).await.is_ok());
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert_eq!(log.matches(r#"INSERT INTO "admin_actions""#).count(), 1, "Unexpected queries: {}", log);
+    assert!(log.contains(&format!("String(Some(\"{}\"))", DECLARED_ACTIONS[missing].name)), "Unexpected queries: {}", log);
+    assert_eq!(log.matches(r#"INSERT INTO "admin_entities""#).count(), DECLARED_ENTITIES.len(), "Unexpected queries: {}", log);
+}
+
+#[test]
+fn test_declarations_are_unique() {
+    for declarations in [DECLARED_ACTIONS, DECLARED_ENTITIES] {
+        for (index, declaration) in declarations.iter().enumerate() {
+            assert!(!declarations[index + 1..].iter().any(|other| other.name == declaration.name), "{} declared twice", declaration.name);
+        }
+    }
+}
+
+#[test]
+fn test_is_declared() {
+    assert!(is_declared(DECLARED_ENTITIES, "Ressource::AuditLog"));
+    assert!(is_declared(DECLARED_ACTIONS, "can_read"));
+    assert!(!is_declared(DECLARED_ENTITIES, "Ressource::Invoice"));
+}
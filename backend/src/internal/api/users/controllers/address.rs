@@ -0,0 +1,184 @@
+use std::sync::Arc;
+use async_graphql::{Context, Error, Object, SimpleObject, InputObject};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+use log::{error, trace};
+use crate::internal::{
+    api::{
+        admin::users::errors::interface::CustomGraphQLError,
+        users::{
+            errors::{address::AddressError, users::UserError},
+            models::address,
+            services::address::{AddressService, AddressServiceImpl},
+        },
+    },
+    graphql::auth::user_claims,
+};
+
+#[derive(SimpleObject)]
+pub struct Address {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub street: String,
+    pub city: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+impl From<address::Model> for Address {
+    fn from(a: address::Model) -> Self {
+        Address {
+            id: a.id,
+            user_id: a.user_id,
+            street: a.street,
+            city: a.city,
+            postal_code: a.postal_code,
+            country: a.country,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreateAddressInput {
+    pub user_id: Uuid,
+    pub street: String,
+    pub city: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+#[derive(InputObject)]
+pub struct UpdateAddressInput {
+    pub id: Uuid,
+    pub street: Option<String>,
+    pub city: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+}
+
+/// The address when it belongs to `user_id`. The addresses of other users are reported
+/// as not found, like unknown ones, so that their existence does not leak.
+pub(crate) async fn owned_address(db: &DatabaseConnection, id: Uuid, user_id: Uuid) -> async_graphql::Result<address::Model> {
+    match AddressServiceImpl::get_address(db, id).await {
+        Ok(Some(address)) if address.user_id == user_id => Ok(address),
+        Ok(_) => Err(AddressError::AddressNotFound(id.to_string()).new()),
+        Err(e) => Err(AddressError::from(e).new()),
+    }
+}
+
+/// Addresses of `user_id`, which only the user themselves can list.
+pub(crate) async fn addresses_of(db: &DatabaseConnection, user_id: Uuid, caller_id: Uuid) -> async_graphql::Result<Vec<Address>> {
+    if user_id != caller_id {
+        return Err(AddressError::AddressNotFound(format!("addresses of user {}", user_id)).new());
+    }
+
+    match AddressServiceImpl::get_addresses_by_user(db, user_id).await {
+        Ok(addresses) => Ok(addresses.into_iter().map(Address::from).collect()),
+        Err(e) => Err(AddressError::from(e).new()),
+    }
+}
+
+#[derive(Default)]
+pub struct AddressQuery;
+
+#[Object]
+impl AddressQuery {
+    /// Fails with `ADDRESS_NOT_FOUND` unless the address belongs to the caller.
+    async fn address(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<Address>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching address with id: {}", id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        owned_address(db.as_ref(), id, claims.sub).await.map(|a| Some(Address::from(a)))
+    }
+
+    /// Fails with `ADDRESS_NOT_FOUND` unless `userId` is the caller.
+    async fn addresses(&self, ctx: &Context<'_>, user_id: Uuid) -> async_graphql::Result<Vec<Address>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching addresses of user: {}", user_id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        addresses_of(db.as_ref(), user_id, claims.sub).await
+    }
+}
+
+#[derive(Default)]
+pub struct AddressMutation;
+
+#[Object]
+impl AddressMutation {
+    /// Users can only add addresses to their own account, `USER_NOT_FOUND` otherwise.
+    async fn create_address(&self, ctx: &Context<'_>, input: CreateAddressInput) -> async_graphql::Result<Address> {
+        let claims = user_claims(ctx)?;
+        trace!("Creating address for user: '{}'", input.user_id);
+        if input.user_id != claims.sub {
+            return Err(UserError::UserNotFound(input.user_id.to_string()).new());
+        }
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        match AddressServiceImpl::create_address(db.as_ref(), input.user_id, input.street, input.city, input.postal_code, input.country).await {
+            Ok(address) => Ok(Address::from(address)),
+            Err(e) => {
+                error!("Failed to create address for user '{}': {}", input.user_id, e);
+                Err(AddressError::from(e).new())
+            }
+        }
+    }
+
+    /// Fails with `ADDRESS_NOT_FOUND` unless the address belongs to the caller.
+    async fn update_address(&self, ctx: &Context<'_>, input: UpdateAddressInput) -> async_graphql::Result<Address> {
+        let claims = user_claims(ctx)?;
+        trace!("Updating address with id: '{}'", input.id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+        owned_address(db.as_ref(), input.id, claims.sub).await?;
+
+        match AddressServiceImpl::update_address(db.as_ref(), input.id, input.street, input.city, input.postal_code, input.country).await {
+            Ok(address) => Ok(Address::from(address)),
+            Err(e) => {
+                error!("Failed to update address with id '{}': {}", input.id, e);
+                Err(AddressError::from(e).new())
+            }
+        }
+    }
+
+    /// Fails with `ADDRESS_NOT_FOUND` unless the address belongs to the caller.
+    async fn delete_address(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let claims = user_claims(ctx)?;
+        trace!("Deleting address with id: {}", id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+        owned_address(db.as_ref(), id, claims.sub).await?;
+
+        match AddressServiceImpl::delete_address(db.as_ref(), id).await {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                error!("Failed to delete address with id '{}': {}", id, e);
+                Err(AddressError::from(e).new())
+            }
+        }
+    }
+}
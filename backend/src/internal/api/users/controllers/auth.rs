@@ -0,0 +1,133 @@
+use std::sync::Arc;
+use log::trace;
+use sea_orm::DatabaseConnection;
+use async_graphql::{Context, InputObject, Object};
+
+use crate::internal::{
+    api::{
+        admin::users::errors::interface::CustomGraphQLError,
+        users::{
+            controllers::users::User,
+            errors::{auth::UserAuthError, users::UserError},
+            services::{
+                account::{UserAccountService, UserAccountServiceImpl},
+                auth::{UserJwtTokenService, UserTokenService},
+                users::{UserService, UserServiceImpl},
+            },
+        },
+    },
+    graphql::{auth::user_claims, client::ClientInfo, site::current_site_id},
+    mail::mailer,
+    security::refresh::TokenPair,
+};
+
+#[derive(InputObject)]
+pub struct LoginInput {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Default)]
+pub struct AuthUserQuery;
+
+#[Object]
+impl AuthUserQuery {
+    async fn me(&self, ctx: &Context<'_>) -> async_graphql::Result<User> {
+        let claims = user_claims(ctx)?;
+        trace!("me: Resolving user {:?}", claims.sub);
+
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(UserError::DatabaseError(format!("{:?}", e)).new());
+            }
+        };
+
+        match UserServiceImpl::get_user(db.as_ref(), claims.sub).await {
+            Ok(Some(u)) => Ok(User {
+                id: u.id,
+                username: u.username,
+                first_name: u.first_name,
+                last_name: u.last_name,
+                email: u.email,
+            }),
+            Ok(None) => Err(UserAuthError::UserNotFound(claims.sub.to_string()).new()),
+            Err(e) => Err(UserError::from(e).new()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AuthUserMutation;
+
+#[Object]
+impl AuthUserMutation {
+    async fn login(&self, ctx: &Context<'_>, input: LoginInput) -> async_graphql::Result<TokenPair> {
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => {
+                trace!("Login: Database connection found");
+                db
+            },
+            Err(e) => {
+                return Err(UserError::DatabaseError(format!("{:?}", e)).new());
+            }
+        };
+
+        match UserJwtTokenService::login(db.as_ref(), input.email, input.password, current_site_id(ctx)).await {
+            Ok(tokens) => {
+                trace!("Login: Token generated successfully");
+                Ok(tokens)
+            },
+            Err(e) => Err(e.new()),
+        }
+    }
+
+    async fn refresh_token(&self, ctx: &Context<'_>, refresh_token: String) -> async_graphql::Result<TokenPair> {
+        let db = database(ctx)?;
+        UserJwtTokenService::refresh_token(db.as_ref(), &refresh_token).await.map_err(|e| e.new())
+    }
+
+    async fn logout(&self, ctx: &Context<'_>, refresh_token: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        UserJwtTokenService::logout(db.as_ref(), &refresh_token).await.map_err(|e| e.new())
+    }
+
+    /// Always succeeds, whether the email belongs to a user of this site or not.
+    async fn request_password_reset(&self, ctx: &Context<'_>, email: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let address = ctx.data_opt::<ClientInfo>().and_then(|client| client.ip_address.clone());
+
+        UserAccountServiceImpl::request_password_reset(db.as_ref(), mailer(ctx)?.as_ref(), &email, current_site_id(ctx), address.as_deref())
+            .await
+            .map_err(|e| e.new())?;
+        Ok(true)
+    }
+
+    async fn reset_password(&self, ctx: &Context<'_>, token: String, password: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+
+        UserAccountServiceImpl::reset_password(db.as_ref(), &token, &password).await.map_err(|e| e.new())?;
+        Ok(true)
+    }
+
+    async fn request_email_verification(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+        let claims = user_claims(ctx)?;
+
+        UserAccountServiceImpl::request_email_verification(db.as_ref(), mailer(ctx)?.as_ref(), claims.sub)
+            .await
+            .map_err(|e| e.new())?;
+        Ok(true)
+    }
+
+    async fn verify_email(&self, ctx: &Context<'_>, token: String) -> async_graphql::Result<bool> {
+        let db = database(ctx)?;
+
+        UserAccountServiceImpl::verify_email(db.as_ref(), &token).await.map_err(|e| e.new())?;
+        Ok(true)
+    }
+}
+
+fn database<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Arc<DatabaseConnection>> {
+    ctx.data::<Arc<DatabaseConnection>>().map_err(|e| UserError::DatabaseError(format!("{:?}", e)).new())
+}
@@ -0,0 +1,222 @@
+use std::sync::Arc;
+use async_graphql::{ComplexObject, Context, Error, InputObject, Object, SimpleObject};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+use log::{error, trace};
+use crate::internal::{
+    api::{
+        admin::users::{errors::interface::CustomGraphQLError, services::sites::is_visible_on},
+        users::{
+            errors::{auth::UserAuthError, membership::MembershipError, organisation::OrganisationError, users::UserError},
+            models::organisation_members,
+            services::{
+                membership::{MembershipService, MembershipServiceImpl},
+                organisation::{OrganisationService, OrganisationServiceImpl},
+                roles::{RoleService, RoleServiceImpl},
+                users::{UserService, UserServiceImpl},
+            },
+        },
+    },
+    graphql::{auth::user_claims, site::current_site_id},
+};
+use super::{organisation::Organisation, roles::Role, users::User};
+
+/// Membership of a user in an organisation, or a pending invitation while `accepted` is false.
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Membership {
+    pub id: Uuid,
+    pub organisation_id: Uuid,
+    pub user_id: Uuid,
+    pub role_id: Option<Uuid>,
+    pub invited_by: Option<Uuid>,
+    pub accepted: bool,
+}
+
+impl From<organisation_members::Model> for Membership {
+    fn from(m: organisation_members::Model) -> Self {
+        Membership {
+            id: m.id,
+            organisation_id: m.organisation_id,
+            user_id: m.user_id,
+            role_id: m.role_id,
+            invited_by: m.invited_by,
+            accepted: m.accepted_at.is_some(),
+        }
+    }
+}
+
+fn database<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Arc<DatabaseConnection>> {
+    ctx.data::<Arc<DatabaseConnection>>()
+        .map_err(|e| Error::new(format!("Failed to access database connection in context with error {:?}", e)))
+}
+
+#[ComplexObject]
+impl Membership {
+    async fn organisation(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Organisation>> {
+        let db = database(ctx)?;
+
+        match OrganisationServiceImpl::get_organisation(db.as_ref(), self.organisation_id).await {
+            Ok(organisation) => Ok(organisation.map(Organisation::from)),
+            Err(e) => Err(OrganisationError::from(e).new()),
+        }
+    }
+
+    async fn user(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<User>> {
+        let db = database(ctx)?;
+
+        match UserServiceImpl::get_user(db.as_ref(), self.user_id).await {
+            Ok(user) => Ok(user.map(User::from)),
+            Err(e) => Err(UserError::from(e).new()),
+        }
+    }
+
+    async fn role(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Role>> {
+        let role_id = match self.role_id {
+            Some(role_id) => role_id,
+            None => return Ok(None),
+        };
+        let db = database(ctx)?;
+
+        match RoleServiceImpl::get_role(db.as_ref(), role_id).await {
+            Ok(role) => Ok(role.map(Role::from)),
+            Err(e) => Err(OrganisationError::from(e).new()),
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct InviteMemberInput {
+    pub organisation_id: Uuid,
+    pub email: String,
+    pub role_id: Option<Uuid>,
+}
+
+#[derive(Default)]
+pub struct MembershipQuery;
+
+#[Object]
+impl MembershipQuery {
+    /// Organisations the caller is a member of.
+    async fn my_memberships(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Membership>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching memberships of user: {}", claims.sub);
+        let db = database(ctx)?;
+
+        match MembershipServiceImpl::get_memberships_of_user(db.as_ref(), claims.sub).await {
+            Ok(memberships) => Ok(memberships.into_iter().map(Membership::from).collect()),
+            Err(e) => Err(MembershipError::from(e).new()),
+        }
+    }
+
+    /// Invitations the caller has not answered yet.
+    async fn my_invitations(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Membership>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching pending invitations of user: {}", claims.sub);
+        let db = database(ctx)?;
+
+        match MembershipServiceImpl::get_invitations_of_user(db.as_ref(), claims.sub).await {
+            Ok(invitations) => Ok(invitations.into_iter().map(Membership::from).collect()),
+            Err(e) => Err(MembershipError::from(e).new()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MembershipMutation;
+
+#[Object]
+impl MembershipMutation {
+    /// Invites an existing user to an organisation the caller owns. Other members get
+    /// `NOT_ORGANISATION_OWNER`.
+    async fn invite_member(&self, ctx: &Context<'_>, input: InviteMemberInput) -> async_graphql::Result<Membership> {
+        let claims = user_claims(ctx)?;
+        trace!("User '{}' invites '{}' to organisation '{}'", claims.sub, input.email, input.organisation_id);
+        let db = database(ctx)?;
+
+        match MembershipServiceImpl::get_membership(db.as_ref(), input.organisation_id, claims.sub).await {
+            Ok(Some(membership)) if membership.is_owner() => {},
+            Ok(Some(membership)) if membership.accepted_at.is_some() => return Err(OrganisationError::NotOwner(input.organisation_id.to_string()).new()),
+            Ok(_) => return Err(MembershipError::NotAMember(input.organisation_id).new()),
+            Err(e) => return Err(MembershipError::from(e).new()),
+        }
+
+        if let Some(role_id) = input.role_id {
+            match RoleServiceImpl::get_role(db.as_ref(), role_id).await {
+                Ok(Some(role)) if role.organisation_id == input.organisation_id => {},
+                Ok(_) => return Err(MembershipError::InvalidRole(role_id).new()),
+                Err(e) => return Err(OrganisationError::from(e).new()),
+            }
+        }
+
+        let invitee = match UserServiceImpl::find_user_by_email(db.as_ref(), input.email.clone()).await {
+            Ok(Some(user)) if is_visible_on(user.site_id, current_site_id(ctx)) => user,
+            Ok(_) => return Err(UserAuthError::UserNotFound(input.email).new()),
+            Err(e) => return Err(UserError::from(e).new()),
+        };
+
+        match MembershipServiceImpl::get_membership(db.as_ref(), input.organisation_id, invitee.id).await {
+            Ok(None) => {},
+            Ok(Some(_)) => return Err(MembershipError::AlreadyMember(input.organisation_id).new()),
+            Err(e) => return Err(MembershipError::from(e).new()),
+        }
+
+        match MembershipServiceImpl::invite_member(db.as_ref(), input.organisation_id, invitee.id, input.role_id, claims.sub).await {
+            Ok(invitation) => Ok(Membership::from(invitation)),
+            Err(e) => {
+                error!("Failed to invite user '{}' to organisation '{}': {}", invitee.id, input.organisation_id, e);
+                Err(MembershipError::from(e).new())
+            }
+        }
+    }
+
+    async fn accept_invitation(&self, ctx: &Context<'_>, organisation_id: Uuid) -> async_graphql::Result<Membership> {
+        let claims = user_claims(ctx)?;
+        let db = database(ctx)?;
+
+        match MembershipServiceImpl::accept_invitation(db.as_ref(), organisation_id, claims.sub).await {
+            Ok(membership) => Ok(Membership::from(membership)),
+            Err(sea_orm::DbErr::RecordNotFound(_)) => Err(MembershipError::InvitationNotFound(organisation_id).new()),
+            Err(e) => {
+                error!("Failed to accept invitation to organisation '{}': {}", organisation_id, e);
+                Err(MembershipError::from(e).new())
+            }
+        }
+    }
+
+    async fn decline_invitation(&self, ctx: &Context<'_>, organisation_id: Uuid) -> async_graphql::Result<bool> {
+        let claims = user_claims(ctx)?;
+        let db = database(ctx)?;
+
+        match MembershipServiceImpl::get_membership(db.as_ref(), organisation_id, claims.sub).await {
+            Ok(Some(membership)) if membership.accepted_at.is_none() => {},
+            Ok(_) => return Err(MembershipError::InvitationNotFound(organisation_id).new()),
+            Err(e) => return Err(MembershipError::from(e).new()),
+        }
+
+        MembershipServiceImpl::remove_membership(db.as_ref(), organisation_id, claims.sub)
+            .await
+            .map_err(|e| {
+                error!("Failed to decline invitation to organisation '{}': {}", organisation_id, e);
+                MembershipError::from(e).new()
+            })
+    }
+
+    async fn leave_organisation(&self, ctx: &Context<'_>, organisation_id: Uuid) -> async_graphql::Result<bool> {
+        let claims = user_claims(ctx)?;
+        let db = database(ctx)?;
+
+        match MembershipServiceImpl::get_membership(db.as_ref(), organisation_id, claims.sub).await {
+            Ok(Some(membership)) if membership.accepted_at.is_some() => {},
+            Ok(_) => return Err(MembershipError::NotAMember(organisation_id).new()),
+            Err(e) => return Err(MembershipError::from(e).new()),
+        }
+
+        MembershipServiceImpl::remove_membership(db.as_ref(), organisation_id, claims.sub)
+            .await
+            .map_err(|e| {
+                error!("Failed to leave organisation '{}': {}", organisation_id, e);
+                MembershipError::from(e).new()
+            })
+    }
+}
@@ -1,4 +1,22 @@
+pub mod auth;
 pub mod users;
+pub mod address;
+pub mod organisation;
+pub mod roles;
+pub mod membership;
+pub use auth::{AuthUserQuery, AuthUserMutation};
 pub use users::{UserQuery, UserMutation};
+pub use address::{AddressQuery, AddressMutation};
+pub use organisation::{OrganisationQuery, OrganisationMutation};
+pub use roles::{RoleQuery, RoleMutation};
+pub use membership::{MembershipQuery, MembershipMutation};
 #[cfg(test)]
 mod test_users;
+#[cfg(test)]
+mod test_auth;
+#[cfg(test)]
+mod test_organisation;
+#[cfg(test)]
+mod test_membership;
+#[cfg(test)]
+mod test_address;
@@ -0,0 +1,236 @@
+use std::sync::Arc;
+use async_graphql::{ComplexObject, Context, Error, Object, SimpleObject, InputObject};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+use log::{error, trace};
+use crate::internal::{
+    api::{
+        admin::users::errors::interface::CustomGraphQLError,
+        users::{
+            errors::{membership::MembershipError, organisation::OrganisationError},
+            models::organisation,
+            services::{
+                membership::{MembershipService, MembershipServiceImpl},
+                organisation::{OrganisationService, OrganisationServiceImpl},
+                roles::{RoleService, RoleServiceImpl},
+            },
+        },
+    },
+    graphql::auth::user_claims,
+};
+use super::{membership::Membership, roles::Role};
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Organisation {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl From<organisation::Model> for Organisation {
+    fn from(o: organisation::Model) -> Self {
+        Organisation {
+            id: o.id,
+            name: o.name,
+            description: o.description,
+        }
+    }
+}
+
+#[ComplexObject]
+impl Organisation {
+    async fn roles(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Role>> {
+        trace!("Fetching roles of organisation: {}", self.id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        match RoleServiceImpl::get_roles_by_organisation(db.as_ref(), self.id).await {
+            Ok(roles) => Ok(roles.into_iter().map(Role::from).collect()),
+            Err(e) => {
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+
+    /// Only listed to members: invitees can see the organisation, not who belongs to it.
+    async fn members(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Membership>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching members of organisation: {}", self.id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        match MembershipServiceImpl::get_membership(db.as_ref(), self.id, claims.sub).await {
+            Ok(Some(membership)) if membership.accepted_at.is_some() => {},
+            Ok(_) => return Err(MembershipError::NotAMember(self.id).new()),
+            Err(e) => return Err(MembershipError::from(e).new()),
+        }
+
+        match MembershipServiceImpl::get_members(db.as_ref(), self.id).await {
+            Ok(members) => Ok(members.into_iter().map(Membership::from).collect()),
+            Err(e) => {
+                Err(MembershipError::from(e).new())
+            }
+        }
+    }
+}
+
+/// Fails with `ORGANISATION_NOT_FOUND` unless `user_id` is an accepted member of the
+/// organisation, so that the organisations of others cannot be told from unknown ones.
+pub(crate) async fn ensure_member(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid) -> async_graphql::Result<()> {
+    match MembershipServiceImpl::get_membership(db, organisation_id, user_id).await {
+        Ok(Some(membership)) if membership.accepted_at.is_some() => Ok(()),
+        Ok(_) => Err(OrganisationError::OrganisationNotFound(organisation_id.to_string()).new()),
+        Err(e) => Err(OrganisationError::from(e).new()),
+    }
+}
+
+/// Like [`ensure_member`], then fails with `NOT_ORGANISATION_OWNER` unless `user_id` founded
+/// the organisation.
+pub(crate) async fn ensure_owner(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid) -> async_graphql::Result<()> {
+    match MembershipServiceImpl::get_membership(db, organisation_id, user_id).await {
+        Ok(Some(membership)) if membership.is_owner() => Ok(()),
+        Ok(Some(membership)) if membership.accepted_at.is_some() => Err(OrganisationError::NotOwner(organisation_id.to_string()).new()),
+        Ok(_) => Err(OrganisationError::OrganisationNotFound(organisation_id.to_string()).new()),
+        Err(e) => Err(OrganisationError::from(e).new()),
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreateOrganisationInput {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(InputObject)]
+pub struct UpdateOrganisationInput {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Default)]
+pub struct OrganisationQuery;
+
+#[Object]
+impl OrganisationQuery {
+    /// Fails with `ORGANISATION_NOT_FOUND` unless the caller is a member of the organisation.
+    async fn organisation(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<Organisation>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching organisation with id: {}", id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+        ensure_member(db.as_ref(), id, claims.sub).await?;
+
+        match OrganisationServiceImpl::get_organisation(db.as_ref(), id).await {
+            Ok(Some(o)) => Ok(Some(Organisation::from(o))),
+            Ok(None) => {
+                Err(OrganisationError::OrganisationNotFound(id.to_string()).new())
+            },
+            Err(e) => {
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+
+    /// Organisations the caller is a member of.
+    async fn organisations(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Organisation>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching organisations of user: {}", claims.sub);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        match OrganisationServiceImpl::get_organisations_of_member(db.as_ref(), claims.sub).await {
+            Ok(organisations) => Ok(organisations.into_iter().map(Organisation::from).collect()),
+            Err(e) => {
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct OrganisationMutation;
+
+#[Object]
+impl OrganisationMutation {
+    /// The caller becomes the first member, and the owner, of the organisation.
+    async fn create_organisation(&self, ctx: &Context<'_>, input: CreateOrganisationInput) -> async_graphql::Result<Organisation> {
+        let claims = user_claims(ctx)?;
+        trace!("Creating organisation with name: '{}'", input.name);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        match OrganisationServiceImpl::create_organisation(db.as_ref(), input.name.clone(), input.description, Some(claims.sub)).await {
+            Ok(organisation) => Ok(Organisation::from(organisation)),
+            Err(e) => {
+                error!("Failed to create organisation '{}': {}", input.name, e);
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+
+    /// Fails with `ORGANISATION_NOT_FOUND` unless the caller is a member of the organisation,
+    /// and with `NOT_ORGANISATION_OWNER` unless they own it.
+    async fn update_organisation(&self, ctx: &Context<'_>, input: UpdateOrganisationInput) -> async_graphql::Result<Organisation> {
+        let claims = user_claims(ctx)?;
+        trace!("Updating organisation with id: '{}'", input.id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+        ensure_owner(db.as_ref(), input.id, claims.sub).await?;
+
+        match OrganisationServiceImpl::update_organisation(db.as_ref(), input.id, input.name, input.description).await {
+            Ok(organisation) => Ok(Organisation::from(organisation)),
+            Err(e) => {
+                error!("Failed to update organisation with id '{}': {}", input.id, e);
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+
+    /// Fails with `ORGANISATION_NOT_FOUND` unless the caller is a member of the organisation,
+    /// and with `NOT_ORGANISATION_OWNER` unless they own it.
+    async fn delete_organisation(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let claims = user_claims(ctx)?;
+        trace!("Deleting organisation with id: {}", id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+        ensure_owner(db.as_ref(), id, claims.sub).await?;
+
+        match OrganisationServiceImpl::delete_organisation(db.as_ref(), id).await {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                error!("Failed to delete organisation with id '{}': {}", id, e);
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+}
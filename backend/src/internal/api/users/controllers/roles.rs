@@ -0,0 +1,162 @@
+use std::sync::Arc;
+use async_graphql::{Context, Error, Object, SimpleObject, InputObject};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+use log::{error, trace};
+use crate::internal::{
+    api::{
+        admin::users::errors::interface::CustomGraphQLError,
+        users::{
+            errors::organisation::OrganisationError,
+            models::roles,
+            services::{
+                membership::{MembershipService, MembershipServiceImpl},
+                roles::{RoleService, RoleServiceImpl},
+            },
+        },
+    },
+    graphql::auth::user_claims,
+};
+use super::organisation::ensure_owner;
+
+/// Role scoped to an organisation, unrelated to admin roles.
+#[derive(SimpleObject)]
+pub struct Role {
+    pub id: Uuid,
+    pub organisation_id: Uuid,
+    pub name: String,
+}
+
+impl From<roles::Model> for Role {
+    fn from(r: roles::Model) -> Self {
+        Role {
+            id: r.id,
+            organisation_id: r.organisation_id,
+            name: r.name,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct CreateOrganisationRoleInput {
+    pub organisation_id: Uuid,
+    pub name: String,
+}
+
+#[derive(InputObject)]
+pub struct UpdateOrganisationRoleInput {
+    pub id: Uuid,
+    pub name: Option<String>,
+}
+
+/// The role when `user_id` is a member of its organisation, `ROLE_NOT_FOUND` otherwise.
+async fn member_role(db: &DatabaseConnection, id: Uuid, user_id: Uuid) -> async_graphql::Result<roles::Model> {
+    let role = match RoleServiceImpl::get_role(db, id).await {
+        Ok(Some(role)) => role,
+        Ok(None) => return Err(OrganisationError::RoleNotFound(id.to_string()).new()),
+        Err(e) => return Err(OrganisationError::from(e).new()),
+    };
+
+    match MembershipServiceImpl::get_membership(db, role.organisation_id, user_id).await {
+        Ok(Some(membership)) if membership.accepted_at.is_some() => Ok(role),
+        Ok(_) => Err(OrganisationError::RoleNotFound(id.to_string()).new()),
+        Err(e) => Err(OrganisationError::from(e).new()),
+    }
+}
+
+#[derive(Default)]
+pub struct RoleQuery;
+
+#[Object]
+impl RoleQuery {
+    /// Fails with `ROLE_NOT_FOUND` unless the caller is a member of the role's organisation.
+    async fn role(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<Role>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching role with id: {}", id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        member_role(db.as_ref(), id, claims.sub).await.map(|r| Some(Role::from(r)))
+    }
+}
+
+#[derive(Default)]
+pub struct RoleMutation;
+
+#[Object]
+impl RoleMutation {
+    /// Fails with `ORGANISATION_NOT_FOUND` unless the caller is a member of the organisation,
+    /// and with `NOT_ORGANISATION_OWNER` unless they own it.
+    async fn create_organisation_role(&self, ctx: &Context<'_>, input: CreateOrganisationRoleInput) -> async_graphql::Result<Role> {
+        let claims = user_claims(ctx)?;
+        trace!("Creating role '{}' in organisation: '{}'", input.name, input.organisation_id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        ensure_owner(db.as_ref(), input.organisation_id, claims.sub).await?;
+
+        match RoleServiceImpl::create_role(db.as_ref(), input.organisation_id, input.name.clone()).await {
+            Ok(role) => Ok(Role::from(role)),
+            Err(e) => {
+                error!("Failed to create role '{}' in organisation '{}': {}", input.name, input.organisation_id, e);
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+
+    /// Fails with `ROLE_NOT_FOUND` unless the caller is a member of the role's organisation,
+    /// and with `NOT_ORGANISATION_OWNER` unless they own it.
+    async fn update_organisation_role(&self, ctx: &Context<'_>, input: UpdateOrganisationRoleInput) -> async_graphql::Result<Role> {
+        let claims = user_claims(ctx)?;
+        trace!("Updating role with id: '{}'", input.id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        let role = member_role(db.as_ref(), input.id, claims.sub).await?;
+        ensure_owner(db.as_ref(), role.organisation_id, claims.sub).await?;
+
+        match RoleServiceImpl::update_role(db.as_ref(), input.id, input.name).await {
+            Ok(role) => Ok(Role::from(role)),
+            Err(e) => {
+                error!("Failed to update role with id '{}': {}", input.id, e);
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+
+    /// Fails with `ROLE_NOT_FOUND` unless the caller is a member of the role's organisation,
+    /// and with `NOT_ORGANISATION_OWNER` unless they own it.
+    async fn delete_organisation_role(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let claims = user_claims(ctx)?;
+        trace!("Deleting role with id: {}", id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        let role = member_role(db.as_ref(), id, claims.sub).await?;
+        ensure_owner(db.as_ref(), role.organisation_id, claims.sub).await?;
+
+        match RoleServiceImpl::delete_role(db.as_ref(), id).await {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                error!("Failed to delete role with id '{}': {}", id, e);
+                Err(OrganisationError::from(e).new())
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Request, Schema};
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+use crate::internal::api::users::{
+    controllers::{AddressMutation, AddressQuery},
+    models::address,
+    services::auth::UserClaims,
+};
+
+fn claims(user_id: Uuid) -> UserClaims {
+    UserClaims {
+        sub: user_id,
+        iss: "template".to_owned(),
+        aud: "users".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+    }
+}
+
+fn test_address(id: Uuid, user_id: Uuid) -> address::Model {
+    address::Model {
+        id,
+        user_id,
+        street: "1 rue de la Paix".to_owned(),
+        city: "Paris".to_owned(),
+        postal_code: "75002".to_owned(),
+        country: "France".to_owned(),
+    }
+}
+
+fn error_code(response: &async_graphql::Response) -> String {
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    extensions.get("message").unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_address_of_owner() {
+    let id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![test_address(id, user_id)]])
+        .into_connection();
+
+    let schema = Schema::build(AddressQuery, AddressMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"{{ address(id: "{}") {{ city }} }}"#, id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    assert_eq!(response.data.into_json().unwrap()["address"]["city"], "Paris");
+}
+
+#[tokio::test]
+async fn test_address_requires_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(AddressQuery, AddressMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"{{ addresses(userId: "{}") {{ city }} }}"#, Uuid::new_v4());
+    let response = schema.execute(query).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"MISSING_TOKEN\"");
+}
+
+#[tokio::test]
+async fn test_addresses_of_other_user() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(AddressQuery, AddressMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"{{ addresses(userId: "{}") {{ city }} }}"#, Uuid::new_v4());
+    let response = schema.execute(Request::new(query).data(claims(Uuid::new_v4()))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"ADDRESS_NOT_FOUND\"");
+}
+
+#[tokio::test]
+async fn test_update_address_of_other_user() {
+    let id = Uuid::new_v4();
+
+    // The ownership lookup only, no update
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![test_address(id, Uuid::new_v4())]])
+        .into_connection();
+    let db = Arc::new(db);
+
+    let schema = Schema::build(AddressQuery, AddressMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = format!(r#"mutation {{ updateAddress(input: {{ id: "{}", city: "Lyon" }}) {{ city }} }}"#, id);
+    let response = schema.execute(Request::new(query).data(claims(Uuid::new_v4()))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"ADDRESS_NOT_FOUND\"");
+    drop(schema);
+    assert_eq!(Arc::try_unwrap(db).unwrap().into_transaction_log().len(), 1);
+}
+
+#[tokio::test]
+async fn test_delete_own_address() {
+    let id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![test_address(id, user_id)]])
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .into_connection();
+
+    let schema = Schema::build(AddressQuery, AddressMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"mutation {{ deleteAddress(id: "{}") }}"#, id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    assert_eq!(response.data.into_json().unwrap()["deleteAddress"], true);
+}
+
+#[tokio::test]
+async fn test_create_address_for_other_user() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(AddressQuery, AddressMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"mutation {{
+        createAddress(input: {{ userId: "{}", street: "1 rue", city: "Paris", postalCode: "75002", country: "France" }}) {{ id }}
+    }}"#, Uuid::new_v4());
+    let response = schema.execute(Request::new(query).data(claims(Uuid::new_v4()))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"USER_NOT_FOUND\"");
+}
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Request, Schema};
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase, MockExecResult};
+use uuid::Uuid;
+use crate::internal::{
+    api::users::{
+        controllers::{AuthUserMutation, AuthUserQuery},
+        models::users,
+        services::auth::UserClaims,
+    },
+    graphql::{auth::authenticate, site::CurrentSite},
+    security::{jwt::install_test_jwt_keys, password::PasswordService},
+};
+
+fn test_user() -> users::Model {
+    users::Model {
+        id: Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap(),
+        username: "test_user".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "user".to_owned(),
+        email: "test@example.com".to_owned(),
+        password: PasswordService::from_env().hash("password123").unwrap(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        site_id: None,
+        email_verified_at: None,
+        deleted_at: None,
+        erased_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_login_then_me() {
+    install_test_jwt_keys();
+    // One query for the credential check, one for `me`, and the refresh token insert
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![test_user()], vec![test_user()]])
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .into_connection();
+
+    let schema = Schema::build(AuthUserQuery, AuthUserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute(r#"
+        mutation {
+            login(input: { email: "test@example.com", password: "password123" }) {
+                accessToken
+                refreshToken
+                expiresIn
+            }
+        }
+    "#).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    let token = data["login"]["accessToken"].as_str().unwrap().to_string();
+    assert_eq!(data["login"]["refreshToken"].as_str().unwrap().len(), 64);
+    assert_eq!(data["login"]["expiresIn"], 3600);
+
+    let request = authenticate(Request::new("{ me { id email } }"), Some(token)).await;
+    let response = schema.execute(request).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["me"]["id"], "51c84da0-6fbe-4db2-81fe-385a38d29353");
+    assert_eq!(data["me"]["email"], "test@example.com");
+}
+
+#[tokio::test]
+async fn test_login_invalid_credentials() {
+    install_test_jwt_keys();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![test_user()]])
+        .into_connection();
+
+    let schema = Schema::build(AuthUserQuery, AuthUserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute(r#"
+        mutation {
+            login(input: { email: "test@example.com", password: "wrong_password" }) {
+                accessToken
+            }
+        }
+    "#).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVALID_CREDENTIALS")));
+}
+
+#[tokio::test]
+async fn test_me_rejects_invalid_token() {
+    install_test_jwt_keys();
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(AuthUserQuery, AuthUserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let request = authenticate(Request::new("{ me { id } }"), Some("not-a-token".to_string())).await;
+    let response = schema.execute(request).await;
+
+    assert!(!response.errors.is_empty(), "Expected errors but found none.");
+}
+
+#[tokio::test]
+async fn test_me_requires_token() {
+    install_test_jwt_keys();
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(AuthUserQuery, AuthUserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute("{ me { id } }").await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("MISSING_TOKEN")));
+}
+
+#[tokio::test]
+async fn test_me_hides_database_errors() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_errors([DbErr::Custom("relation \"users\" does not exist".to_owned())])
+        .into_connection();
+
+    let schema = Schema::build(AuthUserQuery, AuthUserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let claims = UserClaims {
+        sub: test_user().id,
+        iss: "template".to_owned(),
+        aud: "users".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+    };
+    let response = schema.execute(Request::new("{ me { id } }").data(claims)).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    assert!(!response.errors[0].message.contains("relation"), "Database details leaked: {}", response.errors[0].message);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("DATABASE_ACCESS_ERROR")));
+}
+
+#[tokio::test]
+async fn test_login_rejects_user_of_another_site() {
+    install_test_jwt_keys();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![users::Model {
+            site_id: Some(Uuid::new_v4()),
+            ..test_user()
+        }]])
+        .into_connection();
+
+    let schema = Schema::build(AuthUserQuery, AuthUserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let request = Request::new(r#"
+        mutation {
+            login(input: { email: "test@example.com", password: "password123" }) {
+                accessToken
+            }
+        }
+    "#).data(CurrentSite { id: Uuid::new_v4(), domain: "shop.example.com".to_owned() });
+    let response = schema.execute(request).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVALID_CREDENTIALS")));
+}
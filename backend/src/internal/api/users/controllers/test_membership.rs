@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Request, Schema};
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase};
+use uuid::Uuid;
+use crate::internal::api::users::{
+    controllers::{MembershipMutation, MembershipQuery},
+    models::{organisation, organisation_members, users},
+    services::auth::UserClaims,
+};
+
+fn claims(user_id: Uuid) -> UserClaims {
+    UserClaims {
+        sub: user_id,
+        iss: "template".to_owned(),
+        aud: "users".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+    }
+}
+
+fn membership(organisation_id: Uuid, user_id: Uuid, accepted: bool) -> organisation_members::Model {
+    organisation_members::Model {
+        id: Uuid::new_v4(),
+        organisation_id,
+        user_id,
+        role_id: None,
+        invited_by: None,
+        created_at: Utc::now(),
+        accepted_at: accepted.then(Utc::now),
+    }
+}
+
+#[tokio::test]
+async fn test_invite_member() {
+    let organisation_id = Uuid::new_v4();
+    let inviter_id = Uuid::new_v4();
+    let invitee_id = Uuid::new_v4();
+
+    // Inviter membership, invitee lookup, no existing invitee membership, then the insert
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![membership(organisation_id, inviter_id, true)]])
+        .append_query_results([vec![users::Model {
+            id: invitee_id,
+            username: "invitee".to_owned(),
+            first_name: "in".to_owned(),
+            last_name: "vitee".to_owned(),
+            email: "invitee@example.com".to_owned(),
+            password: "hashed_password".to_owned(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            site_id: None,
+            email_verified_at: None,
+            deleted_at: None,
+            erased_at: None,
+        }]])
+        .append_query_results([Vec::<organisation_members::Model>::new()])
+        .append_query_results([vec![organisation_members::Model {
+            invited_by: Some(inviter_id),
+            ..membership(organisation_id, invitee_id, false)
+        }]])
+        .into_connection();
+
+    let schema = Schema::build(MembershipQuery, MembershipMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"mutation {{
+        inviteMember(input: {{ organisationId: "{}", email: "invitee@example.com" }}) {{
+            userId
+            invitedBy
+            accepted
+        }}
+    }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(inviter_id))).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["inviteMember"]["userId"], invitee_id.to_string());
+    assert_eq!(data["inviteMember"]["invitedBy"], inviter_id.to_string());
+    assert_eq!(data["inviteMember"]["accepted"], false);
+}
+
+#[tokio::test]
+async fn test_invite_member_requires_membership() {
+    let organisation_id = Uuid::new_v4();
+    let caller_id = Uuid::new_v4();
+
+    // A pending invitation does not allow inviting others
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![membership(organisation_id, caller_id, false)]])
+        .into_connection();
+
+    let schema = Schema::build(MembershipQuery, MembershipMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"mutation {{
+        inviteMember(input: {{ organisationId: "{}", email: "invitee@example.com" }}) {{ id }}
+    }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(caller_id))).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("NOT_A_MEMBER")));
+}
+
+#[tokio::test]
+async fn test_invite_member_requires_ownership() {
+    let organisation_id = Uuid::new_v4();
+    let caller_id = Uuid::new_v4();
+
+    // Members who were invited themselves cannot invite others
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![organisation_members::Model {
+            invited_by: Some(Uuid::new_v4()),
+            ..membership(organisation_id, caller_id, true)
+        }]])
+        .into_connection();
+
+    let schema = Schema::build(MembershipQuery, MembershipMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"mutation {{
+        inviteMember(input: {{ organisationId: "{}", email: "invitee@example.com" }}) {{ id }}
+    }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(caller_id))).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("NOT_ORGANISATION_OWNER")));
+}
+
+#[tokio::test]
+async fn test_accept_invitation_not_found() {
+    let organisation_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<organisation_members::Model>::new()])
+        .into_connection();
+
+    let schema = Schema::build(MembershipQuery, MembershipMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"mutation {{ acceptInvitation(organisationId: "{}") {{ id }} }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(Uuid::new_v4()))).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVITATION_NOT_FOUND")));
+}
+
+#[tokio::test]
+async fn test_my_invitations_requires_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(MembershipQuery, MembershipMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute("{ myInvitations { id } }").await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("MISSING_TOKEN")));
+}
+
+#[tokio::test]
+async fn test_members_hidden_from_invitee() {
+    let organisation_id = Uuid::new_v4();
+    let invitee_id = Uuid::new_v4();
+
+    // The invitation, its organisation, then the caller's own (pending) membership
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![membership(organisation_id, invitee_id, false)]])
+        .append_query_results([vec![organisation::Model {
+            id: organisation_id,
+            name: "Acme".to_owned(),
+            description: None,
+        }]])
+        .append_query_results([vec![membership(organisation_id, invitee_id, false)]])
+        .into_connection();
+
+    let schema = Schema::build(MembershipQuery, MembershipMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = "{ myInvitations { organisation { name members { user { email } } } } }";
+    let response = schema.execute(Request::new(query).data(claims(invitee_id))).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("NOT_A_MEMBER")));
+    assert_eq!(response.data.into_json().unwrap()["myInvitations"][0]["organisation"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn test_members_listed_to_member() {
+    let organisation_id = Uuid::new_v4();
+    let member_id = Uuid::new_v4();
+
+    // The membership, its organisation, the caller's membership check, then the members
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![membership(organisation_id, member_id, true)]])
+        .append_query_results([vec![organisation::Model {
+            id: organisation_id,
+            name: "Acme".to_owned(),
+            description: None,
+        }]])
+        .append_query_results([vec![membership(organisation_id, member_id, true)]])
+        .append_query_results([vec![membership(organisation_id, member_id, true)]])
+        .into_connection();
+
+    let schema = Schema::build(MembershipQuery, MembershipMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = "{ myMemberships { organisation { members { userId } } } }";
+    let response = schema.execute(Request::new(query).data(claims(member_id))).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["myMemberships"][0]["organisation"]["members"][0]["userId"], member_id.to_string());
+}
+
+#[tokio::test]
+async fn test_my_memberships_hides_database_errors() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_errors([DbErr::Custom("relation \"organisation_members\" does not exist".to_owned())])
+        .into_connection();
+
+    let schema = Schema::build(MembershipQuery, MembershipMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute(Request::new("{ myMemberships { id } }").data(claims(Uuid::new_v4()))).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    assert!(!response.errors[0].message.contains("organisation_members"), "Database details leaked: {}", response.errors[0].message);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("DATABASE_ACCESS_ERROR")));
+}
@@ -0,0 +1,357 @@
+use std::sync::Arc;
+
+use async_graphql::{EmptyMutation, EmptySubscription, Request, Schema};
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase};
+use uuid::Uuid;
+use crate::internal::api::users::{
+    controllers::{OrganisationMutation, OrganisationQuery, RoleMutation, RoleQuery, UserQuery},
+    models::{address, organisation, organisation_members, roles, users},
+    services::auth::UserClaims,
+};
+
+fn claims(user_id: Uuid) -> UserClaims {
+    UserClaims {
+        sub: user_id,
+        iss: "template".to_owned(),
+        aud: "users".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+    }
+}
+
+fn membership(organisation_id: Uuid, user_id: Uuid, accepted: bool) -> organisation_members::Model {
+    organisation_members::Model {
+        id: Uuid::new_v4(),
+        organisation_id,
+        user_id,
+        role_id: None,
+        invited_by: None,
+        created_at: Utc::now(),
+        accepted_at: accepted.then(Utc::now),
+    }
+}
+
+/// An accepted member who joined on someone's invitation, not the owner.
+fn invited_member(organisation_id: Uuid, user_id: Uuid) -> organisation_members::Model {
+    organisation_members::Model {
+        invited_by: Some(Uuid::new_v4()),
+        ..membership(organisation_id, user_id, true)
+    }
+}
+
+fn test_user(user_id: Uuid) -> users::Model {
+    users::Model {
+        id: user_id,
+        username: "test_user".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "user".to_owned(),
+        email: "test@example.com".to_owned(),
+        password: "hashed_password".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        site_id: None,
+        email_verified_at: None,
+        deleted_at: None,
+        erased_at: None,
+    }
+}
+
+fn error_code(response: &async_graphql::Response) -> String {
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    extensions.get("message").unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_organisation_with_roles() {
+    let organisation_id = Uuid::parse_str("0b3f0c1e-5b7a-4f0e-9d4e-7a1c2b3d4e5f").unwrap();
+    let user_id = Uuid::new_v4();
+
+    // The caller's membership, the organisation, then its roles
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![membership(organisation_id, user_id, true)]])
+        .append_query_results([vec![organisation::Model {
+            id: organisation_id,
+            name: "Acme".to_owned(),
+            description: None,
+        }]])
+        .append_query_results([vec![roles::Model {
+            id: Uuid::new_v4(),
+            organisation_id,
+            name: "Manager".to_owned(),
+        }]])
+        .into_connection();
+
+    let schema = Schema::build(OrganisationQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"{{
+        organisation(id: "{}") {{
+            name
+            roles {{ organisationId name }}
+        }}
+    }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["organisation"]["name"], "Acme");
+    assert_eq!(data["organisation"]["roles"][0]["name"], "Manager");
+    assert_eq!(data["organisation"]["roles"][0]["organisationId"], organisation_id.to_string());
+}
+
+#[tokio::test]
+async fn test_user_with_addresses() {
+    let user_id = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![test_user(user_id)]])
+        .append_query_results([vec![address::Model {
+            id: Uuid::new_v4(),
+            user_id,
+            street: "1 rue de la Paix".to_owned(),
+            city: "Paris".to_owned(),
+            postal_code: "75002".to_owned(),
+            country: "France".to_owned(),
+        }]])
+        .into_connection();
+
+    let schema = Schema::build(UserQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"{{
+        user(id: "{}") {{
+            username
+            addresses {{ city postalCode }}
+        }}
+    }}"#, user_id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["user"]["addresses"][0]["city"], "Paris");
+    assert_eq!(data["user"]["addresses"][0]["postalCode"], "75002");
+}
+
+#[tokio::test]
+async fn test_addresses_of_other_user_are_hidden() {
+    let user_id = Uuid::new_v4();
+
+    // Someone else's account is hidden altogether, nothing is fetched
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(UserQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"{{
+        user(id: "{}") {{
+            username
+            addresses {{ city }}
+        }}
+    }}"#, user_id);
+    let response = schema.execute(Request::new(query).data(claims(Uuid::new_v4()))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"USER_NOT_FOUND\"");
+}
+
+#[tokio::test]
+async fn test_organisation_requires_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(OrganisationQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute("{ organisations { name } }").await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"MISSING_TOKEN\"");
+}
+
+#[tokio::test]
+async fn test_organisation_of_non_member_is_hidden() {
+    let organisation_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+
+    // A pending invitation is not a membership
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![membership(organisation_id, user_id, false)]])
+        .into_connection();
+
+    let schema = Schema::build(OrganisationQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"{{ organisation(id: "{}") {{ name }} }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"ORGANISATION_NOT_FOUND\"");
+}
+
+#[tokio::test]
+async fn test_organisations_lists_memberships_only() {
+    let user_id = Uuid::new_v4();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![organisation::Model {
+            id: Uuid::new_v4(),
+            name: "Acme".to_owned(),
+            description: None,
+        }]])
+        .into_connection();
+    let db = Arc::new(db);
+
+    let schema = Schema::build(OrganisationQuery, EmptyMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let response = schema.execute(Request::new("{ organisations { name } }").data(claims(user_id))).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    drop(schema);
+    let log = format!("{:?}", Arc::try_unwrap(db).unwrap().into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains("\"organisation_members\".\"user_id\" = $1"), "Unexpected query: {}", log);
+    assert!(log.contains("\"accepted_at\" IS NOT NULL"), "Unexpected query: {}", log);
+}
+
+#[tokio::test]
+async fn test_delete_organisation_of_non_member() {
+    let organisation_id = Uuid::new_v4();
+
+    // No membership, nothing is deleted
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<organisation_members::Model>::new()])
+        .into_connection();
+    let db = Arc::new(db);
+
+    let schema = Schema::build(OrganisationQuery, OrganisationMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = format!(r#"mutation {{ deleteOrganisation(id: "{}") }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(Uuid::new_v4()))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"ORGANISATION_NOT_FOUND\"");
+    drop(schema);
+    assert_eq!(Arc::try_unwrap(db).unwrap().into_transaction_log().len(), 1);
+}
+
+#[tokio::test]
+async fn test_delete_organisation_by_member_refused() {
+    let (organisation_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+    // A member, not the owner, nothing is deleted
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![invited_member(organisation_id, user_id)]])
+            .into_connection(),
+    );
+
+    let schema = Schema::build(OrganisationQuery, OrganisationMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = format!(r#"mutation {{ deleteOrganisation(id: "{}") }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"NOT_ORGANISATION_OWNER\"");
+    drop(schema);
+    assert_eq!(Arc::try_unwrap(db).unwrap().into_transaction_log().len(), 1);
+}
+
+#[tokio::test]
+async fn test_update_organisation_by_member_refused() {
+    let (organisation_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![invited_member(organisation_id, user_id)]])
+            .into_connection(),
+    );
+
+    let schema = Schema::build(OrganisationQuery, OrganisationMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = format!(r#"mutation {{ updateOrganisation(input: {{ id: "{}", name: "Renamed" }}) {{ id }} }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"NOT_ORGANISATION_OWNER\"");
+    drop(schema);
+    assert_eq!(Arc::try_unwrap(db).unwrap().into_transaction_log().len(), 1);
+}
+
+#[tokio::test]
+async fn test_create_organisation_role_by_member_refused() {
+    let (organisation_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![invited_member(organisation_id, user_id)]])
+            .into_connection(),
+    );
+
+    let schema = Schema::build(RoleQuery, RoleMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = format!(r#"mutation {{ createOrganisationRole(input: {{ organisationId: "{}", name: "Admin" }}) {{ id }} }}"#, organisation_id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"NOT_ORGANISATION_OWNER\"");
+    drop(schema);
+    assert_eq!(Arc::try_unwrap(db).unwrap().into_transaction_log().len(), 1);
+}
+
+#[tokio::test]
+async fn test_delete_organisation_role_by_member_refused() {
+    let (organisation_id, user_id, role_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    // The role, the caller's membership to see it, then again to own it
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![roles::Model { id: role_id, organisation_id, name: "Manager".to_owned() }]])
+            .append_query_results([vec![invited_member(organisation_id, user_id)]])
+            .append_query_results([vec![invited_member(organisation_id, user_id)]])
+            .into_connection(),
+    );
+
+    let schema = Schema::build(RoleQuery, RoleMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = format!(r#"mutation {{ deleteOrganisationRole(id: "{}") }}"#, role_id);
+    let response = schema.execute(Request::new(query).data(claims(user_id))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(error_code(&response), "\"NOT_ORGANISATION_OWNER\"");
+    drop(schema);
+    assert_eq!(Arc::try_unwrap(db).unwrap().into_transaction_log().len(), 3);
+}
+
+#[tokio::test]
+async fn test_organisations_hides_database_errors() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_errors([DbErr::Custom("relation \"organisation\" does not exist".to_owned())])
+        .into_connection();
+
+    let schema = Schema::build(OrganisationQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute(Request::new("{ organisations { name } }").data(claims(Uuid::new_v4()))).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert!(!response.errors[0].message.contains("relation"), "Database details leaked: {}", response.errors[0].message);
+    assert_eq!(error_code(&response), "\"DATABASE_ACCESS_ERROR\"");
+}
@@ -1,18 +1,39 @@
 use std::sync::Arc;
 
+use std::collections::BTreeMap;
+
 use async_graphql::{
+    connection::CursorType,
     EmptyMutation,
     EmptySubscription,
+    Request,
     Schema
 };
-use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase, MockExecResult};
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase, MockExecResult, Value};
 use uuid::Uuid;
 use crate::internal::api::users::{
     controllers::{users::{CreateUserInput, UpdateUserInput}, UserMutation, UserQuery},
-    models::users
+    models::users,
+    services::auth::UserClaims,
 };
+use crate::internal::graphql::pagination::{Cursor, UserSortField};
+use crate::internal::security::password::PasswordService;
+
+// Claims of a logged-in user, as attached to the request by `authenticate`
+fn caller_claims() -> UserClaims {
+    UserClaims {
+        sub: Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap(),
+        iss: "template".to_owned(),
+        aud: "users".to_owned(),
+        iat: 0,
+        nbf: 0,
+        exp: 0,
+        jti: Uuid::new_v4(),
+    }
+}
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs, clippy::useless_conversion)]
 async fn test_user_found() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -29,6 +50,10 @@ async fn test_user_found() {
                 password: "hashed_password".to_owned(),
                 created_at: Utc::now().into(),
                 updated_at: Utc::now().into(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
             }],
         ])
         .into_connection();
@@ -49,7 +74,7 @@ async fn test_user_found() {
         }}
     }}"#, fixed_uuid);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response
     assert!(response.is_ok());
@@ -60,6 +85,7 @@ async fn test_user_found() {
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs)]
 async fn test_user_not_found() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -85,15 +111,17 @@ async fn test_user_not_found() {
         }}
     }}"#, fixed_uuid);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response
     assert!(response.is_err());
     let errors = response.errors;
-    assert!(errors.iter().any(|e| e.message == format!("User with id {} not found", fixed_uuid)));
+    assert_eq!(errors[0].message, "The requested user does not exist.");
+    assert_eq!(errors[0].extensions.as_ref().unwrap().get("message"), Some(&async_graphql::Value::from("USER_NOT_FOUND")));
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs)]
 async fn test_user_db_error() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -119,15 +147,18 @@ async fn test_user_db_error() {
         }}
     }}"#, fixed_uuid);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response
     assert!(response.is_err());
     let errors = response.errors;
-    assert!(errors.iter().any(|e| e.message.contains("Failed to fetch user with error")));
+    assert_eq!(errors[0].extensions.as_ref().unwrap().get("message"), Some(&async_graphql::Value::from("DATABASE_ACCESS_ERROR")));
+    // Database details stay in the logs
+    assert!(!errors[0].message.contains("Database error"));
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs, clippy::useless_conversion, clippy::useless_format)]
 async fn test_users_found() {
     // Mock UUIDs for testing
     let uuid1 = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -145,6 +176,10 @@ async fn test_users_found() {
                 last_name: "test".to_owned(),
                 created_at: Utc::now().into(),
                 updated_at: Utc::now().into(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
             },
             users::Model {
                 id: uuid2,
@@ -155,6 +190,10 @@ async fn test_users_found() {
                 last_name: "test".to_owned(),
                 created_at: Utc::now().into(),
                 updated_at: Utc::now().into(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
             },
         ]])
         .into_connection();
@@ -170,30 +209,36 @@ async fn test_users_found() {
     let query = format!(r#"
         {{
             users {{
-                id
-                username
-                email
+                edges {{
+                    node {{
+                        id
+                        username
+                        email
+                    }}
+                }}
             }}
         }}
     "#);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response
     assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
     let data = response.data.into_json().unwrap();
 
     // Assertions for multiple users
-    assert_eq!(data["users"][0]["id"], uuid1.to_string());
-    assert_eq!(data["users"][0]["username"], "test_user1");
-    assert_eq!(data["users"][0]["email"], "test1@example.com");
-
-    assert_eq!(data["users"][1]["id"], uuid2.to_string());
-    assert_eq!(data["users"][1]["username"], "test_user2");
-    assert_eq!(data["users"][1]["email"], "test2@example.com");
+    let edges = &data["users"]["edges"];
+    assert_eq!(edges[0]["node"]["id"], uuid1.to_string());
+    assert_eq!(edges[0]["node"]["username"], "test_user1");
+    assert_eq!(edges[0]["node"]["email"], "test1@example.com");
+
+    assert_eq!(edges[1]["node"]["id"], uuid2.to_string());
+    assert_eq!(edges[1]["node"]["username"], "test_user2");
+    assert_eq!(edges[1]["node"]["email"], "test2@example.com");
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs, clippy::useless_format)]
 async fn test_users_not_found() {
     // Mock the database to simulate no users found
     let db = MockDatabase::new(DatabaseBackend::Postgres)
@@ -211,25 +256,30 @@ async fn test_users_not_found() {
     let query = format!(r#"
         {{
             users {{
-                id
-                username
-                email
+                edges {{
+                    node {{
+                        id
+                        username
+                        email
+                    }}
+                }}
             }}
         }}
     "#);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response
     assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
     let data = response.data.into_json().unwrap();
 
     // Assertions to confirm no users are returned
-    assert!(data["users"].is_array());
-    assert!(data["users"].as_array().unwrap().is_empty(), "Expected no users but found some.");
+    assert!(data["users"]["edges"].is_array());
+    assert!(data["users"]["edges"].as_array().unwrap().is_empty(), "Expected no users but found some.");
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs, clippy::useless_format)]
 async fn test_users_db_error() {
     // Mock the database to simulate a database error
     let db = MockDatabase::new(DatabaseBackend::Postgres)
@@ -247,28 +297,49 @@ async fn test_users_db_error() {
     let query = format!(r#"
         {{
             users {{
-                id
-                username
-                email
+                edges {{
+                    node {{
+                        id
+                        username
+                        email
+                    }}
+                }}
             }}
         }}
     "#);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response for expected errors
     assert!(!response.errors.is_empty(), "Expected errors but found none.");
     let errors = response.errors;
 
     // Assertions to confirm the error message
-    assert!(
-        errors.iter().any(|e| e.message.contains("Failed to fetch users with error")),
-        "Expected database connection error but found: {:?}",
+    assert_eq!(
+        errors[0].extensions.as_ref().unwrap().get("message"),
+        Some(&async_graphql::Value::from("DATABASE_ACCESS_ERROR")),
+        "Expected database access error but found: {:?}",
         errors
     );
 }
 
 #[tokio::test]
+async fn test_users_requires_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(UserQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute("{ users { totalCount } }").await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("MISSING_TOKEN")));
+}
+
+#[tokio::test]
+#[allow(clippy::default_constructed_unit_structs, clippy::useless_conversion)]
 async fn test_create_user_success() {
     // Mock input for creating a user
     let input = CreateUserInput {
@@ -288,6 +359,7 @@ async fn test_create_user_success() {
             rows_affected: 1, // Simulate successful insertion
             last_insert_id: 0, // Make sure this is set correctly if used
         }])
+        .append_query_results([Vec::<users::Model>::new()]) // Email not in use yet
         .append_query_results([vec![users::Model {
             id: generated_uuid,
             username: input.username.clone(),
@@ -297,6 +369,10 @@ async fn test_create_user_success() {
             last_name: input.last_name.clone(),
             created_at: Utc::now().into(),
             updated_at: Utc::now().into(),
+            site_id: None,
+            email_verified_at: None,
+            deleted_at: None,
+            erased_at: None,
         }]])
         .into_connection();
 
@@ -343,6 +419,7 @@ async fn test_create_user_success() {
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs)]
 async fn test_create_user_db_error() {
     // Mock input for creating a user
     let input = CreateUserInput {
@@ -355,8 +432,8 @@ async fn test_create_user_db_error() {
 
     // Mock the database to return an error during user creation
     let db = MockDatabase::new(DatabaseBackend::Postgres)
-        .append_query_results::<users::Model, Vec<users::Model>, _>([vec![]]) // No prior users in the system
-        .append_exec_errors([DbErr::Custom("Insertion error".into())]) // Simulate a database error
+        .append_query_results([Vec::<users::Model>::new()]) // Email not in use yet
+        .append_query_errors([DbErr::Custom("Insertion error".into())]) // Simulate a database error on INSERT ... RETURNING
         .into_connection();
 
     let db = Arc::new(db);
@@ -392,10 +469,14 @@ async fn test_create_user_db_error() {
     // Assert the response
     assert!(!response.errors.is_empty(), "Expected errors but got none");
     let errors = response.errors;
-    assert!(errors.iter().any(|e| e.message.contains("Failed to create user with username")), "Expected error message not found");
+    assert_eq!(errors[0].extensions.as_ref().unwrap().get("message"), Some(&async_graphql::Value::from("DATABASE_ACCESS_ERROR")));
+    // Neither the input nor the database error are echoed back
+    assert!(!errors[0].message.contains(&input.email), "Input leaked in: {}", errors[0].message);
+    assert!(!errors[0].message.contains("Insertion error"), "Database error leaked in: {}", errors[0].message);
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs, clippy::useless_conversion)]
 async fn test_update_user_success() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -406,6 +487,7 @@ async fn test_update_user_success() {
         username: Some("updated_user".to_string()),
         email: Some("updated@example.com".to_string()),
         password: None,
+        current_password: None,
     };
 
     // Mock the database to simulate successful user update
@@ -419,6 +501,10 @@ async fn test_update_user_success() {
             last_name: "user".to_owned(),
             created_at: Utc::now().into(),
             updated_at: Utc::now().into(),
+            site_id: None,
+            email_verified_at: None,
+            deleted_at: None,
+            erased_at: None,
         }]]) // Simulate finding the user
         .append_exec_results([MockExecResult {
             rows_affected: 1, // Simulate successful update
@@ -433,6 +519,10 @@ async fn test_update_user_success() {
             last_name: "user".to_owned(),
             created_at: Utc::now().into(),
             updated_at: Utc::now().into(),
+            site_id: None,
+            email_verified_at: None,
+            deleted_at: None,
+            erased_at: None,
         }]]) // Simulate returning the updated user
         .into_connection();
 
@@ -457,7 +547,7 @@ async fn test_update_user_success() {
             }}
         }}"#, input.id, input.username.clone().unwrap(), input.email.clone().unwrap());
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response
     assert!(response.errors.is_empty());
@@ -468,6 +558,7 @@ async fn test_update_user_success() {
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs)]
 async fn test_update_user_not_found() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -478,6 +569,7 @@ async fn test_update_user_not_found() {
         username: Some("updated_user".to_string()),
         email: Some("updated@example.com".to_string()),
         password: None,
+        current_password: None,
     };
 
     // Mock the database to simulate user not found
@@ -506,14 +598,15 @@ async fn test_update_user_not_found() {
             }}
         }}"#, input.id, input.username.clone().unwrap(), input.email.clone().unwrap());
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response
     assert!(!response.errors.is_empty());
-    assert!(response.errors.iter().any(|e| e.message.contains(&format!("Failed to update user with id '{}'", fixed_uuid))));
+    assert_eq!(response.errors[0].extensions.as_ref().unwrap().get("message"), Some(&async_graphql::Value::from("USER_NOT_FOUND")));
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs, clippy::useless_conversion)]
 async fn test_update_user_db_error() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -524,6 +617,7 @@ async fn test_update_user_db_error() {
         username: Some("updated_user".to_string()),
         email: Some("updated@example.com".to_string()),
         password: None,
+        current_password: None,
     };
 
     // Mock the database to return an error during update
@@ -537,6 +631,10 @@ async fn test_update_user_db_error() {
             last_name: "user".to_owned(),
             created_at: Utc::now().into(),
             updated_at: Utc::now().into(),
+            site_id: None,
+            email_verified_at: None,
+            deleted_at: None,
+            erased_at: None,
         }]]) // Simulate finding the user
         .append_exec_errors([DbErr::Custom("Update error".into())]) // Simulate an error during update
         .into_connection();
@@ -562,14 +660,15 @@ async fn test_update_user_db_error() {
             }}
         }}"#, input.id, input.username.clone().unwrap(), input.email.clone().unwrap());
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Assert the response
     assert!(!response.errors.is_empty());
-    assert!(response.errors.iter().any(|e| e.message.contains("Failed to update user with id")));
+    assert_eq!(response.errors[0].extensions.as_ref().unwrap().get("message"), Some(&async_graphql::Value::from("DATABASE_ACCESS_ERROR")));
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs)]
 async fn test_delete_user_success() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -595,7 +694,7 @@ async fn test_delete_user_success() {
             deleteUser(id: "{}")
         }}"#, fixed_uuid);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Debug print response for troubleshooting
     println!("Response: {:?}", response);
@@ -609,6 +708,7 @@ async fn test_delete_user_success() {
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs)]
 async fn test_delete_user_not_found() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -634,7 +734,7 @@ async fn test_delete_user_not_found() {
             deleteUser(id: "{}")
         }}"#, fixed_uuid);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Debug print response for troubleshooting
     println!("Response: {:?}", response);
@@ -648,6 +748,7 @@ async fn test_delete_user_not_found() {
 }
 
 #[tokio::test]
+#[allow(clippy::default_constructed_unit_structs)]
 async fn test_delete_user_db_error() {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -670,7 +771,7 @@ async fn test_delete_user_db_error() {
             deleteUser(id: "{}")
         }}"#, fixed_uuid);
 
-    let response = schema.execute(&query).await;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
 
     // Debug print response for troubleshooting
     println!("Response: {:?}", response);
@@ -678,5 +779,273 @@ async fn test_delete_user_db_error() {
     // Assert the response
     assert!(!response.errors.is_empty(), "Expected errors but got none");
     let errors = response.errors;
-    assert!(errors.iter().any(|e| e.message.contains(&format!("Failed to delete user with id '{}'", fixed_uuid))));
+    assert_eq!(errors[0].extensions.as_ref().unwrap().get("message"), Some(&async_graphql::Value::from("DATABASE_ACCESS_ERROR")));
+}
+
+#[tokio::test]
+async fn test_users_page_info_and_cursor() {
+    let user = |id: &str, username: &str| users::Model {
+        id: Uuid::parse_str(id).unwrap(),
+        username: username.to_owned(),
+        email: format!("{}@example.com", username),
+        password: "hashed_password".to_owned(),
+        first_name: "user".to_owned(),
+        last_name: "test".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        site_id: None,
+        email_verified_at: None,
+        deleted_at: None,
+        erased_at: None,
+    };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![
+            user("51c84da0-6fbe-4db2-81fe-385a38d29353", "alice"),
+            user("f30c1f8f-55c8-4ad5-b3e8-4f4530a73a58", "bob"),
+        ]])
+        .append_query_results([vec![BTreeMap::from([("num_items", Value::BigInt(Some(3)))])]])
+        .append_query_results([vec![user("f30c1f8f-55c8-4ad5-b3e8-4f4530a73a58", "bob")]])
+        .into_connection();
+    let db = Arc::new(db);
+
+    let schema = Schema::build(UserQuery, EmptyMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = r#"
+        {
+            users(first: 1, orderBy: { field: USERNAME }) {
+                totalCount
+                pageInfo { hasNextPage hasPreviousPage endCursor }
+                edges { node { username } }
+            }
+        }
+    "#;
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["users"]["totalCount"], 3);
+    assert_eq!(data["users"]["pageInfo"]["hasNextPage"], true);
+    assert_eq!(data["users"]["pageInfo"]["hasPreviousPage"], false);
+    assert_eq!(data["users"]["edges"].as_array().unwrap().len(), 1);
+    assert_eq!(data["users"]["edges"][0]["node"]["username"], "alice");
+
+    // The end cursor resumes right after the last user of the page
+    let cursor = data["users"]["pageInfo"]["endCursor"].as_str().unwrap();
+    let query = format!(r#"{{ users(first: 1, after: "{}", orderBy: {{ field: USERNAME }}) {{ edges {{ node {{ username }} }} }} }}"#, cursor);
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
+
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    drop(schema);
+    let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+    let sql = format!("{:?}", log[2]).replace("\\\"", "\"");
+    assert!(sql.contains(r#"("users"."username" > $"#), "Unexpected query: {}", sql);
+}
+
+#[tokio::test]
+async fn test_users_rejects_invalid_pagination() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+    let schema = Schema::build(UserQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute(Request::new("{ users(first: 1, last: 1) { edges { cursor } } }").data(caller_claims())).await;
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+
+    // A cursor issued for another sort order cannot be reused
+    let cursor = Cursor::text(UserSortField::Email, Uuid::new_v4(), "a@example.com").encode_cursor();
+    let query = format!(r#"{{ users(after: "{}") {{ edges {{ cursor }} }} }}"#, cursor);
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVALID_CURSOR")));
+}
+
+#[tokio::test]
+async fn test_create_user_reports_every_invalid_field() {
+    // Nothing reaches the database
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(UserQuery, UserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute(r#"
+        mutation {
+            createUser(input: { username: "", email: "not-an-email", password: "x", firstName: "test", lastName: " " }) {
+                id
+            }
+        }"#).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVALID_INPUT")));
+    let fields = extensions.get("fields").unwrap().clone().into_json().unwrap();
+    let fields: Vec<(&str, usize)> = fields
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| (f["field"].as_str().unwrap(), f["errors"].as_array().unwrap().len()))
+        .collect();
+    assert_eq!(fields, vec![("username", 1), ("lastName", 1), ("email", 1), ("password", 2)]);
+}
+
+#[tokio::test]
+async fn test_create_user_email_taken() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![users::Model {
+            id: Uuid::new_v4(),
+            username: "someone_else".to_owned(),
+            email: "test@example.com".to_owned(),
+            password: "hashed_password".to_owned(),
+            first_name: "some".to_owned(),
+            last_name: "one".to_owned(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            site_id: None,
+            email_verified_at: None,
+            deleted_at: None,
+            erased_at: None,
+        }]])
+        .into_connection();
+
+    let schema = Schema::build(UserQuery, UserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute(r#"
+        mutation {
+            createUser(input: { username: "test_user", email: "test@example.com", password: "password123", firstName: "test", lastName: "user" }) {
+                id
+            }
+        }"#).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("EMAIL_TAKEN")));
+}
+
+#[tokio::test]
+async fn test_user_requires_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(UserQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let response = schema.execute(format!(r#"{{ user(id: "{}") {{ id }} }}"#, caller_claims().sub)).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("MISSING_TOKEN")));
+}
+
+#[tokio::test]
+async fn test_user_of_someone_else_not_found() {
+    // Nothing reaches the database
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(UserQuery, EmptyMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"{{ user(id: "{}") {{ id }} }}"#, Uuid::new_v4());
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("USER_NOT_FOUND")));
+}
+
+#[tokio::test]
+async fn test_update_someone_else_not_found() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(UserQuery, UserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"mutation {{ updateUser(input: {{ id: "{}", username: "taken_over" }}) {{ id }} }}"#, Uuid::new_v4());
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("USER_NOT_FOUND")));
+}
+
+#[tokio::test]
+async fn test_delete_someone_else_not_found() {
+    let db = Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+
+    let schema = Schema::build(UserQuery, UserMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = format!(r#"mutation {{ deleteUser(id: "{}") }}"#, Uuid::new_v4());
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("USER_NOT_FOUND")));
+    drop(schema);
+    let db = Arc::try_unwrap(db).unwrap_or_else(|_| panic!("Connection still shared"));
+    assert!(db.into_transaction_log().is_empty(), "Nothing should be deleted");
+}
+
+#[tokio::test]
+async fn test_update_password_requires_current_password() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let schema = Schema::build(UserQuery, UserMutation, EmptySubscription)
+        .data(Arc::new(db))
+        .finish();
+
+    let query = format!(r#"mutation {{ updateUser(input: {{ id: "{}", password: "password456" }}) {{ id }} }}"#, caller_claims().sub);
+    let response = schema.execute(Request::new(query).data(caller_claims())).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVALID_INPUT")));
+    let fields = extensions.get("fields").unwrap().clone().into_json().unwrap();
+    assert_eq!(fields[0]["field"], "currentPassword");
+}
+
+#[tokio::test]
+async fn test_update_password_wrong_current_password() {
+    let caller = caller_claims();
+    let db = Arc::new(
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![users::Model {
+                id: caller.sub,
+                username: "test_user".to_owned(),
+                email: "test@example.com".to_owned(),
+                password: PasswordService::from_env().hash("password123").unwrap(),
+                first_name: "test".to_owned(),
+                last_name: "user".to_owned(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
+            }]])
+            .into_connection(),
+    );
+
+    let schema = Schema::build(UserQuery, UserMutation, EmptySubscription)
+        .data(db.clone())
+        .finish();
+
+    let query = format!(
+        r#"mutation {{ updateUser(input: {{ id: "{}", password: "password456", currentPassword: "password789" }}) {{ id }} }}"#,
+        caller.sub
+    );
+    let response = schema.execute(Request::new(query).data(caller)).await;
+
+    assert_eq!(response.errors.len(), 1, "Expected one error: {:?}", response.errors);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVALID_CREDENTIALS")));
+    drop(schema);
+    let db = Arc::try_unwrap(db).unwrap_or_else(|_| panic!("Connection still shared"));
+    assert_eq!(db.into_transaction_log().len(), 1, "Only the password check should run");
 }
@@ -1,11 +1,34 @@
 use std::sync::Arc;
-use async_graphql::{Context, Error, Object, SimpleObject, InputObject};
+use async_graphql::{connection::query, ComplexObject, Context, Error, Object, SimpleObject, InputObject};
 use sea_orm::DatabaseConnection;
 use uuid::Uuid;
 use log::{error, trace};
-use crate::internal::api::users::services::users::{UserService, UserServiceImpl};
+use crate::internal::{
+    api::{
+        admin::users::services::sites::is_visible_on,
+        users::{
+            errors::{auth::UserAuthError, users::UserError, validation::UserValidationError},
+            models::users,
+            services::{
+                gdpr::{GdprService, GdprServiceImpl},
+                users::{UserService, UserServiceImpl},
+                validation::{self, Validator},
+            },
+        },
+    },
+    api::admin::users::errors::interface::CustomGraphQLError,
+    graphql::{
+        auth::user_claims,
+        events::{publish_user_event, UserEvent},
+        pagination::{into_connection, KeysetConnection, PageRequest, UserOrder},
+        site::current_site_id,
+    },
+};
+use serde_json::Value as Json;
+use super::address::{addresses_of, Address};
 
 #[derive(SimpleObject)]
+#[graphql(complex)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
@@ -14,6 +37,35 @@ pub struct User {
     pub email: String,
 }
 
+impl From<users::Model> for User {
+    fn from(u: users::Model) -> Self {
+        User {
+            id: u.id,
+            username: u.username,
+            first_name: u.first_name,
+            last_name: u.last_name,
+            email: u.email,
+        }
+    }
+}
+
+#[ComplexObject]
+impl User {
+    /// Only visible to the user themselves, `ADDRESS_NOT_FOUND` for anyone else.
+    async fn addresses(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Address>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching addresses of user: {}", self.id);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
+            Err(e) => {
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
+            }
+        };
+
+        addresses_of(db.as_ref(), self.id, claims.sub).await
+    }
+}
+
 #[derive(InputObject)]
 pub struct CreateUserInput {
     pub username: String,
@@ -23,12 +75,41 @@ pub struct CreateUserInput {
     pub password: String,
 }
 
+impl CreateUserInput {
+    fn validate(&self) -> Result<(), UserValidationError> {
+        Validator::default()
+            .check("username", &self.username, &[validation::username])
+            .check("firstName", &self.first_name, &[validation::name])
+            .check("lastName", &self.last_name, &[validation::name])
+            .check("email", &self.email, &[validation::email])
+            .check("password", &self.password, &[validation::password])
+            .finish()
+    }
+}
+
 #[derive(InputObject)]
 pub struct UpdateUserInput {
     pub id: Uuid,
     pub username: Option<String>,
     pub email: Option<String>,
     pub password: Option<String>,
+    /// Required along with a new `password`.
+    pub current_password: Option<String>,
+}
+
+impl UpdateUserInput {
+    /// Only the fields being changed are checked.
+    fn validate(&self) -> Result<(), UserValidationError> {
+        let validator = Validator::default()
+            .check_optional("username", self.username.as_deref(), &[validation::username])
+            .check_optional("email", self.email.as_deref(), &[validation::email])
+            .check_optional("password", self.password.as_deref(), &[validation::password]);
+        match self.password {
+            Some(_) => validator.check("currentPassword", self.current_password.as_deref().unwrap_or_default(), &[validation::required]),
+            None => validator,
+        }
+        .finish()
+    }
 }
 
 #[derive(Default)]
@@ -36,8 +117,14 @@ pub struct UserQuery;
 
 #[Object]
 impl UserQuery {
+    /// Fails with `USER_NOT_FOUND` when the user is not the caller or does not exist on the
+    /// current site.
     async fn user(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<User>> {
+        let claims = user_claims(ctx)?;
         trace!("Fetching user with id: {}", id);
+        if claims.sub != id {
+            return Err(UserError::UserNotFound(id.to_string()).new());
+        }
         let db = match ctx.data::<Arc<DatabaseConnection>>() {
             Ok(db) => db,
             Err(e) => {
@@ -46,7 +133,7 @@ impl UserQuery {
         };
 
         match UserServiceImpl::get_user(db.as_ref(), id).await {
-            Ok(Some(u)) => {
+            Ok(Some(u)) if is_visible_on(u.site_id, current_site_id(ctx)) => {
                 trace!("User found: {:?}", u);
                 Ok(Some(User {
                     id: u.id,
@@ -56,38 +143,70 @@ impl UserQuery {
                     email: u.email,
                 }))
             },
-            Ok(None) => {
-                Err(Error::new(format!("User with id {} not found", id)))
+            Ok(_) => {
+                Err(UserError::UserNotFound(id.to_string()).new())
             },
             Err(e) => {
-                Err(Error::new(format!("Failed to fetch user with error {}", e)))
+                Err(UserError::from(e).new())
             }
         }
     }
-    async fn users(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<User>> {
-        trace!("Fetching all users");
+    /// Members of the organisations the caller belongs to.
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        order_by: Option<UserOrder>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<KeysetConnection<User>> {
+        let claims = user_claims(ctx)?;
+        trace!("Fetching users sharing an organisation with user: {}", claims.sub);
         let db = match ctx.data::<Arc<DatabaseConnection>>() {
             Ok(db) => db,
             Err(e) => {
                 return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
             }
         };
+        let order = order_by.unwrap_or_default();
+        let with_total_count = ctx.look_ahead().field("totalCount").exists();
 
-        match UserServiceImpl::get_all_users(db.as_ref()).await {
-            Ok(users) => {
-                trace!("Users found: {:?}", users);
-                Ok(users.into_iter().map(|u| User {
-                    id: u.id,
-                    username: u.username,
-                    first_name: u.first_name,
-                    last_name: u.last_name,
-                    email: u.email,
-                }).collect())
-            },
+        query(after, before, first, last, |after, before, first, last| async move {
+            let page = PageRequest::new(order, after, before, first, last)
+                .map_err(|e| e.new())?
+                .with_total_count(with_total_count);
+
+            match UserServiceImpl::get_users_sharing_organisations(db.as_ref(), claims.sub, current_site_id(ctx), &page).await {
+                Ok(users) => {
+                    trace!("Users found: {:?}", users.items);
+                    Ok(into_connection::<users::Entity, _>(users, order.field, User::from))
+                },
+                Err(e) => {
+                    Err(UserError::from(e).new())
+                }
+            }
+        })
+        .await
+    }
+
+    /// Everything stored about the caller as a JSON archive: the account, addresses,
+    /// organisation memberships and the operations admins carried out on the account.
+    async fn export_my_data(&self, ctx: &Context<'_>) -> async_graphql::Result<Json> {
+        let claims = user_claims(ctx)?;
+        trace!("Exporting the data of user: {}", claims.sub);
+        let db = match ctx.data::<Arc<DatabaseConnection>>() {
+            Ok(db) => db,
             Err(e) => {
-                Err(Error::new(format!("Failed to fetch users with error {}", e)))
+                return Err(Error::new(format!("Failed to access database connection in context with error {:?}", e)));
             }
-        }
+        };
+
+        let export = GdprServiceImpl::export_user_data(db.as_ref(), claims.sub).await.map_err(|e| e.new())?;
+        serde_json::to_value(export).map_err(|e| {
+            error!("Failed to serialize the data export of user '{}': {}", claims.sub, e);
+            Error::new("Failed to build the data export.")
+        })
     }
 }
 
@@ -96,8 +215,11 @@ pub struct UserMutation;
 
 #[Object]
 impl UserMutation {
+    /// Fails with `INVALID_INPUT`, listing the problems of each field under `fields`,
+    /// or with `EMAIL_TAKEN` when another account already uses the email.
     pub async fn create_user(&self, ctx: &Context<'_>, input: CreateUserInput) -> async_graphql::Result<User> {
         trace!("Creating user with username: '{}', email: '{}'", input.username, input.email);
+        input.validate().map_err(|e| e.new())?;
         let db = match ctx.data::<Arc<DatabaseConnection>>() {
             Ok(db) => db,
             Err(e) => {
@@ -105,20 +227,13 @@ impl UserMutation {
             }
         };
 
-        // Check if the email already exists in the database
-        //match UserServiceImpl::find_user_by_email(db.as_ref(), input.email.clone()).await {
-        //    Ok(Some(_)) => {
-        //        return Err(Error::new(format!("Email '{}' is already in use.", input.email)));
-        //    },
-        //    Ok(None) => {
-        //        // Email does not exist, proceed with user creation
-        //    },
-        //    Err(e) => {
-        //        return Err(Error::new(format!("Failed to check email uniqueness: {}", e)));
-        //    }
-        //}
-
-        match UserServiceImpl::create_user(db.as_ref(), input.username.clone(), input.first_name.clone(), input.last_name.clone(), input.email.clone(), input.password).await {
+        // Friendlier than the unique index violation, which still catches concurrent sign-ups
+        match UserServiceImpl::find_user_by_email(db.as_ref(), input.email.clone()).await {
+            Ok(Some(_)) => return Err(UserError::EmailTaken.new()),
+            Ok(None) => {},
+            Err(e) => return Err(UserError::from(e).new()),
+        }
+        match UserServiceImpl::create_user(db.as_ref(), input.username.clone(), input.first_name.clone(), input.last_name.clone(), input.email.clone(), input.password, current_site_id(ctx)).await {
             Ok(user) => {
                 trace!("User created successfully: {:?}", user);
                 Ok(User {
@@ -130,14 +245,22 @@ impl UserMutation {
                 })
             },
             Err(e) => {
-                error!("Failed to create usser with username '{}', email '{}', first_name '{}', last_name '{}', error: {}", input.username, input.email, input.first_name, input.last_name, e);
-                Err(Error::new(format!("Failed to create user with username '{}', email '{}', first_name '{}', last_name '{}', error: {}", input.username, input.email, input.first_name, input.last_name, e)))
+                error!("Failed to create user with username '{}': {}", input.username, e);
+                Err(UserError::from(e).new())
             }
         }
     }
 
+    /// Fails with `INVALID_INPUT` like `createUser`, with `USER_NOT_FOUND` for an unknown id
+    /// or anyone but the caller, with `INVALID_CREDENTIALS` when a new password comes with
+    /// the wrong current one and with `EMAIL_TAKEN` when the new email is in use.
     async fn update_user(&self, ctx: &Context<'_>, input: UpdateUserInput) -> async_graphql::Result<User> {
+        let claims = user_claims(ctx)?;
         trace!("Updating user with id: '{}', username: '{:?}', email: '{:?}'", input.id, input.username, input.email);
+        if claims.sub != input.id {
+            return Err(UserError::UserNotFound(input.id.to_string()).new());
+        }
+        input.validate().map_err(|e| e.new())?;
         let db = match ctx.data::<Arc<DatabaseConnection>>() {
             Ok(db) => db,
             Err(e) => {
@@ -146,6 +269,15 @@ impl UserMutation {
             }
         };
 
+        // A stolen session is not enough to take over the account
+        if let (Some(_), Some(current_password)) = (&input.password, &input.current_password) {
+            match UserServiceImpl::check_password(db.as_ref(), input.id, current_password).await {
+                Ok(true) => {},
+                Ok(false) => return Err(UserAuthError::InvalidCredentials.new()),
+                Err(e) => return Err(UserError::from(e).new()),
+            }
+        }
+
         match UserServiceImpl::update_user(db.as_ref(), input.id, input.username.clone(), input.email.clone(), input.password).await {
             Ok(user) => {
                 trace!("User updated successfully: {:?}", user);
@@ -158,14 +290,19 @@ impl UserMutation {
                 })
             },
             Err(e) => {
-                error!("Failed to update user with id '{}', username '{:?}', email '{:?}': {}", input.id, input.username, input.email, e);
-                Err(Error::new(format!("Failed to update user with id '{}', username '{:?}', email '{:?}': {}", input.id, input.username, input.email, e)))
+                error!("Failed to update user with id '{}': {}", input.id, e);
+                Err(UserError::from(e).new())
             }
         }
     }
 
+    /// Only deletes the caller, `USER_NOT_FOUND` for anyone else.
     async fn delete_user(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let claims = user_claims(ctx)?;
         trace!("Deleting user with id: {}", id);
+        if claims.sub != id {
+            return Err(UserError::UserNotFound(id.to_string()).new());
+        }
         let db = match ctx.data::<Arc<DatabaseConnection>>() {
             Ok(db) => db,
             Err(e) => {
@@ -177,11 +314,14 @@ impl UserMutation {
         match UserServiceImpl::delete_user(db.as_ref(), id).await {
             Ok(result) => {
                 trace!("User with id {} deleted successfully", id);
+                if result {
+                    publish_user_event(ctx, UserEvent::EndUserDeleted(id));
+                }
                 Ok(result)
             },
             Err(e) => {
                 error!("Failed to delete user with id '{}': {}", id, e);
-                Err(Error::new(format!("Failed to delete user with id '{}': {}", id, e)))
+                Err(UserError::from(e).new())
             }
         }
     }
@@ -0,0 +1,57 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::{error, info};
+use sea_orm::DbErr;
+use thiserror::Error;
+
+use crate::internal::api::admin::users::errors::interface::CustomGraphQLError;
+
+/// Errors of the address queries and mutations: `ADDRESS_NOT_FOUND` (404), also reported
+/// for the addresses of other users, and `DATABASE_ACCESS_ERROR` (500).
+#[derive(Error, Debug)]
+pub enum AddressError {
+    #[error("Address not found: {0}")]
+    AddressNotFound(String),
+
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+}
+
+impl From<DbErr> for AddressError {
+    fn from(e: DbErr) -> Self {
+        match e {
+            DbErr::RecordNotFound(msg) => AddressError::AddressNotFound(msg),
+            e => AddressError::DatabaseError(e.to_string()),
+        }
+    }
+}
+
+impl CustomGraphQLError for AddressError {
+    fn new(&self) -> Error {
+        match &self {
+            AddressError::AddressNotFound(address) => {
+                info!("Address not found: {}", address);
+            }
+            AddressError::DatabaseError(e) => {
+                error!("Database error occurred: {:?}", e);
+            }
+        }
+
+        Error::new(match self {
+            AddressError::AddressNotFound(_) => "The requested address does not exist.",
+            AddressError::DatabaseError(_) => "An internal error occurred while accessing the database.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                AddressError::AddressNotFound(_) => {
+                    extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
+                    extensions.set("message", "ADDRESS_NOT_FOUND");
+                }
+                AddressError::DatabaseError(_) => {
+                    extensions.set("code", StatusCode::INTERNAL_SERVER_ERROR.as_u16()); // HTTP 500
+                    extensions.set("message", "DATABASE_ACCESS_ERROR");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::{error, info};
+use thiserror::Error;
+
+use crate::internal::api::admin::users::errors::interface::CustomGraphQLError;
+
+#[derive(Error, Debug)]
+pub enum UserAuthError {
+    #[error("Invalid credentials")]
+    InvalidCredentials,
+
+    #[error("User not found: {0}")]
+    UserNotFound(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl CustomGraphQLError for UserAuthError {
+    fn new(&self) -> Error {
+        match &self {
+            UserAuthError::InvalidCredentials => {
+                info!("Invalid credentials");
+            }
+            UserAuthError::UserNotFound(user) => {
+                info!("User not found: {}", user);
+            }
+            UserAuthError::UnexpectedError(msg) => {
+                error!("Unexpected error: {}", msg);
+            }
+        }
+
+        Error::new(match self {
+            UserAuthError::InvalidCredentials => "Invalid credentials.",
+            UserAuthError::UserNotFound(_) => "The requested user does not exist.",
+            UserAuthError::UnexpectedError(_) => "An unexpected internal error occurred.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                UserAuthError::InvalidCredentials => {
+                    extensions.set("code", StatusCode::UNAUTHORIZED.as_u16()); // HTTP 401
+                    extensions.set("message", "INVALID_CREDENTIALS");
+                }
+                UserAuthError::UserNotFound(_) => {
+                    extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
+                    extensions.set("message", "USER_NOT_FOUND");
+                }
+                UserAuthError::UnexpectedError(_) => {
+                    extensions.set("code", StatusCode::INTERNAL_SERVER_ERROR.as_u16()); // HTTP 500
+                    extensions.set("message", "UNEXPECTED_ERROR");
+                }
+            }
+        })
+    }
+}
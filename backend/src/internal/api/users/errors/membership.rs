@@ -0,0 +1,73 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::{error, info};
+use sea_orm::DbErr;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::errors::interface::CustomGraphQLError;
+
+#[derive(Error, Debug)]
+pub enum MembershipError {
+    #[error("User is not a member of organisation {0}")]
+    NotAMember(Uuid),
+
+    #[error("User is already a member of or invited to organisation {0}")]
+    AlreadyMember(Uuid),
+
+    #[error("No pending invitation to organisation {0}")]
+    InvitationNotFound(Uuid),
+
+    #[error("Role {0} does not belong to the organisation")]
+    InvalidRole(Uuid),
+
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+}
+
+impl From<DbErr> for MembershipError {
+    fn from(e: DbErr) -> Self {
+        MembershipError::DatabaseError(e.to_string())
+    }
+}
+
+impl CustomGraphQLError for MembershipError {
+    fn new(&self) -> Error {
+        match self {
+            MembershipError::DatabaseError(e) => error!("Database error occurred: {:?}", e),
+            _ => info!("{}", self),
+        }
+
+        Error::new(match self {
+            MembershipError::NotAMember(_) => "You are not a member of this organisation.",
+            MembershipError::AlreadyMember(_) => "This user is already a member of the organisation or has a pending invitation.",
+            MembershipError::InvitationNotFound(_) => "No pending invitation to this organisation.",
+            MembershipError::InvalidRole(_) => "The role does not belong to this organisation.",
+            MembershipError::DatabaseError(_) => "An internal error occurred while accessing the database.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                MembershipError::NotAMember(_) => {
+                    extensions.set("code", StatusCode::FORBIDDEN.as_u16()); // HTTP 403
+                    extensions.set("message", "NOT_A_MEMBER");
+                }
+                MembershipError::AlreadyMember(_) => {
+                    extensions.set("code", StatusCode::CONFLICT.as_u16()); // HTTP 409
+                    extensions.set("message", "ALREADY_MEMBER");
+                }
+                MembershipError::InvitationNotFound(_) => {
+                    extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
+                    extensions.set("message", "INVITATION_NOT_FOUND");
+                }
+                MembershipError::InvalidRole(_) => {
+                    extensions.set("code", StatusCode::BAD_REQUEST.as_u16()); // HTTP 400
+                    extensions.set("message", "INVALID_ROLE");
+                }
+                MembershipError::DatabaseError(_) => {
+                    extensions.set("code", StatusCode::INTERNAL_SERVER_ERROR.as_u16()); // HTTP 500
+                    extensions.set("message", "DATABASE_ACCESS_ERROR");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,6 @@
+pub mod address;
+pub mod auth;
+pub mod membership;
+pub mod organisation;
+pub mod users;
+pub mod validation;
@@ -0,0 +1,81 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::{error, info};
+use sea_orm::DbErr;
+use thiserror::Error;
+
+use crate::internal::api::admin::users::errors::interface::CustomGraphQLError;
+
+/// Errors of the organisation and organisation role queries and mutations:
+/// `ORGANISATION_NOT_FOUND` and `ROLE_NOT_FOUND` (404), also reported when the caller is not
+/// a member of the organisation, `NOT_ORGANISATION_OWNER` (403) when a member tries what only
+/// the owner may do, and `DATABASE_ACCESS_ERROR` (500).
+#[derive(Error, Debug)]
+pub enum OrganisationError {
+    #[error("Organisation not found: {0}")]
+    OrganisationNotFound(String),
+
+    #[error("Role not found: {0}")]
+    RoleNotFound(String),
+
+    #[error("Not the owner of organisation: {0}")]
+    NotOwner(String),
+
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+}
+
+impl From<DbErr> for OrganisationError {
+    fn from(e: DbErr) -> Self {
+        match e {
+            DbErr::RecordNotFound(msg) => OrganisationError::OrganisationNotFound(msg),
+            e => OrganisationError::DatabaseError(e.to_string()),
+        }
+    }
+}
+
+impl CustomGraphQLError for OrganisationError {
+    fn new(&self) -> Error {
+        match &self {
+            OrganisationError::OrganisationNotFound(organisation) => {
+                info!("Organisation not found: {}", organisation);
+            }
+            OrganisationError::RoleNotFound(role) => {
+                info!("Role not found: {}", role);
+            }
+            OrganisationError::NotOwner(organisation) => {
+                info!("Not the owner of organisation: {}", organisation);
+            }
+            OrganisationError::DatabaseError(e) => {
+                error!("Database error occurred: {:?}", e);
+            }
+        }
+
+        Error::new(match self {
+            OrganisationError::OrganisationNotFound(_) => "The requested organisation does not exist.",
+            OrganisationError::RoleNotFound(_) => "The requested role does not exist.",
+            OrganisationError::NotOwner(_) => "Only the owner of the organisation can do this.",
+            OrganisationError::DatabaseError(_) => "An internal error occurred while accessing the database.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                OrganisationError::OrganisationNotFound(_) => {
+                    extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
+                    extensions.set("message", "ORGANISATION_NOT_FOUND");
+                }
+                OrganisationError::RoleNotFound(_) => {
+                    extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
+                    extensions.set("message", "ROLE_NOT_FOUND");
+                }
+                OrganisationError::NotOwner(_) => {
+                    extensions.set("code", StatusCode::FORBIDDEN.as_u16()); // HTTP 403
+                    extensions.set("message", "NOT_ORGANISATION_OWNER");
+                }
+                OrganisationError::DatabaseError(_) => {
+                    extensions.set("code", StatusCode::INTERNAL_SERVER_ERROR.as_u16()); // HTTP 500
+                    extensions.set("message", "DATABASE_ACCESS_ERROR");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::{error, info};
+use sea_orm::{DbErr, SqlErr};
+use thiserror::Error;
+
+use crate::internal::api::admin::users::errors::interface::CustomGraphQLError;
+
+/// Errors of the `user`/`users` queries and user mutations.
+///
+/// Clients should match on the `message` extension, which is stable:
+/// `EMAIL_TAKEN` (409), `USER_NOT_FOUND` (404) and `DATABASE_ACCESS_ERROR` (500).
+#[derive(Error, Debug)]
+pub enum UserError {
+    #[error("Email already in use")]
+    EmailTaken,
+
+    #[error("User not found: {0}")]
+    UserNotFound(String),
+
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+}
+
+impl From<DbErr> for UserError {
+    fn from(e: DbErr) -> Self {
+        // Email is the only unique column of users besides the generated id
+        if let Some(SqlErr::UniqueConstraintViolation(_)) = e.sql_err() {
+            return UserError::EmailTaken;
+        }
+        match e {
+            DbErr::RecordNotFound(msg) => UserError::UserNotFound(msg),
+            e => UserError::DatabaseError(e.to_string()),
+        }
+    }
+}
+
+impl CustomGraphQLError for UserError {
+    fn new(&self) -> Error {
+        match &self {
+            UserError::EmailTaken => {
+                info!("Email already in use");
+            }
+            UserError::UserNotFound(user) => {
+                info!("User not found: {}", user);
+            }
+            UserError::DatabaseError(e) => {
+                error!("Database error occurred: {:?}", e);
+            }
+        }
+
+        Error::new(match self {
+            UserError::EmailTaken => "This email address is already in use.",
+            UserError::UserNotFound(_) => "The requested user does not exist.",
+            UserError::DatabaseError(_) => "An internal error occurred while accessing the database.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                UserError::EmailTaken => {
+                    extensions.set("code", StatusCode::CONFLICT.as_u16()); // HTTP 409
+                    extensions.set("message", "EMAIL_TAKEN");
+                }
+                UserError::UserNotFound(_) => {
+                    extensions.set("code", StatusCode::NOT_FOUND.as_u16()); // HTTP 404
+                    extensions.set("message", "USER_NOT_FOUND");
+                }
+                UserError::DatabaseError(_) => {
+                    extensions.set("code", StatusCode::INTERNAL_SERVER_ERROR.as_u16()); // HTTP 500
+                    extensions.set("message", "DATABASE_ACCESS_ERROR");
+                }
+            }
+        })
+    }
+}
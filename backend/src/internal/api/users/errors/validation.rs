@@ -0,0 +1,42 @@
+use actix_web::http::StatusCode;
+use async_graphql::{Error, ErrorExtensions};
+use log::info;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::internal::api::admin::users::errors::interface::CustomGraphQLError;
+
+/// Everything wrong with one input field.
+#[derive(Debug, Serialize)]
+pub struct FieldErrors {
+    pub field: String,
+    pub errors: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum UserValidationError {
+    /// Every rejected field at once, so that forms can show all problems together.
+    #[error("Invalid input: {0:?}")]
+    InvalidInput(Vec<FieldErrors>),
+}
+
+impl CustomGraphQLError for UserValidationError {
+    fn new(&self) -> Error {
+        info!("{}", self);
+
+        Error::new(match self {
+            UserValidationError::InvalidInput(_) => "The input is not valid.",
+        })
+        .extend_with(|_err, extensions| {
+            match self {
+                UserValidationError::InvalidInput(fields) => {
+                    extensions.set("code", StatusCode::BAD_REQUEST.as_u16()); // HTTP 400
+                    extensions.set("message", "INVALID_INPUT");
+                    if let Ok(fields) = async_graphql::to_value(fields) {
+                        extensions.set("fields", fields);
+                    }
+                }
+            }
+        })
+    }
+}
@@ -1 +1,5 @@
 pub mod users;
+pub mod address;
+pub mod organisation;
+pub mod roles;
+pub mod organisation_members;
@@ -14,10 +14,43 @@ pub struct Model {
     pub password: String,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    /// Site the user signed up on, shared by every site when `None`.
+    pub site_id: Option<Uuid>,
+    /// Set once the user followed a verification link, or reset their password by email.
+    pub email_verified_at: Option<DateTimeUtc>,
+    /// Soft deletion time, the row is purged once the retention window has passed.
+    pub deleted_at: Option<DateTimeUtc>,
+    /// Set when the personal data was erased. The anonymised row is kept for good, the
+    /// purge job and restores skip it.
+    pub erased_at: Option<DateTimeUtc>,
+}
+
+impl Entity {
+    /// Accounts that are not soft-deleted, where every lookup should start.
+    pub fn find_active() -> Select<Entity> {
+        Self::find().filter(Column::DeletedAt.is_null())
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::address::Entity")]
+    Address,
+    #[sea_orm(has_many = "super::organisation_members::Entity")]
+    Memberships,
+}
+
+impl Related<super::address::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Address.def()
+    }
+}
+
+impl Related<super::organisation_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Memberships.def()
+    }
+}
 
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {}
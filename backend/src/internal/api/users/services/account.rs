@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use log::{error, trace};
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::internal::api::{
+    admin::users::{
+        errors::{db::AdminDbError, interface::CustomGraphQLError},
+        services::sites::is_visible_on,
+    },
+    users::{
+        errors::auth::UserAuthError,
+        models::users,
+        services::{auth::user_token_audience, users::{UserService, UserServiceImpl}, validation::{self, Validator}},
+    },
+};
+use crate::internal::mail::{templates, Mailer};
+use crate::internal::security::{
+    account_tokens::{AccountTokenPurpose, AccountTokenService, AccountTokenServiceImpl},
+    refresh::{RefreshTokenService, RefreshTokenServiceImpl},
+    throttle::{login_keys, LoginThrottleService, LoginThrottleServiceImpl},
+};
+
+#[async_trait]
+pub trait UserAccountService {
+    /// Emails a reset link when the address belongs to a user visible on `site_id`. Unknown
+    /// addresses succeed alike, and every request counts against the address like a failed
+    /// login, so the mutation can be used neither to probe accounts nor to flood a mailbox.
+    async fn request_password_reset(db: &DatabaseConnection, mailer: &dyn Mailer, email: &str, site_id: Option<Uuid>, address: Option<&str>) -> Result<(), Box<dyn CustomGraphQLError>>;
+    /// Sets the new password, spending the reset token, and ends every session of the user.
+    async fn reset_password(db: &DatabaseConnection, token: &str, password: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>>;
+    async fn request_email_verification(db: &DatabaseConnection, mailer: &dyn Mailer, user_id: Uuid) -> Result<(), Box<dyn CustomGraphQLError>>;
+    async fn verify_email(db: &DatabaseConnection, token: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct UserAccountServiceImpl;
+
+async fn mark_email_verified(db: &DatabaseConnection, user_id: Uuid) -> Result<(), Box<dyn CustomGraphQLError>> {
+    users::Entity::update_many()
+        .col_expr(users::Column::EmailVerifiedAt, Expr::value(Utc::now()))
+        .filter(users::Column::Id.eq(user_id))
+        .filter(users::Column::EmailVerifiedAt.is_null())
+        .exec(db)
+        .await
+        .map(|_| ())
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+}
+
+#[async_trait]
+impl UserAccountService for UserAccountServiceImpl {
+    async fn request_password_reset(db: &DatabaseConnection, mailer: &dyn Mailer, email: &str, site_id: Option<Uuid>, address: Option<&str>) -> Result<(), Box<dyn CustomGraphQLError>> {
+        trace!("Password reset requested for email '{}'", email);
+
+        let keys = login_keys("user-reset", email, address);
+        LoginThrottleServiceImpl::check(db, &keys).await?;
+        LoginThrottleServiceImpl::record_failure(db, &keys).await?;
+
+        // An account of another site is treated as unknown
+        let user = UserServiceImpl::find_user_by_email(db, email.trim().to_string())
+            .await
+            .map_err(|e| Box::new(UserAuthError::UnexpectedError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .filter(|user| is_visible_on(user.site_id, site_id));
+        let user = match user {
+            Some(user) => user,
+            None => {
+                trace!("Unknown email '{}', no reset link sent", email);
+                return Ok(());
+            },
+        };
+
+        let purpose = AccountTokenPurpose::PasswordReset;
+        let token = AccountTokenServiceImpl::issue(db, user.id, &user_token_audience(), purpose).await?;
+        let link = templates::token_link("/reset-password", &token);
+
+        // Only logged: failing here would tell that the account exists
+        if let Err(e) = mailer.send(&templates::password_reset(&user.email, &link, purpose.ttl().num_minutes())).await {
+            error!("Failed to send the password reset email of user {}: {}", user.id, e);
+        }
+        Ok(())
+    }
+
+    async fn reset_password(db: &DatabaseConnection, token: &str, password: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>> {
+        // Checked first, so that a rejected password does not spend the token
+        Validator::default()
+            .check("password", password, &[validation::password])
+            .finish()
+            .map_err(|e| Box::new(e) as Box<dyn CustomGraphQLError>)?;
+
+        let audience = user_token_audience();
+        let user_id = AccountTokenServiceImpl::consume(db, token, &audience, AccountTokenPurpose::PasswordReset).await?;
+        trace!("Resetting password of user {}", user_id);
+
+        UserServiceImpl::update_user(db, user_id, None, None, Some(password.to_string()))
+            .await
+            .map_err(|e| Box::new(UserAuthError::UnexpectedError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        // Following the link proved the address
+        mark_email_verified(db, user_id).await?;
+        RefreshTokenServiceImpl::revoke_all(db, user_id, &audience).await?;
+
+        Ok(user_id)
+    }
+
+    async fn request_email_verification(db: &DatabaseConnection, mailer: &dyn Mailer, user_id: Uuid) -> Result<(), Box<dyn CustomGraphQLError>> {
+        let user = UserServiceImpl::get_user(db, user_id)
+            .await
+            .map_err(|e| Box::new(UserAuthError::UnexpectedError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(UserAuthError::UserNotFound(user_id.to_string())) as Box<dyn CustomGraphQLError>)?;
+        if user.email_verified_at.is_some() {
+            trace!("Email of user {} already verified", user_id);
+            return Ok(());
+        }
+
+        let purpose = AccountTokenPurpose::EmailVerification;
+        let token = AccountTokenServiceImpl::issue(db, user.id, &user_token_audience(), purpose).await?;
+        let link = templates::token_link("/verify-email", &token);
+
+        mailer
+            .send(&templates::email_verification(&user.email, &link, purpose.ttl().num_minutes()))
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn verify_email(db: &DatabaseConnection, token: &str) -> Result<Uuid, Box<dyn CustomGraphQLError>> {
+        let user_id = AccountTokenServiceImpl::consume(db, token, &user_token_audience(), AccountTokenPurpose::EmailVerification).await?;
+        trace!("Email of user {} verified", user_id);
+
+        mark_email_verified(db, user_id).await?;
+        Ok(user_id)
+    }
+}
@@ -0,0 +1,101 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use uuid::Uuid;
+use crate::internal::api::users::models::address;
+use async_trait::async_trait;
+use log::trace;
+
+#[async_trait]
+pub trait AddressService {
+    async fn create_address(db: &DatabaseConnection, user_id: Uuid, street: String, city: String, postal_code: String, country: String) -> Result<address::Model, sea_orm::DbErr>;
+    async fn get_address(db: &DatabaseConnection, id: Uuid) -> Result<Option<address::Model>, sea_orm::DbErr>;
+    async fn get_addresses_by_user(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<address::Model>, sea_orm::DbErr>;
+    async fn update_address(db: &DatabaseConnection, id: Uuid, street: Option<String>, city: Option<String>, postal_code: Option<String>, country: Option<String>) -> Result<address::Model, sea_orm::DbErr>;
+    async fn delete_address(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr>;
+}
+
+pub struct AddressServiceImpl;
+
+#[async_trait]
+impl AddressService for AddressServiceImpl {
+    async fn create_address(db: &DatabaseConnection, user_id: Uuid, street: String, city: String, postal_code: String, country: String) -> Result<address::Model, sea_orm::DbErr> {
+        trace!("Creating address for user: '{}'", user_id);
+
+        let new_address = address::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            street: Set(street),
+            city: Set(city),
+            postal_code: Set(postal_code),
+            country: Set(country),
+        };
+
+        match new_address.insert(db).await {
+            Ok(address) => {
+                trace!("Address created successfully: {:?}", address);
+                Ok(address)
+            },
+            Err(e) => {
+                Err(e)
+            }
+        }
+    }
+
+    async fn get_address(db: &DatabaseConnection, id: Uuid) -> Result<Option<address::Model>, sea_orm::DbErr> {
+        trace!("Fetching address with id: {}", id);
+
+        address::Entity::find_by_id(id).one(db).await
+    }
+
+    async fn get_addresses_by_user(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<address::Model>, sea_orm::DbErr> {
+        trace!("Fetching addresses of user: {}", user_id);
+
+        address::Entity::find()
+            .filter(address::Column::UserId.eq(user_id))
+            .all(db)
+            .await
+    }
+
+    async fn update_address(db: &DatabaseConnection, id: Uuid, street: Option<String>, city: Option<String>, postal_code: Option<String>, country: Option<String>) -> Result<address::Model, sea_orm::DbErr> {
+        trace!("Updating address with id: '{}'", id);
+
+        let mut address: address::ActiveModel = match address::Entity::find_by_id(id).one(db).await {
+            Ok(Some(address)) => address.into(),
+            Ok(None) => {
+                return Err(sea_orm::DbErr::RecordNotFound("Address not found".into()));
+            },
+            Err(e) => {
+                return Err(e);
+            }
+        };
+
+        if let Some(street) = street {
+            address.street = Set(street);
+        }
+        if let Some(city) = city {
+            address.city = Set(city);
+        }
+        if let Some(postal_code) = postal_code {
+            address.postal_code = Set(postal_code);
+        }
+        if let Some(country) = country {
+            address.country = Set(country);
+        }
+
+        match address.update(db).await {
+            Ok(updated_address) => {
+                trace!("Address updated successfully: {:?}", updated_address);
+                Ok(updated_address)
+            },
+            Err(e) => {
+                Err(e)
+            }
+        }
+    }
+
+    async fn delete_address(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr> {
+        trace!("Deleting address with id: {}", id);
+
+        let res = address::Entity::delete_by_id(id).exec(db).await?;
+        Ok(res.rows_affected > 0)
+    }
+}
@@ -0,0 +1,125 @@
+use std::env;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use log::trace;
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::internal::api::{
+    admin::users::{
+        errors::{auth::AuthTokenError, interface::CustomGraphQLError},
+        services::sites::is_visible_on,
+    },
+    users::{
+        errors::auth::UserAuthError,
+        services::users::{UserService, UserServiceImpl},
+    },
+};
+use crate::internal::security::{
+    jwt::{jwt_issuer, jwt_keys, validation_for},
+    refresh::{RefreshTokenService, RefreshTokenServiceImpl, TokenPair},
+};
+
+/// Audience carried by end-user tokens, so they are never accepted by the admin API.
+pub fn user_token_audience() -> String {
+    env::var("JWT_USER_AUDIENCE").unwrap_or_else(|_| "users".to_string())
+}
+
+/// Lifetime of end-user access tokens, in seconds.
+const ACCESS_TOKEN_TTL: i64 = 3600;
+
+#[async_trait]
+pub trait UserTokenService {
+    /// Only users visible on `site_id` may log in there.
+    async fn login(db: &DatabaseConnection, email: String, password: String, site_id: Option<Uuid>) -> Result<TokenPair, Box<dyn CustomGraphQLError>>;
+    async fn refresh_token(db: &DatabaseConnection, refresh_token: &str) -> Result<TokenPair, Box<dyn CustomGraphQLError>>;
+    async fn logout(db: &DatabaseConnection, refresh_token: &str) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    async fn verify_token(token: &str) -> Result<UserClaims, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct UserJwtTokenService;
+
+// Model for end-user JWT claims
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct UserClaims {
+    pub sub: Uuid,
+    pub iss: String,
+    pub aud: String,
+    pub iat: usize,
+    pub nbf: usize,
+    pub exp: usize,
+    pub jti: Uuid,
+}
+
+#[async_trait]
+impl UserTokenService for UserJwtTokenService {
+    async fn login(db: &DatabaseConnection, email: String, password: String, site_id: Option<Uuid>) -> Result<TokenPair, Box<dyn CustomGraphQLError>> {
+        trace!("Logging in user with email: '{}'", email);
+
+        // An account of another site gets the same answer as a wrong password
+        let user = UserServiceImpl::validate_user_credentials(db, email, password)
+            .await
+            .map_err(|e| Box::new(UserAuthError::UnexpectedError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .filter(|user| is_visible_on(user.site_id, site_id))
+            .ok_or_else(|| Box::new(UserAuthError::InvalidCredentials) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(TokenPair {
+            access_token: access_token(user.id)?,
+            refresh_token: RefreshTokenServiceImpl::issue(db, user.id, &user_token_audience()).await?,
+            expires_in: ACCESS_TOKEN_TTL,
+        })
+    }
+
+    async fn refresh_token(db: &DatabaseConnection, refresh_token: &str) -> Result<TokenPair, Box<dyn CustomGraphQLError>> {
+        let rotated = RefreshTokenServiceImpl::rotate(db, refresh_token, &user_token_audience()).await?;
+        trace!("Refreshing token for user {}", rotated.subject_id);
+
+        // The account may have been deleted since the session started
+        let user = UserServiceImpl::get_user(db, rotated.subject_id)
+            .await
+            .map_err(|e| Box::new(UserAuthError::UnexpectedError(e.to_string())) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(UserAuthError::UserNotFound(rotated.subject_id.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(TokenPair {
+            access_token: access_token(user.id)?,
+            refresh_token: rotated.refresh_token,
+            expires_in: ACCESS_TOKEN_TTL,
+        })
+    }
+
+    async fn logout(db: &DatabaseConnection, refresh_token: &str) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        RefreshTokenServiceImpl::revoke(db, refresh_token, &user_token_audience()).await
+    }
+
+    async fn verify_token(token: &str) -> Result<UserClaims, Box<dyn CustomGraphQLError>> {
+        let claims = jwt_keys()
+            .decode::<UserClaims>(token, validation_for(&user_token_audience()))
+            .map(|token_data| token_data.claims)
+            .map_err(|e| Box::new(AuthTokenError::JwtError(e)) as Box<dyn CustomGraphQLError>)?;
+
+        trace!("Verified user token of user {}", claims.sub);
+        Ok(claims)
+    }
+}
+
+pub(crate) fn access_token(user_id: Uuid) -> Result<String, Box<dyn CustomGraphQLError>> {
+    let now = Utc::now();
+    let expiration = now
+        .checked_add_signed(Duration::seconds(ACCESS_TOKEN_TTL))
+        .ok_or_else(|| Box::new(UserAuthError::UnexpectedError("Failed to create expiration timestamp".to_string())) as Box<dyn CustomGraphQLError>)?
+        .timestamp();
+
+    let claims = UserClaims {
+        sub: user_id,
+        iss: jwt_issuer(),
+        aud: user_token_audience(),
+        iat: now.timestamp() as usize,
+        nbf: now.timestamp() as usize,
+        exp: expiration as usize,
+        jti: Uuid::new_v4(),
+    };
+
+    jwt_keys()
+        .encode(&claims)
+        .map_err(|e| Box::new(AuthTokenError::JwtError(e)) as Box<dyn CustomGraphQLError>)
+}
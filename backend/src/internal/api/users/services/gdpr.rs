@@ -0,0 +1,204 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use log::trace;
+use sea_orm::{
+    prelude::DateTimeUtc, sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+use serde::Serialize;
+use serde_json::Value as Json;
+use uuid::Uuid;
+use crate::internal::api::{
+    admin::users::{errors::interface::CustomGraphQLError, models::admin_audit_log},
+    users::{
+        errors::users::UserError,
+        models::{address, organisation_members, users},
+        services::auth::user_token_audience,
+    },
+};
+use crate::internal::security::{
+    models::{account_tokens, refresh_tokens},
+    password::PasswordService,
+    refresh::generate_opaque_token,
+    throttle::{LoginThrottleService, LoginThrottleServiceImpl, ThrottleKey},
+};
+
+/// The account as stored, minus the password hash.
+#[derive(Serialize)]
+pub struct ExportedUser {
+    pub id: Uuid,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub site_id: Option<Uuid>,
+    pub email_verified_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+/// What admins did to the account. Their own addresses and user agents are left out.
+#[derive(Serialize)]
+pub struct ExportedAuditEntry {
+    pub action: String,
+    pub entity: String,
+    pub outcome: String,
+    pub changes: Option<Json>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Serialize)]
+pub struct DataExport {
+    pub exported_at: DateTimeUtc,
+    pub user: ExportedUser,
+    pub addresses: Vec<address::Model>,
+    pub memberships: Vec<organisation_members::Model>,
+    pub audit_entries: Vec<ExportedAuditEntry>,
+}
+
+/// Answers data-subject requests: access to, and erasure of, everything stored about a user.
+#[async_trait]
+pub trait GdprService {
+    async fn export_user_data(db: &DatabaseConnection, user_id: Uuid) -> Result<DataExport, Box<dyn CustomGraphQLError>>;
+    /// Replaces the personal data of the user and their addresses, keeping the rows so that
+    /// memberships and audit entries still point somewhere. The account is deleted as well
+    /// and marked as erased, so that it is neither purged nor restored. Returns how many addresses were anonymised.
+    async fn erase_user(db: &DatabaseConnection, user_id: Uuid) -> Result<u64, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct GdprServiceImpl;
+
+/// Placeholder identity of an erased user, unique like the email column requires.
+pub fn erased_email(user_id: Uuid) -> String {
+    format!("erased-{}@erased.invalid", user_id.simple())
+}
+
+#[async_trait]
+impl GdprService for GdprServiceImpl {
+    async fn export_user_data(db: &DatabaseConnection, user_id: Uuid) -> Result<DataExport, Box<dyn CustomGraphQLError>> {
+        trace!("Exporting the data of user {}", user_id);
+
+        let user = users::Entity::find_active()
+            .filter(users::Column::Id.eq(user_id))
+            .one(db)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(UserError::UserNotFound(user_id.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let addresses = address::Entity::find()
+            .filter(address::Column::UserId.eq(user_id))
+            .all(db)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        let memberships = organisation_members::Entity::find()
+            .filter(organisation_members::Column::UserId.eq(user_id))
+            .order_by_asc(organisation_members::Column::CreatedAt)
+            .all(db)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        let audit_entries = admin_audit_log::Entity::find()
+            .filter(admin_audit_log::Column::TargetId.eq(user_id))
+            .order_by_asc(admin_audit_log::Column::CreatedAt)
+            .all(db)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(DataExport {
+            exported_at: Utc::now(),
+            user: ExportedUser {
+                id: user.id,
+                username: user.username,
+                first_name: user.first_name,
+                last_name: user.last_name,
+                email: user.email,
+                site_id: user.site_id,
+                email_verified_at: user.email_verified_at,
+                created_at: user.created_at,
+                updated_at: user.updated_at,
+            },
+            addresses,
+            memberships,
+            audit_entries: audit_entries
+                .into_iter()
+                .map(|entry| ExportedAuditEntry {
+                    action: entry.action,
+                    entity: entry.entity,
+                    outcome: entry.outcome,
+                    changes: entry.changes,
+                    created_at: entry.created_at,
+                })
+                .collect(),
+        })
+    }
+
+    async fn erase_user(db: &DatabaseConnection, user_id: Uuid) -> Result<u64, Box<dyn CustomGraphQLError>> {
+        trace!("Erasing the personal data of user {}", user_id);
+
+        // Deleted users can be erased too, they are only waiting for the purge
+        let user = users::Entity::find_by_id(user_id)
+            .one(db)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?
+            .ok_or_else(|| Box::new(UserError::UserNotFound(user_id.to_string())) as Box<dyn CustomGraphQLError>)?;
+        let previous_email = user.email.clone();
+
+        // Nobody knows this password, the account cannot be logged into anymore
+        let password = PasswordService::from_env()
+            .hash(&generate_opaque_token())
+            .map_err(|e| Box::new(UserError::DatabaseError(format!("Failed to hash password: {}", e))) as Box<dyn CustomGraphQLError>)?;
+
+        let txn = db.begin()
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        let deleted_at = user.deleted_at.unwrap_or_else(Utc::now);
+        let mut user: users::ActiveModel = user.into();
+        user.username = Set(format!("erased-{}", &user_id.simple().to_string()[..8]));
+        user.first_name = Set(String::new());
+        user.last_name = Set(String::new());
+        user.email = Set(erased_email(user_id));
+        user.password = Set(password);
+        user.email_verified_at = Set(None);
+        user.deleted_at = Set(Some(deleted_at));
+        user.erased_at = Set(Some(Utc::now()));
+        user.updated_at = Set(Utc::now());
+        user.update(&txn)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        let addresses = address::Entity::update_many()
+            .col_expr(address::Column::Street, Expr::value(""))
+            .col_expr(address::Column::City, Expr::value(""))
+            .col_expr(address::Column::PostalCode, Expr::value(""))
+            .col_expr(address::Column::Country, Expr::value(""))
+            .filter(address::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        refresh_tokens::Entity::delete_many()
+            .filter(refresh_tokens::Column::SubjectId.eq(user_id))
+            .filter(refresh_tokens::Column::Audience.eq(user_token_audience()))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        account_tokens::Entity::delete_many()
+            .filter(account_tokens::Column::SubjectId.eq(user_id))
+            .filter(account_tokens::Column::Audience.eq(user_token_audience()))
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        txn.commit()
+            .await
+            .map_err(|e| Box::new(UserError::from(e)) as Box<dyn CustomGraphQLError>)?;
+
+        // Failed reset requests are counted under the old address
+        LoginThrottleServiceImpl::reset(db, &ThrottleKey::account("user-reset", &previous_email)).await?;
+
+        Ok(addresses.rows_affected)
+    }
+}
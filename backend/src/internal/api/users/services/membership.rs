@@ -0,0 +1,143 @@
+use sea_orm::{
+    sea_query::{Expr, Query}, sqlx::types::chrono::Utc, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use uuid::Uuid;
+use crate::internal::api::users::models::{organisation_members, users};
+use async_trait::async_trait;
+use log::trace;
+
+#[async_trait]
+pub trait MembershipService {
+    async fn get_membership(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid) -> Result<Option<organisation_members::Model>, sea_orm::DbErr>;
+    async fn get_members(db: &DatabaseConnection, organisation_id: Uuid) -> Result<Vec<organisation_members::Model>, sea_orm::DbErr>;
+    async fn get_memberships_of_user(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<organisation_members::Model>, sea_orm::DbErr>;
+    async fn get_invitations_of_user(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<organisation_members::Model>, sea_orm::DbErr>;
+    async fn invite_member(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid, role_id: Option<Uuid>, invited_by: Uuid) -> Result<organisation_members::Model, sea_orm::DbErr>;
+    async fn accept_invitation(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid) -> Result<organisation_members::Model, sea_orm::DbErr>;
+    async fn remove_membership(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid) -> Result<bool, sea_orm::DbErr>;
+}
+
+pub struct MembershipServiceImpl;
+
+/// Adds an already accepted membership, used for the creator of an organisation.
+pub async fn add_member<C: ConnectionTrait>(db: &C, organisation_id: Uuid, user_id: Uuid, role_id: Option<Uuid>) -> Result<organisation_members::Model, sea_orm::DbErr> {
+    trace!("Adding user '{}' to organisation '{}'", user_id, organisation_id);
+
+    organisation_members::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        organisation_id: Set(organisation_id),
+        user_id: Set(user_id),
+        role_id: Set(role_id),
+        invited_by: Set(None),
+        created_at: Set(Utc::now()),
+        accepted_at: Set(Some(Utc::now())),
+    }
+    .insert(db)
+    .await
+}
+
+#[async_trait]
+impl MembershipService for MembershipServiceImpl {
+    async fn get_membership(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid) -> Result<Option<organisation_members::Model>, sea_orm::DbErr> {
+        trace!("Fetching membership of user '{}' in organisation '{}'", user_id, organisation_id);
+
+        organisation_members::Entity::find()
+            .filter(organisation_members::Column::OrganisationId.eq(organisation_id))
+            .filter(organisation_members::Column::UserId.eq(user_id))
+            .one(db)
+            .await
+    }
+
+    async fn get_members(db: &DatabaseConnection, organisation_id: Uuid) -> Result<Vec<organisation_members::Model>, sea_orm::DbErr> {
+        trace!("Fetching members of organisation: {}", organisation_id);
+
+        // Memberships of deleted users are kept for a restore, but not listed
+        let active_users = Query::select()
+            .column(users::Column::Id)
+            .from(users::Entity)
+            .and_where(Expr::col(users::Column::DeletedAt).is_null())
+            .to_owned();
+
+        organisation_members::Entity::find()
+            .filter(organisation_members::Column::OrganisationId.eq(organisation_id))
+            .filter(organisation_members::Column::AcceptedAt.is_not_null())
+            .filter(organisation_members::Column::UserId.in_subquery(active_users))
+            .order_by_asc(organisation_members::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    async fn get_memberships_of_user(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<organisation_members::Model>, sea_orm::DbErr> {
+        trace!("Fetching memberships of user: {}", user_id);
+
+        organisation_members::Entity::find()
+            .filter(organisation_members::Column::UserId.eq(user_id))
+            .filter(organisation_members::Column::AcceptedAt.is_not_null())
+            .order_by_asc(organisation_members::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    async fn get_invitations_of_user(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<organisation_members::Model>, sea_orm::DbErr> {
+        trace!("Fetching pending invitations of user: {}", user_id);
+
+        organisation_members::Entity::find()
+            .filter(organisation_members::Column::UserId.eq(user_id))
+            .filter(organisation_members::Column::AcceptedAt.is_null())
+            .order_by_asc(organisation_members::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    async fn invite_member(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid, role_id: Option<Uuid>, invited_by: Uuid) -> Result<organisation_members::Model, sea_orm::DbErr> {
+        trace!("User '{}' invites user '{}' to organisation '{}'", invited_by, user_id, organisation_id);
+
+        let invitation = organisation_members::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            organisation_id: Set(organisation_id),
+            user_id: Set(user_id),
+            role_id: Set(role_id),
+            invited_by: Set(Some(invited_by)),
+            created_at: Set(Utc::now()),
+            accepted_at: Set(None),
+        };
+
+        match invitation.insert(db).await {
+            Ok(invitation) => {
+                trace!("Invitation created successfully: {:?}", invitation);
+                Ok(invitation)
+            },
+            Err(e) => {
+                Err(e)
+            }
+        }
+    }
+
+    async fn accept_invitation(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid) -> Result<organisation_members::Model, sea_orm::DbErr> {
+        trace!("User '{}' accepts the invitation to organisation '{}'", user_id, organisation_id);
+
+        let mut invitation: organisation_members::ActiveModel = match Self::get_membership(db, organisation_id, user_id).await {
+            Ok(Some(membership)) if membership.accepted_at.is_none() => membership.into(),
+            Ok(_) => {
+                return Err(sea_orm::DbErr::RecordNotFound("Invitation not found".into()));
+            },
+            Err(e) => {
+                return Err(e);
+            }
+        };
+
+        invitation.accepted_at = Set(Some(Utc::now()));
+        invitation.update(db).await
+    }
+
+    async fn remove_membership(db: &DatabaseConnection, organisation_id: Uuid, user_id: Uuid) -> Result<bool, sea_orm::DbErr> {
+        trace!("Removing user '{}' from organisation '{}'", user_id, organisation_id);
+
+        let res = organisation_members::Entity::delete_many()
+            .filter(organisation_members::Column::OrganisationId.eq(organisation_id))
+            .filter(organisation_members::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+}
@@ -1,3 +1,25 @@
+pub mod auth;
 pub mod users;
+pub mod address;
+pub mod organisation;
+pub mod roles;
+pub mod membership;
+pub mod account;
+pub mod validation;
+pub mod gdpr;
 #[cfg(test)]
 mod test_users;
+#[cfg(test)]
+mod test_auth;
+#[cfg(test)]
+mod test_address;
+#[cfg(test)]
+mod test_organisation;
+#[cfg(test)]
+mod test_membership;
+#[cfg(test)]
+mod test_account;
+#[cfg(test)]
+mod test_validation;
+#[cfg(test)]
+mod test_gdpr;
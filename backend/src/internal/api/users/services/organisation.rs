@@ -0,0 +1,115 @@
+use sea_orm::{sea_query::Query, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use uuid::Uuid;
+use crate::internal::api::users::models::{organisation, organisation_members, roles};
+use super::membership::add_member;
+use async_trait::async_trait;
+use log::trace;
+
+#[async_trait]
+pub trait OrganisationService {
+    /// Creates the organisation, `owner_id` becoming its first member.
+    async fn create_organisation(db: &DatabaseConnection, name: String, description: Option<String>, owner_id: Option<Uuid>) -> Result<organisation::Model, sea_orm::DbErr>;
+    async fn get_organisation(db: &DatabaseConnection, id: Uuid) -> Result<Option<organisation::Model>, sea_orm::DbErr>;
+    /// Organisations `user_id` is an accepted member of.
+    async fn get_organisations_of_member(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<organisation::Model>, sea_orm::DbErr>;
+    async fn update_organisation(db: &DatabaseConnection, id: Uuid, name: Option<String>, description: Option<String>) -> Result<organisation::Model, sea_orm::DbErr>;
+    async fn delete_organisation(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr>;
+}
+
+pub struct OrganisationServiceImpl;
+
+#[async_trait]
+impl OrganisationService for OrganisationServiceImpl {
+    async fn create_organisation(db: &DatabaseConnection, name: String, description: Option<String>, owner_id: Option<Uuid>) -> Result<organisation::Model, sea_orm::DbErr> {
+        trace!("Creating organisation with name: '{}'", name);
+
+        let new_organisation = organisation::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name),
+            description: Set(description),
+        };
+
+        let txn = db.begin().await?;
+        let organisation = new_organisation.insert(&txn).await?;
+        if let Some(owner_id) = owner_id {
+            add_member(&txn, organisation.id, owner_id, None).await?;
+        }
+        txn.commit().await?;
+
+        trace!("Organisation created successfully: {:?}", organisation);
+        Ok(organisation)
+    }
+
+    async fn get_organisation(db: &DatabaseConnection, id: Uuid) -> Result<Option<organisation::Model>, sea_orm::DbErr> {
+        trace!("Fetching organisation with id: {}", id);
+
+        organisation::Entity::find_by_id(id).one(db).await
+    }
+
+    async fn get_organisations_of_member(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<organisation::Model>, sea_orm::DbErr> {
+        trace!("Fetching organisations of user: {}", user_id);
+
+        let memberships = Query::select()
+            .column(organisation_members::Column::OrganisationId)
+            .from(organisation_members::Entity)
+            .and_where(organisation_members::Column::UserId.eq(user_id))
+            .and_where(organisation_members::Column::AcceptedAt.is_not_null())
+            .to_owned();
+
+        organisation::Entity::find()
+            .filter(organisation::Column::Id.in_subquery(memberships))
+            .order_by_asc(organisation::Column::Name)
+            .all(db)
+            .await
+    }
+
+    async fn update_organisation(db: &DatabaseConnection, id: Uuid, name: Option<String>, description: Option<String>) -> Result<organisation::Model, sea_orm::DbErr> {
+        trace!("Updating organisation with id: '{}'", id);
+
+        let mut organisation: organisation::ActiveModel = match organisation::Entity::find_by_id(id).one(db).await {
+            Ok(Some(organisation)) => organisation.into(),
+            Ok(None) => {
+                return Err(sea_orm::DbErr::RecordNotFound("Organisation not found".into()));
+            },
+            Err(e) => {
+                return Err(e);
+            }
+        };
+
+        if let Some(name) = name {
+            organisation.name = Set(name);
+        }
+        if let Some(description) = description {
+            organisation.description = Set(Some(description));
+        }
+
+        match organisation.update(db).await {
+            Ok(updated_organisation) => {
+                trace!("Organisation updated successfully: {:?}", updated_organisation);
+                Ok(updated_organisation)
+            },
+            Err(e) => {
+                Err(e)
+            }
+        }
+    }
+
+    async fn delete_organisation(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr> {
+        trace!("Deleting organisation with id: {}", id);
+
+        // Memberships and roles are scoped to the organisation and go with it
+        let txn = db.begin().await?;
+        organisation_members::Entity::delete_many()
+            .filter(organisation_members::Column::OrganisationId.eq(id))
+            .exec(&txn)
+            .await?;
+        roles::Entity::delete_many()
+            .filter(roles::Column::OrganisationId.eq(id))
+            .exec(&txn)
+            .await?;
+        let res = organisation::Entity::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await?;
+
+        Ok(res.rows_affected > 0)
+    }
+}
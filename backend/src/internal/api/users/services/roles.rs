@@ -0,0 +1,99 @@
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use uuid::Uuid;
+use crate::internal::api::users::models::{organisation_members, roles};
+use async_trait::async_trait;
+use log::trace;
+
+#[async_trait]
+pub trait RoleService {
+    async fn create_role(db: &DatabaseConnection, organisation_id: Uuid, name: String) -> Result<roles::Model, sea_orm::DbErr>;
+    async fn get_role(db: &DatabaseConnection, id: Uuid) -> Result<Option<roles::Model>, sea_orm::DbErr>;
+    async fn get_roles_by_organisation(db: &DatabaseConnection, organisation_id: Uuid) -> Result<Vec<roles::Model>, sea_orm::DbErr>;
+    async fn update_role(db: &DatabaseConnection, id: Uuid, name: Option<String>) -> Result<roles::Model, sea_orm::DbErr>;
+    async fn delete_role(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr>;
+}
+
+pub struct RoleServiceImpl;
+
+#[async_trait]
+impl RoleService for RoleServiceImpl {
+    async fn create_role(db: &DatabaseConnection, organisation_id: Uuid, name: String) -> Result<roles::Model, sea_orm::DbErr> {
+        trace!("Creating role '{}' in organisation: '{}'", name, organisation_id);
+
+        let new_role = roles::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            organisation_id: Set(organisation_id),
+            name: Set(name),
+        };
+
+        match new_role.insert(db).await {
+            Ok(role) => {
+                trace!("Role created successfully: {:?}", role);
+                Ok(role)
+            },
+            Err(e) => {
+                Err(e)
+            }
+        }
+    }
+
+    async fn get_role(db: &DatabaseConnection, id: Uuid) -> Result<Option<roles::Model>, sea_orm::DbErr> {
+        trace!("Fetching role with id: {}", id);
+
+        roles::Entity::find_by_id(id).one(db).await
+    }
+
+    async fn get_roles_by_organisation(db: &DatabaseConnection, organisation_id: Uuid) -> Result<Vec<roles::Model>, sea_orm::DbErr> {
+        trace!("Fetching roles of organisation: {}", organisation_id);
+
+        roles::Entity::find()
+            .filter(roles::Column::OrganisationId.eq(organisation_id))
+            .order_by_asc(roles::Column::Name)
+            .all(db)
+            .await
+    }
+
+    async fn update_role(db: &DatabaseConnection, id: Uuid, name: Option<String>) -> Result<roles::Model, sea_orm::DbErr> {
+        trace!("Updating role with id: '{}'", id);
+
+        let mut role: roles::ActiveModel = match roles::Entity::find_by_id(id).one(db).await {
+            Ok(Some(role)) => role.into(),
+            Ok(None) => {
+                return Err(sea_orm::DbErr::RecordNotFound("Role not found".into()));
+            },
+            Err(e) => {
+                return Err(e);
+            }
+        };
+
+        if let Some(name) = name {
+            role.name = Set(name);
+        }
+
+        match role.update(db).await {
+            Ok(updated_role) => {
+                trace!("Role updated successfully: {:?}", updated_role);
+                Ok(updated_role)
+            },
+            Err(e) => {
+                Err(e)
+            }
+        }
+    }
+
+    async fn delete_role(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr> {
+        trace!("Deleting role with id: {}", id);
+
+        // Members holding the role stay in the organisation, without a role
+        let txn = db.begin().await?;
+        organisation_members::Entity::update_many()
+            .col_expr(organisation_members::Column::RoleId, Expr::value(Option::<Uuid>::None))
+            .filter(organisation_members::Column::RoleId.eq(id))
+            .exec(&txn)
+            .await?;
+        let res = roles::Entity::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await?;
+
+        Ok(res.rows_affected > 0)
+    }
+}
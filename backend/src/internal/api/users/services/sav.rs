@@ -1,39 +0,0 @@
-#[async_trait]
-pub trait UserService {
-    // ... existing methods ...
-
-    async fn validate_user_credentials(db: &DatabaseConnection, email: String, password: String) -> Result<Option<user::Model>, sea_orm::DbErr>;
-}
-
-#[async_trait]
-impl UserService for UserServiceImpl {
-    // ... existing methods ...
-
-    async fn validate_user_credentials(db: &DatabaseConnection, email: String, password: String) -> Result<Option<user::Model>, sea_orm::DbErr> {
-        trace!("Validating credentials for email: '{}'", email);
-
-        match user::Entity::find()
-            .filter(user::Column::Email.eq(email.clone()))
-            .one(db)
-            .await
-        {
-            Ok(Some(user)) => {
-                // Replace with actual password verification
-                if user.password == password {
-                    trace!("Credentials validated for user: {:?}", user);
-                    Ok(Some(user))
-                } else {
-                    trace!("Invalid password for email: '{}'", email);
-                    Ok(None)
-                }
-            },
-            Ok(None) => {
-                trace!("No user found with email: '{}'", email);
-                Ok(None)
-            },
-            Err(e) => {
-                Err(e)
-            }
-        }
-    }
-}
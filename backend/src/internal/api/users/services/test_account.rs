@@ -0,0 +1,90 @@
+use std::sync::Mutex;
+use async_trait::async_trait;
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+use crate::internal::api::users::{
+    models::users,
+    services::account::{UserAccountService, UserAccountServiceImpl},
+};
+use crate::internal::mail::{Email, MailError, Mailer};
+use crate::internal::security::models::login_attempts;
+
+#[derive(Default)]
+struct RecordingMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+#[async_trait]
+impl Mailer for RecordingMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
+
+fn user(site_id: Option<Uuid>) -> users::Model {
+    users::Model {
+        id: Uuid::new_v4(),
+        username: "test_user".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "user".to_owned(),
+        email: "test@example.com".to_owned(),
+        password: "hashed_password".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        site_id,
+        email_verified_at: None,
+        deleted_at: None,
+        erased_at: None,
+    }
+}
+
+fn attempts() -> login_attempts::Model {
+    login_attempts::Model { key: "user-reset:account:test@example.com".to_owned(), failures: 1, last_failure_at: Utc::now(), locked_until: None }
+}
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult { last_insert_id: 0, rows_affected }
+}
+
+#[tokio::test]
+async fn test_request_password_reset_sends_link() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<login_attempts::Model>::new()])
+        .append_query_results([vec![attempts()]])
+        .append_query_results([vec![user(None)]])
+        .append_exec_results([exec(0), exec(1)])
+        .into_connection();
+    let mailer = RecordingMailer::default();
+
+    UserAccountServiceImpl::request_password_reset(&db, &mailer, "test@example.com", None, None).await.unwrap();
+
+    let sent = mailer.sent.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "test@example.com");
+    assert!(sent[0].text.contains("/reset-password?token="));
+}
+
+#[tokio::test]
+async fn test_request_password_reset_ignores_accounts_of_other_sites() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<login_attempts::Model>::new()])
+        .append_query_results([vec![attempts()]])
+        .append_query_results([vec![user(Some(Uuid::new_v4()))]])
+        .into_connection();
+    let mailer = RecordingMailer::default();
+
+    // Succeeds like for an existing account of this site, but nothing is sent
+    UserAccountServiceImpl::request_password_reset(&db, &mailer, "test@example.com", Some(Uuid::new_v4()), None).await.unwrap();
+
+    assert!(mailer.sent.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_reset_password_rejects_short_password_before_spending_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    assert!(UserAccountServiceImpl::reset_password(&db, "token", "short").await.is_err());
+    assert!(db.into_transaction_log().is_empty());
+}
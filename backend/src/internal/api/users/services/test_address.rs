@@ -0,0 +1,76 @@
+use crate::internal::api::users::services::address::*;
+use crate::internal::api::users::models::address;
+use sea_orm::{DatabaseBackend, DbErr, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+fn test_address(user_id: Uuid) -> address::Model {
+    address::Model {
+        id: Uuid::new_v4(),
+        user_id,
+        street: "1 rue de la Paix".to_owned(),
+        city: "Paris".to_owned(),
+        postal_code: "75002".to_owned(),
+        country: "France".to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn test_create_address() -> Result<(), DbErr> {
+    let user_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![test_address(user_id)]])
+        .into_connection();
+
+    let address = AddressServiceImpl::create_address(
+        &db,
+        user_id,
+        "1 rue de la Paix".to_string(),
+        "Paris".to_string(),
+        "75002".to_string(),
+        "France".to_string(),
+    ).await?;
+
+    assert_eq!(address.user_id, user_id);
+    assert_eq!(address.city, "Paris");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_addresses_by_user() -> Result<(), DbErr> {
+    let user_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![test_address(user_id), test_address(user_id)]])
+        .into_connection();
+
+    let addresses = AddressServiceImpl::get_addresses_by_user(&db, user_id).await?;
+
+    assert_eq!(addresses.len(), 2);
+    assert!(addresses.iter().all(|a| a.user_id == user_id));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_address_not_found() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<address::Model>::new()])
+        .into_connection();
+
+    let result = AddressServiceImpl::update_address(&db, Uuid::new_v4(), None, Some("Lyon".to_string()), None, None).await;
+
+    assert!(matches!(result, Err(DbErr::RecordNotFound(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_address() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .into_connection();
+
+    assert!(AddressServiceImpl::delete_address(&db, Uuid::new_v4()).await?);
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::internal::{
+    api::{
+        admin::users::services::auth::{JwtTokenService, TokenService},
+        users::services::auth::{user_token_audience, UserClaims, UserJwtTokenService, UserTokenService},
+    },
+    security::jwt::{install_test_jwt_keys, jwt_issuer, jwt_keys},
+};
+
+fn claims(issuer: String, audience: String) -> UserClaims {
+    let now = Utc::now().timestamp() as usize;
+    UserClaims {
+        sub: Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap(),
+        iss: issuer,
+        aud: audience,
+        iat: now,
+        nbf: now,
+        exp: now + 60,
+        jti: Uuid::new_v4(),
+    }
+}
+
+#[tokio::test]
+async fn test_verify_user_token() {
+    install_test_jwt_keys();
+    let token = jwt_keys().encode(&claims(jwt_issuer(), user_token_audience())).unwrap();
+
+    let verified = UserJwtTokenService::verify_token(&token).await.unwrap();
+
+    assert_eq!(verified.aud, user_token_audience());
+    assert_eq!(verified.iss, jwt_issuer());
+}
+
+#[tokio::test]
+async fn test_user_token_rejected_by_admin_api() {
+    install_test_jwt_keys();
+    let token = jwt_keys().encode(&claims(jwt_issuer(), user_token_audience())).unwrap();
+
+    assert!(JwtTokenService::verify_token(&token).await.is_err());
+}
+
+#[tokio::test]
+async fn test_token_for_other_audience_rejected() {
+    install_test_jwt_keys();
+    let token = jwt_keys().encode(&claims(jwt_issuer(), "admin".to_string())).unwrap();
+
+    assert!(UserJwtTokenService::verify_token(&token).await.is_err());
+}
+
+#[tokio::test]
+async fn test_token_from_other_issuer_rejected() {
+    install_test_jwt_keys();
+    let token = jwt_keys().encode(&claims("someone-else".to_string(), user_token_audience())).unwrap();
+
+    assert!(UserJwtTokenService::verify_token(&token).await.is_err());
+}
+
+#[tokio::test]
+async fn test_token_not_yet_valid_rejected() {
+    install_test_jwt_keys();
+    let mut not_yet_valid = claims(jwt_issuer(), user_token_audience());
+    not_yet_valid.nbf += 3600;
+    not_yet_valid.exp += 3600;
+    let token = jwt_keys().encode(&not_yet_valid).unwrap();
+
+    assert!(UserJwtTokenService::verify_token(&token).await.is_err());
+}
@@ -0,0 +1,119 @@
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, MockDatabase, MockExecResult};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::models::admin_audit_log;
+use crate::internal::api::users::{
+    models::{address, organisation_members, users},
+    services::gdpr::{erased_email, GdprService, GdprServiceImpl},
+};
+
+fn user(id: Uuid) -> users::Model {
+    users::Model {
+        id,
+        username: "test_user".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "user".to_owned(),
+        email: "test@example.com".to_owned(),
+        password: "hashed_password".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        site_id: None,
+        email_verified_at: None,
+        deleted_at: None,
+        erased_at: None,
+    }
+}
+
+fn home(user_id: Uuid) -> address::Model {
+    address::Model {
+        id: Uuid::new_v4(),
+        user_id,
+        street: "1 rue de la Paix".to_owned(),
+        city: "Paris".to_owned(),
+        postal_code: "75002".to_owned(),
+        country: "France".to_owned(),
+    }
+}
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult { last_insert_id: 0, rows_affected }
+}
+
+#[tokio::test]
+async fn test_export_user_data() {
+    let id = Uuid::new_v4();
+    let membership = organisation_members::Model {
+        id: Uuid::new_v4(),
+        organisation_id: Uuid::new_v4(),
+        user_id: id,
+        role_id: None,
+        invited_by: None,
+        created_at: Utc::now(),
+        accepted_at: Some(Utc::now()),
+    };
+    let entry = admin_audit_log::Model {
+        id: Uuid::new_v4(),
+        actor_id: Some(Uuid::new_v4()),
+        action: "restoreUser".to_owned(),
+        entity: "Ressource::User".to_owned(),
+        target_id: Some(id),
+        outcome: "success".to_owned(),
+        changes: None,
+        ip_address: Some("10.0.0.1".to_owned()),
+        user_agent: Some("curl".to_owned()),
+        created_at: Utc::now(),
+    };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![user(id)]])
+        .append_query_results([vec![home(id)]])
+        .append_query_results([vec![membership.clone()]])
+        .append_query_results([vec![entry]])
+        .into_connection();
+
+    let export = serde_json::to_value(GdprServiceImpl::export_user_data(&db, id).await.unwrap()).unwrap();
+
+    assert_eq!(export["user"]["email"], "test@example.com");
+    assert!(export["user"].get("password").is_none(), "Password hash exported: {}", export);
+    assert_eq!(export["addresses"][0]["city"], "Paris");
+    assert_eq!(export["memberships"][0]["organisation_id"], json!(membership.organisation_id));
+    assert_eq!(export["audit_entries"][0]["action"], "restoreUser");
+    // The admin's own data is not the user's to see
+    assert!(export["audit_entries"][0].get("ip_address").is_none());
+}
+
+#[tokio::test]
+async fn test_export_user_data_unknown_user() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<users::Model>::new()])
+        .into_connection();
+
+    let error = GdprServiceImpl::export_user_data(&db, Uuid::new_v4()).await.err().unwrap().new();
+    let extensions = error.extensions.unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("USER_NOT_FOUND")));
+}
+
+#[tokio::test]
+async fn test_erase_user_replaces_personal_data() {
+    let id = Uuid::new_v4();
+    let erased = users::Model { email: erased_email(id), deleted_at: Some(Utc::now()), ..user(id) };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![user(id)], vec![erased]])
+        .append_exec_results([exec(2), exec(1), exec(0), exec(1)])
+        .into_connection();
+
+    assert_eq!(GdprServiceImpl::erase_user(&db, id).await.unwrap(), 2);
+
+    let transactions = db.into_transaction_log();
+    let log = format!("{:?}", transactions).replace("\\\"", "\"");
+    let erasure = format!("{:?}", transactions[1]);
+    assert!(!erasure.contains("test@example.com") && !erasure.contains("test_user"), "Old data written back: {}", erasure);
+    assert!(log.contains(&erased_email(id)), "Unexpected queries: {}", log);
+    // Marked, so that neither the purge nor a restore touches the row
+    assert!(log.contains(r#""erased_at" = $"#), "Unexpected queries: {}", log);
+    assert!(log.contains(r#"UPDATE "address" SET "street" = $1, "city" = $2, "postal_code" = $3, "country" = $4"#), "Unexpected queries: {}", log);
+    // The rows stay, for memberships and audit entries to keep pointing at them
+    assert!(!log.contains(r#"DELETE FROM "users""#) && !log.contains(r#"DELETE FROM "address""#), "Unexpected queries: {}", log);
+    // Failed reset requests were counted under the old address
+    assert!(log.contains("user-reset:account:test@example.com"), "Unexpected queries: {}", log);
+}
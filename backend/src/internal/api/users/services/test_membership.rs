@@ -0,0 +1,96 @@
+use crate::internal::api::users::services::{membership::*, users::*};
+use crate::internal::api::users::models::{organisation_members, users};
+use crate::internal::graphql::pagination::PageRequest;
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+fn membership(organisation_id: Uuid, user_id: Uuid, accepted: bool) -> organisation_members::Model {
+    organisation_members::Model {
+        id: Uuid::new_v4(),
+        organisation_id,
+        user_id,
+        role_id: None,
+        invited_by: None,
+        created_at: Utc::now(),
+        accepted_at: accepted.then(Utc::now),
+    }
+}
+
+#[tokio::test]
+async fn test_invite_member_is_pending() -> Result<(), DbErr> {
+    let organisation_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![membership(organisation_id, user_id, false)]])
+        .into_connection();
+
+    let invitation = MembershipServiceImpl::invite_member(&db, organisation_id, user_id, None, Uuid::new_v4()).await?;
+
+    assert_eq!(invitation.user_id, user_id);
+    assert!(invitation.accepted_at.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_accept_invitation() -> Result<(), DbErr> {
+    let organisation_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([
+            vec![membership(organisation_id, user_id, false)],
+            vec![membership(organisation_id, user_id, true)],
+        ])
+        .into_connection();
+
+    let membership = MembershipServiceImpl::accept_invitation(&db, organisation_id, user_id).await?;
+
+    assert!(membership.accepted_at.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_accept_invitation_already_member() -> Result<(), DbErr> {
+    let organisation_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![membership(organisation_id, user_id, true)]])
+        .into_connection();
+
+    let result = MembershipServiceImpl::accept_invitation(&db, organisation_id, user_id).await;
+
+    assert!(matches!(result, Err(DbErr::RecordNotFound(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remove_membership() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .into_connection();
+
+    assert!(MembershipServiceImpl::remove_membership(&db, Uuid::new_v4(), Uuid::new_v4()).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_users_sharing_organisations_query() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<users::Model>::new()])
+        .into_connection();
+
+    UserServiceImpl::get_users_sharing_organisations(&db, Uuid::new_v4(), Some(Uuid::new_v4()), &PageRequest::default()).await?;
+
+    // Only accepted memberships of the caller's own organisations are considered
+    let sql = format!("{:?}", db.into_transaction_log()[0]).replace("\\\"", "\"");
+    assert!(sql.contains(r#""id" IN (SELECT "user_id" FROM "organisation_members""#), "Unexpected query: {}", sql);
+    assert!(sql.contains(r#""organisation_id" IN (SELECT "organisation_id" FROM "organisation_members""#), "Unexpected query: {}", sql);
+    assert_eq!(sql.matches(r#""accepted_at" IS NOT NULL"#).count(), 2, "Unexpected query: {}", sql);
+    // and only users shared by every site or belonging to the current one
+    assert!(sql.contains(r#"("site_id" IS NULL OR "site_id" = $2)"#), "Unexpected query: {}", sql);
+
+    Ok(())
+}
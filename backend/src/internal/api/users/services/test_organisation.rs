@@ -0,0 +1,123 @@
+use crate::internal::api::users::services::{organisation::*, roles::*};
+use crate::internal::api::users::models::{organisation, organisation_members, roles};
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_create_organisation() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![organisation::Model {
+            id: Uuid::new_v4(),
+            name: "Acme".to_owned(),
+            description: Some("Anvils and rockets".to_owned()),
+        }]])
+        .into_connection();
+
+    let organisation = OrganisationServiceImpl::create_organisation(&db, "Acme".to_string(), Some("Anvils and rockets".to_string()), None).await?;
+
+    assert_eq!(organisation.name, "Acme");
+    assert_eq!(organisation.description.as_deref(), Some("Anvils and rockets"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_organisation_adds_owner() -> Result<(), DbErr> {
+    let organisation_id = Uuid::new_v4();
+    let owner_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![organisation::Model {
+            id: organisation_id,
+            name: "Acme".to_owned(),
+            description: None,
+        }]])
+        .append_query_results([vec![organisation_members::Model {
+            id: Uuid::new_v4(),
+            organisation_id,
+            user_id: owner_id,
+            role_id: None,
+            invited_by: None,
+            created_at: Utc::now(),
+            accepted_at: Some(Utc::now()),
+        }]])
+        .into_connection();
+
+    let organisation = OrganisationServiceImpl::create_organisation(&db, "Acme".to_string(), None, Some(owner_id)).await?;
+    assert_eq!(organisation.id, organisation_id);
+
+    // The membership insert runs in the same transaction as the organisation
+    let log = db.into_transaction_log();
+    assert_eq!(log.len(), 1);
+    assert!(format!("{:?}", log[0]).contains("organisation_members"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_organisations_of_member() -> Result<(), DbErr> {
+    let user_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![organisation::Model {
+            id: Uuid::new_v4(),
+            name: "Acme".to_owned(),
+            description: None,
+        }]])
+        .into_connection();
+
+    let organisations = OrganisationServiceImpl::get_organisations_of_member(&db, user_id).await?;
+    assert_eq!(organisations.len(), 1);
+
+    // Accepted memberships of the user only
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains("\"id\" IN (SELECT \"organisation_id\" FROM \"organisation_members\""), "Unexpected query: {}", log);
+    assert!(log.contains("\"accepted_at\" IS NOT NULL"), "Unexpected query: {}", log);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_organisation_removes_roles() -> Result<(), DbErr> {
+    // Memberships and roles first, then the organisation itself
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([
+            MockExecResult { last_insert_id: 0, rows_affected: 3 },
+            MockExecResult { last_insert_id: 0, rows_affected: 2 },
+            MockExecResult { last_insert_id: 0, rows_affected: 1 },
+        ])
+        .into_connection();
+
+    assert!(OrganisationServiceImpl::delete_organisation(&db, Uuid::new_v4()).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_roles_by_organisation() -> Result<(), DbErr> {
+    let organisation_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![
+            roles::Model { id: Uuid::new_v4(), organisation_id, name: "Manager".to_owned() },
+            roles::Model { id: Uuid::new_v4(), organisation_id, name: "Member".to_owned() },
+        ]])
+        .into_connection();
+
+    let roles = RoleServiceImpl::get_roles_by_organisation(&db, organisation_id).await?;
+
+    assert_eq!(roles.len(), 2);
+    assert_eq!(roles[0].name, "Manager");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_role_not_found() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<roles::Model>::new()])
+        .into_connection();
+
+    let result = RoleServiceImpl::update_role(&db, Uuid::new_v4(), Some("Owner".to_string())).await;
+
+    assert!(matches!(result, Err(DbErr::RecordNotFound(_))));
+
+    Ok(())
+}
@@ -1,11 +1,15 @@
 use crate::internal::api::users::services::users::*;
 use crate::internal::api::users::models::users;
+use crate::internal::graphql::pagination::{Cursor, PageRequest, SortDirection, UserOrder, UserSortField};
+use crate::internal::security::password::PasswordService;
+use std::collections::BTreeMap;
 use sea_orm::{
-    sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase, MockExecResult
+    sqlx::types::chrono::Utc, DatabaseBackend, DbErr, MockDatabase, MockExecResult, Value
 };
 use uuid::Uuid;
 
 #[tokio::test]
+#[allow(clippy::useless_conversion)]
 async fn test_create_user() -> Result<(), DbErr> {
     // Configure the mock database
     let db = MockDatabase::new(DatabaseBackend::Postgres)
@@ -19,6 +23,10 @@ async fn test_create_user() -> Result<(), DbErr> {
                 password: "hashed_password".to_owned(),
                 created_at: Utc::now().into(),
                 updated_at: Utc::now().into(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
             }],
         ])
         .into_connection();
@@ -30,7 +38,8 @@ async fn test_create_user() -> Result<(), DbErr> {
         "test".to_string(),
         "user".to_string(),
         "test@example.com".to_string(),
-        "password".to_string()
+        "password".to_string(),
+        None
     ).await;
 
     // Ensure that user creation was successful
@@ -57,6 +66,7 @@ async fn test_create_user_db_error() -> Result<(), DbErr> {
         "user".to_string(),
         "test@example.com".to_string(),
         "password".to_string(),
+        None
     ).await;
 
     // Assert: Ensure that the function returns the correct error
@@ -71,6 +81,7 @@ async fn test_create_user_db_error() -> Result<(), DbErr> {
 }
 
 #[tokio::test]
+#[allow(clippy::useless_conversion)]
 async fn test_get_user_found() -> Result<(), DbErr> {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -87,6 +98,10 @@ async fn test_get_user_found() -> Result<(), DbErr> {
                 password: "hashed_password".to_owned(),
                 created_at: Utc::now().into(),
                 updated_at: Utc::now().into(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
             }],
         ])
         .into_connection();
@@ -146,6 +161,7 @@ async fn test_get_user_db_error() -> Result<(), DbErr> {
 }
 
 #[tokio::test]
+#[allow(clippy::useless_conversion)]
 async fn test_get_all_users_success() {
     // Mock UUIDs for testing
     let uuid1 = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -163,6 +179,10 @@ async fn test_get_all_users_success() {
                 password: "hashed_password1".to_owned(),
                 created_at: Utc::now().into(),
                 updated_at: Utc::now().into(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
             },
             users::Model {
                 id: uuid2,
@@ -173,6 +193,10 @@ async fn test_get_all_users_success() {
                 password: "hashed_password2".to_owned(),
                 created_at: Utc::now().into(),
                 updated_at: Utc::now().into(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
             },
         ]])
         .into_connection();
@@ -218,6 +242,7 @@ async fn test_get_all_users_db_error() {
 }
 
 #[tokio::test]
+#[allow(clippy::useless_conversion)]
 async fn test_find_user_by_email_success() {
     // Mock UUID for testing
     let uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -233,6 +258,10 @@ async fn test_find_user_by_email_success() {
             password: "hashed_password".to_owned(),
             created_at: Utc::now().into(),
             updated_at: Utc::now().into(),
+            site_id: None,
+            email_verified_at: None,
+            deleted_at: None,
+            erased_at: None,
         }]])
         .into_connection();
 
@@ -321,6 +350,7 @@ async fn test_update_user_not_found() -> Result<(), DbErr> {
 }
 
 #[tokio::test]
+#[allow(clippy::useless_conversion)]
 async fn test_update_user_success() -> Result<(), DbErr> {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -335,6 +365,10 @@ async fn test_update_user_success() -> Result<(), DbErr> {
         password: "old_password_hash".to_string(),
         created_at: Utc::now().into(),
         updated_at: Utc::now().into(),
+        site_id: None,
+        email_verified_at: None,
+        deleted_at: None,
+        erased_at: None,
     };
 
     // Mock the database with the initial user and expected updated user
@@ -350,8 +384,14 @@ async fn test_update_user_success() -> Result<(), DbErr> {
                 password: "old_password_hash".to_owned(), // Assuming password isn't updated in this test
                 created_at: Utc::now().into(),
                 updated_at: Utc::now().into(),
+                site_id: None,
+                email_verified_at: None,
+                deleted_at: None,
+                erased_at: None,
             }],
         ])
+        // Pending verification links of the old address are revoked
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 0 }])
         .into_connection();
 
     // Act: Call the update_user function with updated values
@@ -404,6 +444,7 @@ async fn test_update_user_db_error() -> Result<(), DbErr> {
 }
 
 #[tokio::test]
+#[allow(clippy::bool_assert_comparison)]
 async fn test_delete_user_success() -> Result<(), DbErr> {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -427,6 +468,7 @@ async fn test_delete_user_success() -> Result<(), DbErr> {
 }
 
 #[tokio::test]
+#[allow(clippy::bool_assert_comparison)]
 async fn test_delete_user_not_found() -> Result<(), DbErr> {
     // Fixed UUID for testing
     let fixed_uuid = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
@@ -472,3 +514,230 @@ async fn test_delete_user_db_error() -> Result<(), DbErr> {
 
     Ok(())
 }
+
+fn user_with_password(password: &str) -> users::Model {
+    users::Model {
+        id: Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap(),
+        username: "test_user".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "user".to_owned(),
+        email: "test@example.com".to_owned(),
+        password: PasswordService::from_env().hash(password).unwrap(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        site_id: None,
+        email_verified_at: None,
+        deleted_at: None,
+        erased_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_validate_user_credentials_success() {
+    // Mock the database with a user whose password is hashed with the current configuration
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![user_with_password("password123")]])
+        .into_connection();
+
+    let result = UserServiceImpl::validate_user_credentials(&db, "test@example.com".to_string(), "password123".to_string()).await;
+
+    assert!(result.is_ok(), "Expected Ok but got Err: {:?}", result);
+    let user = result.unwrap();
+    assert!(user.is_some(), "Expected Some(user) but got None");
+    assert_eq!(user.unwrap().email, "test@example.com");
+}
+
+#[tokio::test]
+async fn test_validate_user_credentials_wrong_password() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![user_with_password("password123")]])
+        .into_connection();
+
+    let result = UserServiceImpl::validate_user_credentials(&db, "test@example.com".to_string(), "wrong_password".to_string()).await;
+
+    assert!(result.is_ok(), "Expected Ok but got Err: {:?}", result);
+    assert!(result.unwrap().is_none(), "Expected None for a wrong password");
+}
+
+#[tokio::test]
+async fn test_validate_user_credentials_unknown_email() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results::<users::Model, Vec<users::Model>, _>([vec![]])
+        .into_connection();
+
+    let result = UserServiceImpl::validate_user_credentials(&db, "unknown@example.com".to_string(), "password123".to_string()).await;
+
+    assert!(result.is_ok(), "Expected Ok but got Err: {:?}", result);
+    assert!(result.unwrap().is_none(), "Expected None for an unknown email");
+}
+
+#[tokio::test]
+async fn test_get_users_sharing_organisations_keyset_page() -> Result<(), DbErr> {
+    let first = user_with_password("password");
+    let second = users::Model { id: Uuid::new_v4(), email: "second@example.com".to_owned(), ..first.clone() };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![first.clone(), second]])
+        .append_query_results([vec![BTreeMap::from([("num_items", Value::BigInt(Some(5)))])]])
+        .into_connection();
+
+    let order = UserOrder { field: UserSortField::Email, direction: SortDirection::Desc };
+    let after = Cursor::text(UserSortField::Email, first.id, "z@example.com");
+    let page = PageRequest::new(order, Some(after), None, Some(1), None).unwrap().with_total_count(true);
+
+    let result = UserServiceImpl::get_users_sharing_organisations(&db, Uuid::new_v4(), None, &page).await?;
+
+    // One row more than requested tells whether another page follows
+    assert_eq!(result.items, vec![first]);
+    assert!(result.has_next_page);
+    assert!(result.has_previous_page);
+    assert_eq!(result.total_count, Some(5));
+
+    let log = db.into_transaction_log();
+    let sql = format!("{:?}", log[0]).replace("\\\"", "\"");
+    assert!(sql.contains(r#"("users"."email" < $"#), "Unexpected query: {}", sql);
+    assert!(sql.contains(r#"ORDER BY "users"."email" DESC, "users"."id" DESC LIMIT $"#), "Unexpected query: {}", sql);
+    // The total ignores the cursor
+    let count = format!("{:?}", log[1]).replace("\\\"", "\"");
+    assert!(count.contains("COUNT(*)") && !count.contains(r#""email" <"#), "Unexpected query: {}", count);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_users_sharing_organisations_backward_page() -> Result<(), DbErr> {
+    let first = user_with_password("password");
+    let second = users::Model { id: Uuid::new_v4(), ..first.clone() };
+    // Rows come back in reverse order when paging from the end
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![second.clone(), first.clone()]])
+        .into_connection();
+
+    let page = PageRequest::new(UserOrder::default(), None, None, None, Some(2)).unwrap();
+
+    let result = UserServiceImpl::get_users_sharing_organisations(&db, Uuid::new_v4(), None, &page).await?;
+
+    assert_eq!(result.items, vec![first, second]);
+    assert!(!result.has_previous_page);
+    assert!(!result.has_next_page);
+    assert_eq!(result.total_count, None);
+
+    let sql = format!("{:?}", db.into_transaction_log()[0]).replace("\\\"", "\"");
+    assert!(sql.contains(r#"ORDER BY "users"."created_at" DESC, "users"."id" DESC"#), "Unexpected query: {}", sql);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_user_is_soft() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([MockExecResult { rows_affected: 1, last_insert_id: 0 }])
+        .into_connection();
+
+    assert!(UserServiceImpl::delete_user(&db, Uuid::new_v4()).await?);
+
+    let sql = format!("{:?}", db.into_transaction_log()[0]).replace("\\\"", "\"");
+    assert!(sql.contains(r#"UPDATE "users" SET "deleted_at" = $1"#), "Unexpected query: {}", sql);
+    assert!(sql.contains(r#""users"."deleted_at" IS NULL"#), "Unexpected query: {}", sql);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_user_skips_deleted_users() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<users::Model>::new()])
+        .into_connection();
+
+    assert_eq!(UserServiceImpl::get_user(&db, Uuid::new_v4()).await?, None);
+
+    let sql = format!("{:?}", db.into_transaction_log()[0]).replace("\\\"", "\"");
+    assert!(sql.contains(r#""users"."deleted_at" IS NULL"#), "Unexpected query: {}", sql);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restore_user() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([
+            MockExecResult { rows_affected: 1, last_insert_id: 0 },
+            MockExecResult { rows_affected: 0, last_insert_id: 0 },
+        ])
+        .into_connection();
+    let id = Uuid::new_v4();
+
+    assert!(UserServiceImpl::restore_user(&db, id).await?);
+    // Not deleted, or already purged
+    assert!(!UserServiceImpl::restore_user(&db, id).await?);
+
+    let sql = format!("{:?}", db.into_transaction_log()[0]).replace("\\\"", "\"");
+    assert!(sql.contains(r#""users"."deleted_at" IS NOT NULL"#), "Unexpected query: {}", sql);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restore_user_refuses_erased_users() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([MockExecResult { rows_affected: 0, last_insert_id: 0 }])
+        .into_connection();
+
+    assert!(!UserServiceImpl::restore_user(&db, Uuid::new_v4()).await?);
+
+    let sql = format!("{:?}", db.into_transaction_log()[0]).replace("\\\"", "\"");
+    assert!(sql.contains(r#""users"."erased_at" IS NULL"#), "Unexpected query: {}", sql);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_purge_deleted_users_removes_dependents_first() -> Result<(), DbErr> {
+    let deleted = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![BTreeMap::from([("id".to_string(), Value::Uuid(Some(Box::new(deleted))))])]])
+        .append_exec_results([
+            MockExecResult { rows_affected: 2, last_insert_id: 0 }, // addresses
+            MockExecResult { rows_affected: 1, last_insert_id: 0 }, // memberships
+            MockExecResult { rows_affected: 3, last_insert_id: 0 }, // refresh tokens
+            MockExecResult { rows_affected: 0, last_insert_id: 0 }, // account tokens
+            MockExecResult { rows_affected: 1, last_insert_id: 0 }, // users
+        ])
+        .into_connection();
+
+    assert_eq!(UserServiceImpl::purge_deleted_users(&db, Utc::now()).await?, 1);
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#""users"."deleted_at" < $1 AND "users"."erased_at" IS NULL FOR UPDATE"#), "Unexpected queries: {}", log);
+    let address = log.find(r#"DELETE FROM "address""#).expect("addresses not purged");
+    let members = log.find(r#"DELETE FROM "organisation_members""#).expect("memberships not purged");
+    let users = log.find(r#"DELETE FROM "users""#).expect("users not purged");
+    assert!(address < users && members < users, "Unexpected queries: {}", log);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_purge_deleted_users_nothing_to_purge() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+        .into_connection();
+
+    assert_eq!(UserServiceImpl::purge_deleted_users(&db, Utc::now()).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_purge_deleted_users_keeps_erased_users() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+        .into_connection();
+
+    assert_eq!(UserServiceImpl::purge_deleted_users(&db, Utc::now()).await?, 0);
+
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#""users"."erased_at" IS NULL"#), "Unexpected queries: {}", log);
+    assert!(!log.contains("DELETE"), "Unexpected queries: {}", log);
+
+    Ok(())
+}
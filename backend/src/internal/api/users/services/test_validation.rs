@@ -0,0 +1,55 @@
+use crate::internal::api::users::{
+    errors::validation::UserValidationError,
+    services::validation::{self, Validator},
+};
+
+#[test]
+fn test_username_rules() {
+    assert!(validation::username("test_user").is_empty());
+    assert!(validation::username("a.b-c").is_empty());
+    assert_eq!(validation::username("ab").len(), 1);
+    assert_eq!(validation::username(&"a".repeat(33)).len(), 1);
+    // Too short and with a forbidden character
+    assert_eq!(validation::username(" é").len(), 2);
+}
+
+#[test]
+fn test_name_rules() {
+    assert!(validation::name("Jean-Pierre").is_empty());
+    assert_eq!(validation::name("   "), vec!["This field cannot be empty.".to_string()]);
+    assert_eq!(validation::name(&"a".repeat(65)).len(), 1);
+}
+
+#[test]
+fn test_email_rules() {
+    assert!(validation::email("user@example.com").is_empty());
+    for invalid in ["", "user", "@example.com", "user@example", "user@.com", "user@example.", "us er@example.com", "a@b@example.com"] {
+        assert_eq!(validation::email(invalid).len(), 1, "'{}' should be rejected", invalid);
+    }
+    assert_eq!(validation::email(&format!("{}@example.com", "a".repeat(250))).len(), 1);
+}
+
+#[test]
+fn test_password_policy() {
+    assert!(validation::password("password123").is_empty());
+    assert_eq!(validation::password("a1").len(), 1);
+    assert_eq!(validation::password("12345678").len(), 1);
+    assert_eq!(validation::password("password").len(), 1);
+    // Every broken rule is reported
+    assert_eq!(validation::password("x").len(), 2);
+    assert_eq!(validation::password(&"a1".repeat(65)).len(), 1);
+}
+
+#[test]
+fn test_validator_collects_every_field() {
+    let result = Validator::default()
+        .check("username", "ab", &[validation::username])
+        .check("email", "user@example.com", &[validation::email])
+        .check_optional("password", Some("x"), &[validation::password])
+        .check_optional("firstName", None, &[validation::name])
+        .finish();
+
+    let UserValidationError::InvalidInput(fields) = result.unwrap_err();
+    let fields: Vec<(&str, usize)> = fields.iter().map(|f| (f.field.as_str(), f.errors.len())).collect();
+    assert_eq!(fields, vec![("username", 1), ("password", 2)]);
+}
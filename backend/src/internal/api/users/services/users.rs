@@ -1,25 +1,63 @@
-use sea_orm::{sqlx::types::chrono::Utc, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{prelude::DateTimeUtc, sea_query::{Expr, Query}, sqlx::types::chrono::Utc, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, Set, TransactionTrait};
 use uuid::Uuid;
-use crate::internal::api::users::models::users;
+use crate::internal::{
+    api::{admin::users::services::sites::site_scope, users::{models::{address, organisation_members, users}, services::auth::user_token_audience}},
+    graphql::pagination::{paginate, Cursor, Keyset, Page, PageRequest, UserSortField},
+    security::{account_tokens::AccountTokenPurpose, models::{account_tokens, refresh_tokens}, password::PasswordService},
+};
 use async_trait::async_trait;
-use log::trace;
+use log::{trace, warn};
 
 #[async_trait]
 pub trait UserService {
-    async fn create_user(db: &DatabaseConnection, username: String, firstname: String, lastname: String, email: String, password: String) -> Result<users::Model, sea_orm::DbErr>;
+    async fn create_user(db: &DatabaseConnection, username: String, firstname: String, lastname: String, email: String, password: String, site_id: Option<Uuid>) -> Result<users::Model, sea_orm::DbErr>;
     async fn get_user(db: &DatabaseConnection, id: Uuid) -> Result<Option<users::Model>, sea_orm::DbErr>;
     async fn get_all_users(db: &DatabaseConnection) -> Result<Vec<users::Model>, sea_orm::DbErr>;
+    /// Members of the organisations `user_id` belongs to, `user_id` included, that are
+    /// visible on `site_id`. Pending invitations count on neither side.
+    async fn get_users_sharing_organisations(db: &DatabaseConnection, user_id: Uuid, site_id: Option<Uuid>, page: &PageRequest) -> Result<Page<users::Model>, sea_orm::DbErr>;
     async fn update_user(db: &DatabaseConnection, id: Uuid, username: Option<String>, email: Option<String>, password: Option<String>) -> Result<users::Model, sea_orm::DbErr>;
+    /// Soft deletes the user, the row is purged once the retention window has passed.
     async fn delete_user(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr>;
+    /// Undoes `delete_user` as long as the user has been neither purged nor erased.
+    async fn restore_user(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr>;
+    /// Hard deletes the users soft-deleted before `deleted_before`, with their addresses,
+    /// memberships and tokens. Erased users are kept. Returns how many users were purged.
+    async fn purge_deleted_users(db: &DatabaseConnection, deleted_before: DateTimeUtc) -> Result<u64, sea_orm::DbErr>;
 
     async fn find_user_by_email(db: &DatabaseConnection, email: String) -> Result<Option<users::Model>, sea_orm::DbErr>;
+    async fn validate_user_credentials(db: &DatabaseConnection, email: String, password: String) -> Result<Option<users::Model>, sea_orm::DbErr>;
+    /// Whether `password` is the current password of the user, `RecordNotFound` for an unknown id.
+    async fn check_password(db: &DatabaseConnection, id: Uuid, password: &str) -> Result<bool, sea_orm::DbErr>;
 }
 
 pub struct UserServiceImpl;
 
+impl Keyset for users::Entity {
+    fn id_column() -> users::Column {
+        users::Column::Id
+    }
+
+    fn sort_column(field: UserSortField) -> users::Column {
+        match field {
+            UserSortField::CreatedAt => users::Column::CreatedAt,
+            UserSortField::Email => users::Column::Email,
+            UserSortField::Username => users::Column::Username,
+        }
+    }
+
+    fn cursor(user: &users::Model, field: UserSortField) -> Cursor {
+        match field {
+            UserSortField::CreatedAt => Cursor::timestamp(field, user.id, user.created_at),
+            UserSortField::Email => Cursor::text(field, user.id, &user.email),
+            UserSortField::Username => Cursor::text(field, user.id, &user.username),
+        }
+    }
+}
+
 #[async_trait]
 impl UserService for UserServiceImpl {
-    async fn create_user(db: &DatabaseConnection, username: String, firstname: String, lastname: String, email: String, password: String) -> Result<users::Model, sea_orm::DbErr> {
+    async fn create_user(db: &DatabaseConnection, username: String, firstname: String, lastname: String, email: String, password: String, site_id: Option<Uuid>) -> Result<users::Model, sea_orm::DbErr> {
         trace!("Creating user with username: '{}', email: '{}'", username, email);
         
         let new_user = users::ActiveModel {
@@ -28,9 +66,13 @@ impl UserService for UserServiceImpl {
             first_name: Set(firstname.clone()),
             last_name: Set(lastname.clone()),
             email: Set(email.clone()),
-            password: Set(hash_password(password)),
-            created_at: Set(Utc::now().into()),
-            updated_at: Set(Utc::now().into()),
+            password: Set(hash_password(&password)?),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+            site_id: Set(site_id),
+            email_verified_at: Set(None),
+            deleted_at: Set(None),
+            erased_at: Set(None),
         };
 
         match new_user.insert(db).await {
@@ -47,7 +89,7 @@ impl UserService for UserServiceImpl {
     async fn get_user(db: &DatabaseConnection, id: Uuid) -> Result<Option<users::Model>, sea_orm::DbErr> {
         trace!("Fetching user with id: {}", id);
 
-        match users::Entity::find_by_id(id).one(db).await {
+        match users::Entity::find_active().filter(users::Column::Id.eq(id)).one(db).await {
             Ok(Some(user)) => {
                 trace!("User found: {:?}", user);
                 Ok(Some(user))
@@ -65,7 +107,7 @@ impl UserService for UserServiceImpl {
     async fn get_all_users(db: &DatabaseConnection) -> Result<Vec<users::Model>, sea_orm::DbErr> {
         trace!("Fetching all users");
 
-        match users::Entity::find().all(db).await {
+        match users::Entity::find_active().all(db).await {
             Ok(users) => {
                 trace!("Users found: {:?}", users);
                 Ok(users)
@@ -76,13 +118,48 @@ impl UserService for UserServiceImpl {
          }
     }
 
+    async fn get_users_sharing_organisations(db: &DatabaseConnection, user_id: Uuid, site_id: Option<Uuid>, page: &PageRequest) -> Result<Page<users::Model>, sea_orm::DbErr> {
+        trace!("Fetching users sharing an organisation with user {} on site {:?}", user_id, site_id);
+
+        // SELECT * FROM users WHERE id IN (
+        //     SELECT user_id FROM organisation_members WHERE accepted_at IS NOT NULL AND organisation_id IN (
+        //         SELECT organisation_id FROM organisation_members WHERE user_id = $1 AND accepted_at IS NOT NULL))
+        let own_organisations = Query::select()
+            .column(organisation_members::Column::OrganisationId)
+            .from(organisation_members::Entity)
+            .and_where(Expr::col(organisation_members::Column::UserId).eq(user_id))
+            .and_where(Expr::col(organisation_members::Column::AcceptedAt).is_not_null())
+            .to_owned();
+
+        let members = Query::select()
+            .column(organisation_members::Column::UserId)
+            .from(organisation_members::Entity)
+            .and_where(Expr::col(organisation_members::Column::AcceptedAt).is_not_null())
+            .and_where(Expr::col(organisation_members::Column::OrganisationId).in_subquery(own_organisations))
+            .to_owned();
+
+        let query = users::Entity::find_active()
+            .filter(users::Column::Id.in_subquery(members))
+            .filter(site_scope(users::Column::SiteId, site_id));
+
+        match paginate(db, query, page).await {
+            Ok(users) => {
+                trace!("Users found: {:?}", users.items);
+                Ok(users)
+            },
+            Err(e) => {
+                Err(e)
+            }
+        }
+    }
+
     async fn update_user(db: &DatabaseConnection, id: Uuid, username: Option<String>, email: Option<String>, password: Option<String>) -> Result<users::Model, sea_orm::DbErr> {
         trace!("Updating user with id: '{}', username: '{:?}', email: '{:?}'", id, username, email);
         
-        let mut user : users::ActiveModel = match users::Entity::find_by_id(id).one(db).await {
+        let mut user : users::ActiveModel = match users::Entity::find_active().filter(users::Column::Id.eq(id)).one(db).await {
             Ok(Some(user)) => user.into(),
             Ok(None) => {
-                return Err(sea_orm::DbErr::RecordNotFound("User not found".into()));
+                return Err(sea_orm::DbErr::RecordNotFound(id.to_string()));
             },
             Err(e) => {
                 return Err(e);
@@ -93,10 +170,21 @@ impl UserService for UserServiceImpl {
             user.username = Set(username);
         }
         if let Some(email) = email.clone() {
+            // A new address has to be verified again, links sent to the old one are void
+            if user.email.as_ref() != &email {
+                user.email_verified_at = Set(None);
+                account_tokens::Entity::delete_many()
+                    .filter(account_tokens::Column::SubjectId.eq(id))
+                    .filter(account_tokens::Column::Audience.eq(user_token_audience()))
+                    .filter(account_tokens::Column::Purpose.eq(AccountTokenPurpose::EmailVerification.as_str()))
+                    .filter(account_tokens::Column::UsedAt.is_null())
+                    .exec(db)
+                    .await?;
+            }
             user.email = Set(email);
         }
         if let Some(password) = password {
-            user.password = Set(hash_password(password));
+            user.password = Set(hash_password(&password)?);
         }
 
         match user.update(db).await {
@@ -113,7 +201,14 @@ impl UserService for UserServiceImpl {
     async fn delete_user(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr> {
         trace!("Deleting user with id: {}", id);
 
-        match users::Entity::delete_by_id(id).exec(db).await {
+        // Addresses and memberships still reference the row, they go with the purge
+        match users::Entity::update_many()
+            .col_expr(users::Column::DeletedAt, Expr::value(Utc::now()))
+            .filter(users::Column::Id.eq(id))
+            .filter(users::Column::DeletedAt.is_null())
+            .exec(db)
+            .await
+        {
             Ok(res) => {
                 if res.rows_affected > 0 {
                     trace!("User with id {} deleted successfully", id);
@@ -129,11 +224,71 @@ impl UserService for UserServiceImpl {
         }
     }
 
+    async fn restore_user(db: &DatabaseConnection, id: Uuid) -> Result<bool, sea_orm::DbErr> {
+        trace!("Restoring user with id: {}", id);
+
+        let res = users::Entity::update_many()
+            .col_expr(users::Column::DeletedAt, Expr::value(Option::<DateTimeUtc>::None))
+            .col_expr(users::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(users::Column::Id.eq(id))
+            .filter(users::Column::DeletedAt.is_not_null())
+            .filter(users::Column::ErasedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    async fn purge_deleted_users(db: &DatabaseConnection, deleted_before: DateTimeUtc) -> Result<u64, sea_orm::DbErr> {
+        let txn = db.begin().await?;
+
+        // Locked, so that a concurrent restore waits for the purge
+        let ids: Vec<Uuid> = users::Entity::find()
+            .select_only()
+            .column(users::Column::Id)
+            .filter(users::Column::DeletedAt.lt(deleted_before))
+            .filter(users::Column::ErasedAt.is_null())
+            .lock_exclusive()
+            .into_tuple()
+            .all(&txn)
+            .await?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        trace!("Purging {} deleted user(s)", ids.len());
+
+        address::Entity::delete_many()
+            .filter(address::Column::UserId.is_in(ids.clone()))
+            .exec(&txn)
+            .await?;
+        organisation_members::Entity::delete_many()
+            .filter(organisation_members::Column::UserId.is_in(ids.clone()))
+            .exec(&txn)
+            .await?;
+        refresh_tokens::Entity::delete_many()
+            .filter(refresh_tokens::Column::SubjectId.is_in(ids.clone()))
+            .filter(refresh_tokens::Column::Audience.eq(user_token_audience()))
+            .exec(&txn)
+            .await?;
+        account_tokens::Entity::delete_many()
+            .filter(account_tokens::Column::SubjectId.is_in(ids.clone()))
+            .filter(account_tokens::Column::Audience.eq(user_token_audience()))
+            .exec(&txn)
+            .await?;
+        let res = users::Entity::delete_many()
+            .filter(users::Column::Id.is_in(ids))
+            .exec(&txn)
+            .await?;
+
+        txn.commit().await?;
+        Ok(res.rows_affected)
+    }
+
 
     async fn find_user_by_email(db: &DatabaseConnection, email: String) -> Result<Option<users::Model>, sea_orm::DbErr> {
         trace!("Searching for user with email: '{}'", email);
         
-        match users::Entity::find()
+        match users::Entity::find_active()
             .filter(users::Column::Email.eq(email.clone())) // Ensure ColumnTrait is in scope
             .one(db)
             .await
@@ -151,9 +306,70 @@ impl UserService for UserServiceImpl {
             }
         }
     }
+
+    async fn validate_user_credentials(db: &DatabaseConnection, email: String, password: String) -> Result<Option<users::Model>, sea_orm::DbErr> {
+        trace!("Validating credentials for email: '{}'", email);
+
+        let user = match UserServiceImpl::find_user_by_email(db, email.clone()).await? {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+
+        let passwords = PasswordService::from_env();
+        let is_valid = passwords
+            .verify(&password, &user.password)
+            .map_err(|e| sea_orm::DbErr::Custom(format!("Failed to verify password: {}", e)))?;
+
+        if !is_valid {
+            trace!("Invalid password for email: '{}'", email);
+            return Ok(None);
+        }
+
+        if !passwords.needs_rehash(&user.password) {
+            return Ok(Some(user));
+        }
+
+        // Transparently upgrade the stored hash, the login must not fail if this does
+        let user_id = user.id;
+        let new_hash = match hash_password(&password) {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("Failed to rehash password for user {}: {}", user_id, e);
+                return Ok(Some(user));
+            }
+        };
+
+        let mut active_user: users::ActiveModel = user.clone().into();
+        active_user.password = Set(new_hash);
+        active_user.updated_at = Set(Utc::now());
+
+        match active_user.update(db).await {
+            Ok(updated_user) => {
+                trace!("Password hash upgraded for user {}", user_id);
+                Ok(Some(updated_user))
+            },
+            Err(e) => {
+                warn!("Failed to store rehashed password for user {}: {}", user_id, e);
+                Ok(Some(user))
+            }
+        }
+    }
+
+    async fn check_password(db: &DatabaseConnection, id: Uuid, password: &str) -> Result<bool, sea_orm::DbErr> {
+        trace!("Checking the password of user {}", id);
+
+        let user = UserServiceImpl::get_user(db, id)
+            .await?
+            .ok_or_else(|| sea_orm::DbErr::RecordNotFound(id.to_string()))?;
+
+        PasswordService::from_env()
+            .verify(password, &user.password)
+            .map_err(|e| sea_orm::DbErr::Custom(format!("Failed to verify password: {}", e)))
+    }
 }
 
-fn hash_password(password: String) -> String {
-    // Implement password hashing here (e.g., using bcrypt)
-    password // Placeholder, replace with actual hash
+fn hash_password(password: &str) -> Result<String, sea_orm::DbErr> {
+    PasswordService::from_env()
+        .hash(password)
+        .map_err(|e| sea_orm::DbErr::Custom(format!("Failed to hash password: {}", e)))
 }
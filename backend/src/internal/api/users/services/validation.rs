@@ -0,0 +1,110 @@
+use crate::internal::api::users::errors::validation::{FieldErrors, UserValidationError};
+
+pub const USERNAME_MIN_LENGTH: usize = 3;
+pub const USERNAME_MAX_LENGTH: usize = 32;
+pub const NAME_MAX_LENGTH: usize = 64;
+pub const EMAIL_MAX_LENGTH: usize = 254;
+pub const PASSWORD_MIN_LENGTH: usize = 8;
+pub const PASSWORD_MAX_LENGTH: usize = 128;
+
+/// A rule returns the reasons a value breaks it, none when the value is fine.
+type Rule = fn(&str) -> Vec<String>;
+
+/// Collects the violations of every field before failing.
+#[derive(Default)]
+pub struct Validator {
+    fields: Vec<FieldErrors>,
+}
+
+impl Validator {
+    pub fn check(mut self, field: &str, value: &str, rules: &[Rule]) -> Self {
+        let errors: Vec<String> = rules.iter().flat_map(|rule| rule(value)).collect();
+        if !errors.is_empty() {
+            self.fields.push(FieldErrors { field: field.to_string(), errors });
+        }
+        self
+    }
+
+    pub fn check_optional(self, field: &str, value: Option<&str>, rules: &[Rule]) -> Self {
+        match value {
+            Some(value) => self.check(field, value, rules),
+            None => self,
+        }
+    }
+
+    pub fn finish(self) -> Result<(), UserValidationError> {
+        if self.fields.is_empty() {
+            Ok(())
+        } else {
+            Err(UserValidationError::InvalidInput(self.fields))
+        }
+    }
+}
+
+pub fn required(value: &str) -> Vec<String> {
+    if value.is_empty() {
+        vec!["This field cannot be empty.".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+pub fn username(value: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let length = value.chars().count();
+    if !(USERNAME_MIN_LENGTH..=USERNAME_MAX_LENGTH).contains(&length) {
+        errors.push(format!("Must be between {} and {} characters long.", USERNAME_MIN_LENGTH, USERNAME_MAX_LENGTH));
+    }
+    if !value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+        errors.push("May only contain letters, digits, '_', '-' and '.'.".to_string());
+    }
+    errors
+}
+
+pub fn name(value: &str) -> Vec<String> {
+    if value.trim().is_empty() {
+        vec!["This field cannot be empty.".to_string()]
+    } else if value.chars().count() > NAME_MAX_LENGTH {
+        vec![format!("Must be at most {} characters long.", NAME_MAX_LENGTH)]
+    } else {
+        Vec::new()
+    }
+}
+
+pub fn email(value: &str) -> Vec<String> {
+    let valid = match value.split_once('@') {
+        Some((local, domain)) => !local.is_empty()
+            && !domain.contains('@')
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.'),
+        None => false,
+    };
+
+    if !valid || value.contains(char::is_whitespace) {
+        vec!["The email address is not valid.".to_string()]
+    } else if value.len() > EMAIL_MAX_LENGTH {
+        vec![format!("Must be at most {} characters long.", EMAIL_MAX_LENGTH)]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Length bounds plus at least one letter and one digit.
+pub fn password(value: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let length = value.chars().count();
+    if length < PASSWORD_MIN_LENGTH {
+        errors.push(format!("Must be at least {} characters long.", PASSWORD_MIN_LENGTH));
+    }
+    if length > PASSWORD_MAX_LENGTH {
+        errors.push(format!("Must be at most {} characters long.", PASSWORD_MAX_LENGTH));
+    }
+    if !value.chars().any(char::is_alphabetic) {
+        errors.push("Must contain at least one letter.".to_string());
+    }
+    if !value.chars().any(|c| c.is_ascii_digit()) {
+        errors.push("Must contain at least one digit.".to_string());
+    }
+    errors
+}
@@ -0,0 +1,123 @@
+use std::env;
+use actix_web::{http::header::AUTHORIZATION, HttpRequest};
+use async_graphql::{Context, Data, Request};
+use serde_json::Value as Json;
+use log::trace;
+
+use crate::internal::api::{
+    admin::users::{
+        errors::{auth::AuthTokenError, interface::CustomGraphQLError},
+        services::auth::{Claims, JwtTokenService, TokenService},
+    },
+    users::services::auth::{UserClaims, UserJwtTokenService, UserTokenService},
+};
+
+/// Stored in the request data when a token was sent but failed verification, so the
+/// resolvers that need a principal can report why (expired, bad signature, …).
+pub struct RejectedToken(pub Box<dyn CustomGraphQLError>);
+
+fn auth_cookie_name() -> String {
+    env::var("AUTH_COOKIE_NAME").unwrap_or_else(|_| "auth_token".to_string())
+}
+
+/// Reads the token from `Authorization: Bearer <token>`, falling back to the auth cookie.
+pub fn extract_token(req: &HttpRequest) -> Option<String> {
+    let bearer = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty());
+
+    bearer.or_else(|| req.cookie(&auth_cookie_name()).map(|cookie| cookie.value().to_string()))
+}
+
+/// Verifies the token once per request and attaches the resulting claims to the request
+/// data. Requests without a token go through untouched: public fields (login, …) must keep
+/// working, protected ones fail when they look the principal up.
+pub async fn authenticate(request: Request, token: Option<String>) -> Request {
+    let token = match token {
+        Some(token) => token,
+        None => return request,
+    };
+
+    match verify(&token).await {
+        Principal::Admin(claims) => request.data(claims),
+        Principal::User(claims) => request.data(claims),
+        Principal::Rejected(rejected) => request.data(rejected),
+    }
+}
+
+enum Principal {
+    Admin(Claims),
+    User(UserClaims),
+    Rejected(RejectedToken),
+}
+
+async fn verify(token: &str) -> Principal {
+    match JwtTokenService::verify_token(token).await {
+        Ok(claims) => {
+            trace!("authenticate: admin token for {:?}", claims.sub);
+            Principal::Admin(claims)
+        },
+        Err(admin_error) => match UserJwtTokenService::verify_token(token).await {
+            Ok(claims) => {
+                trace!("authenticate: user token for {:?}", claims.sub);
+                Principal::User(claims)
+            },
+            Err(_) => Principal::Rejected(RejectedToken(admin_error)),
+        },
+    }
+}
+
+/// Authenticates a WebSocket connection from its `connection_init` payload, which carries
+/// the token as `{"token": "..."}` or `{"Authorization": "Bearer ..."}`. The auth cookie is
+/// ignored: browsers send it along with upgrades started by any other site. Unlike HTTP
+/// requests, a connection is refused outright when no valid token is given.
+pub async fn authenticate_connection(payload: Json) -> async_graphql::Result<Data> {
+    let token = payload
+        .get("token")
+        .and_then(Json::as_str)
+        .or_else(|| {
+            payload
+                .get("Authorization")
+                .or_else(|| payload.get("authorization"))
+                .and_then(Json::as_str)
+                .and_then(|value| value.strip_prefix("Bearer "))
+        })
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| AuthTokenError::MissingToken.new())?;
+
+    let mut data = Data::default();
+    match verify(&token).await {
+        Principal::Admin(claims) => data.insert(claims),
+        Principal::User(claims) => data.insert(claims),
+        Principal::Rejected(RejectedToken(e)) => return Err(e.new()),
+    }
+    Ok(data)
+}
+
+fn missing_principal(ctx: &Context<'_>) -> async_graphql::Error {
+    match ctx.data_opt::<RejectedToken>() {
+        Some(RejectedToken(e)) => e.new(),
+        None => AuthTokenError::MissingToken.new(),
+    }
+}
+
+pub fn admin_claims<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Claims> {
+    match ctx.data_opt::<Claims>() {
+        Some(claims) => Ok(claims),
+        None if ctx.data_opt::<UserClaims>().is_some() => Err(AuthTokenError::InvalidToken.new()),
+        None => Err(missing_principal(ctx)),
+    }
+}
+
+pub fn user_claims<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a UserClaims> {
+    match ctx.data_opt::<UserClaims>() {
+        Some(claims) => Ok(claims),
+        None if ctx.data_opt::<Claims>().is_some() => Err(AuthTokenError::InvalidToken.new()),
+        None => Err(missing_principal(ctx)),
+    }
+}
@@ -0,0 +1,53 @@
+use std::{env, net::IpAddr};
+use actix_web::{http::header::USER_AGENT, HttpRequest};
+use log::warn;
+
+/// Where a request came from, attached to the request data for the audit log.
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// Proxies whose `Forwarded`/`X-Forwarded-For` headers are believed, from TRUSTED_PROXIES
+/// (comma-separated addresses). None by default.
+fn trusted_proxies() -> Vec<IpAddr> {
+    env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|proxy| !proxy.is_empty())
+        .filter_map(|proxy| match proxy.parse() {
+            Ok(ip) => Some(ip),
+            Err(_) => {
+                warn!("client: ignoring invalid TRUSTED_PROXIES entry '{}'", proxy);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Address of the client. The forwarding headers are only honoured when the connection
+/// comes from one of the `trusted` proxies, which must overwrite them: anyone else could
+/// put any address there and dodge, or trigger, the per-address login lockouts.
+pub fn client_address(req: &HttpRequest, trusted: &[IpAddr]) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+    if !trusted.contains(&peer) {
+        return Some(peer.to_string());
+    }
+
+    req.connection_info()
+        .realip_remote_addr()
+        .map(|addr| addr.to_string())
+}
+
+pub fn client_info(req: &HttpRequest) -> ClientInfo {
+    ClientInfo {
+        ip_address: client_address(req, &trusted_proxies()),
+        user_agent: req
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|agent| agent.to_string()),
+    }
+}
@@ -0,0 +1,64 @@
+use async_graphql::{futures_util::{stream, Stream}, Context};
+use log::{trace, warn};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::models::admin_users;
+
+/// How many events a slow subscriber can fall behind before it starts missing some.
+const CAPACITY: usize = 256;
+
+/// Change made to an admin user or an end user through the API, as pushed to the
+/// subscriptions.
+#[derive(Clone, Debug)]
+pub enum UserEvent {
+    Created(admin_users::Model),
+    Updated(admin_users::Model),
+    Deleted(Uuid),
+    /// End user deleted by themselves or by an admin, restorable until the purge.
+    EndUserDeleted(Uuid),
+    EndUserRestored(Uuid),
+    /// End user anonymised for an erasure request, which deletes the account too.
+    EndUserErased(Uuid),
+}
+
+/// In-process broadcast of [`UserEvent`]s, stored in the schema data. Only the subscribers
+/// connected to this instance are notified.
+#[derive(Clone)]
+pub struct UserEvents(broadcast::Sender<UserEvent>);
+
+impl Default for UserEvents {
+    fn default() -> Self {
+        UserEvents(broadcast::channel(CAPACITY).0)
+    }
+}
+
+impl UserEvents {
+    pub fn publish(&self, event: UserEvent) {
+        // Failing only means nobody is listening right now
+        if self.0.send(event).is_err() {
+            trace!("events: no subscriber for the user event");
+        }
+    }
+
+    /// Events published from now on. A subscriber that lags behind skips the events it
+    /// missed rather than being disconnected.
+    pub fn subscribe(&self) -> impl Stream<Item = UserEvent> {
+        stream::unfold(self.0.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(missed)) => warn!("events: subscriber lagged, {} user event(s) skipped", missed),
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
+
+/// Publishes the event on the schema's [`UserEvents`], when there is one.
+pub fn publish_user_event(ctx: &Context<'_>, event: UserEvent) {
+    if let Some(events) = ctx.data_opt::<UserEvents>() {
+        events.publish(event);
+    }
+}
@@ -0,0 +1,101 @@
+use async_graphql::InputObject;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    sea_query::{extension::postgres::PgExpr, Expr, SimpleExpr},
+    ColumnTrait, Condition,
+};
+use uuid::Uuid;
+
+/// Operators on a text column, every operator given must match.
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct StringFilter {
+    pub eq: Option<String>,
+    pub contains: Option<String>,
+    pub starts_with: Option<String>,
+    /// Case-insensitive `ILIKE` pattern, `%` and `_` are wildcards.
+    pub ilike: Option<String>,
+    #[graphql(name = "in")]
+    pub in_list: Option<Vec<String>>,
+}
+
+impl StringFilter {
+    pub fn condition<C: ColumnTrait>(self, column: C) -> Condition {
+        let mut condition = Condition::all();
+
+        if let Some(eq) = self.eq {
+            condition = condition.add(column.eq(eq));
+        }
+        if let Some(contains) = self.contains {
+            condition = condition.add(column.like(format!("%{}%", escape_like(&contains))));
+        }
+        if let Some(prefix) = self.starts_with {
+            condition = condition.add(column.like(format!("{}%", escape_like(&prefix))));
+        }
+        if let Some(pattern) = self.ilike {
+            condition = condition.add(Expr::col(column.as_column_ref()).ilike(pattern));
+        }
+        if let Some(values) = self.in_list {
+            condition = condition.add(column.is_in(values));
+        }
+
+        condition
+    }
+}
+
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct UuidFilter {
+    pub eq: Option<Uuid>,
+    #[graphql(name = "in")]
+    pub in_list: Option<Vec<Uuid>>,
+}
+
+impl UuidFilter {
+    pub fn condition<C: ColumnTrait>(self, column: C) -> Condition {
+        let mut condition = Condition::all();
+
+        if let Some(eq) = self.eq {
+            condition = condition.add(column.eq(eq));
+        }
+        if let Some(values) = self.in_list {
+            condition = condition.add(column.is_in(values));
+        }
+
+        condition
+    }
+}
+
+/// Half-open range: `from` included, `to` excluded.
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct DateRangeFilter {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl DateRangeFilter {
+    pub fn condition<C: ColumnTrait>(self, column: C) -> Condition {
+        let mut condition = Condition::all();
+
+        if let Some(from) = self.from {
+            condition = condition.add(column.gte(from));
+        }
+        if let Some(to) = self.to {
+            condition = condition.add(column.lt(to));
+        }
+
+        condition
+    }
+}
+
+/// Matches `text` against `search` either as whole words, through a full-text query on
+/// `vector`, or as a substring of `text`.
+pub fn search_condition(vector: &str, text: &str, search: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!("({} @@ websearch_to_tsquery('simple', $1) OR ({}) ILIKE $2)", vector, text),
+        [search.to_string(), format!("%{}%", escape_like(search))],
+    )
+}
+
+/// Escapes the `LIKE` wildcards so that user input only ever matches literally.
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
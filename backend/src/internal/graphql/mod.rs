@@ -1,2 +1,16 @@
+pub mod auth;
 pub mod queries;
 pub mod mutations;
+pub mod subscriptions;
+pub mod events;
+pub mod site;
+pub mod pagination;
+pub mod filters;
+pub mod client;
+
+#[cfg(test)]
+mod test_events;
+#[cfg(test)]
+mod test_client;
+#[cfg(test)]
+mod test_filters;
@@ -4,12 +4,22 @@ use crate::internal::api::{admin, users};
 
 #[derive(MergedObject, Default)]
 pub struct AdminMutationRoot(
-    pub admin::users::controllers::auth::AuthAdminMutation
+    pub admin::users::controllers::auth::AuthAdminMutation,
+    pub admin::users::controllers::users::AdminUserMutation,
+    pub admin::users::controllers::roles::AdminRoleMutation,
+    pub admin::users::controllers::permissions::AdminPermissionMutation,
+    pub admin::users::controllers::sites::AdminSiteMutation,
+    pub admin::users::controllers::mfa::AdminMfaMutation
 );
 
 #[derive(MergedObject, Default)]
 pub struct UserMutationRoot(
-    pub users::controllers::UserMutation
+    pub users::controllers::AuthUserMutation,
+    pub users::controllers::UserMutation,
+    pub users::controllers::AddressMutation,
+    pub users::controllers::OrganisationMutation,
+    pub users::controllers::RoleMutation,
+    pub users::controllers::MembershipMutation
 );
 
 #[derive(Default)]
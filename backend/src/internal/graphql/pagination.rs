@@ -0,0 +1,287 @@
+use actix_web::http::StatusCode;
+use async_graphql::{
+    connection::{Connection, CursorType, Edge, EmptyFields},
+    Enum, Error, ErrorExtensions, InputObject, OutputType, SimpleObject,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::info;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, Order, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Select, Value,
+};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::errors::interface::CustomGraphQLError;
+
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+/// Larger `first`/`last` values are clamped to this.
+pub const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(Enum, Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum UserSortField {
+    #[default]
+    CreatedAt,
+    Email,
+    Username,
+}
+
+impl UserSortField {
+    fn as_str(&self) -> &'static str {
+        match self {
+            UserSortField::CreatedAt => "created_at",
+            UserSortField::Email => "email",
+            UserSortField::Username => "username",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "created_at" => Some(UserSortField::CreatedAt),
+            "email" => Some(UserSortField::Email),
+            "username" => Some(UserSortField::Username),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Sort order of a user listing. The id is always used as a tie breaker so that pages
+/// are stable when several users share the same sort key.
+#[derive(InputObject, Copy, Clone, Debug, Default)]
+pub struct UserOrder {
+    #[graphql(default)]
+    pub field: UserSortField,
+    #[graphql(default)]
+    pub direction: SortDirection,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CursorKey {
+    Timestamp(DateTime<Utc>),
+    Text(String),
+}
+
+/// Opaque position in a listing: the sort key and id of the last row seen. It is only
+/// valid for the sort field it was issued for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cursor {
+    pub field: UserSortField,
+    pub id: Uuid,
+    pub key: CursorKey,
+}
+
+impl Cursor {
+    pub fn timestamp(field: UserSortField, id: Uuid, key: DateTime<Utc>) -> Self {
+        Cursor { field, id, key: CursorKey::Timestamp(key) }
+    }
+
+    pub fn text(field: UserSortField, id: Uuid, key: &str) -> Self {
+        Cursor { field, id, key: CursorKey::Text(key.to_string()) }
+    }
+
+    fn value(&self) -> Value {
+        match &self.key {
+            CursorKey::Timestamp(t) => (*t).into(),
+            CursorKey::Text(s) => s.clone().into(),
+        }
+    }
+}
+
+impl CursorType for Cursor {
+    type Error = PaginationError;
+
+    fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+        let decoded = URL_SAFE_NO_PAD
+            .decode(s)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or(PaginationError::InvalidCursor)?;
+
+        // field|id|key, the key goes last as it may itself contain the separator
+        let mut parts = decoded.splitn(3, '|');
+        let field = parts.next().and_then(UserSortField::parse).ok_or(PaginationError::InvalidCursor)?;
+        let id = parts.next().and_then(|id| Uuid::parse_str(id).ok()).ok_or(PaginationError::InvalidCursor)?;
+        let key = parts.next().ok_or(PaginationError::InvalidCursor)?;
+
+        let key = match field {
+            UserSortField::CreatedAt => DateTime::parse_from_rfc3339(key)
+                .map(|t| CursorKey::Timestamp(t.with_timezone(&Utc)))
+                .map_err(|_| PaginationError::InvalidCursor)?,
+            UserSortField::Email | UserSortField::Username => CursorKey::Text(key.to_string()),
+        };
+
+        Ok(Cursor { field, id, key })
+    }
+
+    fn encode_cursor(&self) -> String {
+        let key = match &self.key {
+            CursorKey::Timestamp(t) => t.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            CursorKey::Text(s) => s.clone(),
+        };
+        URL_SAFE_NO_PAD.encode(format!("{}|{}|{}", self.field.as_str(), self.id, key))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum PaginationError {
+    #[error("Cursor could not be decoded")]
+    InvalidCursor,
+
+    #[error("Cursor was issued for another sort order")]
+    CursorOrderMismatch,
+}
+
+impl CustomGraphQLError for PaginationError {
+    fn new(&self) -> Error {
+        info!("{}", self);
+
+        Error::new(match self {
+            PaginationError::InvalidCursor => "The cursor is invalid.",
+            PaginationError::CursorOrderMismatch => "The cursor does not match the requested order.",
+        })
+        .extend_with(|_err, extensions| {
+            extensions.set("code", StatusCode::BAD_REQUEST.as_u16()); // HTTP 400
+            extensions.set("message", "INVALID_CURSOR");
+        })
+    }
+}
+
+/// Page requested by a client, as read from the Relay `first/after/last/before` arguments.
+#[derive(Clone, Debug, Default)]
+pub struct PageRequest {
+    pub order: UserOrder,
+    pub after: Option<Cursor>,
+    pub before: Option<Cursor>,
+    pub first: Option<usize>,
+    pub last: Option<usize>,
+    /// Counting every matching row costs a second query, only done when asked for.
+    pub with_total_count: bool,
+}
+
+impl PageRequest {
+    /// `first` and `last` are never both set, async-graphql rejects such requests.
+    pub fn new(order: UserOrder, after: Option<Cursor>, before: Option<Cursor>, first: Option<usize>, last: Option<usize>) -> Result<Self, PaginationError> {
+        if after.iter().chain(before.iter()).any(|cursor| cursor.field != order.field) {
+            return Err(PaginationError::CursorOrderMismatch);
+        }
+
+        Ok(PageRequest { order, after, before, first, last, with_total_count: false })
+    }
+
+    pub fn with_total_count(mut self, with_total_count: bool) -> Self {
+        self.with_total_count = with_total_count;
+        self
+    }
+
+    fn is_backward(&self) -> bool {
+        self.last.is_some()
+    }
+
+    fn limit(&self) -> usize {
+        self.first.or(self.last).unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub has_previous_page: bool,
+    pub has_next_page: bool,
+    pub total_count: Option<u64>,
+}
+
+/// Entities that can be listed through a keyset paginated user listing.
+pub trait Keyset: EntityTrait {
+    fn id_column() -> Self::Column;
+    fn sort_column(field: UserSortField) -> Self::Column;
+    fn cursor(model: &Self::Model, field: UserSortField) -> Cursor;
+}
+
+#[derive(SimpleObject)]
+pub struct ConnectionFields {
+    /// Number of rows matching the listing, regardless of the page.
+    pub total_count: u64,
+}
+
+pub type KeysetConnection<N> = Connection<Cursor, N, ConnectionFields, EmptyFields>;
+
+/// Runs `select` one page at a time. Rows are compared on `(sort key, id)` rather than
+/// skipped with an offset, so deep pages cost the same as the first one.
+pub async fn paginate<E>(db: &DatabaseConnection, select: Select<E>, request: &PageRequest) -> Result<Page<E::Model>, DbErr>
+where
+    E: Keyset,
+    E::Model: Sync,
+{
+    let sort_column = E::sort_column(request.order.field);
+    let id_column = E::id_column();
+    let ascending = request.order.direction == SortDirection::Asc;
+    let limit = request.limit();
+
+    let mut query = select.clone();
+    if let Some(after) = &request.after {
+        query = query.filter(keyset_condition(sort_column, id_column, after, ascending));
+    }
+    if let Some(before) = &request.before {
+        query = query.filter(keyset_condition(sort_column, id_column, before, !ascending));
+    }
+
+    // Paging from the end walks the listing backwards, the page is flipped back below.
+    let order = if ascending != request.is_backward() { Order::Asc } else { Order::Desc };
+    let mut items = query
+        .order_by(sort_column, order.clone())
+        .order_by(id_column, order)
+        .limit(limit as u64 + 1)
+        .all(db)
+        .await?;
+
+    let has_more = items.len() > limit;
+    items.truncate(limit);
+    if request.is_backward() {
+        items.reverse();
+    }
+
+    let total_count = if request.with_total_count { Some(select.count(db).await?) } else { None };
+
+    Ok(Page {
+        items,
+        has_previous_page: if request.is_backward() { has_more } else { request.after.is_some() },
+        has_next_page: if request.is_backward() { request.before.is_some() } else { has_more },
+        total_count,
+    })
+}
+
+/// Rows strictly past `cursor` in the given direction.
+fn keyset_condition<C: ColumnTrait>(sort_column: C, id_column: C, cursor: &Cursor, greater: bool) -> Condition {
+    let value = cursor.value();
+    let (past_key, past_id) = if greater {
+        (sort_column.gt(value.clone()), id_column.gt(cursor.id))
+    } else {
+        (sort_column.lt(value.clone()), id_column.lt(cursor.id))
+    };
+
+    Condition::any()
+        .add(past_key)
+        .add(Condition::all().add(sort_column.eq(value)).add(past_id))
+}
+
+pub fn into_connection<E, N>(page: Page<E::Model>, field: UserSortField, node: impl Fn(E::Model) -> N) -> KeysetConnection<N>
+where
+    E: Keyset,
+    N: OutputType,
+{
+    let mut connection = Connection::with_additional_fields(
+        page.has_previous_page,
+        page.has_next_page,
+        ConnectionFields { total_count: page.total_count.unwrap_or_default() },
+    );
+    connection.edges.extend(page.items.into_iter().map(|item| Edge::new(E::cursor(&item, field), node(item))));
+    connection
+}
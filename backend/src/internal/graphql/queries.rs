@@ -5,12 +5,22 @@ use crate::internal::api::{admin, users};
 #[derive(MergedObject, Default)]
 pub struct AdminQueryRoot(
     pub admin::users::controllers::auth::AuthAdminQuery,
-    pub admin::users::controllers::users::AdminUserQuery
+    pub admin::users::controllers::users::AdminUserQuery,
+    pub admin::users::controllers::roles::AdminRoleQuery,
+    pub admin::users::controllers::permissions::AdminPermissionQuery,
+    pub admin::users::controllers::sites::AdminSiteQuery,
+    pub admin::users::controllers::audit::AdminAuditQuery,
+    pub admin::users::controllers::mfa::AdminMfaQuery
 );
 
 #[derive(MergedObject, Default)]
 pub struct UserQueryRoot(
-    pub users::controllers::UserQuery
+    pub users::controllers::AuthUserQuery,
+    pub users::controllers::UserQuery,
+    pub users::controllers::AddressQuery,
+    pub users::controllers::OrganisationQuery,
+    pub users::controllers::RoleQuery,
+    pub users::controllers::MembershipQuery
 );
 
 #[derive(Default)]
@@ -0,0 +1,47 @@
+use actix_web::{http::header::HOST, HttpRequest};
+use async_graphql::Context;
+use log::{trace, warn};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::services::sites::{AdminSiteService, AdminSiteServiceImpl};
+
+/// Site the request was sent to, resolved from its `Host` header. Absent when the host
+/// is not a known site, in which case only data shared by every site is visible.
+#[derive(Clone, Debug)]
+pub struct CurrentSite {
+    pub id: Uuid,
+    pub domain: String,
+}
+
+pub fn request_host(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(|host| host.to_string())
+        .or_else(|| req.uri().host().map(|host| host.to_string()))
+}
+
+/// Looks the host up, the result is meant to be attached to the request data.
+pub async fn resolve_site(db: &DatabaseConnection, host: Option<String>) -> Option<CurrentSite> {
+    let host = host?;
+
+    match AdminSiteServiceImpl::find_site_by_host(db, &host).await {
+        Ok(Some(site)) => {
+            trace!("resolve_site: host '{}' is site {}", host, site.id);
+            Some(CurrentSite { id: site.id, domain: site.domain })
+        },
+        Ok(None) => {
+            trace!("resolve_site: host '{}' is not a known site", host);
+            None
+        },
+        Err(_) => {
+            warn!("resolve_site: failed to resolve host '{}', serving it as no site", host);
+            None
+        },
+    }
+}
+
+pub fn current_site_id(ctx: &Context<'_>) -> Option<Uuid> {
+    ctx.data_opt::<CurrentSite>().map(|site| site.id)
+}
@@ -0,0 +1,8 @@
+use async_graphql::MergedSubscription;
+
+use crate::internal::api::admin;
+
+#[derive(MergedSubscription, Default)]
+pub struct SubscriptionRoot(
+    pub admin::users::controllers::users::AdminUserSubscription
+);
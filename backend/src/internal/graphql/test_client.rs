@@ -0,0 +1,40 @@
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::test::TestRequest;
+
+use crate::internal::graphql::client::client_address;
+
+fn peer() -> SocketAddr {
+    "10.0.0.2:51234".parse().unwrap()
+}
+
+#[test]
+fn test_client_address_is_the_peer() {
+    let req = TestRequest::default().peer_addr(peer()).to_http_request();
+
+    // Without the port, which changes with every connection
+    assert_eq!(client_address(&req, &[]).as_deref(), Some("10.0.0.2"));
+}
+
+#[test]
+fn test_forwarded_headers_ignored_from_untrusted_peer() {
+    let req = TestRequest::default()
+        .peer_addr(peer())
+        .insert_header(("X-Forwarded-For", "203.0.113.7"))
+        .insert_header(("Forwarded", "for=198.51.100.1"))
+        .to_http_request();
+
+    let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+    assert_eq!(client_address(&req, &[proxy]).as_deref(), Some("10.0.0.2"));
+}
+
+#[test]
+fn test_forwarded_headers_from_trusted_proxy() {
+    let req = TestRequest::default()
+        .peer_addr(peer())
+        .insert_header(("X-Forwarded-For", "203.0.113.7"))
+        .to_http_request();
+
+    let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+    assert_eq!(client_address(&req, &[proxy]).as_deref(), Some("203.0.113.7"));
+}
@@ -0,0 +1,107 @@
+use std::any::TypeId;
+
+use async_graphql::futures_util::StreamExt;
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::internal::{
+    api::{
+        admin::users::{models::admin_users, services::auth::Claims},
+        users::services::auth::{access_token, UserClaims},
+    },
+    graphql::{
+        auth::authenticate_connection,
+        events::{UserEvent, UserEvents},
+    },
+    security::jwt::install_test_jwt_keys,
+};
+
+fn test_admin_user() -> admin_users::Model {
+    admin_users::Model {
+        id: Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap(),
+        username: "test_admin".to_owned(),
+        first_name: "test".to_owned(),
+        last_name: "admin".to_owned(),
+        email: "admin@example.com".to_owned(),
+        password: "hash".to_owned(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        email_verified_at: None,
+        deleted_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_subscribers_receive_published_events() {
+    let events = UserEvents::default();
+    let first = events.subscribe();
+    let second = events.subscribe();
+    let user = test_admin_user();
+
+    events.publish(UserEvent::Created(user.clone()));
+    events.publish(UserEvent::Deleted(user.id));
+
+    for subscriber in [first, second] {
+        let received: Vec<UserEvent> = subscriber.take(2).collect().await;
+        assert!(matches!(&received[0], UserEvent::Created(created) if created.id == user.id));
+        assert!(matches!(&received[1], UserEvent::Deleted(id) if *id == user.id));
+    }
+}
+
+#[tokio::test]
+async fn test_events_before_subscribing_are_not_replayed() {
+    let events = UserEvents::default();
+    events.publish(UserEvent::Deleted(Uuid::new_v4()));
+
+    let subscriber = events.subscribe();
+    let id = Uuid::new_v4();
+    events.publish(UserEvent::Deleted(id));
+
+    let received: Vec<UserEvent> = subscriber.take(1).collect().await;
+    assert!(matches!(&received[0], UserEvent::Deleted(deleted) if *deleted == id));
+}
+
+#[tokio::test]
+async fn test_lagging_subscriber_skips_missed_events() {
+    let events = UserEvents::default();
+    let subscriber = events.subscribe();
+
+    // Twice the capacity, the oldest half is overwritten before being read
+    let ids: Vec<Uuid> = (0..512).map(|_| Uuid::new_v4()).collect();
+    for id in &ids {
+        events.publish(UserEvent::Deleted(*id));
+    }
+
+    let received: Vec<UserEvent> = subscriber.take(1).collect().await;
+    assert!(matches!(&received[0], UserEvent::Deleted(deleted) if *deleted == ids[256]));
+}
+
+#[tokio::test]
+async fn test_connection_init_with_token() {
+    install_test_jwt_keys();
+    let user_id = Uuid::new_v4();
+    let token = access_token(user_id).unwrap();
+
+    let data = authenticate_connection(json!({ "token": token })).await.unwrap();
+    let claims = data.get(&TypeId::of::<UserClaims>()).and_then(|d| d.downcast_ref::<UserClaims>()).unwrap();
+    assert_eq!(claims.sub, user_id);
+    assert!(data.get(&TypeId::of::<Claims>()).is_none());
+
+    let data = authenticate_connection(json!({ "Authorization": format!("Bearer {}", token) })).await.unwrap();
+    assert!(data.get(&TypeId::of::<UserClaims>()).is_some());
+}
+
+#[tokio::test]
+async fn test_connection_init_without_token() {
+    let error = authenticate_connection(json!({})).await.unwrap_err();
+    let extensions = error.extensions.unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("MISSING_TOKEN")));
+}
+
+#[tokio::test]
+async fn test_connection_init_with_invalid_token() {
+    install_test_jwt_keys();
+    let error = authenticate_connection(json!({ "token": "not-a-token" })).await.unwrap_err();
+    assert!(error.extensions.is_some());
+}
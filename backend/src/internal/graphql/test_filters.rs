@@ -0,0 +1,140 @@
+use chrono::{TimeZone, Utc};
+use sea_orm::{
+    sea_query::{PostgresQueryBuilder, Query},
+    Condition, DatabaseBackend, MockDatabase,
+};
+use uuid::Uuid;
+
+use crate::internal::{
+    api::admin::users::{models::admin_users, services::users::{AdminUserService, AdminUserServiceImpl, UserFilter}},
+    graphql::{filters::*, pagination::PageRequest},
+};
+
+fn where_clause(condition: Condition) -> String {
+    let sql = Query::select()
+        .column(admin_users::Column::Id)
+        .from(admin_users::Entity)
+        .cond_where(condition)
+        .to_string(PostgresQueryBuilder);
+    sql.split_once(" WHERE ").map(|(_, clause)| clause.to_owned()).unwrap_or_default()
+}
+
+#[test]
+fn test_string_filter_operators() {
+    let filter = StringFilter {
+        eq: Some("admin@example.com".to_owned()),
+        contains: Some("example".to_owned()),
+        starts_with: Some("adm".to_owned()),
+        ilike: Some("%@EXAMPLE.%".to_owned()),
+        in_list: Some(vec!["a@example.com".to_owned(), "b@example.com".to_owned()]),
+    };
+
+    assert_eq!(
+        where_clause(filter.condition(admin_users::Column::Email)),
+        r#""admin_users"."email" = 'admin@example.com' AND "admin_users"."email" LIKE '%example%' AND "admin_users"."email" LIKE 'adm%' AND ("admin_users"."email" ILIKE '%@EXAMPLE.%') AND "admin_users"."email" IN ('a@example.com', 'b@example.com')"#
+    );
+}
+
+#[test]
+fn test_string_filter_escapes_wildcards() {
+    // Only `ilike` takes a pattern, the other operators match the input literally
+    let filter = StringFilter { contains: Some("50%_off".to_owned()), starts_with: Some("a\\b".to_owned()), ..Default::default() };
+
+    assert_eq!(
+        where_clause(filter.condition(admin_users::Column::Username)),
+        r#""admin_users"."username" LIKE E'%50\\%\\_off%' AND "admin_users"."username" LIKE E'a\\\\b%'"#
+    );
+}
+
+#[test]
+fn test_empty_filter_matches_everything() {
+    assert_eq!(where_clause(StringFilter::default().condition(admin_users::Column::Email)), "TRUE");
+    assert_eq!(where_clause(UuidFilter::default().condition(admin_users::Column::Id)), "TRUE");
+    assert_eq!(where_clause(DateRangeFilter::default().condition(admin_users::Column::CreatedAt)), "TRUE");
+}
+
+#[test]
+fn test_uuid_filter_operators() {
+    let id = Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap();
+    let other = Uuid::parse_str("a4bd4f8e-3c1c-4a55-9f8e-0a4b1a39c1f2").unwrap();
+    let filter = UuidFilter { eq: Some(id), in_list: Some(vec![id, other]) };
+
+    assert_eq!(
+        where_clause(filter.condition(admin_users::Column::Id)),
+        format!(r#""admin_users"."id" = '{id}' AND "admin_users"."id" IN ('{id}', '{other}')"#)
+    );
+}
+
+#[test]
+fn test_date_range_is_half_open() {
+    let from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let to = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+
+    let both = DateRangeFilter { from: Some(from), to: Some(to) };
+    assert_eq!(
+        where_clause(both.condition(admin_users::Column::CreatedAt)),
+        r#""admin_users"."created_at" >= '2024-01-01 00:00:00 +00:00' AND "admin_users"."created_at" < '2024-02-01 00:00:00 +00:00'"#
+    );
+
+    let open_ended = DateRangeFilter { from: Some(from), to: None };
+    assert_eq!(where_clause(open_ended.condition(admin_users::Column::CreatedAt)), r#""admin_users"."created_at" >= '2024-01-01 00:00:00 +00:00'"#);
+}
+
+#[test]
+fn test_search_condition_binds_the_input() {
+    let sql = Query::select()
+        .column(admin_users::Column::Id)
+        .from(admin_users::Entity)
+        .and_where(search_condition(r#""admin_users"."search_vector""#, r#""admin_users"."email""#, "jo_hn'; DROP TABLE admin_users; --"))
+        .build(PostgresQueryBuilder);
+
+    assert!(sql.0.ends_with(r#"WHERE ("admin_users"."search_vector" @@ websearch_to_tsquery('simple', $1) OR ("admin_users"."email") ILIKE $2)"#), "Unexpected query: {}", sql.0);
+    assert!(!sql.0.contains("DROP TABLE"), "Input not bound: {}", sql.0);
+    assert_eq!(sql.1.0.len(), 2);
+    assert_eq!(sql.1.0[1], "%jo\\_hn'; DROP TABLE admin\\_users; --%".to_owned().into());
+}
+
+#[test]
+fn test_user_filter_combines_and_or() {
+    let filter = UserFilter {
+        username: Some(StringFilter { eq: Some("root".to_owned()), ..Default::default() }),
+        or: Some(vec![
+            UserFilter { first_name: Some(StringFilter { eq: Some("Ada".to_owned()), ..Default::default() }), ..Default::default() },
+            UserFilter { last_name: Some(StringFilter { eq: Some("Lovelace".to_owned()), ..Default::default() }), ..Default::default() },
+        ]),
+        and: Some(vec![UserFilter { email: Some(StringFilter { starts_with: Some("ada".to_owned()), ..Default::default() }), ..Default::default() }]),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        where_clause(filter.condition()),
+        r#""admin_users"."username" = 'root' AND "admin_users"."email" LIKE 'ada%' AND ("admin_users"."first_name" = 'Ada' OR "admin_users"."last_name" = 'Lovelace')"#
+    );
+}
+
+#[test]
+fn test_user_filter_ignores_blank_search() {
+    let filter = UserFilter { search: Some("   ".to_owned()), ..Default::default() };
+
+    assert_eq!(where_clause(filter.condition()), "TRUE");
+}
+
+#[tokio::test]
+async fn test_user_filter_reaches_the_listing_query() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<admin_users::Model>::new()])
+        .into_connection();
+    let filter = UserFilter {
+        email: Some(StringFilter { contains: Some("example".to_owned()), ..Default::default() }),
+        search: Some(" ada ".to_owned()),
+        ..Default::default()
+    };
+
+    let page = AdminUserServiceImpl::get_all_users(&db, Some(filter), &PageRequest::default()).await;
+    assert!(page.is_ok());
+
+    // Filters narrow the active admins, the search words are bound trimmed
+    let log = format!("{:?}", db.into_transaction_log()).replace("\\\"", "\"");
+    assert!(log.contains(r#""admin_users"."deleted_at" IS NULL AND "admin_users"."email" LIKE $1 AND (("admin_users"."search_vector" @@ websearch_to_tsquery('simple', $2)"#), "Unexpected query: {}", log);
+    assert!(log.contains(r#"String(Some("%example%")), String(Some("ada")), String(Some("%ada%"))"#), "Unexpected query: {}", log);
+}
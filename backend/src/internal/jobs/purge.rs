@@ -0,0 +1,57 @@
+use std::{env, sync::Arc, time::Duration as StdDuration};
+
+use chrono::{Duration, Utc};
+use log::{error, info};
+use sea_orm::DatabaseConnection;
+use tokio::{task::JoinHandle, time::{interval, MissedTickBehavior}};
+
+use crate::internal::api::{
+    admin::users::services::users::{AdminUserService, AdminUserServiceImpl},
+    users::services::users::{UserService, UserServiceImpl},
+};
+
+/// How long soft-deleted accounts can still be restored, from SOFT_DELETE_RETENTION_DAYS.
+pub fn retention() -> Duration {
+    let days = env::var("SOFT_DELETE_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(30);
+    Duration::days(days)
+}
+
+fn purge_interval() -> StdDuration {
+    let hours = env::var("PURGE_INTERVAL_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .filter(|hours| *hours > 0)
+        .unwrap_or(24);
+    StdDuration::from_secs(hours * 3600)
+}
+
+/// Hard deletes the end users and admin users deleted longer than the retention window ago.
+pub async fn purge_deleted_accounts(db: &DatabaseConnection) {
+    let deleted_before = Utc::now() - retention();
+
+    match UserServiceImpl::purge_deleted_users(db, deleted_before).await {
+        Ok(0) => {},
+        Ok(purged) => info!("Purged {} deleted user(s)", purged),
+        Err(e) => error!("Failed to purge deleted users: {}", e),
+    }
+    match AdminUserServiceImpl::purge_deleted_users(db, deleted_before).await {
+        Ok(0) => {},
+        Ok(purged) => info!("Purged {} deleted admin user(s)", purged),
+        Err(e) => error!("Failed to purge deleted admin users: {}", e),
+    }
+}
+
+/// Runs the purge at startup, then every PURGE_INTERVAL_HOURS.
+pub fn spawn_purge_job(db: Arc<DatabaseConnection>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticks = interval(purge_interval());
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            purge_deleted_accounts(db.as_ref()).await;
+        }
+    })
+}
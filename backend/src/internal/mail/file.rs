@@ -0,0 +1,48 @@
+use std::{env, fs, path::PathBuf};
+use async_trait::async_trait;
+use chrono::Utc;
+use log::info;
+
+use super::{mail_from, message_id, render_message, Email, MailError, Mailer};
+
+/// Writes every email as an `.eml` file, to be opened with a mail client.
+pub struct FileMailer {
+    directory: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self, MailError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(FileMailer { directory })
+    }
+
+    pub fn from_env() -> Result<Self, MailError> {
+        FileMailer::new(env::var("MAIL_OUTPUT_DIR").map_err(|_| MailError::MissingVariable("MAIL_OUTPUT_DIR"))?)
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        let from = mail_from();
+        let id = message_id(&from);
+        let path = self.directory.join(format!("{}-{}.eml", Utc::now().format("%Y%m%dT%H%M%S%.3f"), id));
+
+        tokio::fs::write(&path, render_message(&from, email, &id)).await?;
+        info!("Mail to {} written to {}", email.to, path.display());
+        Ok(())
+    }
+}
+
+/// Logs every email instead of sending it. Links in the emails end up in the logs, this is
+/// only meant for development.
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        info!("Mail to {}: {}\n{}", email.to, email.subject, email.text);
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use std::{env, sync::Arc};
+use actix_web::http::StatusCode;
+use async_graphql::{Context, Error, ErrorExtensions};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use log::error;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::errors::interface::CustomGraphQLError;
+
+pub mod file;
+pub mod smtp;
+pub mod templates;
+#[cfg(test)]
+mod test_mail;
+
+pub use file::{FileMailer, LogMailer};
+pub use smtp::SmtpMailer;
+
+/// A plain text email to a single recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+}
+
+#[derive(Error, Debug)]
+pub enum MailError {
+    #[error("{0} must be set")]
+    MissingVariable(&'static str),
+
+    #[error("Unknown mailer '{0}', expected smtp, file or log")]
+    UnknownMailer(String),
+
+    #[error("Invalid email address '{0}'")]
+    InvalidAddress(String),
+
+    #[error("Mail transport error: {0}")]
+    Transport(String),
+
+    #[error("Failed to write mail: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("No mailer configured")]
+    NotConfigured,
+}
+
+impl CustomGraphQLError for MailError {
+    fn new(&self) -> Error {
+        error!("Mail error: {}", self);
+
+        Error::new("The email could not be sent.").extend_with(|_err, extensions| {
+            extensions.set("code", StatusCode::INTERNAL_SERVER_ERROR.as_u16()); // HTTP 500
+            extensions.set("message", "MAIL_ERROR");
+        })
+    }
+}
+
+/// Sends the emails of the application. Shared by every request through the schema data,
+/// as `Arc<dyn Mailer>`.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+/// Mailer attached to the schema data.
+pub fn mailer<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Arc<dyn Mailer>> {
+    ctx.data::<Arc<dyn Mailer>>().map_err(|_| MailError::NotConfigured.new())
+}
+
+/// Sender address of every email, read from `MAIL_FROM`.
+pub fn mail_from() -> String {
+    env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@localhost".to_string())
+}
+
+/// Mailer selected by `MAILER`:
+/// - `smtp`: delivers through the server configured by the `SMTP_*` variables
+/// - `file`: writes `.eml` files to `MAIL_OUTPUT_DIR`, for local testing
+/// - `log` (default): logs the emails
+pub fn mailer_from_env() -> Result<Arc<dyn Mailer>, MailError> {
+    match env::var("MAILER").unwrap_or_else(|_| "log".to_string()).as_str() {
+        "smtp" => Ok(Arc::new(SmtpMailer::from_env()?)),
+        "file" => Ok(Arc::new(FileMailer::from_env()?)),
+        "log" => Ok(Arc::new(LogMailer)),
+        other => Err(MailError::UnknownMailer(other.to_string())),
+    }
+}
+
+/// Internet message (RFC 5322) of `email`, with CRLF line endings. Non-ASCII subjects are
+/// sent as encoded words and the body as base64, so any server accepts them.
+pub fn render_message(from: &str, email: &Email, message_id: &str) -> String {
+    let subject = if email.subject.is_ascii() {
+        email.subject.clone()
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(email.subject.as_bytes()))
+    };
+
+    let body = STANDARD.encode(email.text.replace("\r\n", "\n").replace('\n', "\r\n").as_bytes());
+    let body = body
+        .as_bytes()
+        .chunks(76)
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}>\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+        from,
+        email.to,
+        subject,
+        Utc::now().to_rfc2822(),
+        message_id,
+        body,
+    )
+}
+
+/// Unique `Message-ID`, in the domain of the sender.
+pub fn message_id(from: &str) -> String {
+    let domain = from.rsplit_once('@').map(|(_, domain)| domain).unwrap_or("localhost");
+    format!("{}@{}", Uuid::new_v4(), domain.trim_end_matches('>'))
+}
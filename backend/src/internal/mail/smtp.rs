@@ -0,0 +1,89 @@
+use std::env;
+use async_native_tls::TlsConnector;
+use async_smtp::{
+    smtp::authentication::Credentials, ClientSecurity, ClientTlsParameters, EmailAddress, Envelope, SendableEmail,
+    ServerAddress, SmtpClient,
+};
+use async_trait::async_trait;
+use log::trace;
+
+use super::{mail_from, message_id, render_message, Email, MailError, Mailer};
+
+/// How the connection to the SMTP server is secured, from `SMTP_SECURITY`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// TLS from the start, usually on port 465.
+    Tls,
+    /// Plain connection upgraded with STARTTLS, usually on port 587.
+    StartTls,
+    /// No encryption, only for a local relay or a test server.
+    None,
+}
+
+/// Delivers emails through an SMTP server, configured by `SMTP_HOST`, `SMTP_PORT`
+/// (default 587), `SMTP_SECURITY` (`starttls` by default, `tls` or `none`) and the optional
+/// `SMTP_USERNAME`/`SMTP_PASSWORD`. A connection is opened per email.
+pub struct SmtpMailer {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    credentials: Option<(String, String)>,
+}
+
+impl SmtpMailer {
+    pub fn new(host: String, port: u16, security: SmtpSecurity, credentials: Option<(String, String)>) -> Self {
+        SmtpMailer { host, port, security, credentials }
+    }
+
+    pub fn from_env() -> Result<Self, MailError> {
+        let host = env::var("SMTP_HOST").map_err(|_| MailError::MissingVariable("SMTP_HOST"))?;
+        let port = env::var("SMTP_PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(587);
+        let security = match env::var("SMTP_SECURITY").unwrap_or_else(|_| "starttls".to_string()).as_str() {
+            "tls" => SmtpSecurity::Tls,
+            "none" => SmtpSecurity::None,
+            _ => SmtpSecurity::StartTls,
+        };
+        let credentials = match (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+            (Ok(username), Ok(password)) => Some((username, password)),
+            _ => None,
+        };
+
+        Ok(SmtpMailer::new(host, port, security, credentials))
+    }
+
+    fn client(&self) -> SmtpClient {
+        let tls = || ClientTlsParameters::new(self.host.clone(), TlsConnector::new());
+        let security = match self.security {
+            SmtpSecurity::Tls => ClientSecurity::Wrapper(tls()),
+            SmtpSecurity::StartTls => ClientSecurity::Required(tls()),
+            SmtpSecurity::None => ClientSecurity::None,
+        };
+
+        let client = SmtpClient::with_security(ServerAddress::new(self.host.clone(), self.port), security);
+        match &self.credentials {
+            Some((username, password)) => client.credentials(Credentials::new(username.clone(), password.clone())),
+            None => client,
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        let from = mail_from();
+        let id = message_id(&from);
+
+        let sender = EmailAddress::new(from.clone()).map_err(|_| MailError::InvalidAddress(from.clone()))?;
+        let recipient = EmailAddress::new(email.to.clone()).map_err(|_| MailError::InvalidAddress(email.to.clone()))?;
+        let envelope = Envelope::new(Some(sender), vec![recipient]).map_err(|e| MailError::Transport(e.to_string()))?;
+
+        let mut transport = self.client().into_transport();
+        transport
+            .connect_and_send(SendableEmail::new(envelope, &id, render_message(&from, email, &id)))
+            .await
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+
+        trace!("Mail {} sent to {} through {}:{}", id, email.to, self.host, self.port);
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use std::env;
+
+/// Base URL of the frontend the links in emails point to, read from `FRONTEND_URL`.
+pub fn frontend_url() -> String {
+    env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:3000".to_string()).trim_end_matches('/').to_string()
+}
+
+/// Link to the frontend page at `path`, carrying `token`.
+pub fn token_link(path: &str, token: &str) -> String {
+    format!("{}{}?token={}", frontend_url(), path, token)
+}
+
+pub fn password_reset(to: &str, link: &str, valid_minutes: i64) -> super::Email {
+    super::Email {
+        to: to.to_string(),
+        subject: "Reset your password".to_string(),
+        text: format!(
+            "Hello,\n\nA password reset was requested for your account. Follow this link to choose a new password:\n\n{}\n\nThe link can be used once and expires in {} minutes. If you did not ask for it, you can ignore this email, your password is unchanged.\n",
+            link, valid_minutes,
+        ),
+    }
+}
+
+pub fn email_verification(to: &str, link: &str, valid_minutes: i64) -> super::Email {
+    super::Email {
+        to: to.to_string(),
+        subject: "Verify your email address".to_string(),
+        text: format!(
+            "Hello,\n\nFollow this link to confirm that this address belongs to you:\n\n{}\n\nThe link can be used once and expires in {} hours.\n",
+            link, valid_minutes / 60,
+        ),
+    }
+}
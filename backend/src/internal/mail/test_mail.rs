@@ -0,0 +1,58 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::internal::mail::{message_id, render_message, templates, Email, FileMailer, Mailer};
+
+fn email(subject: &str, text: &str) -> Email {
+    Email { to: "user@example.com".to_string(), subject: subject.to_string(), text: text.to_string() }
+}
+
+fn body(message: &str) -> String {
+    let (_, body) = message.split_once("\r\n\r\n").unwrap();
+    String::from_utf8(STANDARD.decode(body.replace("\r\n", "")).unwrap()).unwrap()
+}
+
+#[test]
+fn test_render_message_headers_and_body() {
+    let message = render_message("no-reply@example.com", &email("Hello", "Line one\nLine two"), "1@example.com");
+
+    assert!(message.starts_with("From: no-reply@example.com\r\nTo: user@example.com\r\nSubject: Hello\r\n"));
+    assert!(message.contains("\r\nMessage-ID: <1@example.com>\r\n"));
+    assert!(message.contains("\r\nContent-Transfer-Encoding: base64\r\n"));
+    assert_eq!(body(&message), "Line one\r\nLine two");
+}
+
+#[test]
+fn test_render_message_encodes_non_ascii_subject_and_wraps_body() {
+    let message = render_message("no-reply@example.com", &email("Réinitialisation", &"é".repeat(200)), "1@example.com");
+
+    assert!(message.contains(&format!("Subject: =?UTF-8?B?{}?=\r\n", STANDARD.encode("Réinitialisation"))));
+    let (_, encoded) = message.split_once("\r\n\r\n").unwrap();
+    assert!(encoded.lines().all(|line| line.len() <= 76));
+    assert_eq!(body(&message), "é".repeat(200));
+}
+
+#[test]
+fn test_message_id_uses_sender_domain() {
+    assert!(message_id("no-reply@example.com").ends_with("@example.com"));
+    assert_ne!(message_id("no-reply@example.com"), message_id("no-reply@example.com"));
+}
+
+#[test]
+fn test_token_link() {
+    assert!(templates::token_link("/reset-password", "abc").ends_with("/reset-password?token=abc"));
+}
+
+#[tokio::test]
+async fn test_file_mailer_writes_eml() {
+    let directory = std::env::temp_dir().join(format!("mail-test-{}", uuid::Uuid::new_v4()));
+    let mailer = FileMailer::new(&directory).unwrap();
+
+    mailer.send(&email("Hello", "Body")).await.unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(&directory).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].extension().unwrap(), "eml");
+    assert_eq!(body(&std::fs::read_to_string(&files[0]).unwrap()), "Body");
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
@@ -1,2 +1,5 @@
 pub mod api;
 pub mod graphql;
+pub mod jobs;
+pub mod mail;
+pub mod security;
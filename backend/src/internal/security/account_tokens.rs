@@ -0,0 +1,144 @@
+use std::env;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use log::trace;
+use sea_orm::{prelude::DateTimeUtc, sea_query::{Expr, OnConflict}, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::{
+    errors::{auth::AuthTokenError, db::AdminDbError, interface::CustomGraphQLError},
+    services::auth::MFA_CHALLENGE_TTL,
+};
+use crate::internal::security::{
+    models::account_tokens,
+    refresh::{generate_opaque_token, hash_token},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccountTokenPurpose {
+    PasswordReset,
+    EmailVerification,
+    /// Spent MFA challenges, keyed by the `jti` of the signed challenge.
+    MfaChallenge,
+}
+
+impl AccountTokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountTokenPurpose::PasswordReset => "password_reset",
+            AccountTokenPurpose::EmailVerification => "email_verification",
+            AccountTokenPurpose::MfaChallenge => "mfa_challenge",
+        }
+    }
+
+    /// Read from `PASSWORD_RESET_TTL_MINUTES` (default 60) and
+    /// `EMAIL_VERIFICATION_TTL_HOURS` (default 48). MFA challenges carry their own expiry.
+    pub fn ttl(&self) -> Duration {
+        let env_or = |name: &str, default: i64| env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default);
+        match self {
+            AccountTokenPurpose::PasswordReset => Duration::minutes(env_or("PASSWORD_RESET_TTL_MINUTES", 60)),
+            AccountTokenPurpose::EmailVerification => Duration::hours(env_or("EMAIL_VERIFICATION_TTL_HOURS", 48)),
+            AccountTokenPurpose::MfaChallenge => Duration::seconds(MFA_CHALLENGE_TTL),
+        }
+    }
+}
+
+#[async_trait]
+pub trait AccountTokenService {
+    /// New single-use token, replacing the unused ones of the same subject and purpose so
+    /// that only the last link sent works.
+    async fn issue(db: &DatabaseConnection, subject_id: Uuid, audience: &str, purpose: AccountTokenPurpose) -> Result<String, Box<dyn CustomGraphQLError>>;
+    /// Spends the token and returns its subject. Unknown, used and expired tokens fail alike.
+    async fn consume(db: &DatabaseConnection, token: &str, audience: &str, purpose: AccountTokenPurpose) -> Result<Uuid, Box<dyn CustomGraphQLError>>;
+    /// Drops the unused tokens of the subject, e.g. verification links sent to an address
+    /// that was changed since.
+    async fn revoke(db: &DatabaseConnection, subject_id: Uuid, audience: &str, purpose: AccountTokenPurpose) -> Result<u64, Box<dyn CustomGraphQLError>>;
+    /// Records the signed token `id` (its `jti`) as spent. Returns false when it was spent
+    /// already, so that the token can only be used once before it expires.
+    async fn spend_signed(db: &DatabaseConnection, id: Uuid, subject_id: Uuid, audience: &str, purpose: AccountTokenPurpose, expires_at: DateTimeUtc) -> Result<bool, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct AccountTokenServiceImpl;
+
+#[async_trait]
+impl AccountTokenService for AccountTokenServiceImpl {
+    async fn issue(db: &DatabaseConnection, subject_id: Uuid, audience: &str, purpose: AccountTokenPurpose) -> Result<String, Box<dyn CustomGraphQLError>> {
+        trace!("Issuing {} token for {} ({})", purpose.as_str(), subject_id, audience);
+
+        AccountTokenServiceImpl::revoke(db, subject_id, audience, purpose).await?;
+
+        let token = generate_opaque_token();
+        account_tokens::Entity::insert(account_tokens::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            subject_id: Set(subject_id),
+            audience: Set(audience.to_string()),
+            purpose: Set(purpose.as_str().to_string()),
+            token_hash: Set(hash_token(&token)),
+            expires_at: Set(Utc::now() + purpose.ttl()),
+            used_at: Set(None),
+            created_at: Set(Utc::now()),
+        })
+        .exec_without_returning(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        Ok(token)
+    }
+
+    async fn consume(db: &DatabaseConnection, token: &str, audience: &str, purpose: AccountTokenPurpose) -> Result<Uuid, Box<dyn CustomGraphQLError>> {
+        // Single statement, so that a token followed twice concurrently is only spent once
+        let spent = account_tokens::Entity::update_many()
+            .col_expr(account_tokens::Column::UsedAt, Expr::value(Utc::now()))
+            .filter(account_tokens::Column::TokenHash.eq(hash_token(token)))
+            .filter(account_tokens::Column::Audience.eq(audience))
+            .filter(account_tokens::Column::Purpose.eq(purpose.as_str()))
+            .filter(account_tokens::Column::UsedAt.is_null())
+            .filter(account_tokens::Column::ExpiresAt.gt(Utc::now()))
+            .exec_with_returning(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        match spent.first() {
+            Some(spent) => {
+                trace!("{} token {} spent by {}", purpose.as_str(), spent.id, spent.subject_id);
+                Ok(spent.subject_id)
+            },
+            None => Err(Box::new(AuthTokenError::InvalidAccountToken)),
+        }
+    }
+
+    async fn revoke(db: &DatabaseConnection, subject_id: Uuid, audience: &str, purpose: AccountTokenPurpose) -> Result<u64, Box<dyn CustomGraphQLError>> {
+        account_tokens::Entity::delete_many()
+            .filter(account_tokens::Column::SubjectId.eq(subject_id))
+            .filter(account_tokens::Column::Audience.eq(audience))
+            .filter(account_tokens::Column::Purpose.eq(purpose.as_str()))
+            .filter(account_tokens::Column::UsedAt.is_null())
+            .exec(db)
+            .await
+            .map(|result| result.rows_affected)
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+
+    async fn spend_signed(db: &DatabaseConnection, id: Uuid, subject_id: Uuid, audience: &str, purpose: AccountTokenPurpose, expires_at: DateTimeUtc) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        // The insert is the check, so that a token replayed concurrently is only spent once
+        let inserted = account_tokens::Entity::insert(account_tokens::ActiveModel {
+            id: Set(id),
+            subject_id: Set(subject_id),
+            audience: Set(audience.to_string()),
+            purpose: Set(purpose.as_str().to_string()),
+            token_hash: Set(hash_token(&id.to_string())),
+            expires_at: Set(expires_at),
+            used_at: Set(Some(Utc::now())),
+            created_at: Set(Utc::now()),
+        })
+        .on_conflict(OnConflict::column(account_tokens::Column::Id).do_nothing().to_owned())
+        .exec_without_returning(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        if inserted == 0 {
+            trace!("{} token {} already spent", purpose.as_str(), id);
+        }
+        Ok(inserted > 0)
+    }
+}
@@ -0,0 +1,248 @@
+use std::{env, fs, sync::OnceLock};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    errors::{Error as JwtError, ErrorKind},
+    Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
+};
+use simple_asn1::{from_der, ASN1Block};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+// DER prefix of an Ed25519 SubjectPublicKeyInfo, the raw 32-byte key follows it.
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+static JWT_KEYS: OnceLock<JwtKeys> = OnceLock::new();
+
+#[derive(Error, Debug)]
+pub enum JwtKeyError {
+    #[error("{0} must be set")]
+    MissingVariable(&'static str),
+
+    #[error("Unsupported JWT algorithm '{0}', expected RS256 or EdDSA")]
+    UnsupportedAlgorithm(String),
+
+    #[error("Failed to read key file '{path}': {source}")]
+    Io { path: String, source: std::io::Error },
+
+    #[error("Invalid key '{kid}': {reason}")]
+    InvalidKey { kid: String, reason: String },
+}
+
+struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+struct VerificationKey {
+    kid: String,
+    algorithm: Algorithm,
+    key: DecodingKey,
+    jwk: Value,
+}
+
+/// Key material used to sign and verify every token. Only the current key signs, but
+/// tokens signed by any configured verification key are accepted, so a key can be
+/// rotated out without logging everybody out.
+pub struct JwtKeys {
+    signing: SigningKey,
+    verification: Vec<VerificationKey>,
+}
+
+fn parse_algorithm(name: &str) -> Result<Algorithm, JwtKeyError> {
+    match name {
+        "RS256" => Ok(Algorithm::RS256),
+        "EdDSA" => Ok(Algorithm::EdDSA),
+        other => Err(JwtKeyError::UnsupportedAlgorithm(other.to_string())),
+    }
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, JwtKeyError> {
+    fs::read(path).map_err(|source| JwtKeyError::Io { path: path.to_string(), source })
+}
+
+// SubjectPublicKeyInfo ::= SEQUENCE { algorithm, BIT STRING { SEQUENCE { INTEGER n, INTEGER e } } }
+fn rsa_public_components(spki: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let key = match from_der(spki).ok()?.as_slice() {
+        [ASN1Block::Sequence(_, fields)] => match fields.as_slice() {
+            [_, ASN1Block::BitString(_, _, key)] => from_der(key).ok()?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match key.as_slice() {
+        [ASN1Block::Sequence(_, parts)] => match parts.as_slice() {
+            [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => Some((n.to_bytes_be().1, e.to_bytes_be().1)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn public_jwk(kid: &str, algorithm: Algorithm, public_pem: &[u8]) -> Result<Value, JwtKeyError> {
+    let invalid = |reason: String| JwtKeyError::InvalidKey { kid: kid.to_string(), reason };
+
+    match algorithm {
+        Algorithm::RS256 => {
+            let der = pem::parse(public_pem).map_err(|e| invalid(e.to_string()))?;
+            let (n, e) = rsa_public_components(der.contents())
+                .ok_or_else(|| invalid("not an RSA public key".to_string()))?;
+            Ok(json!({
+                "kty": "RSA",
+                "use": "sig",
+                "alg": "RS256",
+                "kid": kid,
+                "n": URL_SAFE_NO_PAD.encode(n),
+                "e": URL_SAFE_NO_PAD.encode(e),
+            }))
+        },
+        Algorithm::EdDSA => {
+            let der = pem::parse(public_pem).map_err(|e| invalid(e.to_string()))?;
+            let raw = der
+                .contents()
+                .strip_prefix(&ED25519_SPKI_PREFIX[..])
+                .filter(|raw| raw.len() == 32)
+                .ok_or_else(|| invalid("not an Ed25519 public key".to_string()))?;
+            Ok(json!({
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "use": "sig",
+                "alg": "EdDSA",
+                "kid": kid,
+                "x": URL_SAFE_NO_PAD.encode(raw),
+            }))
+        },
+        other => Err(JwtKeyError::UnsupportedAlgorithm(format!("{:?}", other))),
+    }
+}
+
+impl JwtKeys {
+    /// Builds the key set from a PEM key pair, `kid` identifying it in token headers.
+    pub fn from_pem(kid: &str, algorithm: Algorithm, private_pem: &[u8], public_pem: &[u8]) -> Result<Self, JwtKeyError> {
+        let invalid = |e: JwtError| JwtKeyError::InvalidKey { kid: kid.to_string(), reason: e.to_string() };
+
+        let key = match algorithm {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(private_pem).map_err(invalid)?,
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(private_pem).map_err(invalid)?,
+            other => return Err(JwtKeyError::UnsupportedAlgorithm(format!("{:?}", other))),
+        };
+
+        let keys = JwtKeys {
+            signing: SigningKey { kid: kid.to_string(), algorithm, key },
+            verification: Vec::new(),
+        };
+        keys.with_verification_key(kid, algorithm, public_pem)
+    }
+
+    /// Adds a public key accepted for verification only, typically the previous signing key.
+    pub fn with_verification_key(mut self, kid: &str, algorithm: Algorithm, public_pem: &[u8]) -> Result<Self, JwtKeyError> {
+        let key = match algorithm {
+            Algorithm::RS256 => DecodingKey::from_rsa_pem(public_pem),
+            Algorithm::EdDSA => DecodingKey::from_ed_pem(public_pem),
+            other => return Err(JwtKeyError::UnsupportedAlgorithm(format!("{:?}", other))),
+        }
+        .map_err(|e| JwtKeyError::InvalidKey { kid: kid.to_string(), reason: e.to_string() })?;
+
+        let jwk = public_jwk(kid, algorithm, public_pem)?;
+        self.verification.retain(|existing| existing.kid != kid);
+        self.verification.push(VerificationKey { kid: kid.to_string(), algorithm, key, jwk });
+        Ok(self)
+    }
+
+    /// Loads the keys from:
+    /// - `JWT_ALGORITHM`: `RS256` (default) or `EdDSA`
+    /// - `JWT_KEY_ID`: `kid` of the signing key
+    /// - `JWT_PRIVATE_KEY_PATH` / `JWT_PUBLIC_KEY_PATH`: PEM files of the signing key
+    /// - `JWT_VERIFICATION_KEYS`: optional, comma-separated `kid:ALGORITHM:path` public keys
+    pub fn from_env() -> Result<Self, JwtKeyError> {
+        let algorithm = parse_algorithm(&env::var("JWT_ALGORITHM").unwrap_or_else(|_| "RS256".to_string()))?;
+        let kid = env::var("JWT_KEY_ID").map_err(|_| JwtKeyError::MissingVariable("JWT_KEY_ID"))?;
+        let private_path = env::var("JWT_PRIVATE_KEY_PATH").map_err(|_| JwtKeyError::MissingVariable("JWT_PRIVATE_KEY_PATH"))?;
+        let public_path = env::var("JWT_PUBLIC_KEY_PATH").map_err(|_| JwtKeyError::MissingVariable("JWT_PUBLIC_KEY_PATH"))?;
+
+        let mut keys = JwtKeys::from_pem(&kid, algorithm, &read_pem(&private_path)?, &read_pem(&public_path)?)?;
+
+        let additional = env::var("JWT_VERIFICATION_KEYS").unwrap_or_default();
+        for entry in additional.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let mut parts = entry.splitn(3, ':');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(kid), Some(algorithm), Some(path)) => {
+                    keys = keys.with_verification_key(kid, parse_algorithm(algorithm)?, &read_pem(path)?)?;
+                },
+                _ => {
+                    return Err(JwtKeyError::InvalidKey {
+                        kid: entry.to_string(),
+                        reason: "expected 'kid:ALGORITHM:path'".to_string(),
+                    });
+                },
+            }
+        }
+
+        Ok(keys)
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, JwtError> {
+        let mut header = Header::new(self.signing.algorithm);
+        header.kid = Some(self.signing.kid.clone());
+        encode(&header, claims, &self.signing.key)
+    }
+
+    /// Verifies the token with the key named by its `kid` header. The algorithm is taken
+    /// from the key, never from the token.
+    pub fn decode<T: DeserializeOwned>(&self, token: &str, mut validation: Validation) -> Result<TokenData<T>, JwtError> {
+        let header = decode_header(token)?;
+        let key = header
+            .kid
+            .and_then(|kid| self.verification.iter().find(|key| key.kid == kid))
+            .ok_or_else(|| JwtError::from(ErrorKind::InvalidToken))?;
+
+        validation.algorithms = vec![key.algorithm];
+        decode::<T>(token, &key.key, &validation)
+    }
+
+    /// Public keys in JSON Web Key Set format, served at `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> Value {
+        json!({ "keys": self.verification.iter().map(|key| key.jwk.clone()).collect::<Vec<_>>() })
+    }
+}
+
+/// `iss` of every token issued by this API.
+pub fn jwt_issuer() -> String {
+    env::var("JWT_ISSUER").unwrap_or_else(|_| "template".to_string())
+}
+
+/// Validation shared by every API surface: `exp`/`nbf`, our issuer, and the audience of the
+/// surface, so a token minted for one surface is never accepted by another.
+pub fn validation_for(audience: &str) -> Validation {
+    let mut validation = Validation::default();
+    validation.set_issuer(&[jwt_issuer()]);
+    validation.set_audience(&[audience]);
+    validation.validate_nbf = true;
+    validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud", "sub"]);
+    validation
+}
+
+/// Installs the key set used by the whole process. Returns false if keys were already installed.
+pub fn install_jwt_keys(keys: JwtKeys) -> bool {
+    JWT_KEYS.set(keys).is_ok()
+}
+
+pub fn jwt_keys() -> &'static JwtKeys {
+    JWT_KEYS.get().expect("JWT keys are not installed, call install_jwt_keys at startup")
+}
+
+#[cfg(test)]
+pub fn install_test_jwt_keys() {
+    JWT_KEYS.get_or_init(|| {
+        JwtKeys::from_pem(
+            "test",
+            Algorithm::EdDSA,
+            include_bytes!("testdata/ed25519_private.pem"),
+            include_bytes!("testdata/ed25519_public.pem"),
+        )
+        .expect("Test keys should be valid")
+    });
+}
@@ -0,0 +1,19 @@
+pub mod account_tokens;
+pub mod jwt;
+pub mod models;
+pub mod password;
+pub mod refresh;
+pub mod throttle;
+pub mod totp;
+#[cfg(test)]
+mod test_account_tokens;
+#[cfg(test)]
+mod test_jwt;
+#[cfg(test)]
+mod test_password;
+#[cfg(test)]
+mod test_refresh;
+#[cfg(test)]
+mod test_throttle;
+#[cfg(test)]
+mod test_totp;
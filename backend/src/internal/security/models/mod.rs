@@ -0,0 +1,3 @@
+pub mod refresh_tokens;
+pub mod login_attempts;
+pub mod account_tokens;
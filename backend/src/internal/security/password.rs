@@ -0,0 +1,256 @@
+use std::env;
+use argon2::{
+    password_hash::{rand_core::OsRng, SaltString},
+    Algorithm, Argon2, Params, PasswordHash, PasswordHasher as _, PasswordVerifier, Version,
+};
+use log::{trace, warn};
+use thiserror::Error;
+
+const DEFAULT_BCRYPT_COST: u32 = bcrypt::DEFAULT_COST;
+const DEFAULT_ARGON2_MEMORY_KIB: u32 = Params::DEFAULT_M_COST;
+const DEFAULT_ARGON2_ITERATIONS: u32 = Params::DEFAULT_T_COST;
+const DEFAULT_ARGON2_PARALLELISM: u32 = Params::DEFAULT_P_COST;
+
+#[derive(Error, Debug)]
+pub enum PasswordHashError {
+    #[error("Bcrypt error: {0}")]
+    Bcrypt(#[from] bcrypt::BcryptError),
+
+    #[error("Argon2 error: {0}")]
+    Argon2(String),
+
+    #[error("Invalid password hashing configuration: {0}")]
+    InvalidConfig(String),
+}
+
+/// Algorithms a stored hash can be produced with. The algorithm is recovered from
+/// the hash prefix (`$2b$…` for bcrypt, `$argon2id$…` for Argon2id), so rows hashed
+/// with different algorithms can live side by side in the same column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasswordAlgorithm {
+    Bcrypt,
+    Argon2id,
+}
+
+impl PasswordAlgorithm {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "bcrypt" => Some(PasswordAlgorithm::Bcrypt),
+            "argon2" | "argon2id" => Some(PasswordAlgorithm::Argon2id),
+            _ => None,
+        }
+    }
+
+    pub fn from_hash(hash: &str) -> Option<Self> {
+        if ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix)) {
+            Some(PasswordAlgorithm::Bcrypt)
+        } else if hash.starts_with("$argon2id$") {
+            Some(PasswordAlgorithm::Argon2id)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PasswordHashingConfig {
+    pub algorithm: PasswordAlgorithm,
+    pub bcrypt_cost: u32,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+}
+
+impl Default for PasswordHashingConfig {
+    fn default() -> Self {
+        PasswordHashingConfig {
+            algorithm: PasswordAlgorithm::Argon2id,
+            bcrypt_cost: DEFAULT_BCRYPT_COST,
+            argon2_memory_kib: DEFAULT_ARGON2_MEMORY_KIB,
+            argon2_iterations: DEFAULT_ARGON2_ITERATIONS,
+            argon2_parallelism: DEFAULT_ARGON2_PARALLELISM,
+        }
+    }
+}
+
+impl PasswordHashingConfig {
+    /// Reads `PASSWORD_HASH_ALGORITHM` (`bcrypt` or `argon2id`), `PASSWORD_BCRYPT_COST`,
+    /// `PASSWORD_ARGON2_MEMORY_KIB`, `PASSWORD_ARGON2_ITERATIONS` and
+    /// `PASSWORD_ARGON2_PARALLELISM`, falling back to the defaults for unset values.
+    pub fn from_env() -> Self {
+        let defaults = PasswordHashingConfig::default();
+
+        let algorithm = match env::var("PASSWORD_HASH_ALGORITHM") {
+            Ok(name) => PasswordAlgorithm::from_name(&name).unwrap_or_else(|| {
+                warn!("Unknown PASSWORD_HASH_ALGORITHM '{}', using {:?}", name, defaults.algorithm);
+                defaults.algorithm
+            }),
+            Err(_) => defaults.algorithm,
+        };
+
+        PasswordHashingConfig {
+            algorithm,
+            bcrypt_cost: env_u32("PASSWORD_BCRYPT_COST", defaults.bcrypt_cost),
+            argon2_memory_kib: env_u32("PASSWORD_ARGON2_MEMORY_KIB", defaults.argon2_memory_kib),
+            argon2_iterations: env_u32("PASSWORD_ARGON2_ITERATIONS", defaults.argon2_iterations),
+            argon2_parallelism: env_u32("PASSWORD_ARGON2_PARALLELISM", defaults.argon2_parallelism),
+        }
+    }
+}
+
+fn env_u32(key: &str, default: u32) -> u32 {
+    match env::var(key) {
+        Ok(value) => value.parse().unwrap_or_else(|_| {
+            warn!("Invalid value '{}' for {}, using {}", value, key, default);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+pub trait PasswordHasher: Send + Sync {
+    fn hash(&self, password: &str) -> Result<String, PasswordHashError>;
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, PasswordHashError>;
+    /// Whether `hash` was produced with weaker or different parameters than this hasher uses.
+    fn needs_rehash(&self, hash: &str) -> bool;
+}
+
+pub struct BcryptHasher {
+    cost: u32,
+}
+
+impl BcryptHasher {
+    pub fn new(cost: u32) -> Self {
+        BcryptHasher { cost }
+    }
+}
+
+impl PasswordHasher for BcryptHasher {
+    fn hash(&self, password: &str) -> Result<String, PasswordHashError> {
+        Ok(bcrypt::hash(password, self.cost)?)
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, PasswordHashError> {
+        Ok(bcrypt::verify(password, hash)?)
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        // "$2b$12$<salt+hash>" splits into ["", "2b", "12", "<salt+hash>"]
+        match hash.split('$').nth(2).and_then(|cost| cost.parse::<u32>().ok()) {
+            Some(cost) => cost != self.cost,
+            None => true,
+        }
+    }
+}
+
+pub struct Argon2Hasher {
+    params: Params,
+}
+
+impl Argon2Hasher {
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> Result<Self, PasswordHashError> {
+        let params = Params::new(memory_kib, iterations, parallelism, None)
+            .map_err(|e| PasswordHashError::InvalidConfig(e.to_string()))?;
+        Ok(Argon2Hasher { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl PasswordHasher for Argon2Hasher {
+    fn hash(&self, password: &str) -> Result<String, PasswordHashError> {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| PasswordHashError::Argon2(e.to_string()))
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, PasswordHashError> {
+        let parsed = PasswordHash::new(hash).map_err(|e| PasswordHashError::Argon2(e.to_string()))?;
+
+        match self.argon2().verify_password(password.as_bytes(), &parsed) {
+            Ok(()) => Ok(true),
+            Err(argon2::password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(PasswordHashError::Argon2(e.to_string())),
+        }
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        let parsed = match PasswordHash::new(hash) {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+
+        if parsed.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+
+        match Params::try_from(&parsed) {
+            Ok(params) => {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+/// Entry point used by the user services: new hashes are produced with the configured
+/// algorithm, while verification dispatches on the prefix of the stored hash.
+pub struct PasswordService {
+    config: PasswordHashingConfig,
+}
+
+impl PasswordService {
+    pub fn new(config: PasswordHashingConfig) -> Self {
+        PasswordService { config }
+    }
+
+    pub fn from_env() -> Self {
+        PasswordService::new(PasswordHashingConfig::from_env())
+    }
+
+    fn hasher(&self, algorithm: PasswordAlgorithm) -> Result<Box<dyn PasswordHasher>, PasswordHashError> {
+        match algorithm {
+            PasswordAlgorithm::Bcrypt => Ok(Box::new(BcryptHasher::new(self.config.bcrypt_cost))),
+            PasswordAlgorithm::Argon2id => Ok(Box::new(Argon2Hasher::new(
+                self.config.argon2_memory_kib,
+                self.config.argon2_iterations,
+                self.config.argon2_parallelism,
+            )?)),
+        }
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, PasswordHashError> {
+        trace!("Hashing password with {:?}", self.config.algorithm);
+        self.hasher(self.config.algorithm)?.hash(password)
+    }
+
+    /// Returns `Ok(false)` for a wrong password as well as for a stored value that is
+    /// not a recognised hash (e.g. a legacy plaintext row).
+    pub fn verify(&self, password: &str, hash: &str) -> Result<bool, PasswordHashError> {
+        match PasswordAlgorithm::from_hash(hash) {
+            Some(algorithm) => self.hasher(algorithm)?.verify(password, hash),
+            None => {
+                warn!("Stored password is not a recognised hash, refusing verification");
+                Ok(false)
+            }
+        }
+    }
+
+    /// Whether a hash that just verified successfully should be replaced, because it
+    /// uses another algorithm or other cost parameters than the current configuration.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        match PasswordAlgorithm::from_hash(hash) {
+            Some(algorithm) if algorithm == self.config.algorithm => match self.hasher(algorithm) {
+                Ok(hasher) => hasher.needs_rehash(hash),
+                Err(_) => false,
+            },
+            _ => true,
+        }
+    }
+}
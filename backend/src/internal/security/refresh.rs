@@ -0,0 +1,201 @@
+use std::env;
+use async_graphql::SimpleObject;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use log::{trace, warn};
+use rand::RngCore;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::internal::api::admin::users::errors::{auth::AuthTokenError, db::AdminDbError, interface::CustomGraphQLError};
+use crate::internal::security::models::refresh_tokens;
+
+/// Returned by every login/refresh mutation.
+#[derive(SimpleObject, Debug)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Lifetime of the access token, in seconds.
+    pub expires_in: i64,
+}
+
+/// Result of a successful rotation: who the token belonged to and its replacement.
+pub struct RotatedToken {
+    pub subject_id: Uuid,
+    pub refresh_token: String,
+}
+
+fn refresh_token_ttl() -> Duration {
+    let days = env::var("REFRESH_TOKEN_TTL_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(30);
+    Duration::days(days)
+}
+
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[async_trait]
+pub trait RefreshTokenService {
+    /// Starts a new token family, on login.
+    async fn issue(db: &DatabaseConnection, subject_id: Uuid, audience: &str) -> Result<String, Box<dyn CustomGraphQLError>>;
+    /// Exchanges a refresh token for a new one of the same family. Presenting a token that
+    /// was already rotated or revoked revokes the whole family.
+    async fn rotate(db: &DatabaseConnection, token: &str, audience: &str) -> Result<RotatedToken, Box<dyn CustomGraphQLError>>;
+    /// Revokes the family of the given token, on logout. Unknown tokens are ignored.
+    async fn revoke(db: &DatabaseConnection, token: &str, audience: &str) -> Result<bool, Box<dyn CustomGraphQLError>>;
+    /// Revokes every session of the subject, e.g. once their password was reset.
+    async fn revoke_all(db: &DatabaseConnection, subject_id: Uuid, audience: &str) -> Result<u64, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct RefreshTokenServiceImpl;
+
+async fn insert_token<C: ConnectionTrait>(
+    db: &C,
+    family_id: Uuid,
+    subject_id: Uuid,
+    audience: &str,
+) -> Result<(Uuid, String), Box<dyn CustomGraphQLError>> {
+    let id = Uuid::new_v4();
+    let token = generate_opaque_token();
+
+    let model = refresh_tokens::ActiveModel {
+        id: Set(id),
+        family_id: Set(family_id),
+        subject_id: Set(subject_id),
+        audience: Set(audience.to_string()),
+        token_hash: Set(hash_token(&token)),
+        expires_at: Set(Utc::now() + refresh_token_ttl()),
+        revoked_at: Set(None),
+        replaced_by: Set(None),
+        created_at: Set(Utc::now()),
+    };
+
+    refresh_tokens::Entity::insert(model)
+        .exec_without_returning(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+    Ok((id, token))
+}
+
+async fn find_token(db: &DatabaseConnection, token: &str, audience: &str) -> Result<Option<refresh_tokens::Model>, Box<dyn CustomGraphQLError>> {
+    refresh_tokens::Entity::find()
+        .filter(refresh_tokens::Column::TokenHash.eq(hash_token(token)))
+        .filter(refresh_tokens::Column::Audience.eq(audience))
+        .one(db)
+        .await
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+}
+
+async fn revoke_family<C: ConnectionTrait>(db: &C, family_id: Uuid) -> Result<u64, Box<dyn CustomGraphQLError>> {
+    refresh_tokens::Entity::update_many()
+        .col_expr(refresh_tokens::Column::RevokedAt, Expr::value(Utc::now()))
+        .filter(refresh_tokens::Column::FamilyId.eq(family_id))
+        .filter(refresh_tokens::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map(|result| result.rows_affected)
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+}
+
+/// Revokes every session of the subject, inside the caller's transaction when given one.
+pub async fn revoke_subject<C: ConnectionTrait>(db: &C, subject_id: Uuid, audience: &str) -> Result<u64, Box<dyn CustomGraphQLError>> {
+    trace!("Revoking every refresh token of {} ({})", subject_id, audience);
+
+    refresh_tokens::Entity::update_many()
+        .col_expr(refresh_tokens::Column::RevokedAt, Expr::value(Utc::now()))
+        .filter(refresh_tokens::Column::SubjectId.eq(subject_id))
+        .filter(refresh_tokens::Column::Audience.eq(audience))
+        .filter(refresh_tokens::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map(|result| result.rows_affected)
+        .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+}
+
+#[async_trait]
+impl RefreshTokenService for RefreshTokenServiceImpl {
+    async fn issue(db: &DatabaseConnection, subject_id: Uuid, audience: &str) -> Result<String, Box<dyn CustomGraphQLError>> {
+        trace!("Issuing refresh token for {} ({})", subject_id, audience);
+
+        let (_, token) = insert_token(db, Uuid::new_v4(), subject_id, audience).await?;
+        Ok(token)
+    }
+
+    async fn rotate(db: &DatabaseConnection, token: &str, audience: &str) -> Result<RotatedToken, Box<dyn CustomGraphQLError>> {
+        let current = find_token(db, token, audience)
+            .await?
+            .ok_or_else(|| Box::new(AuthTokenError::InvalidRefreshToken) as Box<dyn CustomGraphQLError>)?;
+
+        if current.revoked_at.is_some() || current.replaced_by.is_some() {
+            warn!("Refresh token {} of family {} reused, revoking the family", current.id, current.family_id);
+            revoke_family(db, current.family_id).await?;
+            return Err(Box::new(AuthTokenError::RefreshTokenReused));
+        }
+
+        if current.expires_at < Utc::now() {
+            return Err(Box::new(AuthTokenError::InvalidRefreshToken));
+        }
+
+        let txn = db.begin()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        let (next_id, next_token) = insert_token(&txn, current.family_id, current.subject_id, audience).await?;
+
+        // Only rotates if nobody else did in the meantime, a concurrent rotation is a reuse
+        let rotated = refresh_tokens::Entity::update_many()
+            .col_expr(refresh_tokens::Column::RevokedAt, Expr::value(Utc::now()))
+            .col_expr(refresh_tokens::Column::ReplacedBy, Expr::value(next_id))
+            .filter(refresh_tokens::Column::Id.eq(current.id))
+            .filter(refresh_tokens::Column::RevokedAt.is_null())
+            .exec(&txn)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        if rotated.rows_affected == 0 {
+            txn.rollback()
+                .await
+                .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+            revoke_family(db, current.family_id).await?;
+            return Err(Box::new(AuthTokenError::RefreshTokenReused));
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        trace!("Refresh token {} rotated into {}", current.id, next_id);
+        Ok(RotatedToken {
+            subject_id: current.subject_id,
+            refresh_token: next_token,
+        })
+    }
+
+    async fn revoke(db: &DatabaseConnection, token: &str, audience: &str) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        match find_token(db, token, audience).await? {
+            Some(current) => {
+                trace!("Revoking refresh token family {}", current.family_id);
+                revoke_family(db, current.family_id).await?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    async fn revoke_all(db: &DatabaseConnection, subject_id: Uuid, audience: &str) -> Result<u64, Box<dyn CustomGraphQLError>> {
+        revoke_subject(db, subject_id, audience).await
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::Duration;
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+use crate::internal::security::{
+    account_tokens::{AccountTokenPurpose, AccountTokenService, AccountTokenServiceImpl},
+    models::account_tokens,
+    refresh::hash_token,
+};
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult { last_insert_id: 0, rows_affected }
+}
+
+fn spent_token(subject_id: Uuid, token: &str) -> account_tokens::Model {
+    account_tokens::Model {
+        id: Uuid::new_v4(),
+        subject_id,
+        audience: "users".to_owned(),
+        purpose: "password_reset".to_owned(),
+        token_hash: hash_token(token),
+        expires_at: Utc::now() + Duration::hours(1),
+        used_at: Some(Utc::now()),
+        created_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn test_issue_replaces_unused_tokens() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([exec(1), exec(1)])
+        .into_connection();
+
+    let token = AccountTokenServiceImpl::issue(&db, Uuid::new_v4(), "users", AccountTokenPurpose::PasswordReset).await.unwrap();
+
+    assert_eq!(token.len(), 64);
+    let log = db.into_transaction_log();
+    let delete = format!("{:?}", log[0]).replace("\\\"", "\"");
+    assert!(delete.starts_with(r#"Transaction { stmts: [Statement { sql: "DELETE FROM "account_tokens""#), "Unexpected query: {}", delete);
+    assert!(delete.contains(r#""used_at" IS NULL"#), "Unexpected query: {}", delete);
+    // Only the hash is stored
+    let insert = format!("{:?}", log[1]);
+    assert!(!insert.contains(&token));
+    assert!(insert.contains(&hash_token(&token)));
+}
+
+#[tokio::test]
+async fn test_consume_returns_subject_once() {
+    let subject_id = Uuid::new_v4();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![spent_token(subject_id, "token")], vec![]])
+        .into_connection();
+
+    let consumed = AccountTokenServiceImpl::consume(&db, "token", "users", AccountTokenPurpose::PasswordReset).await.unwrap();
+    assert_eq!(consumed, subject_id);
+
+    // Spent, or expired, or unknown: nothing matches the second time
+    let error = AccountTokenServiceImpl::consume(&db, "token", "users", AccountTokenPurpose::PasswordReset).await.unwrap_err().new();
+    let extensions = error.extensions.unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVALID_ACCOUNT_TOKEN")));
+
+    let sql = format!("{:?}", db.into_transaction_log()[0]).replace("\\\"", "\"");
+    assert!(sql.contains(r#""used_at" IS NULL AND "account_tokens"."expires_at" > "#), "Unexpected query: {}", sql);
+}
+
+#[tokio::test]
+async fn test_spend_signed_only_once() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([exec(1), exec(0)])
+        .into_connection();
+    let jti = Uuid::new_v4();
+    let expires_at = Utc::now() + Duration::minutes(5);
+
+    let first = AccountTokenServiceImpl::spend_signed(&db, jti, Uuid::new_v4(), "admin-mfa", AccountTokenPurpose::MfaChallenge, expires_at).await.unwrap();
+    let replayed = AccountTokenServiceImpl::spend_signed(&db, jti, Uuid::new_v4(), "admin-mfa", AccountTokenPurpose::MfaChallenge, expires_at).await.unwrap();
+
+    assert!(first);
+    assert!(!replayed);
+    let log = db.into_transaction_log();
+    let insert = format!("{:?}", log[0]).replace("\\\"", "\"");
+    assert!(insert.contains(r#"INSERT INTO "account_tokens""#), "Unexpected query: {}", insert);
+    assert!(insert.contains(r#"ON CONFLICT ("id") DO NOTHING"#), "Unexpected query: {}", insert);
+    assert!(insert.contains("mfa_challenge"), "Unexpected query: {}", insert);
+}
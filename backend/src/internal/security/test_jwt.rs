@@ -0,0 +1,92 @@
+use jsonwebtoken::{Algorithm, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::internal::security::jwt::JwtKeys;
+
+const ED25519_PRIVATE: &[u8] = include_bytes!("testdata/ed25519_private.pem");
+const ED25519_PUBLIC: &[u8] = include_bytes!("testdata/ed25519_public.pem");
+const RSA_PRIVATE: &[u8] = include_bytes!("testdata/rsa_private.pem");
+const RSA_PUBLIC: &[u8] = include_bytes!("testdata/rsa_public.pem");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct TestClaims {
+    sub: String,
+    exp: usize,
+}
+
+fn claims() -> TestClaims {
+    TestClaims {
+        sub: "51c84da0-6fbe-4db2-81fe-385a38d29353".to_owned(),
+        exp: (chrono::Utc::now().timestamp() + 60) as usize,
+    }
+}
+
+#[test]
+fn test_eddsa_round_trip() {
+    let keys = JwtKeys::from_pem("ed-1", Algorithm::EdDSA, ED25519_PRIVATE, ED25519_PUBLIC).unwrap();
+
+    let token = keys.encode(&claims()).unwrap();
+    let header = jsonwebtoken::decode_header(&token).unwrap();
+    assert_eq!(header.alg, Algorithm::EdDSA);
+    assert_eq!(header.kid.as_deref(), Some("ed-1"));
+
+    let decoded = keys.decode::<TestClaims>(&token, Validation::default()).unwrap();
+    assert_eq!(decoded.claims, claims());
+}
+
+#[test]
+fn test_rs256_round_trip() {
+    let keys = JwtKeys::from_pem("rsa-1", Algorithm::RS256, RSA_PRIVATE, RSA_PUBLIC).unwrap();
+
+    let token = keys.encode(&claims()).unwrap();
+
+    assert_eq!(jsonwebtoken::decode_header(&token).unwrap().alg, Algorithm::RS256);
+    assert!(keys.decode::<TestClaims>(&token, Validation::default()).is_ok());
+}
+
+#[test]
+fn test_rotated_key_still_verifies() {
+    let previous = JwtKeys::from_pem("rsa-1", Algorithm::RS256, RSA_PRIVATE, RSA_PUBLIC).unwrap();
+    let token = previous.encode(&claims()).unwrap();
+
+    let current = JwtKeys::from_pem("ed-1", Algorithm::EdDSA, ED25519_PRIVATE, ED25519_PUBLIC)
+        .unwrap()
+        .with_verification_key("rsa-1", Algorithm::RS256, RSA_PUBLIC)
+        .unwrap();
+
+    assert!(current.decode::<TestClaims>(&token, Validation::default()).is_ok());
+}
+
+#[test]
+fn test_unknown_kid_is_rejected() {
+    let other = JwtKeys::from_pem("rsa-1", Algorithm::RS256, RSA_PRIVATE, RSA_PUBLIC).unwrap();
+    let token = other.encode(&claims()).unwrap();
+
+    let keys = JwtKeys::from_pem("ed-1", Algorithm::EdDSA, ED25519_PRIVATE, ED25519_PUBLIC).unwrap();
+
+    assert!(keys.decode::<TestClaims>(&token, Validation::default()).is_err());
+}
+
+#[test]
+fn test_jwks_lists_verification_keys() {
+    let keys = JwtKeys::from_pem("ed-1", Algorithm::EdDSA, ED25519_PRIVATE, ED25519_PUBLIC)
+        .unwrap()
+        .with_verification_key("rsa-1", Algorithm::RS256, RSA_PUBLIC)
+        .unwrap();
+
+    let jwks = keys.jwks();
+    let jwks = jwks["keys"].as_array().unwrap();
+
+    assert_eq!(jwks.len(), 2);
+    assert_eq!(jwks[0]["kid"], "ed-1");
+    assert_eq!(jwks[0]["kty"], "OKP");
+    assert_eq!(jwks[0]["x"].as_str().unwrap().len(), 43);
+    assert_eq!(jwks[1]["kid"], "rsa-1");
+    assert_eq!(jwks[1]["kty"], "RSA");
+    assert_eq!(jwks[1]["e"], "AQAB");
+}
+
+#[test]
+fn test_mismatched_key_pair_is_rejected() {
+    assert!(JwtKeys::from_pem("bad", Algorithm::RS256, RSA_PRIVATE, ED25519_PUBLIC).is_err());
+}
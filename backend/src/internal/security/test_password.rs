@@ -0,0 +1,71 @@
+use crate::internal::security::password::*;
+
+// Cheap parameters so the tests don't spend seconds inside the KDFs
+fn config(algorithm: PasswordAlgorithm) -> PasswordHashingConfig {
+    PasswordHashingConfig {
+        algorithm,
+        bcrypt_cost: 4,
+        argon2_memory_kib: 8,
+        argon2_iterations: 1,
+        argon2_parallelism: 1,
+    }
+}
+
+#[test]
+fn test_bcrypt_hash_and_verify() {
+    let service = PasswordService::new(config(PasswordAlgorithm::Bcrypt));
+
+    let hash = service.hash("password123").unwrap();
+
+    assert_eq!(PasswordAlgorithm::from_hash(&hash), Some(PasswordAlgorithm::Bcrypt));
+    assert!(service.verify("password123", &hash).unwrap());
+    assert!(!service.verify("wrong_password", &hash).unwrap());
+    assert!(!service.needs_rehash(&hash));
+}
+
+#[test]
+fn test_argon2id_hash_and_verify() {
+    let service = PasswordService::new(config(PasswordAlgorithm::Argon2id));
+
+    let hash = service.hash("password123").unwrap();
+
+    assert!(hash.starts_with("$argon2id$"));
+    assert_eq!(PasswordAlgorithm::from_hash(&hash), Some(PasswordAlgorithm::Argon2id));
+    assert!(service.verify("password123", &hash).unwrap());
+    assert!(!service.verify("wrong_password", &hash).unwrap());
+    assert!(!service.needs_rehash(&hash));
+}
+
+#[test]
+fn test_verify_dispatches_on_hash_prefix() {
+    // A bcrypt hash must still verify once the configuration switched to Argon2id
+    let bcrypt_hash = PasswordService::new(config(PasswordAlgorithm::Bcrypt)).hash("password123").unwrap();
+    let service = PasswordService::new(config(PasswordAlgorithm::Argon2id));
+
+    assert!(service.verify("password123", &bcrypt_hash).unwrap());
+    assert!(service.needs_rehash(&bcrypt_hash));
+}
+
+#[test]
+fn test_needs_rehash_on_cost_change() {
+    let old_hash = PasswordService::new(config(PasswordAlgorithm::Bcrypt)).hash("password123").unwrap();
+
+    let mut stronger = config(PasswordAlgorithm::Bcrypt);
+    stronger.bcrypt_cost = 5;
+    assert!(PasswordService::new(stronger).needs_rehash(&old_hash));
+
+    let old_hash = PasswordService::new(config(PasswordAlgorithm::Argon2id)).hash("password123").unwrap();
+
+    let mut stronger = config(PasswordAlgorithm::Argon2id);
+    stronger.argon2_iterations = 2;
+    assert!(PasswordService::new(stronger).needs_rehash(&old_hash));
+}
+
+#[test]
+fn test_unrecognised_hash_is_rejected() {
+    let service = PasswordService::new(config(PasswordAlgorithm::Argon2id));
+
+    assert_eq!(PasswordAlgorithm::from_hash("hashedpassword1"), None);
+    assert!(!service.verify("hashedpassword1", "hashedpassword1").unwrap());
+    assert!(service.needs_rehash("hashedpassword1"));
+}
@@ -0,0 +1,101 @@
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, MockDatabase, MockExecResult, Transaction};
+use chrono::Duration;
+use uuid::Uuid;
+
+use crate::internal::security::{
+    models::refresh_tokens,
+    refresh::{hash_token, RefreshTokenService, RefreshTokenServiceImpl},
+};
+
+fn stored_token(token: &str, revoked: bool) -> refresh_tokens::Model {
+    refresh_tokens::Model {
+        id: Uuid::parse_str("0b6d7c1e-2f0e-4d8a-9f55-6a1c5f2b9e01").unwrap(),
+        family_id: Uuid::parse_str("5d1f0a2c-8b7e-4c3d-a1f2-7e9d3c4b5a60").unwrap(),
+        subject_id: Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap(),
+        audience: "users".to_owned(),
+        token_hash: hash_token(token),
+        expires_at: Utc::now() + Duration::days(1),
+        revoked_at: if revoked { Some(Utc::now()) } else { None },
+        replaced_by: None,
+        created_at: Utc::now(),
+    }
+}
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult { last_insert_id: 0, rows_affected }
+}
+
+#[tokio::test]
+async fn test_issue_returns_opaque_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([exec(1)])
+        .into_connection();
+
+    let token = RefreshTokenServiceImpl::issue(&db, Uuid::new_v4(), "users").await.unwrap();
+
+    assert_eq!(token.len(), 64);
+    assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+}
+
+#[tokio::test]
+async fn test_rotate_issues_new_token_in_same_family() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![stored_token("current", false)]])
+        .append_exec_results([exec(1), exec(1)])
+        .into_connection();
+
+    let rotated = RefreshTokenServiceImpl::rotate(&db, "current", "users").await.unwrap();
+
+    assert_eq!(rotated.subject_id, Uuid::parse_str("51c84da0-6fbe-4db2-81fe-385a38d29353").unwrap());
+    assert_ne!(rotated.refresh_token, "current");
+
+    let log = db.into_transaction_log();
+    let inserted = log.iter().any(|t: &Transaction| {
+        let statement = format!("{:?}", t);
+        statement.contains("INSERT") && statement.contains("5d1f0a2c-8b7e-4c3d-a1f2-7e9d3c4b5a60")
+    });
+    assert!(inserted, "New token should be inserted in the same family: {:?}", log);
+}
+
+#[tokio::test]
+async fn test_rotate_reused_token_revokes_family() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![stored_token("stolen", true)]])
+        .append_exec_results([exec(2)])
+        .into_connection();
+
+    let result = RefreshTokenServiceImpl::rotate(&db, "stolen", "users").await;
+
+    let error = result.err().expect("Reused token should be rejected").new();
+    let extensions = error.extensions.unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("REFRESH_TOKEN_REUSED")));
+
+    let log = db.into_transaction_log();
+    let family_revoked = log.iter().any(|t: &Transaction| {
+        let statement = format!("{:?}", t);
+        statement.contains("UPDATE") && statement.contains("family_id")
+    });
+    assert!(family_revoked, "Token family should be revoked: {:?}", log);
+}
+
+#[tokio::test]
+async fn test_rotate_unknown_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<refresh_tokens::Model>::new()])
+        .into_connection();
+
+    let result = RefreshTokenServiceImpl::rotate(&db, "unknown", "users").await;
+
+    let error = result.err().expect("Unknown token should be rejected").new();
+    let extensions = error.extensions.unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("INVALID_REFRESH_TOKEN")));
+}
+
+#[tokio::test]
+async fn test_revoke_unknown_token() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<refresh_tokens::Model>::new()])
+        .into_connection();
+
+    assert!(!RefreshTokenServiceImpl::revoke(&db, "unknown", "users").await.unwrap());
+}
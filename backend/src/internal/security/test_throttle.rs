@@ -0,0 +1,82 @@
+use chrono::Duration;
+use sea_orm::{sqlx::types::chrono::Utc, DatabaseBackend, MockDatabase, MockExecResult};
+
+use crate::internal::security::{
+    models::login_attempts,
+    throttle::{login_keys, LoginThrottleService, LoginThrottleServiceImpl, ThrottleConfig, ThrottleKey, ThrottleKind},
+};
+
+fn attempts(key: &ThrottleKey, failures: i32, locked_for: Option<Duration>) -> login_attempts::Model {
+    login_attempts::Model {
+        key: key.key.clone(),
+        failures,
+        last_failure_at: Utc::now(),
+        locked_until: locked_for.map(|duration| Utc::now() + duration),
+    }
+}
+
+#[test]
+fn test_lockout_doubles_up_to_the_maximum() {
+    let config = ThrottleConfig::default();
+
+    assert_eq!(config.lockout_for(ThrottleKind::Account, 4), None);
+    assert_eq!(config.lockout_for(ThrottleKind::Account, 5), Some(Duration::seconds(30)));
+    assert_eq!(config.lockout_for(ThrottleKind::Account, 6), Some(Duration::seconds(60)));
+    assert_eq!(config.lockout_for(ThrottleKind::Account, 7), Some(Duration::seconds(120)));
+    assert_eq!(config.lockout_for(ThrottleKind::Account, 50), Some(Duration::minutes(15)));
+    // Addresses are shared by many accounts and get more room
+    assert_eq!(config.lockout_for(ThrottleKind::Address, 19), None);
+}
+
+#[test]
+fn test_account_keys_ignore_email_case() {
+    let keys = login_keys("admin", " Admin@Example.com", Some("10.0.0.1"));
+
+    assert_eq!(keys[0], ThrottleKey::account("admin", "admin@example.com"));
+    assert_eq!(keys[1].key, "admin:ip:10.0.0.1");
+    assert_ne!(ThrottleKey::account("admin", "a@example.com"), ThrottleKey::account("users", "a@example.com"));
+}
+
+#[tokio::test]
+async fn test_check_refuses_locked_key() {
+    let key = ThrottleKey::account("admin", "admin@example.com");
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![attempts(&key, 6, Some(Duration::seconds(60)))]])
+        .into_connection();
+
+    let error = LoginThrottleServiceImpl::check(&db, &[key]).await.unwrap_err().new();
+
+    let extensions = error.extensions.unwrap();
+    assert_eq!(extensions.get("message"), Some(&async_graphql::Value::from("TOO_MANY_ATTEMPTS")));
+}
+
+#[tokio::test]
+async fn test_check_passes_without_lock() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<login_attempts::Model>::new()])
+        .into_connection();
+
+    assert!(LoginThrottleServiceImpl::check(&db, &login_keys("admin", "admin@example.com", None)).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_record_failure_locks_past_the_limit() {
+    let account = ThrottleKey::account("admin", "admin@example.com");
+    let address = ThrottleKey::address("admin", "10.0.0.1");
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![attempts(&account, 5, None)]])
+        .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+        .append_query_results([vec![attempts(&address, 5, None)]])
+        .into_connection();
+
+    LoginThrottleServiceImpl::record_failure(&db, &[account, address]).await.unwrap();
+
+    let log = db.into_transaction_log();
+    // The counter is bumped atomically, restarting once the previous failure is old enough
+    let upsert = format!("{:?}", log[0]).replace("\\\"", "\"");
+    assert!(upsert.contains(r#"ON CONFLICT ("key") DO UPDATE SET "failures" = CASE WHEN"#), "Unexpected query: {}", upsert);
+    // Only the account reached its limit
+    assert_eq!(log.len(), 3);
+    let lock = format!("{:?}", log[1]).replace("\\\"", "\"");
+    assert!(lock.contains(r#"UPDATE "login_attempts" SET "locked_until""#), "Unexpected query: {}", lock);
+}
@@ -0,0 +1,50 @@
+use crate::internal::security::totp::{base32_decode, base32_encode, code_at, generate_secret, provisioning_uri, verify};
+
+// RFC 6238 appendix B, SHA-1 variant
+const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+#[test]
+fn test_rfc6238_vectors() {
+    assert_eq!(code_at(RFC_SECRET, 59 / 30, 8), 94287082);
+    assert_eq!(code_at(RFC_SECRET, 1111111109 / 30, 8), 7081804);
+    assert_eq!(code_at(RFC_SECRET, 1234567890 / 30, 8), 89005924);
+    assert_eq!(code_at(RFC_SECRET, 20000000000 / 30, 8), 65353130);
+}
+
+#[test]
+fn test_base32_round_trip() {
+    assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+    assert_eq!(base32_decode("mzxw6ytboi======").unwrap(), b"foobar");
+    assert!(base32_decode("not base32!").is_none());
+
+    let secret = generate_secret();
+    assert_eq!(secret.len(), 32);
+    assert_eq!(base32_decode(&secret).unwrap().len(), 20);
+}
+
+#[test]
+fn test_verify_accepts_adjacent_steps_once() {
+    let secret = base32_encode(RFC_SECRET);
+    let time = 1111111109;
+    let step = time / 30;
+
+    let previous = format!("{:06}", code_at(RFC_SECRET, step - 1, 6));
+    assert_eq!(verify(&secret, &previous, time, None), Some(step - 1));
+
+    let current = format!("{:06}", code_at(RFC_SECRET, step, 6));
+    assert_eq!(verify(&secret, &current, time, None), Some(step));
+    // A code already used, or older than the last one used, is refused
+    assert_eq!(verify(&secret, &current, time, Some(step)), None);
+
+    let stale = format!("{:06}", code_at(RFC_SECRET, step - 2, 6));
+    assert_eq!(verify(&secret, &stale, time, None), None);
+    assert_eq!(verify(&secret, "12345", time, None), None);
+}
+
+#[test]
+fn test_provisioning_uri() {
+    assert_eq!(
+        provisioning_uri("My App", "admin@example.com", "JBSWY3DPEHPK3PXP"),
+        "otpauth://totp/My%20App:admin%40example.com?secret=JBSWY3DPEHPK3PXP&issuer=My%20App&algorithm=SHA1&digits=6&period=30"
+    );
+}
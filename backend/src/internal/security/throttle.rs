@@ -0,0 +1,190 @@
+use std::env;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use log::{trace, warn};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+};
+
+use crate::internal::api::admin::users::errors::{db::AdminDbError, interface::CustomGraphQLError, user::AdminUserAuthError};
+use crate::internal::security::models::login_attempts;
+
+/// Failed login limits, read from `LOGIN_MAX_FAILURES` (per account, default 5),
+/// `LOGIN_IP_MAX_FAILURES` (per client address, default 20), `LOGIN_LOCKOUT_BASE_SECS`
+/// (default 30), `LOGIN_LOCKOUT_MAX_SECS` (default 900) and `LOGIN_FAILURES_RESET_SECS`
+/// (default 3600).
+#[derive(Clone, Debug)]
+pub struct ThrottleConfig {
+    pub account_max_failures: i32,
+    pub address_max_failures: i32,
+    pub base_lockout: Duration,
+    pub max_lockout: Duration,
+    /// Failures older than this are forgotten on the next one.
+    pub reset_after: Duration,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        ThrottleConfig {
+            account_max_failures: 5,
+            address_max_failures: 20,
+            base_lockout: Duration::seconds(30),
+            max_lockout: Duration::minutes(15),
+            reset_after: Duration::hours(1),
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+impl ThrottleConfig {
+    pub fn from_env() -> Self {
+        let defaults = ThrottleConfig::default();
+        ThrottleConfig {
+            account_max_failures: env_or("LOGIN_MAX_FAILURES", defaults.account_max_failures),
+            address_max_failures: env_or("LOGIN_IP_MAX_FAILURES", defaults.address_max_failures),
+            base_lockout: Duration::seconds(env_or("LOGIN_LOCKOUT_BASE_SECS", defaults.base_lockout.num_seconds())),
+            max_lockout: Duration::seconds(env_or("LOGIN_LOCKOUT_MAX_SECS", defaults.max_lockout.num_seconds())),
+            reset_after: Duration::seconds(env_or("LOGIN_FAILURES_RESET_SECS", defaults.reset_after.num_seconds())),
+        }
+    }
+
+    /// Lockout after the `failures`-th consecutive failure: none below the limit, then
+    /// doubling from `base_lockout` with each further failure, up to `max_lockout`.
+    pub fn lockout_for(&self, kind: ThrottleKind, failures: i32) -> Option<Duration> {
+        let max_failures = match kind {
+            ThrottleKind::Account => self.account_max_failures,
+            ThrottleKind::Address => self.address_max_failures,
+        };
+        if failures < max_failures {
+            return None;
+        }
+
+        let doublings = (failures - max_failures).min(20) as u32;
+        let lockout = self.base_lockout.checked_mul(2_i32.pow(doublings)).unwrap_or(self.max_lockout);
+        Some(lockout.min(self.max_lockout))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ThrottleKind {
+    Account,
+    Address,
+}
+
+/// A counter failed logins are tracked on. `scope` keeps the admin and end-user logins
+/// apart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThrottleKey {
+    pub kind: ThrottleKind,
+    pub key: String,
+}
+
+impl ThrottleKey {
+    pub fn account(scope: &str, email: &str) -> Self {
+        ThrottleKey { kind: ThrottleKind::Account, key: format!("{}:account:{}", scope, email.trim().to_lowercase()) }
+    }
+
+    pub fn address(scope: &str, address: &str) -> Self {
+        ThrottleKey { kind: ThrottleKind::Address, key: format!("{}:ip:{}", scope, address) }
+    }
+}
+
+/// Keys a login attempt counts against: the account, and the client address when known.
+pub fn login_keys(scope: &str, email: &str, address: Option<&str>) -> Vec<ThrottleKey> {
+    let mut keys = vec![ThrottleKey::account(scope, email)];
+    if let Some(address) = address {
+        keys.push(ThrottleKey::address(scope, address));
+    }
+    keys
+}
+
+#[async_trait]
+pub trait LoginThrottleService {
+    /// Fails with `TooManyAttempts` while any of the keys is locked.
+    async fn check(db: &DatabaseConnection, keys: &[ThrottleKey]) -> Result<(), Box<dyn CustomGraphQLError>>;
+    /// Counts a failure against every key, locking those past their limit.
+    async fn record_failure(db: &DatabaseConnection, keys: &[ThrottleKey]) -> Result<(), Box<dyn CustomGraphQLError>>;
+    /// Forgets the failures and lock of a key, after a successful login or on an admin unlock.
+    async fn reset(db: &DatabaseConnection, key: &ThrottleKey) -> Result<bool, Box<dyn CustomGraphQLError>>;
+}
+
+pub struct LoginThrottleServiceImpl;
+
+#[async_trait]
+impl LoginThrottleService for LoginThrottleServiceImpl {
+    async fn check(db: &DatabaseConnection, keys: &[ThrottleKey]) -> Result<(), Box<dyn CustomGraphQLError>> {
+        let now = Utc::now();
+
+        let locked = login_attempts::Entity::find()
+            .filter(login_attempts::Column::Key.is_in(keys.iter().map(|key| key.key.clone())))
+            .filter(login_attempts::Column::LockedUntil.gt(now))
+            .all(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+        match locked.iter().filter_map(|attempts| attempts.locked_until).max() {
+            Some(until) => {
+                trace!("Login refused, locked until {}", until);
+                Err(Box::new(AdminUserAuthError::TooManyAttempts((until - now).num_seconds().max(1))))
+            },
+            None => Ok(()),
+        }
+    }
+
+    async fn record_failure(db: &DatabaseConnection, keys: &[ThrottleKey]) -> Result<(), Box<dyn CustomGraphQLError>> {
+        let config = ThrottleConfig::from_env();
+        let now = Utc::now();
+
+        for key in keys {
+            // Single statement so that concurrent failures are all counted
+            let attempts = login_attempts::Entity::insert(login_attempts::ActiveModel {
+                key: Set(key.key.clone()),
+                failures: Set(1),
+                last_failure_at: Set(now),
+                locked_until: Set(None),
+            })
+            .on_conflict(
+                OnConflict::column(login_attempts::Column::Key)
+                    .value(
+                        login_attempts::Column::Failures,
+                        Expr::cust_with_values(
+                            r#"CASE WHEN "login_attempts"."last_failure_at" < $1 THEN 1 ELSE "login_attempts"."failures" + 1 END"#,
+                            [now - config.reset_after],
+                        ),
+                    )
+                    .value(login_attempts::Column::LastFailureAt, now)
+                    .to_owned(),
+            )
+            .exec_with_returning(db)
+            .await
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+
+            if let Some(lockout) = config.lockout_for(key.kind, attempts.failures) {
+                warn!("Locking '{}' for {}s after {} failed logins", key.key, lockout.num_seconds(), attempts.failures);
+
+                login_attempts::Entity::update_many()
+                    .col_expr(login_attempts::Column::LockedUntil, Expr::value(now + lockout))
+                    .filter(login_attempts::Column::Key.eq(key.key.clone()))
+                    .exec(db)
+                    .await
+                    .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn reset(db: &DatabaseConnection, key: &ThrottleKey) -> Result<bool, Box<dyn CustomGraphQLError>> {
+        trace!("Resetting failed logins of '{}'", key.key);
+
+        login_attempts::Entity::delete_by_id(key.key.clone())
+            .exec(db)
+            .await
+            .map(|result| result.rows_affected > 0)
+            .map_err(|e| Box::new(AdminDbError::DatabaseError(e.to_string())) as Box<dyn CustomGraphQLError>)
+    }
+}
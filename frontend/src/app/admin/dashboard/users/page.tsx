@@ -21,14 +21,18 @@ import { useEffect, useState } from "react";
 import { getCookie } from "@/lib/auth/cookies";
 
 const GET_USERS = gql`
-  query users($token: String!) {
+  query users {
     admin {
-      users(token: $token) {
-        id
-        username
-        email
-        firstName
-        lastName
+      users(first: 100, orderBy: { field: USERNAME }) {
+        edges {
+          node {
+            id
+            username
+            email
+            firstName
+            lastName
+          }
+        }
       }
     }
   }
@@ -113,11 +117,12 @@ export default function AdminUsersPage() {
 
   const { loading, error, data } = useQuery(GET_USERS, {
     client,
-    variables: { token },
+    context: { headers: { Authorization: `Bearer ${token}` } },
     skip: !token, // Ne pas exécuter la requête tant que le token n'est pas défini
   });
 
-  const users = data?.admin?.users || [];
+  const users: User[] =
+    data?.admin?.users?.edges?.map((edge: { node: User }) => edge.node) || [];
 
   if (loading) return <p>Loading...</p>;
   if (error) return <p>Error: {error.message}</p>;
@@ -7,17 +7,80 @@ import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { useRouter } from "next/navigation";
 
+interface TokenPair {
+  accessToken: string;
+  refreshToken: string;
+}
+
+interface MfaChallenge {
+  challengeToken: string;
+  enrolmentRequired: boolean;
+}
+
 interface GenerateTokenResponse {
   admin: {
-    generateToken: string;
+    generateToken: {
+      tokens: TokenPair | null;
+      mfaChallenge: MfaChallenge | null;
+    };
   };
 }
 
-// GraphQL mutation to generate token
+interface TotpEnrolment {
+  secret: string;
+  provisioningUri: string;
+}
+
+// GraphQL mutation to generate token, or an MFA challenge when a second factor is needed
 const GENERATE_TOKEN = gql`
   mutation GenerateToken($input: GenerateTokenInput!) {
     admin {
-      generateToken(input: $input)
+      generateToken(input: $input) {
+        tokens {
+          accessToken
+          refreshToken
+        }
+        mfaChallenge {
+          challengeToken
+          enrolmentRequired
+        }
+      }
+    }
+  }
+`;
+
+const VERIFY_MFA_CHALLENGE = gql`
+  mutation VerifyMfaChallenge($challengeToken: String!, $code: String!) {
+    admin {
+      verifyMfaChallenge(challengeToken: $challengeToken, code: $code) {
+        accessToken
+        refreshToken
+      }
+    }
+  }
+`;
+
+const START_TOTP_ENROLMENT = gql`
+  mutation StartTotpEnrolment($challengeToken: String) {
+    admin {
+      startTotpEnrolment(challengeToken: $challengeToken) {
+        secret
+        provisioningUri
+      }
+    }
+  }
+`;
+
+const CONFIRM_TOTP_ENROLMENT = gql`
+  mutation ConfirmTotpEnrolment($code: String!, $challengeToken: String) {
+    admin {
+      confirmTotpEnrolment(code: $code, challengeToken: $challengeToken) {
+        recoveryCodes
+        tokens {
+          accessToken
+          refreshToken
+        }
+      }
     }
   }
 `;
@@ -25,6 +88,11 @@ const GENERATE_TOKEN = gql`
 export default function LoginPage() {
   const [formData, setFormData] = useState({ email: "", password: "" });
   const [error, setError] = useState<string | null>(null);
+  const [challenge, setChallenge] = useState<MfaChallenge | null>(null);
+  const [enrolment, setEnrolment] = useState<TotpEnrolment | null>(null);
+  const [recoveryCodes, setRecoveryCodes] = useState<string[] | null>(null);
+  const [pendingTokens, setPendingTokens] = useState<TokenPair | null>(null);
+  const [code, setCode] = useState("");
   const [redirectTo, setRedirectTo] = useState<string | null>(null);
   const router = useRouter();
 
@@ -35,18 +103,65 @@ export default function LoginPage() {
     }
   }, []);
 
+  const onError = (err: Error) => setError(err.message);
   const [generateToken, { loading }] = useMutation(GENERATE_TOKEN, {
     client,
     onCompleted: handleLoginSuccess,
-    onError: (err) => setError(err.message),
+    onError,
+  });
+  const [verifyMfaChallenge, { loading: verifying }] = useMutation(VERIFY_MFA_CHALLENGE, {
+    client,
+    onCompleted: (data) => storeTokens(data.admin.verifyMfaChallenge),
+    onError,
+  });
+  const [startTotpEnrolment] = useMutation(START_TOTP_ENROLMENT, {
+    client,
+    onCompleted: (data) => setEnrolment(data.admin.startTotpEnrolment),
+    onError,
+  });
+  const [confirmTotpEnrolment, { loading: confirming }] = useMutation(CONFIRM_TOTP_ENROLMENT, {
+    client,
+    onCompleted: (data) => {
+      // Recovery codes are only shown once, the user moves on when they have saved them
+      setRecoveryCodes(data.admin.confirmTotpEnrolment.recoveryCodes);
+      setPendingTokens(data.admin.confirmTotpEnrolment.tokens);
+    },
+    onError,
   });
 
-  function handleLoginSuccess(data: GenerateTokenResponse) {
-    const token = data.admin.generateToken;
-    document.cookie = `auth_token=${token}; path=/admin; secure; samesite=strict`;
+  function storeTokens({ accessToken, refreshToken }: TokenPair) {
+    document.cookie = `auth_token=${accessToken}; path=/admin; secure; samesite=strict`;
+    document.cookie = `refresh_token=${refreshToken}; path=/admin; secure; samesite=strict`;
     router.push(redirectTo || "/admin");
   }
 
+  function handleLoginSuccess(data: GenerateTokenResponse) {
+    const { tokens, mfaChallenge } = data.admin.generateToken;
+    if (tokens) {
+      storeTokens(tokens);
+      return;
+    }
+    if (mfaChallenge) {
+      setChallenge(mfaChallenge);
+      if (mfaChallenge.enrolmentRequired) {
+        startTotpEnrolment({ variables: { challengeToken: mfaChallenge.challengeToken } });
+      }
+    }
+  }
+
+  const handleCode = (e: FormEvent) => {
+    e.preventDefault();
+    if (!challenge) return;
+    setError(null);
+
+    const variables = { challengeToken: challenge.challengeToken, code };
+    if (challenge.enrolmentRequired) {
+      confirmTotpEnrolment({ variables });
+    } else {
+      verifyMfaChallenge({ variables });
+    }
+  };
+
   const handleInputChange = (e: React.ChangeEvent<HTMLInputElement>) => {
     const { name, value } = e.target;
     setFormData((prev) => ({ ...prev, [name]: value }));
@@ -67,6 +182,60 @@ export default function LoginPage() {
     }
   };
 
+  if (recoveryCodes) {
+    return (
+      <div className="p-6 max-w-md mx-auto">
+        <h1 className="text-3xl font-semibold mb-6">Codes de récupération</h1>
+        <p className="mb-4">
+          Conservez ces codes en lieu sûr : chacun permet une connexion si vous perdez votre application
+          d&apos;authentification. Ils ne seront plus affichés.
+        </p>
+        <ul className="mb-6 font-mono">
+          {recoveryCodes.map((recoveryCode) => (
+            <li key={recoveryCode}>{recoveryCode}</li>
+          ))}
+        </ul>
+        <Button className="w-full" onClick={() => pendingTokens && storeTokens(pendingTokens)}>
+          Continuer
+        </Button>
+      </div>
+    );
+  }
+
+  if (challenge) {
+    return (
+      <div className="p-6 max-w-md mx-auto">
+        <h1 className="text-3xl font-semibold mb-6">Vérification en deux étapes</h1>
+        {challenge.enrolmentRequired && enrolment && (
+          <div className="mb-4">
+            <p className="mb-2">
+              Un de vos rôles exige la double authentification. Ajoutez ce compte à votre application
+              d&apos;authentification, puis saisissez le code affiché.
+            </p>
+            <p className="font-mono break-all mb-2">{enrolment.secret}</p>
+            <a className="underline text-sm break-all" href={enrolment.provisioningUri}>
+              {enrolment.provisioningUri}
+            </a>
+          </div>
+        )}
+        <form onSubmit={handleCode}>
+          <FormInput
+            label={challenge.enrolmentRequired ? "Code" : "Code ou code de récupération"}
+            type="text"
+            name="code"
+            value={code}
+            onChange={(e) => setCode(e.target.value)}
+            required
+          />
+          {error && <p className="text-red-600 mb-4">{error}</p>}
+          <Button type="submit" className="w-full" disabled={verifying || confirming}>
+            {verifying || confirming ? "Vérification..." : "Valider"}
+          </Button>
+        </form>
+      </div>
+    );
+  }
+
   return (
     <div className="p-6 max-w-md mx-auto">
       <h1 className="text-3xl font-semibold mb-6">Connexion</h1>
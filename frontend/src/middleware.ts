@@ -26,9 +26,9 @@ type ApolloError = {
 
 // GraphQL query for access check
 const GET_ACCESS_QUERY = gql`
-  query GetAccessPage($token: String!, $page: String!) {
+  query GetAccessPage($page: String!) {
     admin {
-      getAccessPage(token: $token, page: $page)
+      getAccessPage(page: $page)
     }
   }
 `;
@@ -43,7 +43,8 @@ async function checkAccessWithRustBackend(
     console.log(page);
     const { data } = await client.query({
       query: GET_ACCESS_QUERY,
-      variables: { token, page },
+      variables: { page },
+      context: { headers: { Authorization: `Bearer ${token}` } },
     });
 
     return data.admin?.getAccessPage || false;